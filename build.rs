@@ -179,7 +179,8 @@ exec "$TCL_SERVER" --runtime tcl --privileged "$@"
 
     for (script_name, content) in scripts {
         let script_path = Path::new(target_dir).join(script_name);
-        fs::write(&script_path, content).expect(&format!("Failed to write {} script", script_name));
+        fs::write(&script_path, content)
+            .unwrap_or_else(|e| panic!("Failed to write {} script: {}", script_name, e));
 
         // Make the script executable on Unix systems
         #[cfg(unix)]
@@ -188,7 +189,7 @@ exec "$TCL_SERVER" --runtime tcl --privileged "$@"
             let mut perms = fs::metadata(&script_path).unwrap().permissions();
             perms.set_mode(0o755);
             fs::set_permissions(&script_path, perms)
-                .expect(&format!("Failed to set {} permissions", script_name));
+                .unwrap_or_else(|e| panic!("Failed to set {} permissions: {}", script_name, e));
         }
     }
 
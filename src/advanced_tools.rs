@@ -1,4 +1,6 @@
 use crate::namespace::ToolPath;
+use crate::persistence::Integrity;
+use crate::system_tools;
 use crate::tool_registry::{ToolQuery, ToolRegistry, ToolSource};
 /// Advanced TCL tools using the unified tool registry
 /// Provides enhanced listing, searching, and inspection capabilities
@@ -8,24 +10,14 @@ use serde_json::json;
 /// Unified tool listing with VFS support and flexible output formats
 pub async fn list_tools(
     registry: &ToolRegistry,
-    namespace: Option<String>,
-    server: Option<String>,
-    search: Option<String>,
-    include_schemas: bool,
-    limit: Option<usize>,
+    query: ToolQuery,
     format: Option<String>,
 ) -> Result<String> {
-    let query = ToolQuery {
-        namespace,
-        server,
-        search,
-        include_schemas,
-        limit,
-    };
-
-    let tools = registry.query_tools(query).await?;
+    let include_schemas = query.include_schemas;
+    let page = registry.query_page(query).await?;
+    let tools = page.items;
 
-    if tools.is_empty() {
+    if page.total == 0 {
         return Ok("No tools found matching criteria".to_string());
     }
 
@@ -49,23 +41,44 @@ pub async fn list_tools(
     // Handle output format
     match format.as_deref().unwrap_or("detailed") {
         "simple" => {
-            // Simple format: just array of MCP names for backward compatibility
+            // Simple format: MCP names only, paged like the detailed format
             let paths: Vec<String> = tools.iter().map(|t| t.path.to_mcp_name()).collect();
-            Ok(serde_json::to_string_pretty(&paths)?)
+            let mut result = json!({ "tools": paths });
+            if let Some(next_cursor) = page.next_cursor {
+                result["next_cursor"] = json!(next_cursor);
+            }
+            Ok(serde_json::to_string_pretty(&result)?)
         }
         _ => {
             // Detailed format (default): rich metadata
-            Ok(serde_json::to_string_pretty(&json!({
+            let mut result = json!({
                 "tools": output,
-                "count": output.len()
-            }))?)
+                "count": output.len(),
+                "total": page.total
+            });
+            if let Some(next_cursor) = page.next_cursor {
+                result["next_cursor"] = json!(next_cursor);
+            }
+            Ok(serde_json::to_string_pretty(&result)?)
         }
     }
 }
 
 /// Get detailed information about a specific tool
 pub async fn inspect_tool(registry: &ToolRegistry, tool_path: &str) -> Result<String> {
-    let path = ToolPath::parse(tool_path)?;
+    // Quarantined tools are no longer resolvable, but can still be inspected
+    if let Ok(path) = ToolPath::parse(tool_path) {
+        if let Some(integrity @ Integrity::Quarantined { .. }) = registry.integrity(&path) {
+            let info = json!({
+                "mcp_name": path.to_mcp_name(),
+                "namespace": format!("{:?}", path.namespace),
+                "integrity": integrity
+            });
+            return Ok(serde_json::to_string_pretty(&info)?);
+        }
+    }
+
+    let path = registry.resolve(tool_path)?;
 
     if let Some(tool) = registry.get_tool(&path).await {
         let mut info = json!({
@@ -77,15 +90,28 @@ pub async fn inspect_tool(registry: &ToolRegistry, tool_path: &str) -> Result<St
             "schema": tool.schema
         });
 
+        // Show models how the tool is called; `@test` cases are left out
+        let examples: Vec<_> = registry
+            .examples(&tool.path)
+            .iter()
+            .filter(|example| !example.test_only)
+            .collect();
+        if !examples.is_empty() {
+            info["examples"] = json!(examples);
+        }
+
         // Add source-specific information
         match &tool.source {
             ToolSource::System => {
                 info["system_info"] = json!({
                     "type": "built-in",
-                    "privileged": tool.path.to_mcp_name().starts_with("sbin__")
+                    "privileged": system_tools::find_path(&tool.path).is_some_and(|t| t.privileged)
                 });
             }
             ToolSource::UserTcl { script } => {
+                if let Some(integrity) = registry.integrity(&tool.path) {
+                    info["integrity"] = json!(integrity);
+                }
                 info["tcl_info"] = json!({
                     "script_length": script.len(),
                     "script_preview": if script.len() > 100 {
@@ -115,7 +141,7 @@ pub async fn inspect_tool(registry: &ToolRegistry, tool_path: &str) -> Result<St
 
         Ok(serde_json::to_string_pretty(&info)?)
     } else {
-        Ok(format!("Tool not found: {}", tool_path))
+        Err(registry.not_found(tool_path))
     }
 }
 
@@ -164,20 +190,13 @@ pub async fn list_namespaces(registry: &ToolRegistry) -> Result<String> {
     }))?)
 }
 
-/// Search tools by description or name
+/// Search tools by relevance across names, descriptions, parameters and tags
 pub async fn search_tools(
     registry: &ToolRegistry,
     query: &str,
     limit: Option<usize>,
 ) -> Result<String> {
-    let search_query = ToolQuery {
-        search: Some(query.to_string()),
-        include_schemas: false,
-        limit,
-        ..Default::default()
-    };
-
-    let tools = registry.query_tools(search_query).await?;
+    let tools = registry.search_tools(query, limit).await?;
 
     if tools.is_empty() {
         return Ok(format!("No tools found matching search: '{}'", query));
@@ -185,10 +204,11 @@ pub async fn search_tools(
 
     let results: Vec<_> = tools
         .into_iter()
-        .map(|tool| {
+        .map(|(tool, score)| {
             json!({
                 "mcp_name": tool.path.to_mcp_name(),
                 "description": tool.description,
+                "score": (score * 1000.0).round() / 1000.0,
                 "source_type": match tool.source {
                     ToolSource::System => "system",
                     ToolSource::UserTcl { .. } => "user_tcl",
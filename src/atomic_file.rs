@@ -0,0 +1,115 @@
+/// Safe replacement of files shared between server processes
+///
+/// Every stdio client starts its own server process, and they all share one
+/// data directory. Index files are therefore never rewritten in place: a
+/// complete copy is written next to the file and renamed over it, so readers
+/// see either the old or the new contents. Read-modify-write cycles hold an
+/// advisory lock on a sibling `.lock` file so that one process doesn't
+/// overwrite another's changes.
+use anyhow::{Context, Result};
+use fs2::FileExt;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Distinguishes temporary files of concurrent writes within a process
+static TEMP_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Exclusive lock on a file, released when dropped
+pub struct FileLock {
+    _file: File,
+}
+
+/// Lock `path` against other processes, waiting for them to release it
+pub async fn lock(path: &Path) -> Result<FileLock> {
+    let lock_path = sibling(path, ".lock");
+    tokio::task::spawn_blocking(move || {
+        if let Some(parent) = lock_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&lock_path)
+            .with_context(|| format!("Failed to open lock file {}", lock_path.display()))?;
+        file.lock_exclusive()
+            .with_context(|| format!("Failed to lock {}", lock_path.display()))?;
+        Ok(FileLock { _file: file })
+    })
+    .await?
+}
+
+/// Replace the contents of `path` in one step
+pub async fn write(path: &Path, contents: &[u8]) -> Result<()> {
+    let path = path.to_path_buf();
+    let contents = contents.to_vec();
+    tokio::task::spawn_blocking(move || write_blocking(&path, &contents)).await?
+}
+
+fn write_blocking(path: &Path, contents: &[u8]) -> Result<()> {
+    let temp_path = sibling(
+        path,
+        &format!(
+            ".{}.{}.tmp",
+            std::process::id(),
+            TEMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+        ),
+    );
+
+    let written = File::create(&temp_path).and_then(|mut file| {
+        file.write_all(contents)?;
+        file.sync_all()
+    });
+    if let Err(e) = written.and_then(|_| fs::rename(&temp_path, path)) {
+        let _ = fs::remove_file(&temp_path);
+        return Err(e).with_context(|| format!("Failed to write {}", path.display()));
+    }
+    Ok(())
+}
+
+/// `path` with `suffix` appended to its file name
+fn sibling(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(suffix);
+    path.with_file_name(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_write_replaces_contents() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("index.json");
+
+        write(&path, b"{\"version\": 1}").await?;
+        write(&path, b"{\"version\": 2}").await?;
+        assert_eq!(fs::read_to_string(&path)?, "{\"version\": 2}");
+
+        // No temporary files are left behind
+        let names: Vec<_> = fs::read_dir(dir.path())?
+            .map(|entry| entry.unwrap().file_name())
+            .collect();
+        assert_eq!(names, vec!["index.json"]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_lock_is_exclusive() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("index.json");
+
+        let held = lock(&path).await?;
+        let other = OpenOptions::new()
+            .write(true)
+            .open(dir.path().join("index.json.lock"))?;
+        assert!(other.try_lock_exclusive().is_err());
+
+        drop(held);
+        assert!(other.try_lock_exclusive().is_ok());
+        Ok(())
+    }
+}
@@ -1,6 +1,8 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
+use crate::host_commands;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TclRuntimeCapabilities {
     pub runtime_name: String,
@@ -82,6 +84,11 @@ impl TclRuntimeCapabilities {
             ],
         );
 
+        capabilities.command_categories.insert(
+            "mcp".to_string(),
+            host_commands::COMMANDS.iter().map(|c| c.to_string()).collect(),
+        );
+
         if privileged {
             capabilities.privileged_commands = vec![
                 "tcl_tool_add".to_string(),
@@ -109,16 +116,18 @@ impl TclRuntimeCapabilities {
             ],
             is_safe: false,
             command_categories: HashMap::new(),
-            limitations: if privileged {
-                vec![]
-            } else {
-                vec![
-                    "File operations restricted in non-privileged mode".to_string(),
-                    "System commands disabled in non-privileged mode".to_string(),
-                ]
-            },
+            limitations: vec![format!(
+                "No MCP host commands ({}); use the Molt runtime",
+                host_commands::COMMANDS.join(", ")
+            )],
             privileged_commands: vec![],
         };
+        if !privileged {
+            capabilities.limitations.extend([
+                "File operations restricted in non-privileged mode".to_string(),
+                "System commands disabled in non-privileged mode".to_string(),
+            ]);
+        }
 
         // All standard TCL commands
         capabilities.command_categories.insert(
@@ -213,4 +222,26 @@ impl TclRuntimeCapabilities {
 
         capabilities
     }
+
+    /// Whether a command is in one of the runtime's categories
+    pub fn has_command(&self, command: &str) -> bool {
+        self.command_categories
+            .values()
+            .any(|commands| commands.iter().any(|c| c == command))
+    }
+
+    /// Commands of the official TCL runtime that are only available in
+    /// privileged mode
+    pub fn restricted_commands() -> Vec<String> {
+        let restricted = Self::for_tcl(false);
+        let mut commands: Vec<String> = Self::for_tcl(true)
+            .command_categories
+            .into_values()
+            .flatten()
+            .filter(|command| !restricted.has_command(command))
+            .collect();
+        commands.sort();
+        commands.dedup();
+        commands
+    }
 }
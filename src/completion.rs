@@ -0,0 +1,174 @@
+use crate::namespace::ToolPath;
+use crate::system_tools;
+use crate::tcl_tools::ParameterDefinition;
+/// Argument completion for `completion/complete`
+/// Decides where candidate values for a tool argument come from and shapes
+/// the MCP completion result
+use serde_json::{json, Value};
+
+/// Maximum number of values in a single completion result (MCP limit)
+pub const MAX_COMPLETION_VALUES: usize = 100;
+
+/// Where candidate values for an argument come from
+#[derive(Debug, Clone, PartialEq)]
+pub enum CompletionSource {
+    /// Every tool known to the registry, as MCP names
+    ToolPaths,
+    /// IDs of registered MCP servers
+    McpServers,
+    /// A fixed set of values (schema `enum` or `examples`)
+    Values(Vec<String>),
+    /// A TCL completion provider declared on the parameter
+    Provider(String),
+}
+
+/// Pick the completion source for an argument of the given tool
+pub fn source_for(
+    path: &ToolPath,
+    parameters: &[ParameterDefinition],
+    schema: Option<&Value>,
+    argument: &str,
+) -> Option<CompletionSource> {
+    if let Some(provider) = parameters
+        .iter()
+        .find(|param| param.name == argument)
+        .and_then(|param| param.completion.clone())
+    {
+        return Some(CompletionSource::Provider(provider));
+    }
+
+    let system_tool = system_tools::find_path(path);
+    let system_schema = system_tool.map(|tool| tool.input_schema());
+    if let Some(values) = schema
+        .or(system_schema.as_ref())
+        .and_then(|schema| schema_values(schema, argument))
+    {
+        return Some(CompletionSource::Values(values));
+    }
+
+    // Well-known argument names only mean something on system tools
+    system_tool.and(match argument {
+        "tool_path" => Some(CompletionSource::ToolPaths),
+        "server_id" | "server" => Some(CompletionSource::McpServers),
+        _ => None,
+    })
+}
+
+/// Filter candidates against the partially typed value and build the
+/// `completion/complete` result. Prefix matches come first, followed by
+/// other candidates containing the value.
+pub fn completion_result(candidates: Vec<String>, partial: &str) -> Value {
+    let partial_lower = partial.to_lowercase();
+    let (mut values, mut contains): (Vec<String>, Vec<String>) = candidates
+        .into_iter()
+        .filter(|candidate| candidate.to_lowercase().contains(&partial_lower))
+        .partition(|candidate| candidate.to_lowercase().starts_with(&partial_lower));
+
+    values.sort();
+    values.dedup();
+    contains.sort();
+    contains.dedup();
+    values.extend(contains);
+
+    let total = values.len();
+    values.truncate(MAX_COMPLETION_VALUES);
+
+    json!({
+        "completion": {
+            "values": values,
+            "total": total,
+            "hasMore": total > MAX_COMPLETION_VALUES
+        }
+    })
+}
+
+/// Values declared on a property of a JSON schema, from `enum` or, for
+/// open-ended properties, `examples`
+fn schema_values(schema: &Value, argument: &str) -> Option<Vec<String>> {
+    let property = schema.get("properties")?.get(argument)?;
+    let values = property
+        .get("enum")
+        .or_else(|| property.get("examples"))?
+        .as_array()?;
+
+    Some(
+        values
+            .iter()
+            .map(|value| match value {
+                Value::String(s) => s.clone(),
+                other => other.to_string(),
+            })
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_source_selection() {
+        assert_eq!(
+            source_for(&ToolPath::bin("exec_tool"), &[], None, "tool_path"),
+            Some(CompletionSource::ToolPaths)
+        );
+        assert_eq!(
+            source_for(&ToolPath::sbin("mcp_ping"), &[], None, "server_id"),
+            Some(CompletionSource::McpServers)
+        );
+        assert!(matches!(
+            source_for(&ToolPath::docs("molt_book"), &[], None, "topic"),
+            Some(CompletionSource::Values(values)) if values.contains(&"basic_syntax".to_string())
+        ));
+        assert!(matches!(
+            source_for(&ToolPath::bin("list_tools"), &[], None, "namespace"),
+            Some(CompletionSource::Values(values)) if values.contains(&"xmcp".to_string())
+        ));
+
+        let user_tool = ToolPath::user("alice", "paint", "mix", "latest");
+        let params = vec![ParameterDefinition {
+            name: "color".to_string(),
+            description: "Color".to_string(),
+            required: true,
+            type_name: "string".to_string(),
+            completion: Some("return {red green blue}".to_string()),
+            default: None,
+        }];
+        assert_eq!(
+            source_for(&user_tool, &params, None, "color"),
+            Some(CompletionSource::Provider(
+                "return {red green blue}".to_string()
+            ))
+        );
+        assert_eq!(source_for(&user_tool, &params, None, "tool_path"), None);
+    }
+
+    #[test]
+    fn test_completion_result_ordering_and_limit() {
+        let result = completion_result(
+            vec![
+                "bin__list_tools".to_string(),
+                "sbin__mcp_list".to_string(),
+                "bin__list_namespaces".to_string(),
+                "bin__tcl_execute".to_string(),
+            ],
+            "bin__list",
+        );
+        assert_eq!(
+            result["completion"]["values"],
+            json!(["bin__list_namespaces", "bin__list_tools"])
+        );
+
+        let result = completion_result(vec!["list".to_string(), "blist".to_string()], "list");
+        assert_eq!(result["completion"]["values"], json!(["list", "blist"]));
+
+        let many: Vec<String> = (0..150).map(|i| format!("tool_{:03}", i)).collect();
+        let result = completion_result(many, "tool_");
+        assert_eq!(
+            result["completion"]["values"].as_array().unwrap().len(),
+            100
+        );
+        assert_eq!(result["completion"]["total"], 150);
+        assert_eq!(result["completion"]["hasMore"], true);
+    }
+}
@@ -0,0 +1,237 @@
+/// Tool exposure profiles
+///
+/// A profile decides which tools `tools/list` advertises. Patterns are globs
+/// (`*` and `?`) matched against a tool's MCP name, or against its namespace
+/// or MCP server when written as `namespace:<glob>` or `server:<glob>`.
+/// External MCP tools are in namespace `xmcp`, as `bin__list_tools` shows
+/// them; `namespace:mcp` is accepted too.
+/// A tool is exposed when it matches an include pattern (or the profile has
+/// none) and no exclude pattern. Hidden tools stay callable through
+/// `bin__exec_tool`.
+///
+/// Besides the built-in `all` and `meta-only` profiles, named profiles can be
+/// declared in `profiles.json` in the config directory:
+///
+/// ```json
+/// {
+///   "docs": { "include": ["namespace:docs", "bin__*"], "exclude": ["bin__tcl_execute"] }
+/// }
+/// ```
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::namespace::{Namespace, ToolPath};
+use crate::platform_dirs;
+
+/// Profile exposing every tool
+pub const ALL: &str = "all";
+
+/// Profile exposing only the tools needed to find and run everything else
+pub const META_ONLY: &str = "meta-only";
+
+const META_TOOLS: [&str; 3] = ["bin__search_tools", "bin__inspect_tool", "bin__exec_tool"];
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ExposureProfile {
+    #[serde(default)]
+    pub include: Vec<String>,
+    #[serde(default)]
+    pub exclude: Vec<String>,
+}
+
+impl ExposureProfile {
+    /// Built-in profile by name
+    pub fn builtin(name: &str) -> Option<Self> {
+        match name {
+            ALL => Some(Self::default()),
+            META_ONLY => Some(Self {
+                include: META_TOOLS.iter().map(|name| name.to_string()).collect(),
+                exclude: Vec::new(),
+            }),
+            _ => None,
+        }
+    }
+
+    /// Built-in profile, or one declared in the config directory's `profiles.json`
+    pub fn load(name: &str) -> Result<Self> {
+        if let Some(profile) = Self::builtin(name) {
+            return Ok(profile);
+        }
+        Self::load_from(&platform_dirs::profiles_path()?, name)
+    }
+
+    /// Profile declared in the given profiles file
+    pub fn load_from(path: &Path, name: &str) -> Result<Self> {
+        let mut profiles: BTreeMap<String, ExposureProfile> = if path.exists() {
+            serde_json::from_str(&std::fs::read_to_string(path)?)
+                .map_err(|e| anyhow!("Invalid profiles file {}: {}", path.display(), e))?
+        } else {
+            BTreeMap::new()
+        };
+
+        profiles.remove(name).ok_or_else(|| {
+            let available: Vec<&str> = [ALL, META_ONLY]
+                .into_iter()
+                .chain(profiles.keys().map(String::as_str))
+                .collect();
+            anyhow!(
+                "Unknown exposure profile '{}' (available: {})",
+                name,
+                available.join(", ")
+            )
+        })
+    }
+
+    /// Add include and exclude patterns on top of this profile
+    pub fn with_patterns(mut self, include: &[String], exclude: &[String]) -> Self {
+        self.include.extend(include.iter().cloned());
+        self.exclude.extend(exclude.iter().cloned());
+        self
+    }
+
+    /// Whether `tools/list` should advertise the tool
+    pub fn exposes(&self, path: &ToolPath) -> bool {
+        let mcp_name = path.to_mcp_name();
+        let matches = |pattern: &String| pattern_matches(pattern, path, &mcp_name);
+
+        (self.include.is_empty() || self.include.iter().any(matches))
+            && !self.exclude.iter().any(matches)
+    }
+}
+
+fn pattern_matches(pattern: &str, path: &ToolPath, mcp_name: &str) -> bool {
+    if let Some(namespace) = pattern.strip_prefix("namespace:") {
+        let label = match &path.namespace {
+            Namespace::Bin => "bin",
+            Namespace::Sbin => "sbin",
+            Namespace::Docs => "docs",
+            Namespace::User(user) if user == "xmcp" => "xmcp",
+            Namespace::User(_) => "user",
+            Namespace::Mcp(_) => "xmcp",
+        };
+        glob_matches(namespace, label) || (label == "xmcp" && glob_matches(namespace, "mcp"))
+    } else if let Some(server) = pattern.strip_prefix("server:") {
+        match (&path.namespace, &path.package) {
+            (Namespace::Mcp(id), _) => glob_matches(server, id),
+            (Namespace::User(user), Some(id)) if user == "xmcp" => glob_matches(server, id),
+            _ => false,
+        }
+    } else {
+        glob_matches(pattern, mcp_name)
+    }
+}
+
+/// Match `text` against a glob where `*` matches any run of characters and
+/// `?` matches exactly one
+pub fn glob_matches(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    // Position after the last `*` and the text position it was tried at
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p + 1, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                Some((star_p, star_t)) => {
+                    p = star_p;
+                    t = star_t + 1;
+                    backtrack = Some((star_p, star_t + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mcp_tool(server: &str, name: &str) -> ToolPath {
+        ToolPath {
+            namespace: Namespace::Mcp(server.to_string()),
+            package: None,
+            name: name.to_string(),
+            version: "latest".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_glob_matches() {
+        assert!(glob_matches("bin__*", "bin__exec_tool"));
+        assert!(glob_matches("*__list_*", "bin__list_tools"));
+        assert!(glob_matches("docs__molt_boo?", "docs__molt_book"));
+        assert!(glob_matches("*", ""));
+        assert!(!glob_matches("bin__*", "sbin__mcp_add"));
+        assert!(!glob_matches("a*b", "acbc"));
+    }
+
+    #[test]
+    fn test_meta_only_profile() {
+        let profile = ExposureProfile::builtin(META_ONLY).unwrap();
+        assert!(profile.exposes(&ToolPath::bin("exec_tool")));
+        assert!(profile.exposes(&ToolPath::bin("search_tools")));
+        assert!(!profile.exposes(&ToolPath::bin("tcl_execute")));
+        assert!(!profile.exposes(&mcp_tool("context7", "get_docs")));
+    }
+
+    #[test]
+    fn test_namespace_and_server_patterns() {
+        let profile = ExposureProfile::default().with_patterns(
+            &["namespace:docs".to_string(), "server:git*".to_string()],
+            &["*__push".to_string()],
+        );
+        assert!(profile.exposes(&ToolPath::docs("molt_book")));
+        assert!(profile.exposes(&mcp_tool("github", "list_issues")));
+        assert!(!profile.exposes(&mcp_tool("github", "push")));
+        assert!(!profile.exposes(&mcp_tool("context7", "get_docs")));
+        assert!(!profile.exposes(&ToolPath::bin("exec_tool")));
+
+        assert!(ExposureProfile::builtin(ALL)
+            .unwrap()
+            .exposes(&ToolPath::sbin("mcp_add")));
+
+        // External MCP tools are `xmcp` as in listings, and `mcp` still works
+        let xmcp_tool = ToolPath {
+            namespace: Namespace::User("xmcp".to_string()),
+            package: Some("github".to_string()),
+            name: "list_issues".to_string(),
+            version: "latest".to_string(),
+        };
+        for namespace in ["namespace:xmcp", "namespace:mcp"] {
+            let profile = ExposureProfile::default().with_patterns(&[namespace.to_string()], &[]);
+            assert!(profile.exposes(&mcp_tool("github", "list_issues")));
+            assert!(profile.exposes(&xmcp_tool));
+            assert!(!profile.exposes(&ToolPath::user("alice", "utils", "reverse", "latest")));
+        }
+        let profile = ExposureProfile::default().with_patterns(&["server:github".to_string()], &[]);
+        assert!(profile.exposes(&xmcp_tool));
+    }
+
+    #[test]
+    fn test_load_profiles_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("profiles.json");
+        std::fs::write(&path, r#"{"research": {"include": ["server:context7"]}}"#).unwrap();
+
+        let profile = ExposureProfile::load_from(&path, "research").unwrap();
+        assert_eq!(profile.include, vec!["server:context7"]);
+        assert!(profile.exclude.is_empty());
+
+        let err = ExposureProfile::load_from(&path, "missing").unwrap_err();
+        assert!(err.to_string().contains("all, meta-only, research"));
+    }
+}
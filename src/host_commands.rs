@@ -0,0 +1,223 @@
+/// `mcp::*` commands that let TCL scripts talk to the connected MCP client
+///
+/// - `mcp::log level message` sends a `notifications/message` log entry
+/// - `mcp::elicit message fields` asks the user for input via `elicitation/create`
+/// - `mcp::sample prompt ?options?` requests an LLM completion via `sampling/createMessage`
+///
+/// Elicitation and sampling are only usable when the client declared the
+/// matching capability during `initialize`.
+use anyhow::{anyhow, Result};
+use serde_json::{json, Map, Value};
+use std::time::Duration;
+
+use crate::mcp_peer::{LogLevel, McpPeer};
+use crate::tcl_list::{json_to_tcl, parse_dict, parse_list};
+use crate::tcl_runtime::{HostCommand, TclRuntime};
+
+/// How long a script waits for the user to answer an elicitation
+const ELICIT_TIMEOUT: Duration = Duration::from_secs(600);
+
+/// How long a script waits for the client to return a sampled message
+const SAMPLE_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Default `maxTokens` for `mcp::sample`
+const DEFAULT_MAX_TOKENS: u64 = 1024;
+
+/// The commands `install` registers, on runtimes that support host commands
+pub const COMMANDS: &[&str] = &["mcp::log", "mcp::elicit", "mcp::sample"];
+
+/// Register all `mcp::*` commands with the runtime
+pub fn install(runtime: &mut dyn TclRuntime, peer: &McpPeer) {
+    let log_peer = peer.clone();
+    let elicit_peer = peer.clone();
+    let sample_peer = peer.clone();
+
+    let commands: Vec<(&str, HostCommand)> = vec![
+        ("mcp::log", Box::new(move |args| log(&log_peer, args))),
+        (
+            "mcp::elicit",
+            Box::new(move |args| elicit(&elicit_peer, args)),
+        ),
+        (
+            "mcp::sample",
+            Box::new(move |args| sample(&sample_peer, args)),
+        ),
+    ];
+
+    for (name, command) in commands {
+        if let Err(e) = runtime.register_command(name, command) {
+            tracing::debug!("{} not available: {}", name, e);
+        }
+    }
+}
+
+fn log(peer: &McpPeer, args: &[String]) -> Result<String> {
+    if args.len() != 2 {
+        return Err(anyhow!(
+            "wrong # args: should be \"mcp::log level message\""
+        ));
+    }
+
+    let level: LogLevel = args[0].parse()?;
+    peer.log(level, "tcl", args[1].as_str());
+    Ok(String::new())
+}
+
+/// `mcp::elicit message fields`
+///
+/// `fields` is either a JSON schema object or a TCL dict mapping each field
+/// name to `type` or `{type description}`, where type is one of string,
+/// number, integer or boolean. Returns a dict with `action` and, when the
+/// user accepted, `content`.
+fn elicit(peer: &McpPeer, args: &[String]) -> Result<String> {
+    if args.len() != 2 {
+        return Err(anyhow!(
+            "wrong # args: should be \"mcp::elicit message fields\""
+        ));
+    }
+    require_capability(peer, "elicitation", "mcp::elicit")?;
+
+    let requested_schema = elicitation_schema(&args[1])?;
+    let result = peer.request(
+        "elicitation/create",
+        json!({
+            "message": args[0],
+            "requestedSchema": requested_schema
+        }),
+        ELICIT_TIMEOUT,
+    )?;
+
+    Ok(json_to_tcl(&result))
+}
+
+/// `mcp::sample prompt ?-system text? ?-max-tokens n? ?-temperature t?`
+///
+/// Returns the text of the sampled message.
+fn sample(peer: &McpPeer, args: &[String]) -> Result<String> {
+    const USAGE: &str = "wrong # args: should be \"mcp::sample prompt ?-system text? ?-max-tokens n? ?-temperature t?\"";
+
+    let (prompt, options) = args.split_first().ok_or_else(|| anyhow!(USAGE))?;
+    if options.len() % 2 != 0 {
+        return Err(anyhow!(USAGE));
+    }
+    require_capability(peer, "sampling", "mcp::sample")?;
+
+    let mut params = json!({
+        "messages": [{
+            "role": "user",
+            "content": {"type": "text", "text": prompt}
+        }],
+        "maxTokens": DEFAULT_MAX_TOKENS
+    });
+
+    for option in options.chunks(2) {
+        let (name, value) = (option[0].as_str(), &option[1]);
+        match name {
+            "-system" => params["systemPrompt"] = json!(value),
+            "-max-tokens" => {
+                let max_tokens: u64 = value.parse().map_err(|_| {
+                    anyhow!("expected integer for -max-tokens but got \"{}\"", value)
+                })?;
+                params["maxTokens"] = json!(max_tokens);
+            }
+            "-temperature" => {
+                let temperature: f64 = value.parse().map_err(|_| {
+                    anyhow!("expected number for -temperature but got \"{}\"", value)
+                })?;
+                params["temperature"] = json!(temperature);
+            }
+            _ => {
+                return Err(anyhow!(
+                    "bad option \"{}\": must be -system, -max-tokens, or -temperature",
+                    name
+                ))
+            }
+        }
+    }
+
+    let result = peer.request("sampling/createMessage", params, SAMPLE_TIMEOUT)?;
+    match &result["content"] {
+        Value::Object(content) if content.get("type") == Some(&json!("text")) => Ok(content
+            .get("text")
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string()),
+        _ => Err(anyhow!("mcp::sample: client returned non-text content")),
+    }
+}
+
+fn require_capability(peer: &McpPeer, capability: &str, command: &str) -> Result<()> {
+    if peer.client_supports(capability) {
+        Ok(())
+    } else {
+        Err(anyhow!(
+            "{} is not available: the client did not declare the {} capability",
+            command,
+            capability
+        ))
+    }
+}
+
+/// Build an elicitation `requestedSchema` from a JSON schema or a TCL field dict
+fn elicitation_schema(fields: &str) -> Result<Value> {
+    if let Ok(schema @ Value::Object(_)) = serde_json::from_str::<Value>(fields) {
+        return Ok(schema);
+    }
+
+    let mut properties = Map::new();
+    let mut required = Vec::new();
+
+    for (name, spec) in parse_dict(fields)? {
+        let spec = parse_list(&spec)?;
+        let field_type = spec.first().map(String::as_str).unwrap_or("string");
+        if !matches!(field_type, "string" | "number" | "integer" | "boolean") {
+            return Err(anyhow!(
+                "invalid type \"{}\" for field \"{}\": must be string, number, integer, or boolean",
+                field_type,
+                name
+            ));
+        }
+
+        let mut property = json!({"type": field_type});
+        if let Some(description) = spec.get(1) {
+            property["description"] = json!(description);
+        }
+        properties.insert(name.clone(), property);
+        required.push(name);
+    }
+
+    Ok(json!({
+        "type": "object",
+        "properties": properties,
+        "required": required
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_elicitation_schema_from_dict() {
+        let schema = elicitation_schema("confirm boolean reason {string {Why?}}").unwrap();
+        assert_eq!(schema["properties"]["confirm"]["type"], "boolean");
+        assert_eq!(schema["properties"]["reason"]["description"], "Why?");
+        assert_eq!(schema["required"], json!(["confirm", "reason"]));
+
+        assert!(elicitation_schema("when date").is_err());
+        assert_eq!(
+            elicitation_schema(r#"{"type": "object", "properties": {}}"#).unwrap()["type"],
+            "object"
+        );
+    }
+
+    #[test]
+    fn test_commands_require_client_capability() {
+        let peer = McpPeer::new();
+        let err = elicit(&peer, &["Continue?".into(), "ok boolean".into()]).unwrap_err();
+        assert!(err.to_string().contains("elicitation capability"));
+
+        let err = sample(&peer, &["Summarize".into()]).unwrap_err();
+        assert!(err.to_string().contains("sampling capability"));
+    }
+}
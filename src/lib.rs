@@ -1,15 +1,36 @@
 // Expose modules for testing
 pub mod advanced_tools;
+pub mod atomic_file;
 pub mod capabilities;
+pub mod completion;
+pub mod exposure;
+pub mod host_commands;
 pub mod mcp_client;
+pub mod mcp_name;
+pub mod mcp_peer;
 pub mod mcp_persistence;
+pub mod migrations;
 pub mod namespace;
-pub mod path_format;
+pub mod pagination;
+pub mod param_inference;
 pub mod persistence;
 pub mod platform_dirs;
+pub mod repl;
+pub mod search_index;
 pub mod server;
+pub mod signing;
+pub mod sqlite_store;
+pub mod system_tools;
 pub mod tcl_executor;
+pub mod tcl_lint;
+pub mod tcl_list;
 pub mod tcl_runtime;
 pub mod tcl_tools;
 pub mod tool_discovery;
+pub mod tool_history;
+pub mod tool_package;
 pub mod tool_registry;
+pub mod tool_source;
+pub mod tool_store;
+pub mod tool_tests;
+pub mod versioning;
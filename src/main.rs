@@ -1,26 +1,61 @@
+// The modules are shared with the library, whose API the binary only
+// partly uses
+#![allow(dead_code)]
+
 use anyhow::Result;
 use clap::{Parser, Subcommand};
 use tracing::info;
 use chrono::Utc;
 use std::collections::HashMap;
+use serde_json::json;
 
 mod advanced_tools;
+mod atomic_file;
+mod capabilities;
+mod completion;
+mod exposure;
+mod host_commands;
 mod mcp_client;
+mod mcp_name;
+mod mcp_peer;
 mod mcp_persistence;
+mod migrations;
 mod namespace;
-mod path_format;
+mod pagination;
+mod param_inference;
 mod persistence;
 mod platform_dirs;
+mod repl;
+mod search_index;
 mod server;
+mod signing;
+mod sqlite_store;
+mod system_tools;
 mod tcl_executor;
+mod tcl_lint;
+mod tcl_list;
 mod tcl_runtime;
 mod tcl_tools;
 mod tool_discovery;
+mod tool_history;
+mod tool_package;
 mod tool_registry;
-
+mod tool_source;
+mod tool_store;
+mod tool_tests;
+mod versioning;
+
+use exposure::ExposureProfile;
+use namespace::ToolPath;
+use persistence::PersistedTool;
+use signing::{SignatureStatus, TrustPolicy, TrustStore};
 use server::TclMcpServer;
 use mcp_client::{McpClient, McpServerConfig};
 use mcp_persistence::McpPersistence;
+use sqlite_store::SqliteStore;
+use tool_history::RevisionAction;
+use tool_package::ConflictPolicy;
+use tool_store::{StorageBackend, ToolStore};
 
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
@@ -41,6 +76,63 @@ struct Args {
     )]
     runtime: Option<String>,
 
+    /// Select the storage backend for tools and MCP servers
+    #[arg(
+        long,
+        value_name = "BACKEND",
+        help = "Storage backend for tools and MCP servers (json|sqlite). Can also be set via TCL_MCP_STORAGE environment variable"
+    )]
+    storage: Option<String>,
+
+    /// Maximum number of MCP requests processed concurrently
+    #[arg(
+        long,
+        value_name = "N",
+        default_value_t = server::DEFAULT_MAX_CONCURRENT_REQUESTS,
+        help = "Maximum number of MCP requests processed concurrently in server mode"
+    )]
+    max_concurrent_requests: usize,
+
+    #[arg(
+        long,
+        value_name = "N",
+        default_value_t = pagination::DEFAULT_PAGE_SIZE,
+        value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..),
+        help = "Maximum number of tools returned per tools/list page"
+    )]
+    page_size: usize,
+
+    #[arg(
+        long,
+        help = "List only the version of each tool that 'latest' resolves to in tools/list"
+    )]
+    latest_only: bool,
+
+    /// Tool exposure profile for tools/list
+    #[arg(
+        long,
+        value_name = "NAME",
+        default_value = exposure::ALL,
+        help = "Tool exposure profile for tools/list: all, meta-only, or a profile from profiles.json"
+    )]
+    profile: String,
+
+    /// Only list tools matching these patterns
+    #[arg(
+        long = "include",
+        value_name = "GLOB",
+        help = "Only list tools matching a glob over MCP names, or namespace:<glob> / server:<glob> (repeatable)"
+    )]
+    include: Vec<String>,
+
+    /// Hide tools matching these patterns
+    #[arg(
+        long = "exclude",
+        value_name = "GLOB",
+        help = "Hide tools matching a glob over MCP names, or namespace:<glob> / server:<glob> (repeatable)"
+    )]
+    exclude: Vec<String>,
+
     #[command(subcommand)]
     command: Option<Commands>,
 }
@@ -76,6 +168,178 @@ enum Commands {
         #[command(subcommand)]
         command: McpCommands,
     },
+    /// Inspect, search, restore and share stored user tools
+    #[command(alias = "tools")]
+    Tool {
+        #[command(subcommand)]
+        command: ToolCommands,
+    },
+    /// Sign a stored tool or a tool script
+    Sign {
+        /// Stored tool path (e.g., "/alice/utils/reverse_string:1.0") or .tcl file
+        target: String,
+        /// Signing key name, or path to a key file
+        #[arg(short, long)]
+        key: String,
+    },
+    /// Check the signature of a stored tool or a tool script
+    Verify {
+        /// Stored tool path or .tcl file
+        target: String,
+    },
+    /// Manage trusted signing keys and the trust policy
+    Trust {
+        #[command(subcommand)]
+        command: TrustCommands,
+    },
+    /// Start an interactive TCL shell with the configured runtime and tools
+    Repl,
+    /// Run the examples of user and filesystem tools and report JUnit XML
+    Test {
+        /// Glob over MCP tool names (e.g., "user__alice__*"); all tools by default
+        pattern: Option<String>,
+        /// File to write the JUnit XML report to (default: print it)
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+    /// Upgrade stored tools, the MCP server index and the SQLite database
+    /// written in an older format
+    Migrate {
+        /// Report what would change without writing anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Measure tools/list and tools/call latency against an in-process server
+    Bench {
+        /// Number of timed requests per method
+        #[arg(short = 'n', long, default_value = "200")]
+        iterations: usize,
+        /// Number of requests in flight at the same time
+        #[arg(short, long, default_value = "1")]
+        concurrency: usize,
+        /// TCL script executed by the tools/call benchmark
+        #[arg(long, default_value = "expr {1 + 1}")]
+        script: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum ToolCommands {
+    /// Store a tool from a .tcl file with header comments
+    Add {
+        /// Tool path (e.g., "/alice/utils/reverse_string:1.0"); without a
+        /// version, the file's @version header is used
+        path: String,
+        /// Tool source file
+        file: String,
+        /// Replace the tool if it is already stored
+        #[arg(long)]
+        force: bool,
+        /// Refuse the tool if linting its script finds errors
+        #[arg(long)]
+        strict: bool,
+    },
+    /// Edit a stored tool in $VISUAL or $EDITOR
+    Edit {
+        /// Tool path in any form
+        tool: String,
+    },
+    /// Remove a stored tool
+    Rm {
+        /// Tool path in any form
+        tool: String,
+    },
+    /// Move a stored tool to another path
+    Mv {
+        /// Tool path in any form
+        from: String,
+        /// New tool path; without a version, the tool keeps its own
+        to: String,
+        /// Replace a tool already stored at the new path
+        #[arg(long)]
+        force: bool,
+    },
+    /// Print a stored tool as .tcl source
+    Cat {
+        /// Tool path in any form
+        tool: String,
+    },
+    /// List the saved revisions of a tool
+    History {
+        /// Tool path in any form (e.g., "/alice/utils/reverse_string:1.0")
+        tool: String,
+    },
+    /// Show a unified diff between two revisions of a tool
+    Diff {
+        /// Tool path in any form
+        tool: String,
+        /// Older revision (defaults to the one before --to)
+        #[arg(long)]
+        from: Option<u32>,
+        /// Newer revision (defaults to the latest)
+        #[arg(long)]
+        to: Option<u32>,
+    },
+    /// Restore an earlier revision of a tool
+    Rollback {
+        /// Tool path in any form; deleted tools need their exact path
+        tool: String,
+        /// Revision to restore
+        revision: u32,
+    },
+    /// Search stored tools by name, description, parameters and script
+    Search {
+        /// Words to search for
+        query: String,
+        /// Maximum number of results
+        #[arg(short, long, default_value = "20")]
+        limit: usize,
+    },
+    /// Export the stored tools of a user or user package as a .tclpkg bundle
+    Export {
+        /// User or user package (e.g., "alice/utils")
+        package: String,
+        /// File to write the package to (default: print it)
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+    /// Import the tools of a .tclpkg bundle
+    Import {
+        /// Package file
+        file: String,
+        /// What to do with tools whose version is already stored
+        /// (skip|overwrite|new-version)
+        #[arg(long, default_value = "skip")]
+        on_conflict: ConflictPolicy,
+    },
+}
+
+#[derive(Subcommand)]
+enum TrustCommands {
+    /// Generate a signing key and trust its public key
+    Keygen {
+        /// Name of the key
+        name: String,
+    },
+    /// Trust a public key
+    Add {
+        /// Name to trust the key under
+        name: String,
+        /// Base64 ed25519 public key
+        public_key: String,
+    },
+    /// Stop trusting a key
+    Remove {
+        /// Name of the key
+        name: String,
+    },
+    /// List trusted keys and the trust policy
+    List,
+    /// Show or set the trust policy
+    Policy {
+        /// off, warn or enforce
+        mode: Option<String>,
+    },
 }
 
 #[derive(Subcommand)]
@@ -199,6 +463,18 @@ async fn main() -> Result<()> {
         }
     };
 
+    let env_storage = std::env::var("TCL_MCP_STORAGE").ok();
+    let storage = match StorageBackend::from_args_and_env(
+        args.storage.as_deref(),
+        env_storage.as_deref(),
+    ) {
+        Ok(storage) => storage,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+    };
+
     // Show available runtimes if requested runtime is not available
     let requested_available = runtime_config
         .runtime_type
@@ -224,12 +500,52 @@ async fn main() -> Result<()> {
         Some(Commands::Run {
             tool,
             args: tool_args,
-        }) => run_tool(&tool, tool_args, args.privileged, runtime_config).await,
+        }) => run_tool(&tool, tool_args, args.privileged, runtime_config, storage).await,
         Some(Commands::List { namespace, filter }) => {
-            list_tools(namespace, filter, args.privileged, runtime_config).await
+            list_tools(namespace, filter, args.privileged, runtime_config, storage).await
+        }
+        Some(Commands::Info { tool }) => {
+            tool_info(&tool, args.privileged, runtime_config, storage).await
+        }
+        Some(Commands::Mcp { command }) => handle_mcp_command(command, storage).await,
+        Some(Commands::Tool { command }) => {
+            handle_tool_command(command, runtime_config, storage).await
+        }
+        Some(Commands::Sign { target, key }) => sign_target(&target, &key, storage).await,
+        Some(Commands::Verify { target }) => verify_target(&target, storage).await,
+        Some(Commands::Trust { command }) => handle_trust_command(command),
+        Some(Commands::Repl) => {
+            repl::Repl::new(args.privileged, runtime_config, storage)
+                .await?
+                .run()
+                .await
+        }
+        Some(Commands::Test { pattern, output }) => {
+            run_tool_tests(
+                pattern.as_deref(),
+                output.as_deref(),
+                args.privileged,
+                runtime_config,
+                storage,
+            )
+            .await
+        }
+        Some(Commands::Migrate { dry_run }) => run_migrations(dry_run).await,
+        Some(Commands::Bench {
+            iterations,
+            concurrency,
+            script,
+        }) => {
+            run_bench(
+                iterations,
+                concurrency,
+                script,
+                args.privileged,
+                runtime_config,
+                storage,
+            )
+            .await
         }
-        Some(Commands::Info { tool }) => tool_info(&tool, args.privileged, runtime_config).await,
-        Some(Commands::Mcp { command }) => handle_mcp_command(command).await,
         Some(Commands::Server) | None => {
             // Default behavior - run as MCP server
             if args.privileged {
@@ -238,9 +554,25 @@ async fn main() -> Result<()> {
                 info!("Starting TCL MCP Server in RESTRICTED mode - limited TCL access");
             }
 
+            let profile = match ExposureProfile::load(&args.profile) {
+                Ok(profile) => profile.with_patterns(&args.include, &args.exclude),
+                Err(e) => {
+                    eprintln!("{}", e);
+                    std::process::exit(1);
+                }
+            };
+
             // Create and run the MCP server with privilege and runtime settings
-            let server = match TclMcpServer::new_with_runtime(args.privileged, runtime_config) {
-                Ok(server) => server,
+            let server = match TclMcpServer::new_with_storage(
+                args.privileged,
+                runtime_config,
+                storage,
+            ) {
+                Ok(server) => server
+                    .with_max_concurrent_requests(args.max_concurrent_requests)
+                    .with_tools_page_size(args.page_size)
+                    .with_latest_versions_only(args.latest_only)
+                    .with_exposure_profile(profile),
                 Err(e) => {
                     eprintln!("Failed to create server: {}", e);
                     std::process::exit(1);
@@ -265,9 +597,10 @@ async fn run_tool(
     args: Option<serde_json::Value>,
     privileged: bool,
     runtime_config: tcl_runtime::RuntimeConfig,
+    storage: StorageBackend,
 ) -> Result<()> {
     // Create a simple server instance just to run the tool
-    let server = TclMcpServer::new_with_runtime(privileged, runtime_config.clone())
+    let server = TclMcpServer::new_with_storage(privileged, runtime_config.clone(), storage)
         .map_err(|e| anyhow::anyhow!("Failed to create server: {}", e))?;
 
     // Initialize persistence
@@ -275,73 +608,19 @@ async fn run_tool(
         tracing::warn!("Failed to initialize persistence: {}", e);
     }
 
-    // Convert tool name to MCP format if needed
-    let mcp_tool_name = if tool_name.contains("__") {
-        // Already in MCP format
-        tool_name.to_string()
-    } else {
-        // Try to find the tool by searching for it
-        // First, try with common namespace prefixes
-        let candidates = vec![
-            format!("bin__{}", tool_name),
-            format!("sbin__{}", tool_name),
-            format!("docs__{}", tool_name),
-            format!("user__{}", tool_name),
-            format!("debug__{}", tool_name),
-            format!("test__{}", tool_name),
-        ];
-
-        // Get the list of tools to find the exact match
-        let tools_request = serde_json::json!({
-            "jsonrpc": "2.0",
-            "id": 1,
-            "method": "tools/list"
-        });
-
-        if let Ok(response) = server.handle_request(tools_request).await {
-            if let Some(result) = response.get("result") {
-                if let Some(tools) = result.get("tools").and_then(|t| t.as_array()) {
-                    // First try exact matches with common namespaces
-                    for candidate in &candidates {
-                        if tools
-                            .iter()
-                            .any(|t| t.get("name").and_then(|n| n.as_str()) == Some(candidate))
-                        {
-                            break;
-                        }
-                    }
-
-                    // If no exact match, look for any tool ending with the name
-                    for tool in tools {
-                        if let Some(name) = tool.get("name").and_then(|n| n.as_str()) {
-                            if name.ends_with(&format!("__{}", tool_name)) {
-                                // Found the tool - recursively call with full name
-                                return Box::pin(run_tool(&name, args, privileged, runtime_config))
-                                    .await;
-                            }
-                        }
-                    }
-                }
-            }
+    // Accept every tool path form
+    let path = match server.resolve_tool_path(tool_name).await {
+        Ok(path) => path,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
         }
-
-        // Default to bin namespace if not found
-        format!("bin__{}", tool_name)
     };
 
-    // Build the JSON-RPC request
-    let request = serde_json::json!({
-        "jsonrpc": "2.0",
-        "id": 1,
-        "method": "tools/call",
-        "params": {
-            "name": mcp_tool_name,
-            "arguments": args.unwrap_or(serde_json::json!({}))
-        }
-    });
-
-    // Process the request
-    match server.handle_request(request).await {
+    match server
+        .call_tool_path(&path, args.unwrap_or(serde_json::json!({})))
+        .await
+    {
         Ok(response) => {
             // Extract and print the result
             if let Some(result) = response.get("result") {
@@ -377,6 +656,7 @@ async fn list_tools(
     pattern_filter: Option<String>,
     privileged: bool,
     runtime_config: tcl_runtime::RuntimeConfig,
+    storage: StorageBackend,
 ) -> Result<()> {
     // Use the tcl_tool_list tool
     let mut args = serde_json::json!({});
@@ -389,16 +669,17 @@ async fn list_tools(
         args["filter"] = serde_json::json!(filter);
     }
 
-    run_tool("bin__list_tools", Some(args), privileged, runtime_config).await
+    run_tool("bin__list_tools", Some(args), privileged, runtime_config, storage).await
 }
 
 async fn tool_info(
     tool_name: &str,
     privileged: bool,
     runtime_config: tcl_runtime::RuntimeConfig,
+    storage: StorageBackend,
 ) -> Result<()> {
-    // Create a server to get tool info
-    let server = TclMcpServer::new_with_runtime(privileged, runtime_config)
+    // Create a server to resolve the path and get tool info
+    let server = TclMcpServer::new_with_storage(privileged, runtime_config, storage)
         .map_err(|e| anyhow::anyhow!("Failed to create server: {}", e))?;
 
     // Initialize persistence
@@ -406,92 +687,700 @@ async fn tool_info(
         tracing::warn!("Failed to initialize persistence: {}", e);
     }
 
+    let path = match server.resolve_tool_path(tool_name).await {
+        Ok(path) => path,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    // System tools are described by the built-in table, including the
+    // privileged ones that tools/list hides in restricted mode
+    if let Some(tool) = system_tools::find_path(&path) {
+        print_tool_info(
+            &tool.mcp_name(),
+            Some(tool.description),
+            Some(&tool.input_schema()),
+        );
+        if tool.privileged && !privileged {
+            println!("\nRequires --privileged mode");
+        }
+        return Ok(());
+    }
+
     // Get the list of tools
-    let tools_request = serde_json::json!({
-        "jsonrpc": "2.0",
-        "id": 1,
-        "method": "tools/list"
-    });
+    let tools = match server.list_all_tools().await {
+        Ok(tools) => tools,
+        Err(e) => {
+            eprintln!("Error getting tool info: {}", e);
+            std::process::exit(1);
+        }
+    };
 
-    match server.handle_request(tools_request).await {
-        Ok(response) => {
-            if let Some(result) = response.get("result") {
-                if let Some(tools) = result.get("tools").and_then(|t| t.as_array()) {
-                    // Find the tool
-                    for tool in tools {
-                        if let Some(name) = tool.get("name").and_then(|n| n.as_str()) {
-                            if name == tool_name || name.ends_with(&format!("__{}", tool_name)) {
-                                // Found it - print info
-                                println!("Tool: {}", name);
-
-                                if let Some(desc) = tool.get("description").and_then(|d| d.as_str())
-                                {
-                                    println!("Description: {}", desc);
-                                }
-
-                                if let Some(schema) = tool.get("inputSchema") {
-                                    println!("\nParameters:");
-                                    if let Some(props) =
-                                        schema.get("properties").and_then(|p| p.as_object())
-                                    {
-                                        for (param_name, prop_schema) in props {
-                                            let desc = prop_schema
-                                                .get("description")
-                                                .and_then(|d| d.as_str())
-                                                .unwrap_or("");
-                                            let prop_type = prop_schema
-                                                .get("type")
-                                                .and_then(|t| t.as_str())
-                                                .unwrap_or("unknown");
-
-                                            let required = schema
-                                                .get("required")
-                                                .and_then(|r| r.as_array())
-                                                .map(|arr| {
-                                                    arr.iter()
-                                                        .any(|v| v.as_str() == Some(param_name))
-                                                })
-                                                .unwrap_or(false);
-
-                                            println!(
-                                                "  {} ({}) {} - {}",
-                                                param_name,
-                                                prop_type,
-                                                if required { "[required]" } else { "[optional]" },
-                                                desc
-                                            );
-                                        }
-                                    } else {
-                                        println!("  No parameters required");
-                                    }
-                                }
-
-                                return Ok(());
-                            }
-                        }
-                    }
+    let mcp_name = path.to_mcp_name();
+    let found = tools
+        .iter()
+        .find(|tool| tool.get("name").and_then(|n| n.as_str()) == Some(mcp_name.as_str()));
+
+    match found {
+        Some(tool) => {
+            print_tool_info(
+                tool.get("name").and_then(|n| n.as_str()).unwrap_or(tool_name),
+                tool.get("description").and_then(|d| d.as_str()),
+                tool.get("inputSchema"),
+            );
+            Ok(())
+        }
+        None => {
+            eprintln!("Tool '{}' not found", tool_name);
+            std::process::exit(1);
+        }
+    }
+}
 
-                    eprintln!("Tool '{}' not found", tool_name);
-                    std::process::exit(1);
-                } else {
-                    eprintln!("No tools found");
-                    std::process::exit(1);
-                }
-            } else if let Some(error) = response.get("error") {
-                eprintln!("Error: {}", serde_json::to_string_pretty(error)?);
-                std::process::exit(1);
+fn print_tool_info(name: &str, description: Option<&str>, schema: Option<&serde_json::Value>) {
+    println!("Tool: {}", name);
+
+    if let Some(desc) = description {
+        println!("Description: {}", desc);
+    }
+
+    let Some(schema) = schema else {
+        return;
+    };
+
+    println!("\nParameters:");
+    match schema.get("properties").and_then(|p| p.as_object()) {
+        Some(props) if !props.is_empty() => {
+            for (param_name, prop_schema) in props {
+                let desc = prop_schema
+                    .get("description")
+                    .and_then(|d| d.as_str())
+                    .unwrap_or("");
+                let prop_type = prop_schema
+                    .get("type")
+                    .and_then(|t| t.as_str())
+                    .unwrap_or("unknown");
+
+                let required = schema
+                    .get("required")
+                    .and_then(|r| r.as_array())
+                    .map(|arr| arr.iter().any(|v| v.as_str() == Some(param_name)))
+                    .unwrap_or(false);
+
+                println!(
+                    "  {} ({}) {} - {}",
+                    param_name,
+                    prop_type,
+                    if required { "[required]" } else { "[optional]" },
+                    desc
+                );
             }
+        }
+        _ => println!("  No parameters required"),
+    }
+}
 
-            Ok(())
+async fn run_bench(
+    iterations: usize,
+    concurrency: usize,
+    script: String,
+    privileged: bool,
+    runtime_config: tcl_runtime::RuntimeConfig,
+    storage: StorageBackend,
+) -> Result<()> {
+    let server = TclMcpServer::new_with_storage(privileged, runtime_config, storage)
+        .map_err(|e| anyhow::anyhow!("Failed to create server: {}", e))?;
+
+    // Go through the same lifecycle a client would
+    server
+        .handle_message(serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": 0,
+            "method": "initialize",
+            "params": {"protocolVersion": "2024-11-05", "capabilities": {}}
+        }))
+        .await;
+    server
+        .handle_message(serde_json::json!({
+            "jsonrpc": "2.0",
+            "method": "notifications/initialized"
+        }))
+        .await;
+
+    let concurrency = concurrency.clamp(1, iterations.max(1));
+    let benchmarks = [
+        ("tools/list", serde_json::json!({})),
+        (
+            "tools/call",
+            serde_json::json!({
+                "name": "bin__tcl_execute",
+                "arguments": {"script": script}
+            }),
+        ),
+    ];
+
+    println!("{} requests per method, concurrency {}", iterations, concurrency);
+    println!(
+        "{:<12} {:>10} {:>10} {:>10} {:>10} {:>12}",
+        "method", "mean", "p50", "p95", "max", "req/s"
+    );
+
+    for (method, params) in benchmarks {
+        let request = serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": method,
+            "params": params
+        });
+
+        // Warm up caches and the executor before timing
+        for _ in 0..concurrency.min(10) {
+            let response = server.handle_message(request.clone()).await;
+            if let Some(error) = response.as_ref().and_then(|r| r.get("error")) {
+                return Err(anyhow::anyhow!("{} failed: {}", method, error));
+            }
+        }
+
+        let started = std::time::Instant::now();
+        let mut workers = Vec::with_capacity(concurrency);
+        for worker in 0..concurrency {
+            let server = server.clone();
+            let request = request.clone();
+            let count =
+                iterations / concurrency + usize::from(worker < iterations % concurrency);
+            workers.push(tokio::spawn(async move {
+                let mut latencies = Vec::with_capacity(count);
+                for _ in 0..count {
+                    let start = std::time::Instant::now();
+                    server.handle_message(request.clone()).await;
+                    latencies.push(start.elapsed());
+                }
+                latencies
+            }));
+        }
+
+        let mut latencies = Vec::with_capacity(iterations);
+        for worker in workers {
+            latencies.extend(worker.await?);
+        }
+        let elapsed = started.elapsed();
+        latencies.sort();
+
+        let percentile = |p: usize| {
+            latencies
+                .get((latencies.len() * p / 100).min(latencies.len().saturating_sub(1)))
+                .copied()
+                .unwrap_or_default()
+        };
+        let mean = latencies.iter().sum::<std::time::Duration>() / latencies.len().max(1) as u32;
+
+        println!(
+            "{:<12} {:>10.3?} {:>10.3?} {:>10.3?} {:>10.3?} {:>12.1}",
+            method,
+            mean,
+            percentile(50),
+            percentile(95),
+            latencies.last().copied().unwrap_or_default(),
+            latencies.len() as f64 / elapsed.as_secs_f64()
+        );
+    }
+
+    Ok(())
+}
+
+/// Tool history, export and import commands run the privileged sbin tools on
+/// the user's behalf; search and the add, edit, rm, mv and cat commands work
+/// on the store directly, so running servers see their changes after
+/// `sbin__tcl_tool_reload`
+async fn handle_tool_command(
+    command: ToolCommands,
+    runtime_config: tcl_runtime::RuntimeConfig,
+    storage: StorageBackend,
+) -> Result<()> {
+    let (tool, args) = match command {
+        ToolCommands::History { tool } => ("sbin__tcl_tool_history", json!({ "path": tool })),
+        ToolCommands::Diff { tool, from, to } => (
+            "sbin__tcl_tool_diff",
+            json!({ "path": tool, "from": from, "to": to }),
+        ),
+        ToolCommands::Rollback { tool, revision } => (
+            "sbin__tcl_tool_rollback",
+            json!({ "path": tool, "revision": revision }),
+        ),
+        ToolCommands::Export { package, output } => (
+            "sbin__tcl_tool_export",
+            json!({ "package": package, "output": output }),
+        ),
+        ToolCommands::Import { file, on_conflict } => (
+            "sbin__tcl_tool_import",
+            json!({ "file": file, "on_conflict": on_conflict }),
+        ),
+        ToolCommands::Search { query, limit } => {
+            return search_stored_tools(&query, limit, storage).await
+        }
+        ToolCommands::Add {
+            path,
+            file,
+            force,
+            strict,
+        } => {
+            return add_stored_tool(&path, &file, force, strict, runtime_config, storage).await
+        }
+        ToolCommands::Edit { tool } => return edit_stored_tool(&tool, storage).await,
+        ToolCommands::Rm { tool } => return remove_stored_tool(&tool, storage).await,
+        ToolCommands::Mv { from, to, force } => {
+            return move_stored_tool(&from, &to, force, storage).await
+        }
+        ToolCommands::Cat { tool } => {
+            let store = tool_store::open(storage).await?;
+            let persisted = load_stored_tool(store.as_ref(), &tool).await?;
+            print!("{}", tool_source::render(&persisted.tool));
+            return Ok(());
+        }
+    };
+    run_tool(tool, Some(args), true, runtime_config, storage).await
+}
+
+async fn search_stored_tools(query: &str, limit: usize, storage: StorageBackend) -> Result<()> {
+    let mut store = tool_store::open(storage).await?;
+    let tools = store.search_tools(query, limit).await?;
+    if tools.is_empty() {
+        println!("No stored tools match '{}'", query);
+        return Ok(());
+    }
+    for persisted in tools {
+        println!("{} - {}", persisted.tool.path, persisted.tool.description);
+    }
+    Ok(())
+}
+
+/// The stored tool a CLI argument names. Tools refused by the trust policy
+/// are never loaded, so this reads the store directly.
+fn stored_tool(persistence: &dyn ToolStore, target: &str) -> Result<ToolPath> {
+    let path = ToolPath::parse(target)?;
+    persistence
+        .stored_path(&path)?
+        .ok_or_else(|| anyhow::anyhow!("Tool '{}' is not stored", target))
+}
+
+async fn load_stored_tool(store: &dyn ToolStore, target: &str) -> Result<PersistedTool> {
+    let path = stored_tool(store, target)?;
+    store
+        .load_persisted(&path)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("Tool '{}' is not stored", path))
+}
+
+/// Whether a tool is stored at exactly this path
+fn is_stored(store: &dyn ToolStore, path: &ToolPath) -> Result<bool> {
+    Ok(store.stored_path(path)?.as_ref() == Some(path))
+}
+
+async fn add_stored_tool(
+    target: &str,
+    file: &str,
+    force: bool,
+    strict: bool,
+    runtime_config: tcl_runtime::RuntimeConfig,
+    storage: StorageBackend,
+) -> Result<()> {
+    let source = std::fs::read_to_string(file)
+        .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", file, e))?;
+    let (header, script) = tool_source::parse(&source);
+    let mut path = ToolPath::parse(target)?;
+    if path.version == versioning::LATEST {
+        if let Some(version) = &header.version {
+            path = path.with_version(version.clone());
+        }
+    }
+    let tool = tool_source::definition(path, header, script)?;
+
+    // Lint against the runtime the server would run the tool on
+    let mut runtime = tcl_runtime::create_runtime_with_config(runtime_config)?;
+    host_commands::install(runtime.as_mut(), &mcp_peer::McpPeer::new());
+    let diagnostics = tcl_lint::lint(&tool.script, &tool.parameters, runtime.as_ref());
+    for diagnostic in &diagnostics {
+        eprintln!("{}: {}", file, diagnostic);
+    }
+    if strict && tcl_lint::has_errors(&diagnostics) {
+        return Err(anyhow::anyhow!(
+            "Tool '{}' was not added because its script has errors",
+            tool.path
+        ));
+    }
+
+    let mut store = tool_store::open(storage).await?;
+    let replaced = is_stored(store.as_ref(), &tool.path)?;
+    if replaced && !force {
+        return Err(anyhow::anyhow!(
+            "Tool '{}' already exists (use --force to replace it)",
+            tool.path
+        ));
+    }
+    store.save_tool(&tool).await?;
+    println!(
+        "✓ {} {}",
+        if replaced { "Replaced" } else { "Added" },
+        tool.path
+    );
+    Ok(())
+}
+
+async fn edit_stored_tool(target: &str, storage: StorageBackend) -> Result<()> {
+    let mut store = tool_store::open(storage).await?;
+    let persisted = load_stored_tool(store.as_ref(), target).await?;
+    let path = persisted.tool.path.clone();
+    let original = tool_source::render(&persisted.tool);
+
+    let file = std::env::temp_dir().join(format!(
+        "tcl-mcp-{}-{}.tcl",
+        path.to_mcp_name(),
+        std::process::id()
+    ));
+    std::fs::write(&file, &original)?;
+
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .unwrap_or_else(|_| "vi".to_string());
+    let mut words = editor.split_whitespace();
+    let program = words
+        .next()
+        .ok_or_else(|| anyhow::anyhow!("The editor command is empty"))?;
+    let status = std::process::Command::new(program)
+        .args(words)
+        .arg(&file)
+        .status()
+        .map_err(|e| anyhow::anyhow!("Failed to run editor '{}': {}", editor, e))?;
+    if !status.success() {
+        return Err(anyhow::anyhow!(
+            "Editor exited with {}; the tool was not changed (your edits are in {})",
+            status,
+            file.display()
+        ));
+    }
+
+    let edited = std::fs::read_to_string(&file)?;
+    let (header, script) = tool_source::parse(&edited);
+    let version = header.version.as_deref().unwrap_or(versioning::LATEST);
+    if version != path.version {
+        return Err(anyhow::anyhow!(
+            "The @version header changed from {} to {}; use `tool mv` to change a tool's version (your edits are in {})",
+            path.version,
+            version,
+            file.display()
+        ));
+    }
+    let tool = tool_source::definition(path.clone(), header, script)
+        .map_err(|e| anyhow::anyhow!("{} (your edits are in {})", e, file.display()))?;
+
+    if tool_source::render(&tool) == original {
+        println!("No changes to {}", path);
+    } else {
+        store.save_tool(&tool).await?;
+        println!("✓ Saved {}", path);
+    }
+    std::fs::remove_file(&file)?;
+    Ok(())
+}
+
+async fn remove_stored_tool(target: &str, storage: StorageBackend) -> Result<()> {
+    let mut store = tool_store::open(storage).await?;
+    let path = stored_tool(store.as_ref(), target)?;
+    store.delete_tool(&path).await?;
+    let revision = store
+        .history(&path)
+        .await?
+        .iter()
+        .rev()
+        .find(|revision| revision.action != RevisionAction::Delete)
+        .map(|revision| revision.revision);
+    println!("✓ Removed {}", path);
+    if let Some(revision) = revision {
+        println!(
+            "  Restore it with: tcl-mcp-server tool rollback {} {}",
+            path, revision
+        );
+    }
+    Ok(())
+}
+
+async fn move_stored_tool(
+    from: &str,
+    to: &str,
+    force: bool,
+    storage: StorageBackend,
+) -> Result<()> {
+    let mut store = tool_store::open(storage).await?;
+    let persisted = load_stored_tool(store.as_ref(), from).await?;
+    let source = persisted.tool.path.clone();
+    let mut target = ToolPath::parse(to)?;
+    if target.version == versioning::LATEST {
+        target = target.with_version(source.version.clone());
+    }
+    tool_source::validate_path(&target)?;
+    if target == source {
+        return Err(anyhow::anyhow!("Tool '{}' is already at that path", source));
+    }
+    if is_stored(store.as_ref(), &target)? && !force {
+        return Err(anyhow::anyhow!(
+            "Tool '{}' already exists (use --force to replace it)",
+            target
+        ));
+    }
+
+    let tool = tcl_tools::ToolDefinition {
+        path: target.clone(),
+        ..persisted.tool
+    };
+    store.save_tool(&tool).await?;
+
+    // Only remove the source once the target reads back as saved
+    let saved = store.load_persisted(&target).await?;
+    let checksum = persistence::calculate_checksum(&tool);
+    if saved.map(|saved| persistence::calculate_checksum(&saved.tool)) != Some(checksum) {
+        return Err(anyhow::anyhow!(
+            "Tool '{}' was not moved: saving it as '{}' could not be confirmed",
+            source,
+            target
+        ));
+    }
+    match store.delete_tool(&source).await {
+        Ok(true) => {}
+        Ok(false) => {
+            return Err(anyhow::anyhow!(
+                "Copied {} to {}, but {} was no longer stored to remove",
+                source,
+                target,
+                source
+            ))
         }
         Err(e) => {
-            eprintln!("Error getting tool info: {}", e);
-            std::process::exit(1);
+            return Err(anyhow::anyhow!(
+                "Copied {} to {}, but could not remove {}: {}. Remove it with `tool rm`",
+                source,
+                target,
+                source,
+                e
+            ))
+        }
+    }
+    println!("✓ Moved {} to {}", source, target);
+
+    // A signature covers the tool's path, so it can't follow the tool
+    if persisted.metadata.signature.is_some() {
+        println!(
+            "⚠ {} was signed; sign {} again with `tcl-mcp-server sign`",
+            source, target
+        );
+    }
+    Ok(())
+}
+
+async fn sign_target(target: &str, key: &str, storage: StorageBackend) -> Result<()> {
+    let key = signing::load_key(key)?;
+    let file = std::path::Path::new(target);
+    if file.is_file() {
+        signing::sign_file(file, &key)?;
+        println!(
+            "✓ Signed {} ({})",
+            file.display(),
+            signing::signature_path(file).display()
+        );
+        return Ok(());
+    }
+
+    let mut persistence = tool_store::open(storage).await?;
+    let path = stored_tool(persistence.as_ref(), target)?;
+    let signature = persistence.sign_tool(&path, &key).await?;
+    println!("✓ Signed {} with key {}", path, signature.public_key);
+    Ok(())
+}
+
+async fn verify_target(target: &str, storage: StorageBackend) -> Result<()> {
+    let trust = TrustStore::load()?;
+    let file = std::path::Path::new(target);
+    let (name, status) = if file.is_file() {
+        (
+            file.display().to_string(),
+            signing::verify_file(&trust, file)?,
+        )
+    } else {
+        let persistence = tool_store::open(storage).await?;
+        let path = stored_tool(persistence.as_ref(), target)?;
+        let persisted = persistence
+            .load_persisted(&path)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Tool '{}' is not stored", path))?;
+        let checksum = persistence::calculate_checksum(&persisted.tool);
+        (
+            path.to_string(),
+            trust.verify(checksum.as_bytes(), persisted.metadata.signature.as_ref()),
+        )
+    };
+
+    println!("{}: {} (trust policy: {})", name, status, trust.policy());
+    if !matches!(status, SignatureStatus::Trusted { .. }) {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+async fn run_tool_tests(
+    pattern: Option<&str>,
+    output: Option<&str>,
+    privileged: bool,
+    runtime_config: tcl_runtime::RuntimeConfig,
+    storage: StorageBackend,
+) -> Result<()> {
+    let server = TclMcpServer::new_with_storage(privileged, runtime_config, storage)
+        .map_err(|e| anyhow::anyhow!("Failed to create server: {}", e))?;
+    if let Err(e) = server.initialize_persistence().await {
+        tracing::warn!("Failed to initialize persistence: {}", e);
+    }
+
+    let cases = tool_tests::run(&server, pattern).await?;
+    let report = tool_tests::junit_xml(&cases);
+    match output {
+        Some(file) => std::fs::write(file, report)?,
+        None => print!("{}", report),
+    }
+
+    // The summary goes to stderr so the report can be piped
+    let failures: Vec<_> = cases.iter().filter(|case| case.failure.is_some()).collect();
+    for case in &failures {
+        eprintln!(
+            "FAILED {} {}: {}",
+            case.tool,
+            case.name,
+            case.failure.as_deref().unwrap_or_default()
+        );
+    }
+    if cases.is_empty() {
+        eprintln!("No tool examples to run");
+    } else {
+        eprintln!(
+            "{} passed, {} failed",
+            cases.len() - failures.len(),
+            failures.len()
+        );
+    }
+    if !failures.is_empty() {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+/// Upgrade every store in the data directory. Servers upgrade the stores
+/// they open on their own; this covers both backends at once and can report
+/// the changes beforehand.
+async fn run_migrations(dry_run: bool) -> Result<()> {
+    platform_dirs::init_directories()?;
+    let tools_dir = platform_dirs::tools_dir()?;
+    let mcp_index_path = platform_dirs::mcp_index_path()?;
+    let db_path = platform_dirs::database_path()?;
+
+    let mut migrated = {
+        let _lock = atomic_file::lock(&tools_dir.join("index.json")).await?;
+        migrations::migrate_tool_store(&tools_dir, dry_run).await?
+    };
+    if mcp_index_path.exists() {
+        let _lock = atomic_file::lock(&mcp_index_path).await?;
+        migrated.extend(
+            migrations::migrate_file(migrations::Format::McpIndex, &mcp_index_path, dry_run)
+                .await?,
+        );
+    }
+
+    let verb = if dry_run { "Would migrate" } else { "Migrated" };
+    for migration in &migrated {
+        println!(
+            "{} {} from format version {} to {}",
+            verb,
+            migration.path.display(),
+            migration.from,
+            migration.to
+        );
+        for step in &migration.steps {
+            println!("  - {}", step);
+        }
+        if let Some(backup) = &migration.backup {
+            println!("  Original kept at {}", backup.display());
         }
     }
+
+    let mut db_migrated = false;
+    if db_path.exists() {
+        let (version, steps) = SqliteStore::pending_migrations(&db_path)?;
+        if !steps.is_empty() {
+            println!(
+                "{} {} from schema version {} to {}",
+                verb,
+                db_path.display(),
+                version,
+                version as usize + steps.len()
+            );
+            for step in &steps {
+                println!("  - {}", step);
+            }
+            if !dry_run {
+                SqliteStore::open(&db_path)?;
+                let backup = db_path.with_extension(format!("db.v{}-backup", version));
+                if backup.exists() {
+                    println!("  Original kept at {}", backup.display());
+                }
+            }
+            db_migrated = true;
+        }
+    }
+
+    if migrated.is_empty() && !db_migrated {
+        println!("All stores are in the current format");
+    }
+    Ok(())
+}
+
+fn handle_trust_command(command: TrustCommands) -> Result<()> {
+    let mut trust = TrustStore::load()?;
+    match command {
+        TrustCommands::Keygen { name } => {
+            let (key_path, public_key) = signing::generate_key(&name)?;
+            trust.trust(&name, &public_key)?;
+            trust.save()?;
+            println!("✓ Signing key '{}' written to {}", name, key_path.display());
+            println!("Public key: {}", public_key);
+        }
+        TrustCommands::Add { name, public_key } => {
+            trust.trust(&name, &public_key)?;
+            trust.save()?;
+            println!("✓ Trusting key '{}'", name);
+        }
+        TrustCommands::Remove { name } => {
+            if !trust.untrust(&name) {
+                eprintln!("Key '{}' is not trusted", name);
+                std::process::exit(1);
+            }
+            trust.save()?;
+            println!("✓ No longer trusting key '{}'", name);
+        }
+        TrustCommands::List => {
+            println!("Trust policy: {}", trust.policy());
+            for (name, key) in trust.keys() {
+                println!("  {} {} (added {})", name, key.public_key, key.added_at.format("%Y-%m-%d"));
+            }
+        }
+        TrustCommands::Policy { mode: None } => println!("{}", trust.policy()),
+        TrustCommands::Policy { mode: Some(mode) } => {
+            trust.set_policy(mode.parse::<TrustPolicy>()?);
+            trust.save()?;
+            println!("✓ Trust policy set to {}", trust.policy());
+        }
+    }
+    Ok(())
 }
 
-async fn handle_mcp_command(command: McpCommands) -> Result<()> {
+async fn handle_mcp_command(command: McpCommands, storage: StorageBackend) -> Result<()> {
     match command {
         McpCommands::Add {
             id,
@@ -502,16 +1391,17 @@ async fn handle_mcp_command(command: McpCommands) -> Result<()> {
             timeout_ms,
             max_retries,
             command_args,
-        } => add_mcp_server(id, name, command_args, description, env, auto_start, timeout_ms, max_retries).await,
-        McpCommands::Remove { id, force } => remove_mcp_server(id, force).await,
-        McpCommands::List { detailed } => list_mcp_servers(detailed).await,
-        McpCommands::Info { id } => mcp_server_info(id).await,
-        McpCommands::Connect { id } => connect_mcp_server(id).await,
+        } => add_mcp_server(id, name, command_args, description, env, auto_start, timeout_ms, max_retries, storage).await,
+        McpCommands::Remove { id, force } => remove_mcp_server(id, force, storage).await,
+        McpCommands::List { detailed } => list_mcp_servers(detailed, storage).await,
+        McpCommands::Info { id } => mcp_server_info(id, storage).await,
+        McpCommands::Connect { id } => connect_mcp_server(id, storage).await,
         McpCommands::Disconnect { id } => disconnect_mcp_server(id).await,
-        McpCommands::Ping { id } => ping_mcp_server(id).await,
+        McpCommands::Ping { id } => ping_mcp_server(id, storage).await,
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn add_mcp_server(
     id: String,
     name: String,
@@ -521,6 +1411,7 @@ async fn add_mcp_server(
     auto_start: bool,
     timeout_ms: u64,
     max_retries: u32,
+    storage: StorageBackend,
 ) -> Result<()> {
     // Parse command and arguments
     if command_args.is_empty() {
@@ -556,7 +1447,7 @@ async fn add_mcp_server(
     };
 
     // Save to persistence
-    let mut persistence = McpPersistence::new().await?;
+    let mut persistence = McpPersistence::open(storage).await?;
     persistence.save_server(id.clone(), config.clone(), auto_start).await?;
 
     // Create MCP client and register server
@@ -573,9 +1464,9 @@ async fn add_mcp_server(
     Ok(())
 }
 
-async fn remove_mcp_server(id: String, force: bool) -> Result<()> {
+async fn remove_mcp_server(id: String, force: bool, storage: StorageBackend) -> Result<()> {
     // Remove from persistence
-    let mut persistence = McpPersistence::new().await?;
+    let mut persistence = McpPersistence::open(storage).await?;
     let removed = persistence.remove_server(&id).await?;
 
     if !removed {
@@ -593,8 +1484,8 @@ async fn remove_mcp_server(id: String, force: bool) -> Result<()> {
     Ok(())
 }
 
-async fn list_mcp_servers(detailed: bool) -> Result<()> {
-    let persistence = McpPersistence::new().await?;
+async fn list_mcp_servers(detailed: bool, storage: StorageBackend) -> Result<()> {
+    let persistence = McpPersistence::open(storage).await?;
     let servers = persistence.list_servers();
 
     if servers.is_empty() {
@@ -641,8 +1532,8 @@ async fn list_mcp_servers(detailed: bool) -> Result<()> {
     Ok(())
 }
 
-async fn mcp_server_info(id: String) -> Result<()> {
-    let persistence = McpPersistence::new().await?;
+async fn mcp_server_info(id: String, storage: StorageBackend) -> Result<()> {
+    let persistence = McpPersistence::open(storage).await?;
     
     if let Some(entry) = persistence.get_server(&id) {
         println!("MCP Server Information");
@@ -685,8 +1576,8 @@ async fn mcp_server_info(id: String) -> Result<()> {
     Ok(())
 }
 
-async fn connect_mcp_server(id: String) -> Result<()> {
-    let persistence = McpPersistence::new().await?;
+async fn connect_mcp_server(id: String, storage: StorageBackend) -> Result<()> {
+    let persistence = McpPersistence::open(storage).await?;
     
     if let Some(entry) = persistence.get_server(&id) {
         let client = McpClient::new();
@@ -725,8 +1616,8 @@ async fn disconnect_mcp_server(id: String) -> Result<()> {
     Ok(())
 }
 
-async fn ping_mcp_server(id: String) -> Result<()> {
-    let persistence = McpPersistence::new().await?;
+async fn ping_mcp_server(id: String, storage: StorageBackend) -> Result<()> {
+    let persistence = McpPersistence::open(storage).await?;
     
     if let Some(entry) = persistence.get_server(&id) {
         let client = McpClient::new();
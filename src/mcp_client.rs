@@ -12,6 +12,8 @@ use tokio::sync::{Mutex, RwLock};
 use tokio::time::{timeout, Duration};
 use tracing::{debug, error, info, warn};
 
+use crate::mcp_peer::{LogLevel, McpPeer};
+
 /// MCP server configuration for registration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct McpServerConfig {
@@ -92,14 +94,21 @@ struct JsonRpcError {
 pub struct McpClient {
     servers: Arc<RwLock<HashMap<String, Arc<Mutex<McpServerConnection>>>>>,
     request_id: Arc<Mutex<u64>>,
+    peer: McpPeer,
 }
 
 impl McpClient {
     /// Create a new MCP client
     pub fn new() -> Self {
+        Self::with_peer(McpPeer::new())
+    }
+
+    /// Create a new MCP client that reports connection events to the given peer
+    pub fn with_peer(peer: McpPeer) -> Self {
         Self {
             servers: Arc::new(RwLock::new(HashMap::new())),
             request_id: Arc::new(Mutex::new(1)),
+            peer,
         }
     }
 
@@ -239,6 +248,11 @@ impl McpClient {
             Err(e) => {
                 connection.status =
                     ConnectionStatus::Error(format!("Failed to start process: {}", e));
+                self.peer.log(
+                    LogLevel::Error,
+                    "mcp_client",
+                    format!("Failed to start MCP server '{}': {}", server_id, e),
+                );
                 return Err(anyhow!("Failed to start MCP server {}: {}", server_id, e));
             }
         };
@@ -266,6 +280,11 @@ impl McpClient {
             connection.status = ConnectionStatus::Error(e.to_string());
             connection.stdin = None;
             connection.stdout = None;
+            self.peer.log(
+                LogLevel::Error,
+                "mcp_client",
+                format!("Failed to initialize MCP server '{}': {}", server_id, e),
+            );
             return Err(e);
         }
 
@@ -274,6 +293,11 @@ impl McpClient {
         connection.retry_count = 0;
 
         info!("Connected to MCP server: {}", server_id);
+        self.peer.log(
+            LogLevel::Info,
+            "mcp_client",
+            format!("Connected to MCP server '{}'", server_id),
+        );
         Ok(())
     }
 
@@ -639,6 +663,14 @@ impl McpClient {
             _ => {
                 info!("Reconnecting to server {}", server_id);
                 connection.retry_count += 1;
+                self.peer.log(
+                    LogLevel::Warning,
+                    "mcp_client",
+                    format!(
+                        "Reconnecting to MCP server '{}' (attempt {})",
+                        server_id, connection.retry_count
+                    ),
+                );
 
                 // Clean up any existing resources
                 connection.stdin = None;
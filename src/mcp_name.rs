@@ -0,0 +1,285 @@
+/// Encoding of tool path components into MCP tool names
+///
+/// MCP names join path components with `__` and may only contain
+/// `[A-Za-z0-9_-]`. Component text is kept as-is where possible, so ordinary
+/// names such as `reverse_string` or `my-server` are unchanged. Anything that
+/// would break the format is escaped as `-x` plus two lowercase hex digits
+/// per UTF-8 byte:
+///
+/// - an underscore at the start or end of a component, or next to another
+///   underscore (`a__b` becomes `a-x5f-x5fb`)
+/// - a hyphen followed by `x`, which would read as an escape
+/// - any other character outside the allowed set
+///
+/// Encoded components therefore never contain `__` and never start or end
+/// with `_`, so splitting on `__` recovers them exactly.
+///
+/// Names longer than [`MAX_MCP_NAME_LEN`] are shortened to a readable prefix
+/// plus a hash of the full name. The shortened name is deterministic, and an
+/// alias table maps it back to the full name. The table holds the names this
+/// process has generated, which includes every tool in the registry; a
+/// shortened name that is not in it, or that two names shorten to, is an
+/// error rather than a guess.
+use anyhow::{anyhow, Result};
+use sha2::{Digest, Sha256};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::sync::{LazyLock, RwLock};
+
+/// Longest tool name major MCP clients accept
+pub const MAX_MCP_NAME_LEN: usize = 64;
+
+/// Separator between path components
+pub const SEPARATOR: &str = "__";
+
+/// Marks the hash suffix of a shortened name
+const HASH_MARKER: &str = "-h";
+
+/// Hex digits of the SHA-256 hash in a shortened name
+const HASH_LEN: usize = 24;
+
+/// Shortened name -> the full encoded names that shorten to it
+static ALIASES: LazyLock<RwLock<HashMap<String, Vec<String>>>> =
+    LazyLock::new(|| RwLock::new(HashMap::new()));
+
+/// Escape one path component
+pub fn encode_component(text: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut encoded = String::with_capacity(text.len());
+
+    for (i, &c) in chars.iter().enumerate() {
+        let escape = match c {
+            'a'..='z' | 'A'..='Z' | '0'..='9' => false,
+            '_' => {
+                i == 0
+                    || i == chars.len() - 1
+                    || chars[i - 1] == '_'
+                    || chars[i + 1] == '_'
+            }
+            '-' => chars.get(i + 1) == Some(&'x'),
+            _ => true,
+        };
+
+        if escape {
+            let mut buf = [0; 4];
+            for byte in c.encode_utf8(&mut buf).bytes() {
+                encoded.push_str(&format!("-x{:02x}", byte));
+            }
+        } else {
+            encoded.push(c);
+        }
+    }
+
+    encoded
+}
+
+/// Reverse `encode_component`
+pub fn decode_component(encoded: &str) -> Result<String> {
+    let invalid = || anyhow!("Invalid escape in MCP name component: {}", encoded);
+    let bytes = encoded.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        if bytes[i] == b'-' && bytes.get(i + 1) == Some(&b'x') {
+            let hex = encoded.get(i + 2..i + 4).ok_or_else(invalid)?;
+            if !hex.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f')) {
+                return Err(invalid());
+            }
+            decoded.push(u8::from_str_radix(hex, 16).map_err(|_| invalid())?);
+            i += 4;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+
+    String::from_utf8(decoded).map_err(|_| invalid())
+}
+
+/// Encode a version as `v` plus the version with dots written as underscores
+/// (`1.0` becomes `v1_0`). Literal underscores are escaped.
+pub fn encode_version(version: &str) -> String {
+    encode_component(&format!("v{}", swap_dots(version)))
+}
+
+/// The version held by a decoded version component, if it is one
+pub fn version_from_component(component: &str) -> Option<String> {
+    component.strip_prefix('v').map(swap_dots)
+}
+
+fn swap_dots(text: &str) -> String {
+    text.chars()
+        .map(|c| match c {
+            '.' => '_',
+            '_' => '.',
+            other => other,
+        })
+        .collect()
+}
+
+/// Join encoded components into an MCP name, shortening it if it is too long
+pub fn join(components: &[String]) -> String {
+    let full = components.join(SEPARATOR);
+    // A name that only looks shortened is recorded as standing for itself,
+    // so that `resolve` can tell it from a shortened name it doesn't know
+    let short = if full.len() <= MAX_MCP_NAME_LEN {
+        if !looks_shortened(&full) {
+            return full;
+        }
+        full.clone()
+    } else {
+        shorten(&full)
+    };
+
+    let mut aliases = ALIASES.write().unwrap();
+    let names = aliases.entry(short.clone()).or_default();
+    if !names.contains(&full) {
+        names.push(full);
+        if names.len() > 1 {
+            tracing::error!(
+                "MCP name {} is shared by {}; it will not resolve",
+                short,
+                names.join(" and ")
+            );
+        }
+    }
+    short
+}
+
+/// Split an MCP name into decoded components, expanding shortened names
+pub fn split(mcp_name: &str) -> Result<Vec<String>> {
+    resolve(mcp_name)?
+        .split(SEPARATOR)
+        .map(decode_component)
+        .collect()
+}
+
+/// The full name behind a shortened name, or the name itself. Fails for a
+/// shortened name this process did not generate, or one that more than one
+/// name shortens to.
+pub fn resolve(mcp_name: &str) -> Result<Cow<'_, str>> {
+    match ALIASES.read().unwrap().get(mcp_name).map(Vec::as_slice) {
+        Some([full]) => Ok(Cow::Owned(full.clone())),
+        Some(names) => Err(anyhow!(
+            "MCP name {} is ambiguous: it is shared by {}",
+            mcp_name,
+            names.join(" and ")
+        )),
+        None if looks_shortened(mcp_name) => Err(anyhow!(
+            "Unknown shortened MCP name {}: no loaded tool has a name that shortens to it",
+            mcp_name
+        )),
+        None => Ok(Cow::Borrowed(mcp_name)),
+    }
+}
+
+/// Deterministic short form of a long name: as much of the name as fits,
+/// then `-h` and the first 96 bits of the SHA-256 hash of the full name
+fn shorten(full: &str) -> String {
+    let digest = Sha256::digest(full.as_bytes());
+    let hash: String = digest
+        .iter()
+        .take(HASH_LEN / 2)
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+
+    let keep = MAX_MCP_NAME_LEN - HASH_MARKER.len() - HASH_LEN;
+    let prefix = full[..keep].trim_end_matches(['_', '-']);
+    format!("{}{}{}", prefix, HASH_MARKER, hash)
+}
+
+/// Whether a name ends like a shortened name: `-h` and the hash digits
+fn looks_shortened(name: &str) -> bool {
+    name.len()
+        .checked_sub(HASH_LEN)
+        .and_then(|start| Some((name.get(..start)?, name.get(start..)?)))
+        .is_some_and(|(prefix, hash)| {
+            prefix.ends_with(HASH_MARKER)
+                && hash.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f'))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_component_round_trip() {
+        for text in [
+            "reverse_string",
+            "my-server",
+            "get__docs",
+            "_private",
+            "trailing_",
+            "a-xb",
+            "dots.and spaces",
+            "ünïcode",
+            "-",
+            "_",
+        ] {
+            let encoded = encode_component(text);
+            assert!(!encoded.contains(SEPARATOR), "{}", encoded);
+            assert!(!encoded.starts_with('_') && !encoded.ends_with('_'));
+            assert!(encoded
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-'));
+            assert_eq!(decode_component(&encoded).unwrap(), text);
+        }
+
+        assert_eq!(encode_component("reverse_string"), "reverse_string");
+        assert_eq!(encode_component("my-server"), "my-server");
+        assert_eq!(encode_component("get__docs"), "get-x5f-x5fdocs");
+        assert!(decode_component("bad-xzz").is_err());
+    }
+
+    #[test]
+    fn test_version_encoding() {
+        assert_eq!(encode_version("1.0"), "v1_0");
+        assert_eq!(encode_version("1.0.0-beta.1"), "v1_0_0-beta_1");
+        for version in ["1.0", "2.1.3", "1_0", "1.0.0-beta.1"] {
+            let component = decode_component(&encode_version(version)).unwrap();
+            assert_eq!(version_from_component(&component).unwrap(), version);
+        }
+    }
+
+    #[test]
+    fn test_long_names_are_shortened_and_resolvable() {
+        let components: Vec<String> = ["user", "alice", "a_rather_long_package_name"]
+            .iter()
+            .map(|s| s.to_string())
+            .chain(["an_even_longer_tool_name_that_goes_on".to_string()])
+            .collect();
+        let full = components.join(SEPARATOR);
+        assert!(full.len() > MAX_MCP_NAME_LEN);
+
+        let short = join(&components);
+        assert!(short.len() <= MAX_MCP_NAME_LEN);
+        assert_eq!(short, join(&components));
+        assert!(short.starts_with("user__alice__a_rather_long"));
+        assert_eq!(split(&short).unwrap(), components);
+
+        let mut other = components.clone();
+        other[3].push('2');
+        assert_ne!(join(&other), short);
+    }
+
+    #[test]
+    fn test_unknown_and_ambiguous_short_names_fail() {
+        let unknown = format!("user__alice__utils__tool{}{}", HASH_MARKER, "0".repeat(HASH_LEN));
+        assert!(split(&unknown).is_err());
+
+        // A real name that only looks shortened resolves once generated
+        let lookalike = vec!["bin".to_string(), unknown["user__".len()..].replace("__", "-")];
+        let name = join(&lookalike);
+        assert_eq!(split(&name).unwrap(), lookalike);
+
+        let short = "user__carol__shared-h0123456789abcdef01234567".to_string();
+        ALIASES.write().unwrap().insert(
+            short.clone(),
+            vec!["user__carol__a".to_string(), "user__carol__b".to_string()],
+        );
+        let err = split(&short).unwrap_err();
+        assert!(err.to_string().contains("ambiguous"), "{}", err);
+    }
+}
@@ -0,0 +1,362 @@
+/// Server-to-client messaging for the MCP session
+///
+/// The transport (stdio) owns the writer; everything else in the server holds
+/// a cloned `McpPeer` and uses it to push notifications and requests back to
+/// the connected client. When no transport is attached (CLI mode, tests)
+/// notifications are dropped and requests fail immediately.
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::atomic::{AtomicU64, AtomicU8, Ordering};
+use std::sync::{mpsc as std_mpsc, Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+/// Log severity levels as defined by the MCP logging capability (RFC 5424)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+    Debug,
+    Info,
+    Notice,
+    Warning,
+    Error,
+    Critical,
+    Alert,
+    Emergency,
+}
+
+impl LogLevel {
+    pub const ALL: [LogLevel; 8] = [
+        LogLevel::Debug,
+        LogLevel::Info,
+        LogLevel::Notice,
+        LogLevel::Warning,
+        LogLevel::Error,
+        LogLevel::Critical,
+        LogLevel::Alert,
+        LogLevel::Emergency,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LogLevel::Debug => "debug",
+            LogLevel::Info => "info",
+            LogLevel::Notice => "notice",
+            LogLevel::Warning => "warning",
+            LogLevel::Error => "error",
+            LogLevel::Critical => "critical",
+            LogLevel::Alert => "alert",
+            LogLevel::Emergency => "emergency",
+        }
+    }
+
+    fn from_u8(value: u8) -> Self {
+        Self::ALL
+            .get(value as usize)
+            .copied()
+            .unwrap_or(LogLevel::Emergency)
+    }
+}
+
+impl std::str::FromStr for LogLevel {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "debug" => Ok(LogLevel::Debug),
+            "info" => Ok(LogLevel::Info),
+            "notice" => Ok(LogLevel::Notice),
+            "warning" | "warn" => Ok(LogLevel::Warning),
+            "error" => Ok(LogLevel::Error),
+            "critical" => Ok(LogLevel::Critical),
+            "alert" => Ok(LogLevel::Alert),
+            "emergency" => Ok(LogLevel::Emergency),
+            _ => Err(anyhow!(
+                "Invalid log level '{}'. Valid levels: debug, info, notice, warning, error, critical, alert, emergency",
+                s
+            )),
+        }
+    }
+}
+
+impl fmt::Display for LogLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Handle for sending messages from the server to the connected client
+#[derive(Clone)]
+pub struct McpPeer {
+    inner: Arc<PeerInner>,
+}
+
+struct PeerInner {
+    /// Outbound message queue drained by the transport writer
+    outbound: Mutex<Option<mpsc::UnboundedSender<Value>>>,
+    /// Minimum level forwarded as `notifications/message`
+    min_level: AtomicU8,
+    /// Capabilities the client declared in `initialize`
+    client_capabilities: Mutex<Value>,
+    /// Server-initiated requests awaiting a response, keyed by request id.
+    /// Replies go through a std channel because waiters are TCL commands
+    /// running synchronously on the executor thread.
+    pending: Mutex<HashMap<u64, std_mpsc::Sender<Result<Value>>>>,
+    next_request_id: AtomicU64,
+}
+
+impl McpPeer {
+    /// Create a detached peer; messages are dropped until a transport attaches
+    pub fn new() -> Self {
+        Self {
+            inner: Arc::new(PeerInner {
+                outbound: Mutex::new(None),
+                min_level: AtomicU8::new(LogLevel::Info as u8),
+                client_capabilities: Mutex::new(Value::Null),
+                pending: Mutex::new(HashMap::new()),
+                next_request_id: AtomicU64::new(1),
+            }),
+        }
+    }
+
+    /// Attach the transport's outbound queue
+    pub fn attach(&self, outbound: mpsc::UnboundedSender<Value>) {
+        *self.inner.outbound.lock().unwrap() = Some(outbound);
+    }
+
+    /// Detach the transport; subsequent messages are dropped and requests
+    /// still awaiting a response fail
+    pub fn detach(&self) {
+        self.inner.outbound.lock().unwrap().take();
+        self.cancel_pending();
+    }
+
+    /// Fail all requests still awaiting a response
+    pub fn cancel_pending(&self) {
+        self.inner.pending.lock().unwrap().clear();
+    }
+
+    /// Whether a transport is currently attached
+    pub fn is_attached(&self) -> bool {
+        self.inner.outbound.lock().unwrap().is_some()
+    }
+
+    /// Queue a raw JSON-RPC message for the client
+    pub fn send(&self, message: Value) -> bool {
+        match self.inner.outbound.lock().unwrap().as_ref() {
+            Some(tx) => tx.send(message).is_ok(),
+            None => false,
+        }
+    }
+
+    /// Send a JSON-RPC notification to the client
+    pub fn notify(&self, method: &str, params: Value) -> bool {
+        self.send(json!({
+            "jsonrpc": "2.0",
+            "method": method,
+            "params": params
+        }))
+    }
+
+    /// Set the minimum level forwarded to the client (`logging/setLevel`)
+    pub fn set_log_level(&self, level: LogLevel) {
+        self.inner.min_level.store(level as u8, Ordering::Relaxed);
+    }
+
+    /// Current minimum log level
+    pub fn log_level(&self) -> LogLevel {
+        LogLevel::from_u8(self.inner.min_level.load(Ordering::Relaxed))
+    }
+
+    /// Record the capabilities the client declared in `initialize`
+    pub fn set_client_capabilities(&self, capabilities: Value) {
+        *self.inner.client_capabilities.lock().unwrap() = capabilities;
+    }
+
+    /// Whether the client declared the given capability (e.g. "sampling")
+    pub fn client_supports(&self, capability: &str) -> bool {
+        self.inner
+            .client_capabilities
+            .lock()
+            .unwrap()
+            .get(capability)
+            .is_some_and(|value| !value.is_null())
+    }
+
+    /// Send a request to the client and block until it responds
+    ///
+    /// Must not be called from an async task on the transport's runtime, since
+    /// the transport has to keep reading to deliver the response.
+    pub fn request(&self, method: &str, params: Value, timeout: Duration) -> Result<Value> {
+        let id = self.inner.next_request_id.fetch_add(1, Ordering::Relaxed);
+        let (tx, rx) = std_mpsc::channel();
+        self.inner.pending.lock().unwrap().insert(id, tx);
+
+        let sent = self.send(json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "method": method,
+            "params": params
+        }));
+        if !sent {
+            self.inner.pending.lock().unwrap().remove(&id);
+            return Err(anyhow!("{} failed: no client connected", method));
+        }
+
+        match rx.recv_timeout(timeout) {
+            Ok(result) => result.map_err(|e| anyhow!("{} failed: {}", method, e)),
+            Err(std_mpsc::RecvTimeoutError::Timeout) => {
+                self.inner.pending.lock().unwrap().remove(&id);
+                Err(anyhow!(
+                    "{} failed: no response within {}s",
+                    method,
+                    timeout.as_secs()
+                ))
+            }
+            Err(std_mpsc::RecvTimeoutError::Disconnected) => {
+                Err(anyhow!("{} failed: client disconnected", method))
+            }
+        }
+    }
+
+    /// Whether an incoming message is a response rather than a request or notification
+    pub fn is_response(message: &Value) -> bool {
+        message.get("method").is_none()
+            && message.get("id").is_some()
+            && (message.get("result").is_some() || message.get("error").is_some())
+    }
+
+    /// Route a client response to the request waiting for it.
+    /// Returns false if no request with that id is pending.
+    pub fn handle_response(&self, message: &Value) -> bool {
+        let Some(id) = message.get("id").and_then(Value::as_u64) else {
+            return false;
+        };
+        let Some(waiter) = self.inner.pending.lock().unwrap().remove(&id) else {
+            return false;
+        };
+
+        let result = match message.get("error") {
+            Some(error) => Err(anyhow!(
+                "{}",
+                error
+                    .get("message")
+                    .and_then(Value::as_str)
+                    .unwrap_or("unknown error")
+            )),
+            None => Ok(message.get("result").cloned().unwrap_or(Value::Null)),
+        };
+        let _ = waiter.send(result);
+        true
+    }
+
+    /// Send a `notifications/message` log entry if it passes the level filter
+    pub fn log(&self, level: LogLevel, logger: &str, data: impl Into<Value>) -> bool {
+        if level < self.log_level() {
+            return false;
+        }
+
+        self.notify(
+            "notifications/message",
+            json!({
+                "level": level,
+                "logger": logger,
+                "data": data.into()
+            }),
+        )
+    }
+}
+
+impl Default for McpPeer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for McpPeer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("McpPeer")
+            .field("attached", &self.is_attached())
+            .field("log_level", &self.log_level())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_log_level_ordering_and_parsing() {
+        assert!(LogLevel::Debug < LogLevel::Info);
+        assert!(LogLevel::Error > LogLevel::Warning);
+        assert_eq!("WARNING".parse::<LogLevel>().unwrap(), LogLevel::Warning);
+        assert!("verbose".parse::<LogLevel>().is_err());
+
+        for level in LogLevel::ALL {
+            assert_eq!(LogLevel::from_u8(level as u8), level);
+        }
+    }
+
+    #[test]
+    fn test_log_filtering() {
+        let peer = McpPeer::new();
+        let (tx, mut rx) = mpsc::unbounded_channel();
+
+        // Detached peers drop everything
+        assert!(!peer.log(LogLevel::Error, "test", "dropped"));
+
+        peer.attach(tx);
+        peer.set_log_level(LogLevel::Warning);
+        assert!(!peer.log(LogLevel::Info, "test", "filtered"));
+        assert!(peer.log(LogLevel::Error, "test", "delivered"));
+
+        let message = rx.try_recv().unwrap();
+        assert_eq!(message["method"], "notifications/message");
+        assert_eq!(message["params"]["level"], "error");
+        assert_eq!(message["params"]["logger"], "test");
+        assert_eq!(message["params"]["data"], "delivered");
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_request_round_trip() {
+        let peer = McpPeer::new();
+        assert!(peer
+            .request("ping", json!({}), Duration::from_secs(1))
+            .is_err());
+
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        peer.attach(tx);
+        peer.set_client_capabilities(json!({"sampling": {}}));
+        assert!(peer.client_supports("sampling"));
+        assert!(!peer.client_supports("elicitation"));
+
+        let requester = peer.clone();
+        let handle = std::thread::spawn(move || {
+            requester.request(
+                "sampling/createMessage",
+                json!({"maxTokens": 10}),
+                Duration::from_secs(5),
+            )
+        });
+
+        let request = rx.blocking_recv().unwrap();
+        assert_eq!(request["method"], "sampling/createMessage");
+        let response = json!({
+            "jsonrpc": "2.0",
+            "id": request["id"],
+            "result": {"content": {"type": "text", "text": "hi"}}
+        });
+        assert!(McpPeer::is_response(&response));
+        assert!(!McpPeer::is_response(&request));
+        assert!(peer.handle_response(&response));
+        assert!(!peer.handle_response(&response));
+
+        let result = handle.join().unwrap().unwrap();
+        assert_eq!(result["content"]["text"], "hi");
+    }
+}
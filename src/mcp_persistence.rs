@@ -8,15 +8,20 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use tokio::fs;
 
+use crate::atomic_file::{self, FileLock};
 use crate::mcp_client::McpServerConfig;
+use crate::migrations::{self, Format, MCP_INDEX_VERSION};
 use crate::platform_dirs;
+use crate::sqlite_store::SqliteStore;
+use crate::tool_store::StorageBackend;
 
 /// MCP Server Index containing all registered servers
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct McpServerIndex {
     /// Map of server ID to configuration
     pub servers: HashMap<String, McpServerEntry>,
-    /// Index version for future migrations
+    /// Format version, upgraded by `migrations` when it is behind
+    #[serde(default)]
     pub version: u32,
     /// Last time the index was updated
     pub last_updated: DateTime<Utc>,
@@ -37,9 +42,17 @@ pub struct McpServerEntry {
     pub metadata: HashMap<String, serde_json::Value>,
 }
 
+/// Where server configurations are kept
+enum McpBackend {
+    /// `mcp-index.json`
+    Json(std::path::PathBuf),
+    /// The `mcp_servers` table of the SQLite store
+    Sqlite(SqliteStore),
+}
+
 /// MCP Server persistence manager
 pub struct McpPersistence {
-    index_path: std::path::PathBuf,
+    backend: McpBackend,
     index: McpServerIndex,
 }
 
@@ -50,14 +63,41 @@ impl McpPersistence {
         platform_dirs::init_directories()?;
 
         let index_path = platform_dirs::mcp_index_path()?;
-        let index = Self::load_or_create_index(&index_path).await?;
+        let index = {
+            let _lock = atomic_file::lock(&index_path).await?;
+            Self::load_or_create_index(&index_path).await?
+        };
 
-        Ok(Self { index_path, index })
+        Ok(Self {
+            backend: McpBackend::Json(index_path),
+            index,
+        })
+    }
+
+    /// Open the server configurations of a storage backend
+    pub async fn open(backend: StorageBackend) -> Result<Self> {
+        match backend {
+            StorageBackend::Json => Self::new().await,
+            StorageBackend::Sqlite => {
+                let store = SqliteStore::new().await?;
+                let index = McpServerIndex {
+                    servers: store.mcp_servers()?,
+                    version: MCP_INDEX_VERSION,
+                    last_updated: Utc::now(),
+                };
+                Ok(Self {
+                    backend: McpBackend::Sqlite(store),
+                    index,
+                })
+            }
+        }
     }
 
-    /// Load index from disk or create a new one
-    async fn load_or_create_index(path: &std::path::Path) -> Result<McpServerIndex> {
+    /// Load index from disk or create a new one. An index in an older format
+    /// is upgraded first, and one from a newer server is refused.
+    pub(crate) async fn load_or_create_index(path: &std::path::Path) -> Result<McpServerIndex> {
         if path.exists() {
+            migrations::migrate_file(Format::McpIndex, path, false).await?;
             match fs::read_to_string(path).await {
                 Ok(content) => match serde_json::from_str::<McpServerIndex>(&content) {
                     Ok(index) => {
@@ -68,38 +108,76 @@ impl McpPersistence {
                         Ok(index)
                     }
                     Err(e) => {
-                        tracing::warn!("Failed to parse MCP index, creating new: {}", e);
+                        // Server configurations live only in the index, so
+                        // keep the unreadable file for manual recovery
+                        let backup = path.with_extension(format!(
+                            "json.corrupt-{}",
+                            Utc::now().format("%Y%m%dT%H%M%S")
+                        ));
+                        fs::rename(path, &backup).await?;
+                        tracing::warn!(
+                            "Failed to parse MCP index ({}), moved it to {} and created a new one",
+                            e,
+                            backup.display()
+                        );
                         Ok(McpServerIndex {
-                            version: 1,
+                            version: MCP_INDEX_VERSION,
                             last_updated: Utc::now(),
                             ..Default::default()
                         })
                     }
                 },
-                Err(e) => {
-                    tracing::warn!("Failed to read MCP index, creating new: {}", e);
-                    Ok(McpServerIndex {
-                        version: 1,
-                        last_updated: Utc::now(),
-                        ..Default::default()
-                    })
-                }
+                // Replacing an index that exists but can't be read would
+                // lose every server on the next save
+                Err(e) => Err(anyhow::anyhow!(
+                    "Failed to read MCP index {}: {}",
+                    path.display(),
+                    e
+                )),
             }
         } else {
             tracing::info!("No existing MCP index found, creating new");
             Ok(McpServerIndex {
-                version: 1,
+                version: MCP_INDEX_VERSION,
                 last_updated: Utc::now(),
                 ..Default::default()
             })
         }
     }
 
-    /// Save the current index to disk
-    async fn save_index(&self) -> Result<()> {
-        let json = serde_json::to_string_pretty(&self.index)?;
-        fs::write(&self.index_path, json).await?;
-        tracing::debug!("Saved MCP index to {}", self.index_path.display());
+    /// Lock the index against other server processes and re-read it, so
+    /// that servers they added or removed are kept. The SQLite store needs
+    /// no lock, as each server is written on its own.
+    async fn lock_index(&mut self) -> Result<Option<FileLock>> {
+        match &self.backend {
+            McpBackend::Json(index_path) => {
+                let lock = atomic_file::lock(index_path).await?;
+                self.index = Self::load_or_create_index(index_path).await?;
+                Ok(Some(lock))
+            }
+            McpBackend::Sqlite(store) => {
+                self.index.servers = store.mcp_servers()?;
+                Ok(None)
+            }
+        }
+    }
+
+    /// Store the indexed entry of a server, or its removal; callers hold
+    /// the index lock
+    async fn save_entry(&self, id: &str) -> Result<()> {
+        match &self.backend {
+            McpBackend::Json(index_path) => {
+                let json = serde_json::to_string_pretty(&self.index)?;
+                atomic_file::write(index_path, json.as_bytes()).await?;
+                tracing::debug!("Saved MCP index to {}", index_path.display());
+            }
+            McpBackend::Sqlite(store) => match self.index.servers.get(id) {
+                Some(entry) => store.save_mcp_server(id, entry)?,
+                None => {
+                    store.remove_mcp_server(id)?;
+                }
+            },
+        }
         Ok(())
     }
 
@@ -110,6 +188,7 @@ impl McpPersistence {
         config: McpServerConfig,
         auto_start: bool,
     ) -> Result<()> {
+        let _lock = self.lock_index().await?;
         let now = Utc::now();
 
         // Create a config with the provided auto_start setting
@@ -138,7 +217,7 @@ impl McpPersistence {
 
         self.index.servers.insert(id.clone(), entry);
         self.index.last_updated = now;
-        self.save_index().await?;
+        self.save_entry(&id).await?;
 
         tracing::info!("Saved MCP server configuration: {}", id);
         Ok(())
@@ -146,9 +225,10 @@ impl McpPersistence {
 
     /// Remove a server configuration
     pub async fn remove_server(&mut self, id: &str) -> Result<bool> {
+        let _lock = self.lock_index().await?;
         if self.index.servers.remove(id).is_some() {
             self.index.last_updated = Utc::now();
-            self.save_index().await?;
+            self.save_entry(id).await?;
             tracing::info!("Removed MCP server configuration: {}", id);
             Ok(true)
         } else {
@@ -187,11 +267,12 @@ impl McpPersistence {
         key: String,
         value: serde_json::Value,
     ) -> Result<bool> {
+        let _lock = self.lock_index().await?;
         if let Some(entry) = self.index.servers.get_mut(id) {
             entry.metadata.insert(key, value);
             entry.updated_at = Utc::now();
             self.index.last_updated = Utc::now();
-            self.save_index().await?;
+            self.save_entry(id).await?;
             Ok(true)
         } else {
             Ok(false)
@@ -280,4 +361,22 @@ mod tests {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_corrupt_index_is_kept() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let index_path = temp_dir.path().join("mcp-index.json");
+        std::fs::write(&index_path, "{\"servers\": {\"test-ser")?;
+
+        let index = McpPersistence::load_or_create_index(&index_path).await?;
+        assert!(index.servers.is_empty());
+
+        let backups: Vec<String> = std::fs::read_dir(temp_dir.path())?
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        assert_eq!(backups.len(), 1);
+        assert!(backups[0].starts_with("mcp-index.json.corrupt-"));
+
+        Ok(())
+    }
 }
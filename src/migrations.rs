@@ -0,0 +1,302 @@
+/// Versioned migrations of the persisted formats
+///
+/// Tool files, the tool index and the MCP server index each record the
+/// version of their format. Files written by an older server are upgraded
+/// one version at a time when they are loaded: each step edits the raw JSON,
+/// so old files never have to deserialize into the current structs. The
+/// original is kept next to the upgraded file as `<name>.v<N>-backup`.
+/// Files from a newer server are refused rather than reset.
+use anyhow::{anyhow, Result};
+use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
+use tokio::fs;
+
+use crate::atomic_file;
+use crate::persistence;
+
+/// Current format of tool files, recorded as `metadata.format_version`
+pub const TOOL_FILE_VERSION: u32 = 1;
+
+/// Current format of the tool index, recorded as `version`
+pub const TOOL_INDEX_VERSION: u32 = 1;
+
+/// Current format of the MCP server index, recorded as `version`
+pub const MCP_INDEX_VERSION: u32 = 1;
+
+/// A persisted format with its own version
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    ToolFile,
+    ToolIndex,
+    McpIndex,
+}
+
+/// One upgrade step, from `from` to `from + 1`
+pub struct Migration {
+    pub from: u32,
+    pub description: &'static str,
+    apply: fn(&mut Value),
+}
+
+const TOOL_FILE_MIGRATIONS: &[Migration] = &[Migration {
+    from: 0,
+    description: "Record the format version in the tool metadata",
+    apply: |_| {},
+}];
+
+const TOOL_INDEX_MIGRATIONS: &[Migration] = &[Migration {
+    from: 0,
+    description: "Add version pins and quarantine records",
+    apply: |index| {
+        for field in ["version_pins", "quarantined"] {
+            if index.get(field).is_none() {
+                index[field] = json!({});
+            }
+        }
+    },
+}];
+
+const MCP_INDEX_MIGRATIONS: &[Migration] = &[Migration {
+    from: 0,
+    description: "Add auto-start flags and metadata to server entries",
+    apply: |index| {
+        if let Some(servers) = index.get_mut("servers").and_then(Value::as_object_mut) {
+            for entry in servers.values_mut() {
+                let auto_start = entry
+                    .pointer("/config/auto_start")
+                    .cloned()
+                    .unwrap_or(Value::Bool(false));
+                if let Some(entry) = entry.as_object_mut() {
+                    entry.entry("auto_start").or_insert(auto_start);
+                    entry.entry("metadata").or_insert_with(|| json!({}));
+                }
+            }
+        }
+    },
+}];
+
+impl Format {
+    /// The version this server writes
+    pub fn current(self) -> u32 {
+        match self {
+            Format::ToolFile => TOOL_FILE_VERSION,
+            Format::ToolIndex => TOOL_INDEX_VERSION,
+            Format::McpIndex => MCP_INDEX_VERSION,
+        }
+    }
+
+    fn migrations(self) -> &'static [Migration] {
+        match self {
+            Format::ToolFile => TOOL_FILE_MIGRATIONS,
+            Format::ToolIndex => TOOL_INDEX_MIGRATIONS,
+            Format::McpIndex => MCP_INDEX_MIGRATIONS,
+        }
+    }
+
+    /// JSON pointer to the version field
+    fn version_pointer(self) -> &'static str {
+        match self {
+            Format::ToolFile => "/metadata/format_version",
+            Format::ToolIndex | Format::McpIndex => "/version",
+        }
+    }
+
+    /// Version of a file in this format; files from before versioning are 0
+    pub fn version_of(self, value: &Value) -> u32 {
+        value
+            .pointer(self.version_pointer())
+            .and_then(Value::as_u64)
+            .map_or(0, |version| version as u32)
+    }
+
+    fn set_version(self, value: &mut Value, version: u32) {
+        match self {
+            Format::ToolFile => {
+                if let Some(metadata) = value.get_mut("metadata") {
+                    metadata["format_version"] = json!(version);
+                }
+            }
+            Format::ToolIndex | Format::McpIndex => value["version"] = json!(version),
+        }
+    }
+}
+
+/// Upgrade a file's JSON to the current version, returning the steps that
+/// were applied
+pub fn upgrade(format: Format, value: &mut Value) -> Result<Vec<&'static str>> {
+    let version = format.version_of(value);
+    check_supported(format, version)?;
+
+    let mut steps = Vec::new();
+    for migration in format.migrations().iter().filter(|m| m.from >= version) {
+        (migration.apply)(value);
+        format.set_version(value, migration.from + 1);
+        steps.push(migration.description);
+    }
+    Ok(steps)
+}
+
+fn check_supported(format: Format, version: u32) -> Result<()> {
+    if version > format.current() {
+        return Err(anyhow!(
+            "format version {} is newer than this server supports ({}); upgrade tcl-mcp-server to read it",
+            version,
+            format.current()
+        ));
+    }
+    Ok(())
+}
+
+/// An upgrade of one file, applied or planned
+#[derive(Debug, Clone)]
+pub struct FileMigration {
+    pub path: PathBuf,
+    pub from: u32,
+    pub to: u32,
+    pub steps: Vec<&'static str>,
+    /// Where the original was kept; `None` in a dry run
+    pub backup: Option<PathBuf>,
+}
+
+/// Upgrade a file on disk, keeping the original beside it. Files that are
+/// already current or don't parse are left alone; loaders deal with
+/// unparseable files.
+pub async fn migrate_file(
+    format: Format,
+    path: &Path,
+    dry_run: bool,
+) -> Result<Option<FileMigration>> {
+    let content = fs::read_to_string(path).await?;
+    let mut value: Value = match serde_json::from_str(&content) {
+        Ok(value) => value,
+        Err(_) => return Ok(None),
+    };
+    let from = format.version_of(&value);
+    let steps = upgrade(format, &mut value).map_err(|e| anyhow!("{}: {}", path.display(), e))?;
+    if steps.is_empty() {
+        return Ok(None);
+    }
+
+    let backup = if dry_run {
+        None
+    } else {
+        let backup = path.with_extension(format!("json.v{}-backup", from));
+        fs::copy(path, &backup).await?;
+        let json = serde_json::to_string_pretty(&value)?;
+        atomic_file::write(path, json.as_bytes()).await?;
+        tracing::info!(
+            "Migrated {} from format version {} to {}, original kept at {}",
+            path.display(),
+            from,
+            format.current(),
+            backup.display()
+        );
+        Some(backup)
+    };
+
+    Ok(Some(FileMigration {
+        path: path.to_path_buf(),
+        from,
+        to: format.current(),
+        steps,
+        backup,
+    }))
+}
+
+/// Upgrade the tool files and index of a JSON tool store. The index is
+/// upgraded last, so a store whose index is current needs no scan and an
+/// interrupted upgrade is picked up again. Callers hold the index lock.
+pub async fn migrate_tool_store(storage_dir: &Path, dry_run: bool) -> Result<Vec<FileMigration>> {
+    let index_path = storage_dir.join("index.json");
+    if index_path.exists() {
+        let content = fs::read_to_string(&index_path).await?;
+        if let Ok(index) = serde_json::from_str::<Value>(&content) {
+            let version = Format::ToolIndex.version_of(&index);
+            check_supported(Format::ToolIndex, version)
+                .map_err(|e| anyhow!("{}: {}", index_path.display(), e))?;
+            if version == TOOL_INDEX_VERSION {
+                return Ok(Vec::new());
+            }
+        }
+    } else if !storage_dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut migrated = Vec::new();
+    for file_path in persistence::tool_files(storage_dir).await? {
+        if let Some(migration) = migrate_file(Format::ToolFile, &file_path, dry_run).await? {
+            migrated.push(migration);
+        }
+    }
+    if index_path.exists() {
+        if let Some(migration) = migrate_file(Format::ToolIndex, &index_path, dry_run).await? {
+            migrated.push(migration);
+        }
+    }
+    Ok(migrated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_upgrade_steps_through_versions() -> Result<()> {
+        let mut index = json!({ "tools": {}, "last_updated": "2025-01-01T00:00:00Z" });
+        let steps = upgrade(Format::ToolIndex, &mut index)?;
+        assert_eq!(steps, vec!["Add version pins and quarantine records"]);
+        assert_eq!(index["version"], json!(TOOL_INDEX_VERSION));
+        assert_eq!(index["version_pins"], json!({}));
+
+        // A current file needs nothing
+        assert!(upgrade(Format::ToolIndex, &mut index)?.is_empty());
+
+        // A newer file is refused
+        index["version"] = json!(TOOL_INDEX_VERSION + 1);
+        assert!(upgrade(Format::ToolIndex, &mut index).is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_migrate_file_keeps_original() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let path = temp_dir.path().join("mcp-index.json");
+        let original = json!({
+            "servers": {
+                "files": {
+                    "config": { "id": "files", "auto_start": true },
+                    "added_at": "2025-01-01T00:00:00Z",
+                    "updated_at": "2025-01-01T00:00:00Z"
+                }
+            },
+            "last_updated": "2025-01-01T00:00:00Z"
+        });
+        std::fs::write(&path, original.to_string())?;
+
+        // A dry run reports the upgrade without touching the file
+        let planned = migrate_file(Format::McpIndex, &path, true).await?.unwrap();
+        assert_eq!((planned.from, planned.to), (0, MCP_INDEX_VERSION));
+        assert!(planned.backup.is_none());
+        assert_eq!(
+            serde_json::from_str::<Value>(&std::fs::read_to_string(&path)?)?,
+            original
+        );
+
+        let applied = migrate_file(Format::McpIndex, &path, false).await?.unwrap();
+        let backup = applied.backup.unwrap();
+        assert_eq!(
+            serde_json::from_str::<Value>(&std::fs::read_to_string(&backup)?)?,
+            original
+        );
+        let upgraded: Value = serde_json::from_str(&std::fs::read_to_string(&path)?)?;
+        assert_eq!(upgraded["version"], json!(MCP_INDEX_VERSION));
+        assert_eq!(upgraded["servers"]["files"]["auto_start"], json!(true));
+        assert_eq!(upgraded["servers"]["files"]["metadata"], json!({}));
+
+        assert!(migrate_file(Format::McpIndex, &path, false)
+            .await?
+            .is_none());
+        Ok(())
+    }
+}
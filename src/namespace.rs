@@ -2,31 +2,8 @@ use anyhow::{anyhow, Result};
 use serde::{Deserialize, Serialize};
 use std::fmt;
 
-/// MCP path separator
-const SEPARATOR: &str = "__";
-
-/// Parse an MCP-formatted path into components
-fn parse_path(path: &str) -> Vec<&str> {
-    path.split(SEPARATOR).collect()
-}
-
-/// Join components into an MCP-formatted path
-fn join_path(components: &[&str]) -> String {
-    components.join(SEPARATOR)
-}
-
-/// Format a version string for use in paths (dots to underscores)
-fn format_version(version: &str) -> String {
-    format!("v{}", version.replace('.', "_"))
-}
-
-/// Parse a version string from path format (underscores to dots)
-fn parse_version(version: &str) -> String {
-    version
-        .strip_prefix('v')
-        .unwrap_or(version)
-        .replace('_', ".")
-}
+use crate::mcp_name::{self, encode_component, encode_version, version_from_component};
+use crate::versioning;
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Namespace {
@@ -105,81 +82,154 @@ impl ToolPath {
         }
     }
 
-    /// Parse a tool path from MCP name format
-    /// Examples:
-    /// - "bin__tcl_execute"
-    /// - "sbin__tcl_tool_add"
-    /// - "mcp__filesystem__read_file__v1_0"
-    /// - "user__alice__utils__reverse_string__v1_0"
-    /// - "user__bob__math__calculate"
+    /// Parse a tool path in any supported form:
+    /// - MCP names: "bin__tcl_execute", "user__alice__utils__reverse_string__v1_0"
+    /// - Slash paths: "/bin/tcl_execute", "/mcp/filesystem/read_file:1.0",
+    ///   "/user/alice/utils/reverse_string:1.0"
+    /// - Slash paths without the user prefix: "/alice/utils/reverse_string:1.0"
+    ///
+    /// Bare tool names such as "reverse_string" carry no namespace and are
+    /// resolved against the known tools by `ToolRegistry::resolve`.
     pub fn parse(path: &str) -> Result<Self> {
-        Self::from_mcp_name(path)
+        let path = path.trim();
+        if let Some(slash_path) = path.strip_prefix('/') {
+            Self::from_slash_path(slash_path)
+                .map_err(|e| anyhow!("Invalid tool path '{}': {}", path, e))
+        } else if !Self::is_bare_name(path) {
+            Self::from_mcp_name(path)
+        } else {
+            Err(anyhow!(
+                "Tool path '{}' has no namespace (expected e.g. 'bin__{}' or '/bin/{}')",
+                path,
+                path,
+                path
+            ))
+        }
     }
 
-    /// Convert to MCP-compatible tool name using standardized path format
-    pub fn to_mcp_name(&self) -> String {
-        match &self.namespace {
-            Namespace::Bin => join_path(&["bin", &self.name]),
-            Namespace::Sbin => join_path(&["sbin", &self.name]),
-            Namespace::Docs => join_path(&["docs", &self.name]),
-            Namespace::Mcp(server) => {
-                if self.version == "latest" {
-                    join_path(&["mcp", server, &self.name])
-                } else {
-                    join_path(&["mcp", server, &self.name, &format_version(&self.version)])
-                }
+    /// Whether `parse` failed only because the input is a bare tool name
+    pub fn is_bare_name(path: &str) -> bool {
+        let path = path.trim();
+        !path.is_empty() && !path.starts_with('/') && !path.contains(mcp_name::SEPARATOR)
+    }
+
+    /// Parse the part of a slash path after the leading `/`. The last
+    /// component may carry a `:version` suffix.
+    fn from_slash_path(path: &str) -> Result<Self> {
+        let (path, version) = match path.rsplit_once(':') {
+            Some((path, version)) if !version.is_empty() && !version.contains('/') => {
+                (path, Some(version))
             }
-            Namespace::User(user) => {
-                if let Some(package) = &self.package {
-                    if self.version == "latest" {
-                        join_path(&["user", user, package, &self.name])
-                    } else {
-                        join_path(&["user", user, package, &self.name, &format_version(&self.version)])
-                    }
-                } else {
-                    join_path(&["user", user, &self.name])
-                }
+            _ => (path, None),
+        };
+        let parts: Vec<&str> = path.split('/').collect();
+        if parts.iter().any(|part| part.is_empty()) {
+            return Err(anyhow!("empty path component"));
+        }
+        let versioned = |path: Self| match version {
+            Some(version) => Ok(Self { version: version.to_string(), ..path }),
+            None => Ok(path),
+        };
+
+        match parts.as_slice() {
+            ["bin" | "sbin" | "docs", _] if version.is_some() => {
+                Err(anyhow!("system tools are not versioned"))
+            }
+            ["bin", name] => Ok(Self::bin(*name)),
+            ["sbin", name] => Ok(Self::sbin(*name)),
+            ["docs", name] => Ok(Self::docs(*name)),
+            ["mcp", server, name] => versioned(Self::mcp(*server, *name, "latest")),
+            ["user", _, _] if version.is_some() => {
+                Err(anyhow!("user tools without a package are not versioned"))
             }
+            ["user", user, name] => Ok(Self {
+                namespace: Namespace::User(user.to_string()),
+                package: None,
+                name: name.to_string(),
+                version: "latest".to_string(),
+            }),
+            ["user", user, package, name] => versioned(Self::user(*user, *package, *name, "latest")),
+            [namespace, ..] if Self::is_namespace_keyword(namespace) => Err(anyhow!(
+                "wrong number of components for the {} namespace",
+                namespace
+            )),
+            [user, package, name] => versioned(Self::user(*user, *package, *name, "latest")),
+            _ => Err(anyhow!(
+                "expected /<namespace>/.../<name>[:version] or /<user>/<package>/<name>[:version]"
+            )),
         }
     }
 
+    fn is_namespace_keyword(text: &str) -> bool {
+        matches!(text, "bin" | "sbin" | "docs" | "mcp" | "user")
+    }
+
+    /// Convert to MCP-compatible tool name using standardized path format.
+    /// Components are escaped so the name parses back to the same path, and
+    /// names over 64 characters are shortened (see `mcp_name`).
+    pub fn to_mcp_name(&self) -> String {
+        let name = encode_component(&self.name);
+        let mut components = match &self.namespace {
+            Namespace::Bin => vec!["bin".to_string(), name],
+            Namespace::Sbin => vec!["sbin".to_string(), name],
+            Namespace::Docs => vec!["docs".to_string(), name],
+            Namespace::Mcp(server) => vec!["mcp".to_string(), encode_component(server), name],
+            Namespace::User(user) => match &self.package {
+                Some(package) => vec!["user".to_string(), encode_component(user), encode_component(package), name],
+                None => vec!["user".to_string(), encode_component(user), name],
+            },
+        };
+
+        // User tools without a package have no version component
+        let versioned = !matches!((&self.namespace, &self.package), (Namespace::User(_), None))
+            && !self.is_system();
+        if versioned && self.version != "latest" {
+            components.push(encode_version(&self.version));
+        }
+
+        mcp_name::join(&components)
+    }
+
     /// Convert from MCP tool name back to ToolPath using standardized parsing
     pub fn from_mcp_name(mcp_name: &str) -> Result<Self> {
-        let parts = parse_path(mcp_name);
+        let parts = mcp_name::split(mcp_name)
+            .map_err(|e| anyhow!("Invalid MCP path format: {} ({})", mcp_name, e))?;
+        let parts: Vec<&str> = parts.iter().map(String::as_str).collect();
+        let invalid = |part: &str| part.is_empty() || part == "_";
 
         match parts.as_slice() {
             ["bin", name] => {
-                if name.is_empty() || *name == "_" {
+                if invalid(name) {
                     return Err(anyhow!("Tool name cannot be empty or just underscore"));
                 }
                 Ok(Self::bin(name.to_string()))
             }
             ["sbin", name] => {
-                if name.is_empty() || *name == "_" {
+                if invalid(name) {
                     return Err(anyhow!("Tool name cannot be empty or just underscore"));
                 }
                 Ok(Self::sbin(name.to_string()))
             }
             ["docs", name] => {
-                if name.is_empty() || *name == "_" {
+                if invalid(name) {
                     return Err(anyhow!("Tool name cannot be empty or just underscore"));
                 }
                 Ok(Self::docs(name.to_string()))
             }
             ["mcp", server, name] => {
-                if name.is_empty() || server.is_empty() || *name == "_" || *server == "_" {
+                if invalid(name) || invalid(server) {
                     return Err(anyhow!("MCP tool name and server cannot be empty or just underscore"));
                 }
                 Ok(Self::mcp(server.to_string(), name.to_string(), "latest".to_string()))
             }
             ["mcp", server, name, version] if version.starts_with('v') => {
-                if name.is_empty() || server.is_empty() || *name == "_" || *server == "_" {
+                if invalid(name) || invalid(server) {
                     return Err(anyhow!("MCP tool name and server cannot be empty or just underscore"));
                 }
-                Ok(Self::mcp(server.to_string(), name.to_string(), parse_version(version)))
+                Ok(Self::mcp(server.to_string(), name.to_string(), version_from_component(version).unwrap_or_default()))
             }
             ["user", user, name] => {
-                if name.is_empty() || user.is_empty() || *name == "_" || *user == "_" {
+                if invalid(name) || invalid(user) {
                     return Err(anyhow!("User tool name and user cannot be empty or just underscore"));
                 }
                 Ok(Self {
@@ -190,26 +240,16 @@ impl ToolPath {
                 })
             }
             ["user", user, package, name] => {
-                if name.is_empty() || user.is_empty() || package.is_empty() || *name == "_" || *user == "_" || *package == "_" {
+                if invalid(name) || invalid(user) || invalid(package) {
                     return Err(anyhow!("User tool name, user, and package cannot be empty or just underscore"));
                 }
-                Ok(Self {
-                    namespace: Namespace::User(user.to_string()),
-                    package: Some(package.to_string()),
-                    name: name.to_string(),
-                    version: "latest".to_string(),
-                })
+                Ok(Self::user(*user, *package, *name, "latest"))
             }
             ["user", user, package, name, version] if version.starts_with('v') => {
-                if name.is_empty() || user.is_empty() || package.is_empty() || *name == "_" || *user == "_" || *package == "_" {
+                if invalid(name) || invalid(user) || invalid(package) {
                     return Err(anyhow!("User tool name, user, and package cannot be empty or just underscore"));
                 }
-                Ok(Self {
-                    namespace: Namespace::User(user.to_string()),
-                    package: Some(package.to_string()),
-                    name: name.to_string(),
-                    version: parse_version(version),
-                })
+                Ok(Self::user(*user, *package, *name, version_from_component(version).unwrap_or_default()))
             }
             _ => Err(anyhow!("Invalid MCP path format: {}", mcp_name)),
         }
@@ -227,6 +267,25 @@ impl ToolPath {
     pub fn is_mcp(&self) -> bool {
         matches!(self.namespace, Namespace::Mcp(_))
     }
+
+    /// The same tool at another version
+    pub fn with_version(&self, version: impl Into<String>) -> Self {
+        Self {
+            version: version.into(),
+            ..self.clone()
+        }
+    }
+
+    /// Check if both paths name the same tool, whatever their versions
+    pub fn same_tool(&self, other: &ToolPath) -> bool {
+        self.namespace == other.namespace && self.package == other.package && self.name == other.name
+    }
+
+    /// The version as semver, reading `1.2` as `1.2.0`; `None` for `latest`
+    /// and other non-semver versions
+    pub fn semver(&self) -> Option<semver::Version> {
+        versioning::parse_version(&self.version)
+    }
 }
 
 impl fmt::Display for ToolPath {
@@ -287,6 +346,9 @@ mod tests {
             ToolPath::sbin("tcl_tool_add"),
             ToolPath::user("alice", "utils", "reverse_string", "1.0"),
             ToolPath::user("bob", "math", "calculate", "latest"),
+            ToolPath::mcp("my-server", "get__docs", "latest"),
+            ToolPath::mcp("files", "_hidden", "2.0.1"),
+            ToolPath::user("carol", "text.tools", "trim_", "1.0.0-beta"),
         ];
 
         for path in paths {
@@ -295,4 +357,104 @@ mod tests {
             assert_eq!(path, parsed);
         }
     }
+
+    #[test]
+    fn test_escaped_and_shortened_names() {
+        assert_eq!(
+            ToolPath::mcp("my-server", "get__docs", "latest").to_mcp_name(),
+            "mcp__my-server__get-x5f-x5fdocs"
+        );
+
+        let long = ToolPath::user(
+            "alice",
+            "document_processing",
+            "convert_markdown_to_formatted_html_with_toc",
+            "2.0",
+        );
+        let mcp_name = long.to_mcp_name();
+        assert!(mcp_name.len() <= crate::mcp_name::MAX_MCP_NAME_LEN);
+        assert_eq!(mcp_name, long.to_mcp_name());
+        assert_eq!(ToolPath::from_mcp_name(&mcp_name).unwrap(), long);
+    }
+
+    #[test]
+    fn test_parse_all_namespace_forms() {
+        let cases = vec![
+            ("bin__tcl_execute", ToolPath::bin("tcl_execute")),
+            ("sbin__tool_add", ToolPath::sbin("tool_add")),
+            ("docs__molt_book", ToolPath::docs("molt_book")),
+            (
+                "mcp__context7__resolve-library-id",
+                ToolPath::mcp("context7", "resolve-library-id", "latest"),
+            ),
+            (
+                "mcp__myserver__tool__v1_2_3",
+                ToolPath::mcp("myserver", "tool", "1.2.3"),
+            ),
+            (
+                "user__alice__my_tool",
+                ToolPath {
+                    namespace: Namespace::User("alice".to_string()),
+                    package: None,
+                    name: "my_tool".to_string(),
+                    version: "latest".to_string(),
+                },
+            ),
+            (
+                "user__alice__utils__helper",
+                ToolPath::user("alice", "utils", "helper", "latest"),
+            ),
+            (
+                "user__bob__math__calc__v2_0",
+                ToolPath::user("bob", "math", "calc", "2.0"),
+            ),
+        ];
+
+        for (mcp_name, path) in cases {
+            assert_eq!(ToolPath::parse(mcp_name).unwrap(), path, "{}", mcp_name);
+            assert_eq!(path.to_mcp_name(), mcp_name);
+        }
+    }
+
+    #[test]
+    fn test_parse_slash_paths() {
+        assert_eq!(
+            ToolPath::parse("/bin/tcl_execute").unwrap(),
+            ToolPath::bin("tcl_execute")
+        );
+        assert_eq!(
+            ToolPath::parse("/mcp/filesystem/read_file:1.0").unwrap(),
+            ToolPath::mcp("filesystem", "read_file", "1.0")
+        );
+        assert_eq!(
+            ToolPath::parse("/user/alice/utils/reverse_string:1.0").unwrap(),
+            ToolPath::user("alice", "utils", "reverse_string", "1.0")
+        );
+        assert_eq!(
+            ToolPath::parse("/alice/utils/reverse_string:1.0").unwrap(),
+            ToolPath::user("alice", "utils", "reverse_string", "1.0")
+        );
+        assert_eq!(
+            ToolPath::parse("/alice/utils/get__docs").unwrap(),
+            ToolPath::user("alice", "utils", "get__docs", "latest")
+        );
+        assert_eq!(
+            ToolPath::parse("/xmcp/context7/get_docs").unwrap(),
+            ToolPath::user("xmcp", "context7", "get_docs", "latest")
+        );
+
+        assert!(ToolPath::parse("/bin/tcl_execute:1.0").is_err());
+        assert!(ToolPath::parse("/user/alice/tool:1.0").is_err());
+        assert!(ToolPath::parse("/mcp/only_server").is_err());
+        assert!(ToolPath::parse("/alice//tool").is_err());
+    }
+
+    #[test]
+    fn test_bare_names() {
+        assert!(ToolPath::is_bare_name("reverse_string"));
+        assert!(!ToolPath::is_bare_name("bin__tcl_execute"));
+        assert!(!ToolPath::is_bare_name("/bin/tcl_execute"));
+        let err = ToolPath::parse("tcl_execute").unwrap_err();
+        assert!(err.to_string().contains("no namespace"));
+    }
 }
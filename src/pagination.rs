@@ -0,0 +1,148 @@
+/// Cursor-based pagination for tool listings
+///
+/// Items are ordered by a unique key and a cursor records the last key on
+/// the previous page, so paging stays consistent when tools are added or
+/// removed between requests. Cursors are opaque to clients.
+use anyhow::{anyhow, Result};
+
+/// Page size used when none is configured
+pub const DEFAULT_PAGE_SIZE: usize = 100;
+
+/// Prefix identifying the cursor format
+const CURSOR_PREFIX: &str = "tc1.";
+
+/// One page of a listing
+#[derive(Debug, Clone, PartialEq)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    /// Cursor for the following page, if there is one
+    pub next_cursor: Option<String>,
+    /// Number of items across all pages
+    pub total: usize,
+}
+
+/// Encode the key of the last item on a page as a cursor
+pub fn encode_cursor(last_key: &str) -> String {
+    let hex: String = last_key.bytes().map(|b| format!("{:02x}", b)).collect();
+    format!("{}{}", CURSOR_PREFIX, hex)
+}
+
+/// Decode a cursor produced by `encode_cursor`
+pub fn decode_cursor(cursor: &str) -> Result<String> {
+    let invalid = || anyhow!("Invalid cursor: {}", cursor);
+
+    let hex = cursor.strip_prefix(CURSOR_PREFIX).ok_or_else(invalid)?;
+    if hex.len() % 2 != 0 {
+        return Err(invalid());
+    }
+
+    let bytes = (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16))
+        .collect::<std::result::Result<Vec<u8>, _>>()
+        .map_err(|_| invalid())?;
+    String::from_utf8(bytes).map_err(|_| invalid())
+}
+
+/// Sort items by key and return the page that starts after `cursor` (if
+/// any) and a further `offset` items. Keys must be unique, or items sharing
+/// a key across a page boundary would be skipped. A `limit` of 0 is an
+/// error.
+pub fn paginate<T, K>(
+    mut items: Vec<T>,
+    key: K,
+    cursor: Option<&str>,
+    offset: usize,
+    limit: usize,
+) -> Result<Page<T>>
+where
+    K: Fn(&T) -> &str,
+{
+    if limit == 0 {
+        return Err(anyhow!("Page limit must be at least 1"));
+    }
+
+    items.sort_by(|a, b| key(a).cmp(key(b)));
+    let total = items.len();
+
+    let start = match cursor {
+        Some(cursor) => {
+            let after = decode_cursor(cursor)?;
+            items.partition_point(|item| key(item) <= after.as_str())
+        }
+        None => 0,
+    };
+    let start = start.saturating_add(offset).min(total);
+    let end = start.saturating_add(limit).min(total);
+
+    let mut items: Vec<T> = items.into_iter().skip(start).take(end - start).collect();
+    let next_cursor = if end < total {
+        items.last().map(|item| encode_cursor(key(item)))
+    } else {
+        None
+    };
+    items.shrink_to_fit();
+
+    Ok(Page {
+        items,
+        next_cursor,
+        total,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(count: usize) -> Vec<String> {
+        (0..count).rev().map(|i| format!("tool_{:02}", i)).collect()
+    }
+
+    #[test]
+    fn test_cursor_round_trip() {
+        let cursor = encode_cursor("user__alice__utils__reverse");
+        assert!(!cursor.contains("alice"));
+        assert_eq!(
+            decode_cursor(&cursor).unwrap(),
+            "user__alice__utils__reverse"
+        );
+        assert!(decode_cursor("garbage").is_err());
+        assert!(decode_cursor("tc1.zz").is_err());
+    }
+
+    #[test]
+    fn test_pages_cover_all_items_once() {
+        let mut seen = Vec::new();
+        let mut cursor = None;
+        loop {
+            let page = paginate(names(25), |s| s.as_str(), cursor.as_deref(), 0, 10).unwrap();
+            assert_eq!(page.total, 25);
+            seen.extend(page.items);
+            match page.next_cursor {
+                Some(next) => cursor = Some(next),
+                None => break,
+            }
+        }
+        let mut expected = names(25);
+        expected.sort();
+        assert_eq!(seen, expected);
+    }
+
+    #[test]
+    fn test_cursor_is_stable_across_changes() {
+        let first = paginate(names(10), |s| s.as_str(), None, 0, 4).unwrap();
+        assert_eq!(first.items.last().unwrap(), "tool_03");
+
+        // Removing an item already returned must not shift the next page
+        let mut changed = names(10);
+        changed.retain(|name| name != "tool_01");
+        let second = paginate(changed, |s| s.as_str(), first.next_cursor.as_deref(), 0, 4).unwrap();
+        assert_eq!(second.items.first().unwrap(), "tool_04");
+
+        let offset = paginate(names(10), |s| s.as_str(), None, 8, 4).unwrap();
+        assert_eq!(offset.items, vec!["tool_08", "tool_09"]);
+        assert!(offset.next_cursor.is_none());
+
+        assert!(paginate(names(10), |s| s.as_str(), None, 0, 0).is_err());
+    }
+}
@@ -0,0 +1,205 @@
+/// Inferring tool parameters from scripts
+///
+/// `sbin__tcl_tool_add` infers a tool's parameters when they are omitted. A
+/// script that is a single `proc` definition takes the proc's arguments:
+/// arguments with a default are optional, typed by their default, and a call
+/// of the proc is appended so the tool returns its result. Any other script
+/// takes the variables it reads without setting them, as required strings.
+use anyhow::{anyhow, Result};
+use serde_json::{json, Value};
+
+use crate::namespace::ToolPath;
+use crate::tcl_lint;
+use crate::tcl_tools::ParameterDefinition;
+use crate::tool_registry::ToolRegistry;
+
+#[derive(Debug)]
+pub struct Inference {
+    pub parameters: Vec<ParameterDefinition>,
+    /// The script to store: for a proc, the definition followed by a call
+    pub script: String,
+    /// The proc the parameters came from; `None` for free variables
+    pub proc_name: Option<String>,
+}
+
+impl Inference {
+    /// Whether anything was inferred, so the result should be previewed
+    /// before it is saved
+    pub fn needs_review(&self) -> bool {
+        !self.parameters.is_empty() || self.proc_name.is_some()
+    }
+
+    /// The inferred tool, returned instead of saving it
+    pub fn preview(&self, path: &ToolPath) -> Value {
+        let source = match &self.proc_name {
+            Some(name) => format!("arguments of proc {}", name),
+            None => "free variables".to_string(),
+        };
+        json!({
+            "saved": false,
+            "path": path.to_string(),
+            "inferred_from": source,
+            "parameters": self.parameters,
+            "input_schema": ToolRegistry::generate_schema_from_parameters(&self.parameters),
+            "script": self.script,
+            "message": "Not saved. Call sbin__tcl_tool_add again with \"confirm\": true to save these parameters, or pass \"parameters\" to set them yourself",
+        })
+    }
+}
+
+pub fn infer(script: &str) -> Result<Inference> {
+    if let Some(proc) = tcl_lint::proc_definition(script) {
+        let parameters: Vec<ParameterDefinition> = proc
+            .args
+            .iter()
+            .map(|(name, default)| proc_parameter(name, default.as_deref()))
+            .collect();
+        let mut call = vec![proc.name.clone()];
+        call.extend(
+            parameters
+                .iter()
+                .filter(|param| param.name != "args")
+                .map(|param| variable(&param.name)),
+        );
+        // A trailing `args` takes the words of a list, which eval expands
+        let call = if parameters.last().is_some_and(|param| param.name == "args") {
+            format!("eval [list {}] $args", call.join(" "))
+        } else {
+            call.join(" ")
+        };
+        return Ok(Inference {
+            parameters,
+            script: format!("{}\n\n{}", script.trim_end(), call),
+            proc_name: Some(proc.name),
+        });
+    }
+
+    let variables = tcl_lint::free_variables(script)
+        .map_err(|diagnostic| anyhow!("Cannot infer parameters: {}", diagnostic))?;
+    let parameters = variables
+        .into_iter()
+        .filter(|name| name.chars().all(|c| c.is_alphanumeric() || c == '_'))
+        .map(|name| ParameterDefinition {
+            name,
+            description: String::new(),
+            required: true,
+            type_name: "string".to_string(),
+            completion: None,
+            default: None,
+        })
+        .collect();
+    Ok(Inference {
+        parameters,
+        script: script.to_string(),
+        proc_name: None,
+    })
+}
+
+/// A parameter for a proc argument, optional and typed by its default if it
+/// has one
+fn proc_parameter(name: &str, default: Option<&str>) -> ParameterDefinition {
+    let (type_name, description, default) = match (name, default) {
+        ("args", _) => ("string", "Further arguments, as a TCL list", json!("")),
+        (_, None) => {
+            return ParameterDefinition {
+                name: name.to_string(),
+                description: String::new(),
+                required: true,
+                type_name: "string".to_string(),
+                completion: None,
+                default: None,
+            }
+        }
+        (_, Some(default)) => {
+            if let Ok(n) = default.parse::<i64>() {
+                ("integer", "", json!(n))
+            } else if let Ok(f) = default.parse::<f64>() {
+                ("number", "", json!(f))
+            } else if let Ok(b) = default.parse::<bool>() {
+                ("boolean", "", json!(b))
+            } else {
+                ("string", "", json!(default))
+            }
+        }
+    };
+    ParameterDefinition {
+        name: name.to_string(),
+        description: description.to_string(),
+        required: false,
+        type_name: type_name.to_string(),
+        completion: None,
+        default: Some(default),
+    }
+}
+
+/// A `$` substitution of a variable
+fn variable(name: &str) -> String {
+    if name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        format!("${}", name)
+    } else {
+        format!("${{{}}}", name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_infer_from_proc() -> Result<()> {
+        let script = "proc repeat {text {times 2} {sep \" \"} args} {\n    string repeat $text$sep $times\n}\n";
+        let inference = infer(script)?;
+        assert_eq!(inference.proc_name.as_deref(), Some("repeat"));
+        assert!(inference
+            .script
+            .ends_with("}\n\neval [list repeat $text $times $sep] $args"));
+
+        let params: Vec<(&str, bool, &str, Option<&Value>)> = inference
+            .parameters
+            .iter()
+            .map(|p| {
+                (
+                    p.name.as_str(),
+                    p.required,
+                    p.type_name.as_str(),
+                    p.default.as_ref(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            params,
+            vec![
+                ("text", true, "string", None),
+                ("times", false, "integer", Some(&json!(2))),
+                ("sep", false, "string", Some(&json!(" "))),
+                ("args", false, "string", Some(&json!(""))),
+            ]
+        );
+
+        let preview = inference.preview(&ToolPath::user("alice", "text", "repeat", "latest"));
+        assert_eq!(preview["saved"], json!(false));
+        assert_eq!(preview["input_schema"]["required"], json!(["text"]));
+        assert_eq!(
+            preview["input_schema"]["properties"]["times"]["default"],
+            json!(2)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_infer_from_free_variables() -> Result<()> {
+        let inference = infer("set greeting \"Hello, $name\"\nreturn \"$greeting ($count)\"")?;
+        let names: Vec<&str> = inference
+            .parameters
+            .iter()
+            .map(|p| p.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["name", "count"]);
+        assert!(inference.parameters.iter().all(|p| p.required));
+        assert!(inference.proc_name.is_none());
+
+        assert!(!infer("return 42")?.needs_review());
+        assert!(infer("return [expr {1 +").is_err());
+        Ok(())
+    }
+}
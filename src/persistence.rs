@@ -1,14 +1,23 @@
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use tokio::fs;
 use uuid::Uuid;
 
+use crate::atomic_file::{self, FileLock};
+use crate::migrations::{self, Format, TOOL_FILE_VERSION, TOOL_INDEX_VERSION};
 use crate::namespace::{Namespace, ToolPath};
 use crate::platform_dirs;
+use crate::signing::{self, ToolSignature};
 use crate::tcl_tools::ToolDefinition;
+use crate::tool_history::{HistoryLog, RevisionAction, ToolRevision};
+use crate::versioning::{self, VersionSpec};
+
+/// Marks checksums that are SHA-256 content hashes
+pub(crate) const CHECKSUM_PREFIX: &str = "sha256:";
 
 /// Metadata associated with a persisted tool
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -17,7 +26,14 @@ pub struct ToolMetadata {
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
     pub checksum: String,
+    /// Revision of the tool this file holds
     pub file_version: u32,
+    /// Layout of the file, upgraded by `migrations` when it is behind
+    #[serde(default)]
+    pub format_version: u32,
+    /// Signature over `checksum`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<ToolSignature>,
 }
 
 /// A tool with its metadata for persistence
@@ -30,8 +46,61 @@ pub struct PersistedTool {
 /// Index for fast tool lookups
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct ToolIndex {
+    /// Layout of the index, upgraded by `migrations` when it is behind
+    #[serde(default)]
+    pub version: u32,
     pub tools: HashMap<String, ToolIndexEntry>,
     pub last_updated: DateTime<Utc>,
+    /// Pinned default versions, keyed by the unversioned MCP name
+    #[serde(default)]
+    pub version_pins: HashMap<String, String>,
+    /// Tools that failed verification, keyed like `tools`
+    #[serde(default)]
+    pub quarantined: HashMap<String, QuarantinedTool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuarantinedTool {
+    pub path: ToolPath,
+    pub reason: String,
+    /// Where the failing tool file was moved
+    pub file_path: PathBuf,
+    pub quarantined_at: DateTime<Utc>,
+}
+
+/// Result of verifying a stored tool against its checksum
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum Integrity {
+    /// The content matches its SHA-256 checksum
+    Verified { checksum: String },
+    /// Saved with a checksum from before SHA-256; verified from its next save
+    Unverified,
+    /// No longer matches its checksum; quarantined the next time it is loaded
+    Failed { reason: String },
+    /// Failed verification and was moved out of the store
+    Quarantined {
+        reason: String,
+        file_path: PathBuf,
+        quarantined_at: DateTime<Utc>,
+    },
+}
+
+impl Integrity {
+    /// Integrity of a stored tool from the result of verifying it
+    pub fn of_verification(verified: &std::result::Result<PersistedTool, String>) -> Self {
+        match verified {
+            Ok(persisted) if persisted.metadata.checksum.starts_with(CHECKSUM_PREFIX) => {
+                Integrity::Verified {
+                    checksum: persisted.metadata.checksum.clone(),
+                }
+            }
+            Ok(_) => Integrity::Unverified,
+            Err(reason) => Integrity::Failed {
+                reason: reason.clone(),
+            },
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -52,29 +121,20 @@ pub struct FilePersistence {
 impl FilePersistence {
     /// Create a new file persistence manager
     pub async fn new() -> Result<Self> {
-        let storage_dir = get_storage_directory()?;
-        let index_path = storage_dir.join("index.json");
-
-        // Create storage directory if it doesn't exist
-        fs::create_dir_all(&storage_dir).await?;
-
-        // Load or create index
-        let index = Self::load_or_create_index(&index_path).await?;
-
-        Ok(Self {
-            storage_dir,
-            index_path,
-            index,
-        })
+        Self::with_directory(get_storage_directory()?).await
     }
 
-    /// Create with custom storage directory (for testing)
-    #[cfg(test)]
+    /// Create with a custom storage directory, upgrading tool files and the
+    /// index written in an older format
     pub async fn with_directory(storage_dir: PathBuf) -> Result<Self> {
         let index_path = storage_dir.join("index.json");
 
         fs::create_dir_all(&storage_dir).await?;
-        let index = Self::load_or_create_index(&index_path).await?;
+        let index = {
+            let _lock = atomic_file::lock(&index_path).await?;
+            migrations::migrate_tool_store(&storage_dir, false).await?;
+            Self::load_index(&storage_dir, &index_path).await?
+        };
 
         Ok(Self {
             storage_dir,
@@ -83,23 +143,109 @@ impl FilePersistence {
         })
     }
 
-    async fn load_or_create_index(index_path: &Path) -> Result<ToolIndex> {
-        if index_path.exists() {
-            let content = fs::read_to_string(index_path).await?;
-            match serde_json::from_str(&content) {
-                Ok(index) => Ok(index),
+    /// Read the index, rebuilding it from the tool files if it can't be
+    /// parsed. The unreadable index is kept next to it, while an index from a
+    /// newer server is refused. Callers hold the index lock.
+    async fn load_index(storage_dir: &Path, index_path: &Path) -> Result<ToolIndex> {
+        if !index_path.exists() {
+            return Ok(ToolIndex {
+                version: TOOL_INDEX_VERSION,
+                ..Default::default()
+            });
+        }
+
+        let content = fs::read_to_string(index_path).await?;
+        let parsed = match serde_json::from_str::<serde_json::Value>(&content) {
+            Ok(mut value) => {
+                // The store is migrated on open, but an older server process
+                // may have rewritten the index since
+                migrations::upgrade(Format::ToolIndex, &mut value)
+                    .map_err(|e| anyhow!("{}: {}", index_path.display(), e))?;
+                serde_json::from_value(value)
+            }
+            Err(e) => Err(e),
+        };
+        match parsed {
+            Ok(index) => Ok(index),
+            Err(e) => {
+                let backup = index_path.with_extension(format!(
+                    "json.corrupt-{}",
+                    Utc::now().format("%Y%m%dT%H%M%S")
+                ));
+                fs::rename(index_path, &backup).await?;
+                let index = Self::rebuild_index(storage_dir).await?;
+                let json = serde_json::to_string_pretty(&index)?;
+                atomic_file::write(index_path, json.as_bytes()).await?;
+                tracing::warn!(
+                    "Index file is corrupt ({}), moved it to {} and rebuilt the index from {} tool files. Version pins and quarantine records were not recovered",
+                    e,
+                    backup.display(),
+                    index.tools.len()
+                );
+                Ok(index)
+            }
+        }
+    }
+
+    /// Index of the tool files under the storage directory
+    async fn rebuild_index(storage_dir: &Path) -> Result<ToolIndex> {
+        let mut index = ToolIndex {
+            version: TOOL_INDEX_VERSION,
+            last_updated: Utc::now(),
+            ..Default::default()
+        };
+        for file_path in tool_files(storage_dir).await? {
+            match Self::read_persisted(&file_path).await {
+                Ok(Some(persisted)) => {
+                    index.tools.insert(
+                        persisted.tool.path.to_string(),
+                        ToolIndexEntry {
+                            path: persisted.tool.path,
+                            file_path,
+                            checksum: persisted.metadata.checksum,
+                            updated_at: persisted.metadata.updated_at,
+                        },
+                    );
+                }
+                Ok(None) => {}
                 Err(e) => {
-                    tracing::warn!("Failed to parse index file, creating new one: {}", e);
-                    Ok(ToolIndex::default())
+                    tracing::warn!(
+                        "Skipping unreadable tool file {}: {}",
+                        file_path.display(),
+                        e
+                    )
                 }
             }
-        } else {
-            Ok(ToolIndex::default())
         }
+        Ok(index)
     }
 
-    /// Save a tool to persistent storage
+    /// Lock the index against other server processes and re-read it, so
+    /// that changes they saved are kept. Hold the lock until the changed
+    /// index is saved.
+    async fn lock_index(&mut self) -> Result<FileLock> {
+        let lock = atomic_file::lock(&self.index_path).await?;
+        self.index = Self::load_index(&self.storage_dir, &self.index_path).await?;
+        Ok(lock)
+    }
+
+    /// Save a tool to persistent storage, recording the new revision in its
+    /// history
     pub async fn save_tool(&mut self, tool: &ToolDefinition) -> Result<()> {
+        self.write_revision(tool, RevisionAction::Save, None)
+            .await?;
+        Ok(())
+    }
+
+    /// Write the tool file and index entry, then append the revision to the
+    /// tool's history
+    async fn write_revision(
+        &mut self,
+        tool: &ToolDefinition,
+        action: RevisionAction,
+        restored_from: Option<u32>,
+    ) -> Result<ToolRevision> {
+        let _lock = self.lock_index().await?;
         let file_path = self.get_tool_file_path(&tool.path);
 
         // Create directory structure if needed
@@ -108,33 +254,60 @@ impl FilePersistence {
         }
 
         // Calculate checksum
-        let checksum = calculate_checksum(&tool.script);
+        let checksum = calculate_checksum(tool);
 
-        // Create persisted tool
+        // Keep the identity of a tool that is being overwritten
         let now = Utc::now();
+        let previous = Self::read_persisted(&file_path).await?;
+        let log = self.history_log(&tool.path);
+        let mut revisions = log.read().await?;
+        if revisions.is_empty() {
+            // Tools saved before history was kept start at revision 1
+            if let Some(previous) = &previous {
+                let seed = ToolRevision {
+                    revision: 1,
+                    action: RevisionAction::Save,
+                    saved_at: previous.metadata.updated_at,
+                    checksum: previous.metadata.checksum.clone(),
+                    restored_from: None,
+                    tool: previous.tool.clone(),
+                };
+                log.append(&seed).await?;
+                revisions.push(seed);
+            }
+        }
+        let revision = revisions.last().map_or(1, |last| last.revision + 1);
+
+        // Create persisted tool
         let persisted = PersistedTool {
             metadata: ToolMetadata {
-                id: Uuid::new_v4().to_string(),
-                created_at: now,
+                id: previous
+                    .as_ref()
+                    .map_or_else(|| Uuid::new_v4().to_string(), |p| p.metadata.id.clone()),
+                created_at: previous.as_ref().map_or(now, |p| p.metadata.created_at),
                 updated_at: now,
                 checksum: checksum.clone(),
-                file_version: 1,
+                file_version: revision,
+                format_version: TOOL_FILE_VERSION,
+                // A signature only covers the content it was made for
+                signature: None,
             },
             tool: tool.clone(),
         };
 
         // Write tool file
         let json = serde_json::to_string_pretty(&persisted)?;
-        fs::write(&file_path, json).await?;
+        atomic_file::write(&file_path, json.as_bytes()).await?;
 
         // Update index
         let path_key = tool.path.to_string();
+        self.index.quarantined.remove(&path_key);
         self.index.tools.insert(
             path_key,
             ToolIndexEntry {
                 path: tool.path.clone(),
                 file_path: file_path.clone(),
-                checksum,
+                checksum: checksum.clone(),
                 updated_at: now,
             },
         );
@@ -143,45 +316,71 @@ impl FilePersistence {
         // Save index
         self.save_index().await?;
 
-        tracing::info!("Saved tool to {}", file_path.display());
-        Ok(())
-    }
+        let entry = ToolRevision {
+            revision,
+            action,
+            saved_at: now,
+            checksum,
+            restored_from,
+            tool: tool.clone(),
+        };
+        log.append(&entry).await?;
 
-    /// Load a tool from persistent storage
-    pub async fn load_tool(&self, path: &ToolPath) -> Result<Option<ToolDefinition>> {
-        let path_key = path.to_string();
+        tracing::info!(
+            "Saved tool to {} (revision {})",
+            file_path.display(),
+            revision
+        );
+        Ok(entry)
+    }
 
-        // Check index first
-        if let Some(entry) = self.index.tools.get(&path_key) {
-            if entry.file_path.exists() {
-                let content = fs::read_to_string(&entry.file_path).await?;
-                let persisted: PersistedTool = serde_json::from_str(&content)?;
-
-                // Verify checksum if desired
-                if persisted.metadata.checksum == entry.checksum {
-                    return Ok(Some(persisted.tool));
-                } else {
-                    tracing::warn!("Checksum mismatch for tool {}, file may be corrupted", path);
-                }
-            }
+    async fn read_persisted(file_path: &Path) -> Result<Option<PersistedTool>> {
+        if !file_path.exists() {
+            return Ok(None);
         }
+        let content = fs::read_to_string(file_path).await?;
+        Ok(Some(parse_tool_file(&content)?))
+    }
 
-        // Fallback: try to load directly from expected path
-        let file_path = self.get_tool_file_path(path);
-        if file_path.exists() {
-            let content = fs::read_to_string(&file_path).await?;
-            let persisted: PersistedTool = serde_json::from_str(&content)?;
-            return Ok(Some(persisted.tool));
+    /// Load a tool with its metadata, verifying its checksum
+    pub async fn load_persisted(&self, path: &ToolPath) -> Result<Option<PersistedTool>> {
+        let entry = self.index.tools.get(&path.to_string());
+        let file_path = match entry {
+            Some(entry) if entry.file_path.exists() => entry.file_path.clone(),
+            // Fallback: try to load directly from expected path
+            _ => self.get_tool_file_path(path),
+        };
+        if !file_path.exists() {
+            return Ok(None);
         }
 
-        Ok(None)
+        let expected = entry.map(|entry| entry.checksum.as_str());
+        match Self::verify(&file_path, expected).await {
+            Ok(persisted) => Ok(Some(persisted)),
+            Err(reason) => Err(anyhow!(
+                "Tool '{}' failed its integrity check: {}",
+                path,
+                reason
+            )),
+        }
     }
 
-    /// List all persisted tools
-    pub async fn list_tools(&self, namespace_filter: Option<&str>) -> Result<Vec<ToolDefinition>> {
+    /// List all persisted tools with their metadata. Tools that fail
+    /// verification are moved to the quarantine directory and left out.
+    pub async fn list_tools(
+        &mut self,
+        namespace_filter: Option<&str>,
+    ) -> Result<Vec<PersistedTool>> {
+        let _lock = self.lock_index().await?;
         let mut tools = Vec::new();
-
-        for entry in self.index.tools.values() {
+        let entries: Vec<(String, ToolIndexEntry)> = self
+            .index
+            .tools
+            .iter()
+            .map(|(key, entry)| (key.clone(), entry.clone()))
+            .collect();
+
+        for (key, entry) in entries {
             // Apply namespace filter if specified
             if let Some(filter) = namespace_filter {
                 let matches = match &entry.path.namespace {
@@ -198,20 +397,197 @@ impl FilePersistence {
             }
 
             // Load tool
-            if let Ok(Some(tool)) = self.load_tool(&entry.path).await {
-                tools.push(tool);
+            match self.load_persisted(&entry.path).await {
+                Ok(Some(tool)) => tools.push(tool),
+                Ok(None) => tracing::warn!("Tool file {} is missing", entry.file_path.display()),
+                Err(e) => self.quarantine(&key, &entry, e.to_string()).await?,
             }
         }
 
         Ok(tools)
     }
 
+    /// The stored tool a path names: the exact path if it is stored, or
+    /// else the stored version its version resolves to
+    pub fn stored_path(&self, path: &ToolPath) -> Option<ToolPath> {
+        if self.index.tools.contains_key(&path.to_string()) {
+            return Some(path.clone());
+        }
+        let spec = VersionSpec::parse(&path.version).ok()?;
+        let available: Vec<&str> = self
+            .index
+            .tools
+            .values()
+            .filter(|entry| entry.path.same_tool(path))
+            .map(|entry| entry.path.version.as_str())
+            .collect();
+        let pin = self
+            .index
+            .version_pins
+            .get(&path.with_version(versioning::LATEST).to_mcp_name())
+            .map(String::as_str);
+        versioning::select(&spec, &available, pin).map(|version| path.with_version(version))
+    }
+
+    /// Sign a stored tool's checksum
+    pub async fn sign_tool(
+        &mut self,
+        path: &ToolPath,
+        key: &ed25519_dalek::SigningKey,
+    ) -> Result<ToolSignature> {
+        let _lock = self.lock_index().await?;
+        let mut persisted = self
+            .load_persisted(path)
+            .await?
+            .ok_or_else(|| anyhow!("Tool '{}' is not stored", path))?;
+
+        // Tools saved before SHA-256 checksums get one now
+        let checksum = calculate_checksum(&persisted.tool);
+        let signature = signing::sign(key, checksum.as_bytes());
+        persisted.metadata.checksum = checksum.clone();
+        persisted.metadata.signature = Some(signature.clone());
+
+        let file_path = self.get_tool_file_path(path);
+        atomic_file::write(
+            &file_path,
+            serde_json::to_string_pretty(&persisted)?.as_bytes(),
+        )
+        .await?;
+        if let Some(entry) = self.index.tools.get_mut(&path.to_string()) {
+            entry.checksum = checksum;
+        }
+        self.index.last_updated = Utc::now();
+        self.save_index().await?;
+        Ok(signature)
+    }
+
+    /// Integrity of every stored and quarantined tool, verifying each stored
+    /// tool file against its checksum
+    pub fn integrity(&self) -> HashMap<ToolPath, Integrity> {
+        let stored = self.index.tools.values().map(|entry| {
+            let verified = std::fs::read_to_string(&entry.file_path)
+                .map_err(|e| format!("unreadable tool file: {}", e))
+                .and_then(|content| Self::check(&content, Some(&entry.checksum)));
+            (entry.path.clone(), Integrity::of_verification(&verified))
+        });
+        let quarantined = self.index.quarantined.values().map(|tool| {
+            (
+                tool.path.clone(),
+                Integrity::Quarantined {
+                    reason: tool.reason.clone(),
+                    file_path: tool.file_path.clone(),
+                    quarantined_at: tool.quarantined_at,
+                },
+            )
+        });
+        stored.chain(quarantined).collect()
+    }
+
+    /// Read a tool file and check it against the checksum it was saved with
+    /// and the one recorded in the index
+    async fn verify(
+        file_path: &Path,
+        expected: Option<&str>,
+    ) -> std::result::Result<PersistedTool, String> {
+        let content = fs::read_to_string(file_path)
+            .await
+            .map_err(|e| format!("unreadable tool file: {}", e))?;
+        Self::check(&content, expected)
+    }
+
+    /// Check the content of a tool file against its checksums
+    fn check(content: &str, expected: Option<&str>) -> std::result::Result<PersistedTool, String> {
+        let persisted =
+            parse_tool_file(content).map_err(|e| format!("unreadable tool file: {}", e))?;
+        let recorded = &persisted.metadata.checksum;
+
+        if let Some(expected) = expected {
+            // An index entry from before SHA-256 can't vouch for the file
+            if expected.starts_with(CHECKSUM_PREFIX) && expected != recorded {
+                return Err(format!(
+                    "checksum mismatch: index records {}, file records {}",
+                    expected, recorded
+                ));
+            }
+        }
+        if recorded.starts_with(CHECKSUM_PREFIX) {
+            let actual = calculate_checksum(&persisted.tool);
+            if &actual != recorded {
+                return Err(format!(
+                    "checksum mismatch: file records {}, content hashes to {}",
+                    recorded, actual
+                ));
+            }
+        }
+
+        Ok(persisted)
+    }
+
+    /// Move a tool that failed verification out of the store
+    async fn quarantine(
+        &mut self,
+        key: &str,
+        entry: &ToolIndexEntry,
+        reason: String,
+    ) -> Result<()> {
+        let now = Utc::now();
+        let relative = entry
+            .file_path
+            .strip_prefix(&self.storage_dir)
+            .unwrap_or(&entry.file_path);
+        let file_path = self
+            .storage_dir
+            .join("quarantine")
+            .join(relative)
+            .with_extension(format!("{}.json", now.format("%Y%m%dT%H%M%S")));
+        if let Some(parent) = file_path.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+        fs::rename(&entry.file_path, &file_path).await?;
+        self.cleanup_empty_dirs(&entry.file_path).await?;
+
+        tracing::warn!(
+            "Quarantined tool {} ({}), moved to {}",
+            entry.path,
+            reason,
+            file_path.display()
+        );
+        self.index.tools.remove(key);
+        self.index.quarantined.insert(
+            key.to_string(),
+            QuarantinedTool {
+                path: entry.path.clone(),
+                reason,
+                file_path,
+                quarantined_at: now,
+            },
+        );
+        self.index.last_updated = now;
+        self.save_index().await
+    }
+
     /// Delete a tool from persistent storage
     pub async fn delete_tool(&mut self, path: &ToolPath) -> Result<bool> {
+        let _lock = self.lock_index().await?;
         let path_key = path.to_string();
 
         // Remove from index
         if let Some(entry) = self.index.tools.remove(&path_key) {
+            // Record the deleted definition so it can be restored
+            if let Some(persisted) = Self::read_persisted(&entry.file_path).await? {
+                let log = self.history_log(path);
+                let revision = log.read().await?.last().map_or(1, |last| last.revision + 1);
+                log.append(&ToolRevision {
+                    revision,
+                    action: RevisionAction::Delete,
+                    saved_at: Utc::now(),
+                    checksum: persisted.metadata.checksum,
+                    restored_from: None,
+                    tool: persisted.tool,
+                })
+                .await?;
+            }
+
             // Delete file
             if entry.file_path.exists() {
                 fs::remove_file(&entry.file_path).await?;
@@ -221,6 +597,12 @@ impl FilePersistence {
             // Clean up empty directories
             self.cleanup_empty_dirs(&entry.file_path).await?;
 
+            // A pin on the deleted version no longer applies
+            let pin_key = path.with_version(versioning::LATEST).to_mcp_name();
+            if self.index.version_pins.get(&pin_key) == Some(&path.version) {
+                self.index.version_pins.remove(&pin_key);
+            }
+
             // Update index
             self.index.last_updated = Utc::now();
             self.save_index().await?;
@@ -231,10 +613,57 @@ impl FilePersistence {
         }
     }
 
+    /// Every recorded revision of a tool, oldest first
+    pub async fn history(&self, path: &ToolPath) -> Result<Vec<ToolRevision>> {
+        self.history_log(path).read().await
+    }
+
+    /// Restore the definition saved in an earlier revision as a new revision.
+    /// Deleted tools can be restored this way too.
+    pub async fn rollback(&mut self, path: &ToolPath, revision: u32) -> Result<ToolRevision> {
+        let revisions = self.history(path).await?;
+        let target = revisions
+            .iter()
+            .find(|r| r.revision == revision)
+            .ok_or_else(|| {
+                anyhow!(
+                    "Tool '{}' has no revision {} (latest is {})",
+                    path,
+                    revision,
+                    revisions.last().map_or(0, |last| last.revision)
+                )
+            })?;
+
+        let tool = target.tool.clone();
+        self.write_revision(&tool, RevisionAction::Rollback, Some(revision))
+            .await
+    }
+
+    /// Pinned default versions, keyed by the unversioned MCP name
+    pub fn version_pins(&self) -> &HashMap<String, String> {
+        &self.index.version_pins
+    }
+
+    /// Pin or unpin the default version of a tool
+    pub async fn set_version_pin(
+        &mut self,
+        path: &ToolPath,
+        version: Option<String>,
+    ) -> Result<()> {
+        let _lock = self.lock_index().await?;
+        let key = path.with_version(versioning::LATEST).to_mcp_name();
+        match version {
+            Some(version) => self.index.version_pins.insert(key, version),
+            None => self.index.version_pins.remove(&key),
+        };
+        self.index.last_updated = Utc::now();
+        self.save_index().await
+    }
+
+    /// Replace the index file; callers hold the index lock
     async fn save_index(&self) -> Result<()> {
         let json = serde_json::to_string_pretty(&self.index)?;
-        fs::write(&self.index_path, json).await?;
-        Ok(())
+        atomic_file::write(&self.index_path, json.as_bytes()).await
     }
 
     fn get_tool_file_path(&self, path: &ToolPath) -> PathBuf {
@@ -262,6 +691,20 @@ impl FilePersistence {
         file_path.join(filename)
     }
 
+    /// History file of a tool, mirroring its tool file under `history/`
+    fn history_log(&self, path: &ToolPath) -> HistoryLog {
+        let file_path = self.get_tool_file_path(path);
+        let relative = file_path
+            .strip_prefix(&self.storage_dir)
+            .unwrap_or(&file_path);
+        HistoryLog::new(
+            self.storage_dir
+                .join("history")
+                .join(relative)
+                .with_extension("jsonl"),
+        )
+    }
+
     fn cleanup_empty_dirs<'a>(
         &'a self,
         file_path: &'a Path,
@@ -288,19 +731,55 @@ impl FilePersistence {
     }
 }
 
+/// Tool files under a storage directory. The index, history and
+/// quarantined copies are not live tools and are left out.
+pub(crate) async fn tool_files(storage_dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let mut dirs = vec![storage_dir.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        let mut entries = fs::read_dir(&dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let file_path = entry.path();
+            if entry.file_type().await?.is_dir() {
+                if dir != storage_dir
+                    || !matches!(entry.file_name().to_str(), Some("history" | "quarantine"))
+                {
+                    dirs.push(file_path);
+                }
+                continue;
+            }
+            if dir != storage_dir && file_path.extension().and_then(|e| e.to_str()) == Some("json")
+            {
+                files.push(file_path);
+            }
+        }
+    }
+    Ok(files)
+}
+
+/// Parse a tool file, upgrading an older format in memory
+fn parse_tool_file(content: &str) -> Result<PersistedTool> {
+    let mut value: serde_json::Value = serde_json::from_str(content)?;
+    migrations::upgrade(Format::ToolFile, &mut value)?;
+    Ok(serde_json::from_value(value)?)
+}
+
 /// Get the appropriate storage directory for the current platform
 fn get_storage_directory() -> Result<PathBuf> {
     platform_dirs::tools_dir()
 }
 
-/// Calculate a simple checksum for tool script content
-fn calculate_checksum(content: &str) -> String {
-    use std::collections::hash_map::DefaultHasher;
-    use std::hash::{Hash, Hasher};
+/// SHA-256 of a tool's serialized definition, so the description and
+/// parameters are covered as well as the script
+pub fn calculate_checksum(tool: &ToolDefinition) -> String {
+    sha256_checksum(&serde_json::to_vec(tool).expect("tool definitions serialize"))
+}
 
-    let mut hasher = DefaultHasher::new();
-    content.hash(&mut hasher);
-    format!("{:x}", hasher.finish())
+/// `sha256:` and the hex SHA-256 of the content
+pub fn sha256_checksum(content: &[u8]) -> String {
+    let digest = Sha256::digest(content);
+    let hex: String = digest.iter().map(|byte| format!("{:02x}", byte)).collect();
+    format!("{}{}", CHECKSUM_PREFIX, hex)
 }
 
 #[cfg(test)]
@@ -325,7 +804,10 @@ mod tests {
                 description: "Message to display".to_string(),
                 required: true,
                 type_name: "string".to_string(),
+                completion: None,
+                default: None,
             }],
+            examples: Vec::new(),
         }
     }
 
@@ -338,7 +820,10 @@ mod tests {
         persistence.save_tool(&tool).await?;
 
         // Load tool
-        let loaded = persistence.load_tool(&tool.path).await?;
+        let loaded = persistence
+            .load_persisted(&tool.path)
+            .await?
+            .map(|persisted| persisted.tool);
         assert!(loaded.is_some());
 
         let loaded_tool = loaded.unwrap();
@@ -360,6 +845,7 @@ mod tests {
             description: "Calculator tool".to_string(),
             script: "expr $a + $b".to_string(),
             parameters: vec![],
+            examples: vec![],
         };
 
         persistence.save_tool(&tool1).await?;
@@ -373,7 +859,7 @@ mod tests {
         let alice_tools = persistence.list_tools(Some("alice")).await?;
         assert_eq!(alice_tools.len(), 1);
         assert_eq!(
-            alice_tools[0].path.namespace,
+            alice_tools[0].tool.path.namespace,
             Namespace::User("alice".to_string())
         );
 
@@ -389,14 +875,22 @@ mod tests {
         persistence.save_tool(&tool).await?;
 
         // Verify it exists
-        assert!(persistence.load_tool(&tool.path).await?.is_some());
+        assert!(persistence
+            .load_persisted(&tool.path)
+            .await?
+            .map(|persisted| persisted.tool)
+            .is_some());
 
         // Delete tool
         let deleted = persistence.delete_tool(&tool.path).await?;
         assert!(deleted);
 
         // Verify it's gone
-        assert!(persistence.load_tool(&tool.path).await?.is_none());
+        assert!(persistence
+            .load_persisted(&tool.path)
+            .await?
+            .map(|persisted| persisted.tool)
+            .is_none());
 
         // Try to delete again
         let deleted_again = persistence.delete_tool(&tool.path).await?;
@@ -404,4 +898,263 @@ mod tests {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_processes_keep_each_others_changes() -> Result<()> {
+        let temp = TempDir::new()?;
+        let mut first = FilePersistence::with_directory(temp.path().to_path_buf()).await?;
+        let mut second = FilePersistence::with_directory(temp.path().to_path_buf()).await?;
+
+        let tool = create_test_tool();
+        let mut other = create_test_tool();
+        other.path = ToolPath::user("bob", "utils", "other_tool", "1.0");
+        first.save_tool(&tool).await?;
+        second.save_tool(&other).await?;
+
+        let mut reopened = FilePersistence::with_directory(temp.path().to_path_buf()).await?;
+        assert_eq!(reopened.list_tools(None).await?.len(), 2);
+        assert_eq!(first.list_tools(None).await?.len(), 2);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_corrupt_index_is_rebuilt() -> Result<()> {
+        let temp = TempDir::new()?;
+        let mut persistence = FilePersistence::with_directory(temp.path().to_path_buf()).await?;
+        let tool = create_test_tool();
+        persistence.save_tool(&tool).await?;
+        persistence.delete_tool(&tool.path).await?;
+        persistence.save_tool(&tool).await?;
+
+        // A write interrupted by a crash under the old in-place writes
+        std::fs::write(temp.path().join("index.json"), "{\"tools\": {\"user__al")?;
+
+        let mut reopened = FilePersistence::with_directory(temp.path().to_path_buf()).await?;
+        let tools = reopened.list_tools(None).await?;
+        assert_eq!(tools.len(), 1);
+        assert_eq!(tools[0].tool.path, tool.path);
+
+        let backups = std::fs::read_dir(temp.path())?
+            .filter(|entry| {
+                entry
+                    .as_ref()
+                    .unwrap()
+                    .file_name()
+                    .to_string_lossy()
+                    .starts_with("index.json.corrupt-")
+            })
+            .count();
+        assert_eq!(backups, 1);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_history_and_rollback() -> Result<()> {
+        let (mut persistence, _temp) = create_test_persistence().await?;
+        let tool = create_test_tool();
+        persistence.save_tool(&tool).await?;
+
+        let edited = ToolDefinition {
+            script: "puts \"Edited\"".to_string(),
+            ..tool.clone()
+        };
+        persistence.save_tool(&edited).await?;
+
+        let restored = persistence.rollback(&tool.path, 1).await?;
+        assert_eq!(restored.revision, 3);
+        assert_eq!(restored.restored_from, Some(1));
+        let loaded = persistence
+            .load_persisted(&tool.path)
+            .await?
+            .map(|persisted| persisted.tool)
+            .unwrap();
+        assert_eq!(loaded.script, tool.script);
+
+        persistence.delete_tool(&tool.path).await?;
+        let history = persistence.history(&tool.path).await?;
+        let actions: Vec<RevisionAction> = history.iter().map(|r| r.action).collect();
+        assert_eq!(
+            actions,
+            vec![
+                RevisionAction::Save,
+                RevisionAction::Save,
+                RevisionAction::Rollback,
+                RevisionAction::Delete
+            ]
+        );
+
+        // A deleted tool can be brought back from its history
+        persistence.rollback(&tool.path, 2).await?;
+        let loaded = persistence
+            .load_persisted(&tool.path)
+            .await?
+            .map(|persisted| persisted.tool)
+            .unwrap();
+        assert_eq!(loaded.script, edited.script);
+        assert!(persistence.rollback(&tool.path, 9).await.is_err());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_tampered_tools_are_quarantined() -> Result<()> {
+        let (mut persistence, temp) = create_test_persistence().await?;
+        let tool = create_test_tool();
+        persistence.save_tool(&tool).await?;
+        assert!(matches!(
+            persistence.integrity().get(&tool.path),
+            Some(Integrity::Verified { .. })
+        ));
+
+        // Edit the script behind the store's back
+        let file_path = persistence.get_tool_file_path(&tool.path);
+        let content = std::fs::read_to_string(&file_path)?;
+        std::fs::write(&file_path, content.replace("Hello from", "Goodbye from"))?;
+        match persistence.integrity().get(&tool.path) {
+            Some(Integrity::Failed { reason }) => {
+                assert!(reason.contains("checksum mismatch"), "{}", reason)
+            }
+            other => panic!("expected a failed check, got {:?}", other),
+        }
+
+        let err = persistence.load_persisted(&tool.path).await.unwrap_err();
+        assert!(err.to_string().contains("checksum mismatch"), "{}", err);
+
+        assert!(persistence.list_tools(None).await?.is_empty());
+        assert!(!file_path.exists());
+        match persistence.integrity().get(&tool.path) {
+            Some(Integrity::Quarantined { file_path, .. }) => {
+                assert!(file_path.starts_with(temp.path().join("quarantine")));
+                assert!(file_path.exists());
+            }
+            other => panic!("expected quarantine, got {:?}", other),
+        }
+
+        // Saving the tool again replaces the quarantined copy
+        persistence.save_tool(&tool).await?;
+        assert_eq!(persistence.list_tools(None).await?.len(), 1);
+        assert!(matches!(
+            persistence.integrity().get(&tool.path),
+            Some(Integrity::Verified { .. })
+        ));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_older_formats_are_migrated_on_open() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let tool = create_test_tool();
+        let file_path = temp_dir.path().join("users/alice/utils/test_tool_1.0.json");
+        std::fs::create_dir_all(file_path.parent().unwrap())?;
+
+        // A tool file and index as written before format versions
+        let tool_file = serde_json::json!({
+            "metadata": {
+                "id": "legacy",
+                "created_at": "2025-01-01T00:00:00Z",
+                "updated_at": "2025-01-01T00:00:00Z",
+                "checksum": calculate_checksum(&tool),
+                "file_version": 1
+            },
+            "tool": tool
+        });
+        std::fs::write(&file_path, tool_file.to_string())?;
+        let index = serde_json::json!({
+            "tools": {
+                tool.path.to_string(): {
+                    "path": tool.path,
+                    "file_path": file_path,
+                    "checksum": calculate_checksum(&tool),
+                    "updated_at": "2025-01-01T00:00:00Z"
+                }
+            },
+            "last_updated": "2025-01-01T00:00:00Z"
+        });
+        let index_path = temp_dir.path().join("index.json");
+        std::fs::write(&index_path, index.to_string())?;
+
+        let mut persistence =
+            FilePersistence::with_directory(temp_dir.path().to_path_buf()).await?;
+        assert_eq!(persistence.index.version, TOOL_INDEX_VERSION);
+        assert!(temp_dir.path().join("index.json.v0-backup").exists());
+        assert!(file_path.with_extension("json.v0-backup").exists());
+
+        let tools = persistence.list_tools(None).await?;
+        assert_eq!(tools.len(), 1);
+        assert_eq!(tools[0].metadata.format_version, TOOL_FILE_VERSION);
+        assert_eq!(tools[0].metadata.id, "legacy");
+
+        // An index from a newer server is refused rather than rebuilt
+        let mut newer: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&index_path)?)?;
+        newer["version"] = serde_json::json!(TOOL_INDEX_VERSION + 1);
+        std::fs::write(&index_path, newer.to_string())?;
+        assert!(
+            FilePersistence::with_directory(temp_dir.path().to_path_buf())
+                .await
+                .is_err()
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_legacy_checksums_are_unverified() -> Result<()> {
+        let (mut persistence, _temp) = create_test_persistence().await?;
+        let tool = create_test_tool();
+        persistence.save_tool(&tool).await?;
+
+        // Checksums written before SHA-256 can't be checked
+        let key = tool.path.to_string();
+        let file_path = persistence.get_tool_file_path(&tool.path);
+        let mut persisted: PersistedTool =
+            serde_json::from_str(&std::fs::read_to_string(&file_path)?)?;
+        persisted.metadata.checksum = "1f0c2e4d5a6b7c8d".to_string();
+        std::fs::write(&file_path, serde_json::to_string(&persisted)?)?;
+        persistence.index.tools.get_mut(&key).unwrap().checksum = "1f0c2e4d5a6b7c8d".to_string();
+        persistence.save_index().await?;
+
+        assert_eq!(persistence.list_tools(None).await?.len(), 1);
+        assert_eq!(
+            persistence.integrity().get(&tool.path),
+            Some(&Integrity::Unverified)
+        );
+        assert!(calculate_checksum(&tool).starts_with("sha256:"));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_sign_tool() -> Result<()> {
+        let (mut persistence, temp) = create_test_persistence().await?;
+        let tool = create_test_tool();
+        persistence.save_tool(&tool).await?;
+
+        let key = ed25519_dalek::SigningKey::from_bytes(&[7; 32]);
+        let latest = ToolPath::user("alice", "utils", "test_tool", "latest");
+        let path = persistence.stored_path(&latest).unwrap();
+        let signature = persistence.sign_tool(&path, &key).await?;
+
+        let persisted = persistence.load_persisted(&tool.path).await?.unwrap();
+        assert_eq!(persisted.metadata.signature, Some(signature.clone()));
+        let mut trust = signing::TrustStore::load_from(&temp.path().join("trust.json"))?;
+        trust.trust("alice", &signature.public_key)?;
+        assert!(matches!(
+            trust.verify(
+                calculate_checksum(&persisted.tool).as_bytes(),
+                persisted.metadata.signature.as_ref()
+            ),
+            signing::SignatureStatus::Trusted { .. }
+        ));
+
+        // Saving a new revision drops the signature
+        persistence.save_tool(&tool).await?;
+        let persisted = persistence.load_persisted(&tool.path).await?.unwrap();
+        assert!(persisted.metadata.signature.is_none());
+
+        Ok(())
+    }
 }
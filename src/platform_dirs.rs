@@ -65,6 +65,30 @@ pub fn mcp_index_path() -> Result<PathBuf> {
     Ok(data.join("mcp-index.json"))
 }
 
+/// Get the path of the SQLite database used by `--storage sqlite`
+pub fn database_path() -> Result<PathBuf> {
+    let data = data_dir()?;
+    Ok(data.join("store.db"))
+}
+
+/// Get the path of the tool exposure profiles file
+pub fn profiles_path() -> Result<PathBuf> {
+    let config = config_dir()?;
+    Ok(config.join("profiles.json"))
+}
+
+/// Get the path of the trusted signing keys and trust policy
+pub fn trust_path() -> Result<PathBuf> {
+    let config = config_dir()?;
+    Ok(config.join("trust.json"))
+}
+
+/// Get the directory holding tool signing keys
+pub fn keys_dir() -> Result<PathBuf> {
+    let config = config_dir()?;
+    Ok(config.join("keys"))
+}
+
 /// Get the path for storing TCL scripts
 pub fn scripts_dir() -> Result<PathBuf> {
     let data = data_dir()?;
@@ -0,0 +1,380 @@
+/// Interactive TCL shell
+///
+/// Each line is evaluated in one long-lived interpreter, so procs and
+/// variables defined on one line are there on the next. Lines starting with
+/// `.` are meta-commands for working with tools: they go through an
+/// in-process server with the same runtime, privileges and storage backend,
+/// so a tool called from the shell behaves as it does under `run`.
+use anyhow::{anyhow, Result};
+use rustyline::error::ReadlineError;
+use rustyline::DefaultEditor;
+use serde_json::{json, Value};
+
+use crate::host_commands;
+use crate::mcp_peer::McpPeer;
+use crate::platform_dirs;
+use crate::server::TclMcpServer;
+use crate::tcl_list;
+use crate::tcl_runtime::{self, RuntimeConfig, RuntimeType, TclRuntime};
+use crate::tool_store::StorageBackend;
+
+const HELP: &str = "\
+Lines are evaluated as TCL; unbalanced braces, brackets or quotes continue
+on the next line. Meta-commands:
+  .tools [filter]          List tools, optionally those whose name contains filter
+  .load <tool>             Define a tool's script as a proc taking its parameters
+  .call <tool> [args]      Call a tool with a JSON object or key value pairs
+  .runtime [molt|tcl]      Show or switch the runtime (resets interpreter state)
+  .help                    Show this help
+  .quit                    Leave the shell (or press Ctrl-D)";
+
+#[derive(Debug, PartialEq)]
+enum MetaCommand {
+    Help,
+    Tools(Option<String>),
+    Load(String),
+    Call { tool: String, args: Value },
+    Runtime(Option<RuntimeType>),
+    Quit,
+}
+
+impl MetaCommand {
+    fn parse(line: &str) -> Result<Self> {
+        let (command, rest) = split_word(line.trim());
+        match command {
+            ".help" => Ok(Self::Help),
+            ".tools" => Ok(Self::Tools((!rest.is_empty()).then(|| rest.to_string()))),
+            ".load" if !rest.is_empty() => Ok(Self::Load(rest.to_string())),
+            ".load" => Err(anyhow!("Usage: .load <tool>")),
+            ".call" if !rest.is_empty() => {
+                let (tool, args) = split_word(rest);
+                Ok(Self::Call {
+                    tool: tool.to_string(),
+                    args: parse_call_args(args)?,
+                })
+            }
+            ".call" => Err(anyhow!("Usage: .call <tool> [args]")),
+            ".runtime" if rest.is_empty() => Ok(Self::Runtime(None)),
+            ".runtime" => Ok(Self::Runtime(Some(rest.parse()?))),
+            ".quit" | ".exit" => Ok(Self::Quit),
+            _ => Err(anyhow!(
+                "Unknown command '{}'; type .help for the list",
+                command
+            )),
+        }
+    }
+}
+
+fn split_word(text: &str) -> (&str, &str) {
+    text.split_once(char::is_whitespace)
+        .map_or((text, ""), |(word, rest)| (word, rest.trim()))
+}
+
+/// Tool arguments as a JSON object, or TCL-style `key value` pairs whose
+/// values are read as numbers or booleans where they parse as one
+fn parse_call_args(args: &str) -> Result<Value> {
+    if args.is_empty() {
+        return Ok(json!({}));
+    }
+    if let Ok(object @ Value::Object(_)) = serde_json::from_str(args) {
+        return Ok(object);
+    }
+
+    let mut map = serde_json::Map::new();
+    for (key, value) in tcl_list::parse_dict(args)? {
+        let value = if let Ok(n) = value.parse::<i64>() {
+            json!(n)
+        } else if let Ok(f) = value.parse::<f64>() {
+            json!(f)
+        } else if let Ok(b) = value.parse::<bool>() {
+            json!(b)
+        } else {
+            json!(value)
+        };
+        map.insert(key, value);
+    }
+    Ok(Value::Object(map))
+}
+
+/// Whether a script is a complete command: braces, brackets and quotes are
+/// balanced and the last line does not end in a backslash
+fn is_complete(script: &str) -> bool {
+    let mut braces = 0;
+    let mut brackets = 0;
+    let mut quoted = false;
+    let mut chars = script.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if chars.next().is_none() => return false,
+            '{' if !quoted => braces += 1,
+            '}' if !quoted => braces -= 1,
+            '"' if braces == 0 => quoted = !quoted,
+            '[' if braces == 0 => brackets += 1,
+            ']' if braces == 0 => brackets -= 1,
+            _ => {}
+        }
+    }
+    braces <= 0 && brackets <= 0 && !quoted
+}
+
+pub struct Repl {
+    runtime: Box<dyn TclRuntime>,
+    server: TclMcpServer,
+    privileged: bool,
+    storage: StorageBackend,
+}
+
+impl Repl {
+    pub async fn new(
+        privileged: bool,
+        runtime_config: RuntimeConfig,
+        storage: StorageBackend,
+    ) -> Result<Self> {
+        let (runtime, server) = start(privileged, runtime_config, storage).await?;
+        Ok(Self {
+            runtime,
+            server,
+            privileged,
+            storage,
+        })
+    }
+
+    /// Read and evaluate lines until `.quit` or end of input, keeping the
+    /// line history in the data directory
+    pub async fn run(&mut self) -> Result<()> {
+        let mut editor = DefaultEditor::new()?;
+        let history_path = platform_dirs::data_dir()?.join("repl_history");
+        // There is no history before the first session
+        let _ = editor.load_history(&history_path);
+
+        println!(
+            "TCL MCP Server shell ({} runtime). Type .help for commands, .quit to leave.",
+            self.runtime.name()
+        );
+
+        let mut buffer = String::new();
+        loop {
+            let prompt = if buffer.is_empty() { "% " } else { "> " };
+            match editor.readline(prompt) {
+                Ok(line) => {
+                    if buffer.is_empty() && line.trim_start().starts_with('.') {
+                        editor.add_history_entry(line.as_str())?;
+                        match MetaCommand::parse(&line) {
+                            Ok(MetaCommand::Quit) => break,
+                            Ok(command) => {
+                                if let Err(e) = self.meta(command).await {
+                                    eprintln!("Error: {}", e);
+                                }
+                            }
+                            Err(e) => eprintln!("Error: {}", e),
+                        }
+                        continue;
+                    }
+
+                    if !buffer.is_empty() {
+                        buffer.push('\n');
+                    }
+                    buffer.push_str(&line);
+                    if !is_complete(&buffer) {
+                        continue;
+                    }
+
+                    let script = std::mem::take(&mut buffer);
+                    if script.trim().is_empty() {
+                        continue;
+                    }
+                    editor.add_history_entry(script.as_str())?;
+                    match self.runtime.eval(&script) {
+                        Ok(result) if result.is_empty() => {}
+                        Ok(result) => println!("{}", result),
+                        Err(e) => eprintln!("Error: {}", e),
+                    }
+                }
+                // Ctrl-C abandons the command being entered
+                Err(ReadlineError::Interrupted) => buffer.clear(),
+                Err(ReadlineError::Eof) => break,
+                Err(e) => return Err(e.into()),
+            }
+        }
+
+        platform_dirs::ensure_dir(&platform_dirs::data_dir()?)?;
+        editor.save_history(&history_path)?;
+        Ok(())
+    }
+
+    async fn meta(&mut self, command: MetaCommand) -> Result<()> {
+        match command {
+            MetaCommand::Help => println!("{}", HELP),
+            MetaCommand::Tools(filter) => {
+                let filter = filter.map(|f| f.to_lowercase());
+                for tool in self.server.list_all_tools().await? {
+                    let name = tool["name"].as_str().unwrap_or_default();
+                    if filter
+                        .as_ref()
+                        .is_some_and(|f| !name.to_lowercase().contains(f))
+                    {
+                        continue;
+                    }
+                    let description = tool["description"].as_str().unwrap_or_default();
+                    println!(
+                        "{} - {}",
+                        name,
+                        description.lines().next().unwrap_or_default()
+                    );
+                }
+            }
+            MetaCommand::Load(tool) => {
+                let tool = self.server.tool_definition(&tool).await?;
+                let args = tcl_list::format_list(tool.parameters.iter().map(|param| {
+                    let default = match &param.default {
+                        Some(Value::String(value)) => value.clone(),
+                        Some(value) => value.to_string(),
+                        None => String::new(),
+                    };
+                    if param.required {
+                        param.name.clone()
+                    } else {
+                        tcl_list::format_list([param.name.as_str(), default.as_str()])
+                    }
+                }));
+                let proc = tcl_list::format_list([
+                    "proc",
+                    tool.path.name.as_str(),
+                    args.as_str(),
+                    tool.script.as_str(),
+                ]);
+                self.runtime.eval(&proc)?;
+
+                let usage: Vec<String> = tool
+                    .parameters
+                    .iter()
+                    .map(|param| {
+                        if param.required {
+                            param.name.clone()
+                        } else {
+                            format!("?{}?", param.name)
+                        }
+                    })
+                    .collect();
+                println!(
+                    "Loaded {} as proc: {} {}",
+                    tool.path,
+                    tool.path.name,
+                    usage.join(" ")
+                );
+            }
+            MetaCommand::Call { tool, args } => {
+                let path = self.server.resolve_tool_path(&tool).await?;
+                let response = self.server.call_tool_path(&path, args).await?;
+                if let Some(error) = response.get("error") {
+                    return Err(anyhow!(
+                        "{}",
+                        error["message"].as_str().unwrap_or("tool call failed")
+                    ));
+                }
+                let result = &response["result"];
+                match result["content"].as_array() {
+                    Some(content) => {
+                        for text in content.iter().filter_map(|item| item["text"].as_str()) {
+                            println!("{}", text);
+                        }
+                    }
+                    None => println!("{}", serde_json::to_string_pretty(result)?),
+                }
+            }
+            MetaCommand::Runtime(None) => {
+                let available: Vec<&str> = tcl_runtime::available_runtimes()
+                    .iter()
+                    .map(RuntimeType::as_str)
+                    .collect();
+                println!(
+                    "Runtime: {} (available: {})",
+                    self.runtime.name(),
+                    available.join(", ")
+                );
+            }
+            MetaCommand::Runtime(Some(runtime_type)) => {
+                let config = RuntimeConfig {
+                    runtime_type: Some(runtime_type),
+                    fallback_enabled: false,
+                };
+                let (runtime, server) = start(self.privileged, config, self.storage).await?;
+                self.runtime = runtime;
+                self.server = server;
+                println!(
+                    "Switched to the {} runtime; interpreter state was reset",
+                    self.runtime.name()
+                );
+            }
+            MetaCommand::Quit => {}
+        }
+        Ok(())
+    }
+}
+
+/// An interpreter for the shell and a server for its tool commands
+async fn start(
+    privileged: bool,
+    runtime_config: RuntimeConfig,
+    storage: StorageBackend,
+) -> Result<(Box<dyn TclRuntime>, TclMcpServer)> {
+    let mut runtime = tcl_runtime::create_runtime_with_config(runtime_config.clone())?;
+    host_commands::install(runtime.as_mut(), &McpPeer::new());
+
+    let server = TclMcpServer::new_with_storage(privileged, runtime_config, storage)
+        .map_err(|e| anyhow!("Failed to create server: {}", e))?;
+    if let Err(e) = server.initialize_persistence().await {
+        tracing::warn!("Failed to initialize persistence: {}", e);
+    }
+    Ok((runtime, server))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_complete() {
+        assert!(is_complete("set x 1"));
+        assert!(is_complete("puts \"a { b\""));
+        assert!(is_complete("set s {a \"b}"));
+        assert!(!is_complete("proc greet {name} {"));
+        assert!(is_complete(
+            "proc greet {name} {\n    return \"Hi $name\"\n}"
+        ));
+        assert!(!is_complete("set x [expr {1 +"));
+        assert!(!is_complete("set x \\"));
+        assert!(!is_complete("puts \"unterminated"));
+    }
+
+    #[test]
+    fn test_parse_meta_commands() -> Result<()> {
+        assert_eq!(MetaCommand::parse(".tools")?, MetaCommand::Tools(None));
+        assert_eq!(
+            MetaCommand::parse(".tools  reverse ")?,
+            MetaCommand::Tools(Some("reverse".to_string()))
+        );
+        assert_eq!(
+            MetaCommand::parse(".runtime molt")?,
+            MetaCommand::Runtime(Some(RuntimeType::Molt))
+        );
+        assert_eq!(
+            MetaCommand::parse(".call reverse_string text {hello world} count 2")?,
+            MetaCommand::Call {
+                tool: "reverse_string".to_string(),
+                args: json!({ "text": "hello world", "count": 2 }),
+            }
+        );
+        assert_eq!(
+            MetaCommand::parse(r#".call bin__tcl_execute {"script": "expr 1"}"#)?,
+            MetaCommand::Call {
+                tool: "bin__tcl_execute".to_string(),
+                args: json!({ "script": "expr 1" }),
+            }
+        );
+        assert!(MetaCommand::parse(".load").is_err());
+        assert!(MetaCommand::parse(".runtime perl").is_err());
+        assert!(MetaCommand::parse(".bogus").is_err());
+        Ok(())
+    }
+}
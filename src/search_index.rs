@@ -0,0 +1,366 @@
+/// Ranked tool search
+///
+/// A BM25 index over tool names, descriptions, parameter names and tags.
+/// Query terms match indexed terms exactly, through a small synonym table, by
+/// prefix, or within a small edit distance. Looser matches score lower, so
+/// typos and related words still find tools without outranking exact hits.
+/// Documents are added and removed one at a time; the index is never rebuilt.
+use std::collections::{HashMap, HashSet};
+
+/// BM25 term frequency saturation
+const K1: f64 = 1.2;
+/// BM25 document length normalization
+const B: f64 = 0.75;
+
+/// How much an occurrence in each field counts towards term frequency
+const NAME_WEIGHT: f64 = 3.0;
+const TAG_WEIGHT: f64 = 2.0;
+const PARAMETER_WEIGHT: f64 = 1.5;
+const DESCRIPTION_WEIGHT: f64 = 1.0;
+
+/// Score multipliers for each kind of term match
+const SYNONYM_MATCH: f64 = 0.8;
+const PREFIX_MATCH: f64 = 0.7;
+const ONE_EDIT_MATCH: f64 = 0.6;
+const TWO_EDIT_MATCH: f64 = 0.4;
+
+/// Groups of words treated as equivalent in queries
+const SYNONYMS: &[&[&str]] = &[
+    &["add", "create", "new", "register", "define"],
+    &["remove", "delete", "rm", "drop", "unregister"],
+    &["list", "ls", "enumerate"],
+    &["run", "execute", "exec", "call", "invoke", "eval"],
+    &["search", "find", "lookup", "query", "grep"],
+    &["inspect", "info", "describe", "details", "show"],
+    &["docs", "documentation", "help", "manual", "book"],
+    &["reload", "refresh", "reread"],
+    &["connect", "attach"],
+    &["disconnect", "detach"],
+    &["dir", "directory", "folder"],
+    &["server", "servers", "mcp"],
+];
+
+/// The searchable text of one tool
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SearchDocument {
+    pub name: String,
+    pub description: String,
+    pub parameters: Vec<String>,
+    pub tags: Vec<String>,
+}
+
+/// A tool matching a query, with its relevance score
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchHit {
+    pub key: String,
+    pub score: f64,
+}
+
+#[derive(Debug, Clone)]
+struct IndexedDocument {
+    source: SearchDocument,
+    /// Field-weighted term frequencies
+    terms: HashMap<String, f64>,
+    length: f64,
+}
+
+/// Incrementally maintained BM25 index keyed by tool MCP name
+#[derive(Debug, Clone, Default)]
+pub struct SearchIndex {
+    documents: HashMap<String, IndexedDocument>,
+    /// Term -> keys of the documents containing it
+    postings: HashMap<String, HashSet<String>>,
+    total_length: f64,
+}
+
+impl SearchIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a document, replacing any previous document with the same key.
+    /// Unchanged documents are left alone.
+    pub fn upsert(&mut self, key: &str, document: SearchDocument) {
+        if self
+            .documents
+            .get(key)
+            .is_some_and(|indexed| indexed.source == document)
+        {
+            return;
+        }
+        self.remove(key);
+
+        let mut terms: HashMap<String, f64> = HashMap::new();
+        let fields = [
+            (vec![document.name.as_str()], NAME_WEIGHT),
+            (
+                document.tags.iter().map(String::as_str).collect(),
+                TAG_WEIGHT,
+            ),
+            (
+                document.parameters.iter().map(String::as_str).collect(),
+                PARAMETER_WEIGHT,
+            ),
+            (vec![document.description.as_str()], DESCRIPTION_WEIGHT),
+        ];
+        for (texts, weight) in fields {
+            for term in texts.into_iter().flat_map(tokenize) {
+                *terms.entry(term).or_default() += weight;
+            }
+        }
+
+        let length = terms.values().sum();
+        for term in terms.keys() {
+            self.postings
+                .entry(term.clone())
+                .or_default()
+                .insert(key.to_string());
+        }
+        self.total_length += length;
+        self.documents.insert(
+            key.to_string(),
+            IndexedDocument {
+                source: document,
+                terms,
+                length,
+            },
+        );
+    }
+
+    /// Remove a document. Returns whether it was indexed.
+    pub fn remove(&mut self, key: &str) -> bool {
+        let Some(document) = self.documents.remove(key) else {
+            return false;
+        };
+
+        for term in document.terms.keys() {
+            if let Some(keys) = self.postings.get_mut(term) {
+                keys.remove(key);
+                if keys.is_empty() {
+                    self.postings.remove(term);
+                }
+            }
+        }
+        self.total_length -= document.length;
+        true
+    }
+
+    /// Keys of all indexed documents
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        self.documents.keys().map(String::as_str)
+    }
+
+    /// Documents matching the query, best first. Ties are ordered by key.
+    pub fn search(&self, query: &str) -> Vec<SearchHit> {
+        if self.documents.is_empty() {
+            return Vec::new();
+        }
+
+        let count = self.documents.len() as f64;
+        let average_length = (self.total_length / count).max(1.0);
+        let mut scores: HashMap<&str, f64> = HashMap::new();
+
+        for query_term in tokenize(query) {
+            // Best match of this query term in each document
+            let mut best: HashMap<&str, f64> = HashMap::new();
+
+            for (term, keys) in &self.postings {
+                let Some(weight) = match_weight(&query_term, term) else {
+                    continue;
+                };
+                let frequency = keys.len() as f64;
+                let idf = (1.0 + (count - frequency + 0.5) / (frequency + 0.5)).ln();
+
+                for key in keys {
+                    let document = &self.documents[key];
+                    let tf = document.terms[term];
+                    let norm = K1 * (1.0 - B + B * document.length / average_length);
+                    let score = weight * idf * tf * (K1 + 1.0) / (tf + norm);
+
+                    let entry = best.entry(key.as_str()).or_default();
+                    *entry = entry.max(score);
+                }
+            }
+
+            for (key, score) in best {
+                *scores.entry(key).or_default() += score;
+            }
+        }
+
+        let mut hits: Vec<SearchHit> = scores
+            .into_iter()
+            .map(|(key, score)| SearchHit {
+                key: key.to_string(),
+                score,
+            })
+            .collect();
+        hits.sort_by(|a, b| b.score.total_cmp(&a.score).then_with(|| a.key.cmp(&b.key)));
+        hits
+    }
+}
+
+/// Split text into lowercase alphanumeric terms. `bin__list_tools` yields
+/// `bin`, `list` and `tools`.
+fn tokenize(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+}
+
+/// How well an indexed term matches a query term, if at all
+fn match_weight(query_term: &str, term: &str) -> Option<f64> {
+    if query_term == term {
+        return Some(1.0);
+    }
+    if are_synonyms(query_term, term) {
+        return Some(SYNONYM_MATCH);
+    }
+    if query_term.len() >= 3 && term.starts_with(query_term) {
+        return Some(PREFIX_MATCH);
+    }
+
+    // Allow one typo in medium words and two in long ones
+    let max_edits = match query_term.chars().count() {
+        0..=3 => return None,
+        4..=7 => 1,
+        _ => 2,
+    };
+    match edit_distance(query_term, term, max_edits)? {
+        1 => Some(ONE_EDIT_MATCH),
+        _ => Some(TWO_EDIT_MATCH),
+    }
+}
+
+fn are_synonyms(a: &str, b: &str) -> bool {
+    SYNONYMS
+        .iter()
+        .any(|group| group.contains(&a) && group.contains(&b))
+}
+
+/// Optimal string alignment distance (Levenshtein plus adjacent
+/// transpositions), or `None` when it exceeds `max`
+fn edit_distance(a: &str, b: &str, max: usize) -> Option<usize> {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    if a.len().abs_diff(b.len()) > max {
+        return None;
+    }
+
+    let mut previous: Vec<usize> = Vec::new();
+    let mut current: Vec<usize> = (0..=b.len()).collect();
+    for i in 1..=a.len() {
+        let before = std::mem::replace(&mut previous, current.clone());
+        current[0] = i;
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            current[j] = (previous[j] + 1)
+                .min(current[j - 1] + 1)
+                .min(previous[j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                current[j] = current[j].min(before[j - 2] + 1);
+            }
+        }
+        if current.iter().all(|&distance| distance > max) {
+            return None;
+        }
+    }
+
+    Some(current[b.len()]).filter(|&distance| distance <= max)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn document(name: &str, description: &str, parameters: &[&str]) -> SearchDocument {
+        SearchDocument {
+            name: name.to_string(),
+            description: description.to_string(),
+            parameters: parameters.iter().map(|p| p.to_string()).collect(),
+            tags: Vec::new(),
+        }
+    }
+
+    fn sample_index() -> SearchIndex {
+        let mut index = SearchIndex::new();
+        index.upsert(
+            "bin__tcl_execute",
+            document(
+                "bin__tcl_execute",
+                "Execute a TCL script and return the result",
+                &["script"],
+            ),
+        );
+        index.upsert(
+            "sbin__tcl_tool_remove",
+            document("sbin__tcl_tool_remove", "Remove a TCL tool", &["path"]),
+        );
+        index.upsert(
+            "bin__list_dir",
+            SearchDocument {
+                tags: vec!["filesystem".to_string()],
+                ..document("bin__list_dir", "List directory contents", &["path"])
+            },
+        );
+        index
+    }
+
+    #[test]
+    fn test_ranking_prefers_name_matches() {
+        let mut index = sample_index();
+        index.upsert(
+            "user__alice__notes__todo",
+            document(
+                "user__alice__notes__todo",
+                "Notes that mention how to execute things",
+                &[],
+            ),
+        );
+
+        let hits = index.search("execute");
+        assert_eq!(hits[0].key, "bin__tcl_execute");
+        assert!(hits[0].score > hits[1].score);
+        assert_eq!(hits.len(), 2);
+    }
+
+    #[test]
+    fn test_typos_synonyms_and_prefixes() {
+        let index = sample_index();
+
+        assert_eq!(index.search("exectue")[0].key, "bin__tcl_execute");
+        assert_eq!(index.search("delete tool")[0].key, "sbin__tcl_tool_remove");
+        assert_eq!(index.search("direct")[0].key, "bin__list_dir");
+        assert_eq!(index.search("filesystm")[0].key, "bin__list_dir");
+        assert!(index.search("zzz").is_empty());
+    }
+
+    #[test]
+    fn test_incremental_updates() {
+        let mut index = sample_index();
+        assert_eq!(index.documents.len(), 3);
+
+        assert!(index.remove("bin__list_dir"));
+        assert!(!index.remove("bin__list_dir"));
+        assert!(index.search("directory").is_empty());
+        assert!(!index.postings.contains_key("directory"));
+
+        index.upsert(
+            "bin__tcl_execute",
+            document("bin__tcl_execute", "Evaluate a script", &["script"]),
+        );
+        assert_eq!(index.documents.len(), 2);
+        assert!(index.search("result").is_empty());
+        assert_eq!(index.search("evaluate")[0].key, "bin__tcl_execute");
+
+        let total: f64 = index.documents.values().map(|d| d.length).sum();
+        assert!((index.total_length - total).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("execute", "exectue", 1), Some(1));
+        assert_eq!(edit_distance("remove", "remvoe", 1), Some(1));
+        assert_eq!(edit_distance("namespace", "namspase", 2), Some(2));
+        assert_eq!(edit_distance("list", "tools", 1), None);
+    }
+}
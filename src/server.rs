@@ -2,20 +2,50 @@ use anyhow::{anyhow, Result};
 use jsonrpc_core::{IoHandler, Params, Value};
 use serde::{Deserialize, Serialize};
 use serde_json::json;
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
 use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::sync::{mpsc, Semaphore};
 use tracing::{debug, info};
 
+use crate::exposure::ExposureProfile;
+use crate::mcp_peer::{LogLevel, McpPeer};
 use crate::namespace::ToolPath;
+use crate::pagination;
+use crate::system_tools::{self, SystemHandler};
 use crate::tcl_executor::TclExecutor;
 use crate::tcl_runtime::RuntimeConfig;
-use crate::tcl_tools::{
-    TclExecToolRequest, TclExecuteRequest, TclToolAddRequest, TclToolBox, TclToolRemoveRequest,
-};
+use crate::tcl_tools::{TclExecToolRequest, TclToolBox, ToolDefinition};
+use crate::tool_store::StorageBackend;
+
+/// JSON-RPC error code for requests received before `initialize`
+const SERVER_NOT_INITIALIZED: i64 = -32002;
+
+/// Default limit on requests processed concurrently by `run_stdio`
+pub const DEFAULT_MAX_CONCURRENT_REQUESTS: usize = 16;
+
+/// Where the MCP session is in its lifecycle
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SessionState {
+    /// Waiting for the client's `initialize` request
+    Uninitialized,
+    /// `initialize` answered, waiting for `notifications/initialized`
+    Initializing,
+    /// Normal operation
+    Ready,
+}
 
 #[derive(Clone)]
 pub struct TclMcpServer {
     tool_box: TclToolBox,
     handler: IoHandler,
+    peer: McpPeer,
+    session: Arc<Mutex<SessionState>>,
+    max_concurrent_requests: usize,
+    page_size: Arc<AtomicUsize>,
+    latest_only: Arc<AtomicBool>,
+    profile: Arc<Mutex<ExposureProfile>>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -29,6 +59,29 @@ struct McpToolInfo {
 #[derive(Debug, Serialize, Deserialize)]
 struct McpListToolsResult {
     tools: Vec<McpToolInfo>,
+    #[serde(rename = "nextCursor", skip_serializing_if = "Option::is_none")]
+    next_cursor: Option<String>,
+}
+
+/// Per-session exposure profile, sent by the client at `initialize` as
+/// `capabilities.experimental.toolProfile`
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct McpToolProfileParams {
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    include: Vec<String>,
+    #[serde(default)]
+    exclude: Vec<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct McpListToolsParams {
+    #[serde(default)]
+    cursor: Option<String>,
+    /// Only list the version of each tool that `latest` resolves to
+    #[serde(default, rename = "latestOnly")]
+    latest_only: Option<bool>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -37,6 +90,29 @@ struct McpCallToolParams {
     arguments: Value,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+struct McpCompleteParams {
+    #[serde(rename = "ref")]
+    reference: McpCompleteReference,
+    argument: McpCompleteArgument,
+    #[serde(default)]
+    context: Option<Value>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct McpCompleteReference {
+    #[serde(rename = "type")]
+    ref_type: String,
+    name: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct McpCompleteArgument {
+    name: String,
+    #[serde(default)]
+    value: String,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 struct McpCallToolResult {
     content: Vec<McpContent>,
@@ -51,34 +127,78 @@ enum McpContent {
 
 impl TclMcpServer {
     pub fn new(privileged: bool) -> Self {
-        // Spawn the TCL executor with privilege settings
-        let executor = TclExecutor::spawn(privileged);
-        let tool_box = TclToolBox::new(executor);
-        let handler = IoHandler::new();
-
-        Self::setup_handler(tool_box, handler, privileged)
+        Self::new_with_runtime(privileged, RuntimeConfig::default())
+            .expect("Failed to create executor with default runtime")
     }
 
     pub fn new_with_runtime(
         privileged: bool,
         runtime_config: RuntimeConfig,
     ) -> Result<Self, String> {
-        // Spawn the TCL executor with privilege and runtime settings
-        let executor = TclExecutor::spawn_with_runtime(privileged, runtime_config)?;
+        Self::new_with_storage(privileged, runtime_config, StorageBackend::default())
+    }
+
+    /// Create a server that persists tools in the given storage backend
+    pub fn new_with_storage(
+        privileged: bool,
+        runtime_config: RuntimeConfig,
+        storage: StorageBackend,
+    ) -> Result<Self, String> {
+        // Spawn the TCL executor with privilege, runtime and storage settings
+        let peer = McpPeer::new();
+        let executor =
+            TclExecutor::spawn_with_storage(privileged, runtime_config, peer.clone(), storage)?;
         let tool_box = TclToolBox::new(executor);
         let handler = IoHandler::new();
 
-        Ok(Self::setup_handler(tool_box, handler, privileged))
+        Ok(Self::setup_handler(tool_box, handler, peer, privileged))
     }
 
-    fn setup_handler(tool_box: TclToolBox, mut handler: IoHandler, privileged: bool) -> Self {
+    fn setup_handler(
+        tool_box: TclToolBox,
+        mut handler: IoHandler,
+        peer: McpPeer,
+        privileged: bool,
+    ) -> Self {
+        let page_size = Arc::new(AtomicUsize::new(pagination::DEFAULT_PAGE_SIZE));
+        let latest_only = Arc::new(AtomicBool::new(false));
+        let profile = Arc::new(Mutex::new(ExposureProfile::default()));
+
         // Register MCP methods
-        handler.add_sync_method("initialize", move |_params: Params| {
+        let init_peer = peer.clone();
+        let init_profile = profile.clone();
+        handler.add_sync_method("initialize", move |params: Params| {
             info!("MCP initialize called");
+
+            // Remember what the client supports so scripts can use
+            // elicitation and sampling only when they are available
+            let params: Value = params.parse().unwrap_or(Value::Null);
+            let capabilities = params.get("capabilities").cloned().unwrap_or(Value::Null);
+
+            // The client may pick the tools this session sees
+            if let Some(requested) = capabilities.pointer("/experimental/toolProfile") {
+                let requested: McpToolProfileParams = serde_json::from_value(requested.clone())
+                    .map_err(|e| jsonrpc_core::Error::invalid_params(e.to_string()))?;
+                let mut profile = init_profile.lock().unwrap();
+                let base = match &requested.name {
+                    Some(name) => ExposureProfile::load(name)
+                        .map_err(|e| jsonrpc_core::Error::invalid_params(e.to_string()))?,
+                    None => profile.clone(),
+                };
+                *profile = base.with_patterns(&requested.include, &requested.exclude);
+                info!("Session tool profile: {:?}", *profile);
+            }
+            init_peer.set_client_capabilities(capabilities);
+
             Ok(json!({
                 "protocolVersion": "2024-11-05",
                 "capabilities": {
-                    "tools": {}
+                    "tools": {},
+                    "logging": {},
+                    "completions": {},
+                    "experimental": {
+                        "toolProfile": {}
+                    }
                 },
                 "serverInfo": {
                     "name": "tcl-mcp-server",
@@ -87,575 +207,676 @@ impl TclMcpServer {
             }))
         });
 
-        let tb = tool_box.clone();
-        let tb2 = tool_box.clone();
-        let is_privileged = privileged;
-        handler.add_sync_method("tools/list", move |_params: Params| {
-            debug!("MCP tools/list called (privileged: {})", is_privileged);
-            let tb = tb.clone();
-            
-            // Don't use async block here since we're in a sync context
-            let mut tools = vec![];
-                
-                // Add system tools with MCP-compatible names
-                let mut system_tools = vec![
-                    (ToolPath::bin("tcl_execute"), "Execute a TCL script and return the result", json!({
-                        "$schema": "https://json-schema.org/draft/2020-12/schema",
-                        "type": "object",
-                        "properties": {
-                            "script": {
-                                "type": "string",
-                                "description": "TCL script to execute"
-                            }
-                        },
-                        "required": ["script"]
-                    })),
-                    (ToolPath::docs("molt_book"), "Access Molt TCL interpreter documentation and examples", json!({
-                        "$schema": "https://json-schema.org/draft/2020-12/schema",
-                        "type": "object",
-                        "properties": {
-                            "topic": {
-                                "type": "string",
-                                "description": "Documentation topic: 'overview', 'commands', 'examples', 'links', or 'basic_syntax'",
-                                "enum": ["overview", "commands", "examples", "links", "basic_syntax"]
-                            }
-                        },
-                        "required": ["topic"]
-                    })),
-                    (ToolPath::bin("exec_tool"), "Execute a tool by its path with parameters", json!({
-                        "$schema": "https://json-schema.org/draft/2020-12/schema",
-                        "type": "object",
-                        "properties": {
-                            "tool_path": {
-                                "type": "string",
-                                "description": "Full path to the tool (e.g., '/bin/list_dir')"
-                            },
-                            "params": {
-                                "type": "object",
-                                "description": "Parameters to pass to the tool",
-                                "default": {}
-                            }
-                        },
-                        "required": ["tool_path"]
-                    })),
-                    (ToolPath::bin("discover_tools"), "Discover and index tools from the filesystem", json!({
-                        "$schema": "https://json-schema.org/draft/2020-12/schema",
-                        "type": "object",
-                        "properties": {}
-                    })),
-                    (ToolPath::bin("execute_mcp"), "Execute a tool on a registered MCP server", json!({
-                        "$schema": "https://json-schema.org/draft/2020-12/schema",
-                        "type": "object",
-                        "properties": {
-                            "server_id": {
-                                "type": "string",
-                                "description": "ID of the MCP server to execute tool on"
-                            },
-                            "tool_name": {
-                                "type": "string",
-                                "description": "Name of the tool to execute"
-                            },
-                            "params": {
-                                "type": "object",
-                                "description": "Parameters to pass to the tool",
-                                "default": {}
-                            },
-                            "response_format": {
-                                "type": "string",
-                                "description": "Response format: json, text, or auto",
-                                "enum": ["json", "text", "auto"],
-                                "default": "auto"
-                            },
-                            "timeout_ms": {
-                                "type": "integer",
-                                "description": "Timeout in milliseconds",
-                                "default": 30000
-                            }
-                        },
-                        "required": ["server_id", "tool_name"]
-                    })),
-                    (ToolPath::bin("list_tools"), "List tools with flexible output formats and advanced filtering", json!({
-                        "$schema": "https://json-schema.org/draft/2020-12/schema",
-                        "type": "object",
-                        "properties": {
-                            "namespace": {
-                                "type": "string",
-                                "description": "Filter by namespace (bin, sbin, docs, user, xmcp)"
-                            },
-                            "server": {
-                                "type": "string", 
-                                "description": "Filter by server (for xmcp namespace)"
-                            },
-                            "search": {
-                                "type": "string",
-                                "description": "Text search filter"
-                            },
-                            "include_schemas": {
-                                "type": "boolean",
-                                "description": "Include detailed schemas",
-                                "default": false
-                            },
-                            "limit": {
-                                "type": "integer",
-                                "description": "Limit number of results"
-                            },
-                            "format": {
-                                "type": "string",
-                                "description": "Output format: 'simple' (paths only) or 'detailed' (rich metadata)",
-                                "default": "detailed"
-                            }
-                        }
-                    })),
-                    (ToolPath::bin("inspect_tool"), "Get detailed information about a specific tool including schema", json!({
-                        "$schema": "https://json-schema.org/draft/2020-12/schema",
-                        "type": "object",
-                        "properties": {
-                            "tool_path": {
-                                "type": "string",
-                                "description": "Tool path to inspect (e.g., '/bin/tcl_execute')"
-                            }
-                        },
-                        "required": ["tool_path"]
-                    })),
-                    (ToolPath::bin("list_namespaces"), "List available namespaces and their tool counts", json!({
-                        "$schema": "https://json-schema.org/draft/2020-12/schema",
-                        "type": "object",
-                        "properties": {}
-                    })),
-                    (ToolPath::bin("search_tools"), "Search tools by description or name", json!({
-                        "$schema": "https://json-schema.org/draft/2020-12/schema",
-                        "type": "object",
-                        "properties": {
-                            "query": {
-                                "type": "string",
-                                "description": "Search query string"
-                            },
-                            "limit": {
-                                "type": "integer",
-                                "description": "Limit number of results"
-                            }
-                        },
-                        "required": ["query"]
-                    })),
-                    (ToolPath::bin("list_xmcp_tools"), "List tools in xmcp namespace with server grouping", json!({
-                        "$schema": "https://json-schema.org/draft/2020-12/schema",
-                        "type": "object",
-                        "properties": {
-                            "server": {
-                                "type": "string",
-                                "description": "Filter by server ID"
-                            }
-                        }
-                    })),
-                    (ToolPath::bin("mcp_list"), "List registered MCP servers", json!({
-                        "$schema": "https://json-schema.org/draft/2020-12/schema",
-                        "type": "object",
-                        "properties": {}
+        handler.add_notification("notifications/initialized", |_params: Params| {
+            info!("MCP client finished initialization");
+        });
+
+        handler.add_sync_method("ping", |_params: Params| Ok(json!({})));
+
+        let log_peer = peer.clone();
+        handler.add_sync_method("logging/setLevel", move |params: Params| {
+            #[derive(Deserialize)]
+            struct SetLevelParams {
+                level: String,
+            }
+
+            let params: SetLevelParams = params.parse()?;
+            let level: LogLevel = params
+                .level
+                .parse()
+                .map_err(|e: anyhow::Error| jsonrpc_core::Error::invalid_params(e.to_string()))?;
+
+            info!("MCP log level set to {}", level);
+            log_peer.set_log_level(level);
+            Ok(json!({}))
+        });
+
+        let tb_complete = tool_box.clone();
+        handler.add_method("completion/complete", move |params: Params| {
+            let tb = tb_complete.clone();
+            async move {
+                let params: McpCompleteParams = params.parse()?;
+                debug!(
+                    "MCP completion/complete called for {}.{}",
+                    params.reference.name, params.argument.name
+                );
+
+                // Tools are completed by name; prompts share the same shape
+                if !matches!(params.reference.ref_type.as_str(), "ref/tool" | "ref/prompt") {
+                    return Err(jsonrpc_core::Error::invalid_params(format!(
+                        "Unsupported completion reference type: {}",
+                        params.reference.ref_type
+                    )));
+                }
+
+                let context = params
+                    .context
+                    .and_then(|context| context.get("arguments").cloned())
+                    .and_then(|arguments| arguments.as_object().cloned())
+                    .unwrap_or_default();
+
+                tb.complete(
+                    params.reference.name,
+                    params.argument.name,
+                    params.argument.value,
+                    context,
+                )
+                .await
+                .map_err(|e| jsonrpc_core::Error {
+                    code: jsonrpc_core::ErrorCode::InvalidParams,
+                    message: e.to_string(),
+                    data: None,
+                })
+            }
+        });
+
+        let tb_list = tool_box.clone();
+        let list_page_size = page_size.clone();
+        let list_latest_only = latest_only.clone();
+        let list_profile = profile.clone();
+        handler.add_method("tools/list", move |params: Params| {
+            let tb = tb_list.clone();
+            let page_size = list_page_size.load(Ordering::Relaxed);
+            let latest_only = list_latest_only.load(Ordering::Relaxed);
+            let profile = list_profile.lock().unwrap().clone();
+
+            async move {
+                let params: McpListToolsParams = match params {
+                    Params::None => McpListToolsParams::default(),
+                    params => params.parse()?,
+                };
+                let latest_only = params.latest_only.unwrap_or(latest_only);
+                Self::list_tools(tb, privileged, &profile, latest_only, params.cursor, page_size)
+                    .await
+            }
+        });
+
+        let tb_call = tool_box.clone();
+        handler.add_method("tools/call", move |params: Params| {
+            debug!("MCP tools/call called with params: {:?}", params);
+            let tb = tb_call.clone();
+
+            async move {
+                let params: McpCallToolParams = params.parse()?;
+                info!("Calling tool: {} (privileged: {})", params.name, privileged);
+
+                match Self::call_tool(&tb, privileged, params).await {
+                    Ok(text) => Ok(json!(McpCallToolResult {
+                        content: vec![McpContent::Text { text }],
                     })),
-                ];
-                
-                // Add privileged tools only if in privileged mode
-                if is_privileged {
-                    system_tools.push((ToolPath::sbin("tcl_tool_add"), "Add a new TCL tool to the available tools (PRIVILEGED)", json!({
-                        "$schema": "https://json-schema.org/draft/2020-12/schema",
-                        "type": "object",
-                        "properties": {
-                            "user": {
-                                "type": "string",
-                                "description": "User namespace"
-                            },
-                            "package": {
-                                "type": "string",
-                                "description": "Package name"
-                            },
-                            "name": {
-                                "type": "string",
-                                "description": "Name of the new tool"
-                            },
-                            "version": {
-                                "type": "string",
-                                "description": "Version of the tool (defaults to 'latest')",
-                                "default": "latest"
-                            },
-                            "description": {
-                                "type": "string",
-                                "description": "Description of what the tool does"
-                            },
-                            "script": {
-                                "type": "string",
-                                "description": "TCL script that implements the tool"
-                            },
-                            "parameters": {
-                                "type": "array",
-                                "description": "Parameters that the tool accepts",
-                                "items": {
-                                    "type": "object",
-                                    "properties": {
-                                        "name": { "type": "string" },
-                                        "description": { "type": "string" },
-                                        "required": { "type": "boolean" },
-                                        "type_name": { "type": "string" }
-                                    },
-                                    "required": ["name", "description", "required", "type_name"]
-                                }
-                            }
-                        },
-                        "required": ["user", "package", "name", "description", "script"]
-                    })));
-                    system_tools.push((ToolPath::sbin("tcl_tool_remove"), "Remove a TCL tool from the available tools (PRIVILEGED)", json!({
-                        "$schema": "https://json-schema.org/draft/2020-12/schema",
-                        "type": "object",
-                        "properties": {
-                            "path": {
-                                "type": "string",
-                                "description": "Full tool path (e.g., '/alice/utils/reverse_string:1.0')"
-                            }
-                        },
-                        "required": ["path"]
-                    })));
-                    
-                    system_tools.push((ToolPath::sbin("tcl_tool_reload"), "Reload tools from persistent storage (PRIVILEGED)", json!({
-                        "$schema": "https://json-schema.org/draft/2020-12/schema",
-                        "type": "object",
-                        "properties": {}
-                    })));
-                    
-                    system_tools.push((ToolPath::sbin("mcp_add"), "Register a new MCP server (PRIVILEGED)", json!({
-                        "$schema": "https://json-schema.org/draft/2020-12/schema",
-                        "type": "object",
-                        "properties": {
-                            "id": {
-                                "type": "string",
-                                "description": "Unique identifier for the server"
-                            },
-                            "name": {
-                                "type": "string",
-                                "description": "Human-readable name for the server"
-                            },
-                            "description": {
-                                "type": "string",
-                                "description": "Optional description of the server"
-                            },
-                            "command": {
-                                "type": "string",
-                                "description": "Command to start the server"
-                            },
-                            "args": {
-                                "type": "array",
-                                "description": "Command line arguments",
-                                "items": { "type": "string" },
-                                "default": []
-                            },
-                            "env": {
-                                "type": "object",
-                                "description": "Environment variables",
-                                "additionalProperties": { "type": "string" },
-                                "default": {}
-                            },
-                            "auto_start": {
-                                "type": "boolean",
-                                "description": "Whether to auto-start the server",
-                                "default": true
-                            },
-                            "timeout_ms": {
-                                "type": "integer",
-                                "description": "Connection timeout in milliseconds",
-                                "default": 30000
-                            },
-                            "max_retries": {
-                                "type": "integer",
-                                "description": "Maximum retry attempts",
-                                "default": 3
-                            }
-                        },
-                        "required": ["id", "name", "command"]
-                    })));
-                    system_tools.push((ToolPath::sbin("mcp_remove"), "Remove an MCP server (PRIVILEGED)", json!({
-                        "$schema": "https://json-schema.org/draft/2020-12/schema",
-                        "type": "object",
-                        "properties": {
-                            "server_id": {
-                                "type": "string",
-                                "description": "ID of the server to remove"
-                            },
-                            "force": {
-                                "type": "boolean",
-                                "description": "Whether to force removal (kill process)",
-                                "default": false
-                            }
-                        },
-                        "required": ["server_id"]
-                    })));
-                    
-                    // Add MCP debugging tools
-                    system_tools.push((ToolPath::sbin("mcp_connect"), "Manually connect to an MCP server (PRIVILEGED)", json!({
-                        "$schema": "https://json-schema.org/draft/2020-12/schema",
-                        "type": "object",
-                        "properties": {
-                            "server_id": {
-                                "type": "string",
-                                "description": "ID of the server to connect to"
-                            }
-                        },
-                        "required": ["server_id"]
-                    })));
-                    
-                    system_tools.push((ToolPath::sbin("mcp_disconnect"), "Manually disconnect from an MCP server (PRIVILEGED)", json!({
-                        "$schema": "https://json-schema.org/draft/2020-12/schema",
-                        "type": "object",
-                        "properties": {
-                            "server_id": {
-                                "type": "string",
-                                "description": "ID of the server to disconnect from"
-                            }
-                        },
-                        "required": ["server_id"]
-                    })));
-                    
-                    system_tools.push((ToolPath::sbin("mcp_info"), "Get detailed information about an MCP server (PRIVILEGED)", json!({
-                        "$schema": "https://json-schema.org/draft/2020-12/schema",
-                        "type": "object",
-                        "properties": {
-                            "server_id": {
-                                "type": "string",
-                                "description": "ID of the server to get info for"
-                            }
-                        },
-                        "required": ["server_id"]
-                    })));
-                    
-                    system_tools.push((ToolPath::sbin("mcp_ping"), "Test connectivity to an MCP server (PRIVILEGED)", json!({
-                        "$schema": "https://json-schema.org/draft/2020-12/schema",
-                        "type": "object",
-                        "properties": {
-                            "server_id": {
-                                "type": "string",
-                                "description": "ID of the server to ping"
-                            }
-                        },
-                        "required": ["server_id"]
-                    })));
+                    Err(e) => Err(jsonrpc_core::Error {
+                        code: jsonrpc_core::ErrorCode::InternalError,
+                        message: e.to_string(),
+                        data: None,
+                    }),
                 }
-                
-                for (path, description, schema) in system_tools {
-                    tools.push(McpToolInfo {
-                        name: path.to_mcp_name(),
-                        description: Some(description.to_string()),
-                        input_schema: schema,
+            }
+        });
+
+        Self {
+            tool_box,
+            handler,
+            peer,
+            session: Arc::new(Mutex::new(SessionState::Uninitialized)),
+            max_concurrent_requests: DEFAULT_MAX_CONCURRENT_REQUESTS,
+            page_size,
+            latest_only,
+            profile,
+        }
+    }
+
+    /// Limit how many requests `run_stdio` processes at the same time
+    pub fn with_max_concurrent_requests(mut self, limit: usize) -> Self {
+        self.max_concurrent_requests = limit.max(1);
+        self
+    }
+
+    /// Set how many tools a single `tools/list` response returns, at least 1
+    pub fn with_tools_page_size(self, page_size: usize) -> Self {
+        self.page_size.store(page_size.max(1), Ordering::Relaxed);
+        self
+    }
+
+    /// Make `tools/list` show only the version of each tool that `latest`
+    /// resolves to. Clients can override this per request with `latestOnly`.
+    pub fn with_latest_versions_only(self, latest_only: bool) -> Self {
+        self.latest_only.store(latest_only, Ordering::Relaxed);
+        self
+    }
+
+    /// Choose which tools `tools/list` advertises. Clients can override this
+    /// per session at `initialize`.
+    pub fn with_exposure_profile(self, profile: ExposureProfile) -> Self {
+        *self.profile.lock().unwrap() = profile;
+        self
+    }
+
+    /// Build one page of the `tools/list` result: system tools, TCL tools and
+    /// the tools of connected MCP servers exposed by the profile, ordered by name
+    async fn list_tools(
+        tb: TclToolBox,
+        privileged: bool,
+        profile: &ExposureProfile,
+        latest_only: bool,
+        cursor: Option<String>,
+        page_size: usize,
+    ) -> jsonrpc_core::Result<Value> {
+        debug!("MCP tools/list called (privileged: {}, cursor: {:?})", privileged, cursor);
+
+        let mut tools = vec![];
+
+        // Add system tools with MCP-compatible names
+        for tool in system_tools::available(privileged) {
+            if !profile.exposes(&tool.path()) {
+                continue;
+            }
+            tools.push(McpToolInfo {
+                name: tool.mcp_name(),
+                description: Some(tool.description.to_string()),
+                input_schema: tool.input_schema(),
+            });
+        }
+
+        // Get custom tools from the executor
+        let custom_tools = tb.get_tool_definitions(latest_only).await;
+
+        // Add custom tools to the list
+        if let Ok(tool_defs) = custom_tools {
+            for tool_def in tool_defs.into_iter().filter(|tool_def| profile.exposes(&tool_def.path)) {
+                // Build input schema for custom tool
+                let mut properties = serde_json::Map::new();
+                let mut required = Vec::new();
+
+                for param in &tool_def.parameters {
+                    // Validate and normalize JSON Schema type
+                    let json_type = match param.type_name.to_lowercase().as_str() {
+                        "string" | "str" | "text" => "string",
+                        "number" | "float" | "double" | "real" => "number",
+                        "integer" | "int" | "long" => "integer", 
+                        "boolean" | "bool" => "boolean",
+                        "array" | "list" => "array",
+                        "object" | "dict" | "map" => "object",
+                        "null" | "nil" | "none" => "null",
+                        // Default to string for unknown types to maintain compatibility
+                        _ => "string"
+                    };
+
+                    let mut property = json!({
+                        "type": json_type,
+                        "description": param.description,
                     });
+                    if let Some(default) = &param.default {
+                        property["default"] = default.clone();
+                    }
+                    properties.insert(param.name.clone(), property);
+
+                    if param.required {
+                        required.push(param.name.clone());
+                    }
                 }
-                
-            // Get custom tools synchronously - this should be fast
-            let tb_for_tools = tb.clone();
-            let custom_tools = match std::thread::spawn(move || {
-                let rt = tokio::runtime::Runtime::new().unwrap();
-                rt.block_on(tb_for_tools.get_tool_definitions())
-            }).join() {
-                Ok(result) => result,
-                Err(_) => {
-                    return Err(jsonrpc_core::Error::internal_error());
+
+                // Build the schema object, only including "required" if it's not empty
+                let mut schema_obj = serde_json::Map::new();
+                schema_obj.insert("$schema".to_string(), json!("https://json-schema.org/draft/2020-12/schema"));
+                schema_obj.insert("type".to_string(), json!("object"));
+                schema_obj.insert("properties".to_string(), json!(properties));
+
+                // Only add "required" array if there are required parameters
+                if !required.is_empty() {
+                    schema_obj.insert("required".to_string(), json!(required));
                 }
-            };
-            
-            // Add custom tools to the list
-            if let Ok(tool_defs) = custom_tools {
-                for tool_def in tool_defs {
-                    // Build input schema for custom tool
-                    let mut properties = serde_json::Map::new();
-                    let mut required = Vec::new();
-                    
-                    for param in &tool_def.parameters {
-                        // Validate and normalize JSON Schema type
-                        let json_type = match param.type_name.to_lowercase().as_str() {
-                            "string" | "str" | "text" => "string",
-                            "number" | "float" | "double" | "real" => "number",
-                            "integer" | "int" | "long" => "integer", 
-                            "boolean" | "bool" => "boolean",
-                            "array" | "list" => "array",
-                            "object" | "dict" | "map" => "object",
-                            "null" | "nil" | "none" => "null",
-                            // Default to string for unknown types to maintain compatibility
-                            _ => "string"
+
+                let input_schema = serde_json::Value::Object(schema_obj);
+
+                tools.push(McpToolInfo {
+                    name: tool_def.path.to_mcp_name(),
+                    description: Some(format!("{} [{}]", tool_def.description, tool_def.path)),
+                    input_schema,
+                });
+            }
+        }
+
+        // Add MCP server tools
+        match tb.get_mcp_server_tools().await {
+            Ok(mcp_tools) => {
+                // Add each MCP server's tools to the list
+                for (server_id, server_tools) in mcp_tools {
+                    for tool in server_tools {
+                        // Create MCP tool path under the MCP namespace
+                        let tool_path = ToolPath {
+                            namespace: crate::namespace::Namespace::Mcp(server_id.clone()),
+                            name: tool.name.clone(),
+                            package: None,
+                            version: "latest".to_string(),
                         };
-                        
-                        properties.insert(
-                            param.name.clone(),
-                            json!({
-                                "type": json_type,
-                                "description": param.description,
-                            }),
-                        );
-                        
-                        if param.required {
-                            required.push(param.name.clone());
+                        if !profile.exposes(&tool_path) {
+                            continue;
                         }
+
+                        tools.push(McpToolInfo {
+                            name: tool_path.to_mcp_name(),
+                            description: Some(format!("{} [MCP:{}]", 
+                                tool.description.as_ref().unwrap_or(&"No description".to_string()), 
+                                server_id
+                            )),
+                            input_schema: tool.input_schema,
+                        });
                     }
-                    
-                    // Build the schema object, only including "required" if it's not empty
-                    let mut schema_obj = serde_json::Map::new();
-                    schema_obj.insert("$schema".to_string(), json!("https://json-schema.org/draft/2020-12/schema"));
-                    schema_obj.insert("type".to_string(), json!("object"));
-                    schema_obj.insert("properties".to_string(), json!(properties));
-                    
-                    // Only add "required" array if there are required parameters
-                    if !required.is_empty() {
-                        schema_obj.insert("required".to_string(), json!(required));
+                }
+            }
+            Err(e) => {
+                debug!("Failed to get MCP server tools: {}", e);
+            }
+        }
+
+        // A client can only call one tool per name: the first listed wins
+        let mut seen = HashSet::new();
+        tools.retain(|tool| seen.insert(tool.name.clone()));
+
+        let page = pagination::paginate(tools, |tool| tool.name.as_str(), cursor.as_deref(), 0, page_size)
+            .map_err(|e| jsonrpc_core::Error::invalid_params(e.to_string()))?;
+
+        Ok(json!(McpListToolsResult {
+            tools: page.items,
+            next_cursor: page.next_cursor,
+        }))
+    }
+
+    /// Run the tool named in a `tools/call` request and return its text output
+    async fn call_tool(
+        tb: &TclToolBox,
+        privileged: bool,
+        params: McpCallToolParams,
+    ) -> Result<String> {
+        let Some(tool) = system_tools::find(&params.name) else {
+            // Try to execute as a custom tool
+            return tb.execute_custom_tool(&params.name, params.arguments).await;
+        };
+
+        if tool.privileged && !privileged {
+            return Err(anyhow!("{} requires --privileged mode", params.name));
+        }
+
+        let arguments = params.arguments;
+        match tool.handler {
+            SystemHandler::TclExecute => tb.tcl_execute(serde_json::from_value(arguments)?).await,
+            SystemHandler::ExecTool => {
+                let request: TclExecToolRequest = serde_json::from_value(arguments)?;
+                let path = tb.resolve_tool_path(&request.tool_path).await?;
+
+                // System tools go through the normal dispatch, so tools hidden
+                // from tools/list stay reachable here
+                match system_tools::find_path(&path) {
+                    Some(target) => {
+                        let params = McpCallToolParams {
+                            name: target.mcp_name(),
+                            arguments: request.params,
+                        };
+                        Box::pin(Self::call_tool(tb, privileged, params)).await
+                    }
+                    None => {
+                        tb.exec_tool(TclExecToolRequest {
+                            tool_path: path.to_mcp_name(),
+                            params: request.params,
+                        })
+                        .await
                     }
-                    
-                    let input_schema = serde_json::Value::Object(schema_obj);
-                    
-                    tools.push(McpToolInfo {
-                        name: tool_def.path.to_mcp_name(),
-                        description: Some(format!("{} [{}]", tool_def.description, tool_def.path)),
-                        input_schema,
-                    });
                 }
             }
-            
-            // Add MCP server tools
-            let tb_for_mcp = tb.clone();
-            match std::thread::spawn(move || {
-                let rt = tokio::runtime::Runtime::new().unwrap();
-                rt.block_on(async move {
-                    // Get list of MCP servers and their tools
-                    tb_for_mcp.get_mcp_server_tools().await
+            SystemHandler::DiscoverTools => tb.discover_tools().await,
+            SystemHandler::ExecuteMcp => tb.mcp_execute(serde_json::from_value(arguments)?).await,
+            SystemHandler::McpList => tb.mcp_list_servers().await,
+            SystemHandler::MoltBook => {
+                molt_book(arguments.get("topic").and_then(|v| v.as_str()).unwrap_or("overview"))
+            }
+            SystemHandler::ToolAdd => tb.tcl_tool_add(serde_json::from_value(arguments)?).await,
+            SystemHandler::ToolRemove => {
+                tb.tcl_tool_remove(serde_json::from_value(arguments)?).await
+            }
+            SystemHandler::ToolPin => {
+                tb.pin_tool_version(serde_json::from_value(arguments)?).await
+            }
+            SystemHandler::ToolHistory => {
+                let history = tb.tool_history(serde_json::from_value(arguments)?).await?;
+                Ok(serde_json::to_string_pretty(&history)?)
+            }
+            SystemHandler::ToolDiff => tb.tool_diff(serde_json::from_value(arguments)?).await,
+            SystemHandler::ToolRollback => {
+                tb.tool_rollback(serde_json::from_value(arguments)?).await
+            }
+            SystemHandler::ToolExport => tb.tool_export(serde_json::from_value(arguments)?).await,
+            SystemHandler::ToolImport => {
+                let imported = tb.tool_import(serde_json::from_value(arguments)?).await?;
+                Ok(serde_json::to_string_pretty(&imported)?)
+            }
+            SystemHandler::ToolReload => tb.reload_tools().await,
+            SystemHandler::McpAdd => tb.mcp_add_server(serde_json::from_value(arguments)?).await,
+            SystemHandler::McpRemove => {
+                tb.mcp_remove_server(serde_json::from_value(arguments)?).await
+            }
+            SystemHandler::McpConnect => {
+                tb.debug_connect_mcp(serde_json::from_value(arguments)?).await
+            }
+            SystemHandler::McpDisconnect => {
+                tb.debug_disconnect_mcp(serde_json::from_value(arguments)?).await
+            }
+            SystemHandler::McpInfo => tb.debug_mcp_info(serde_json::from_value(arguments)?).await,
+            SystemHandler::McpPing => tb.debug_ping_mcp(serde_json::from_value(arguments)?).await,
+            // Registry-backed tools run inside the executor
+            SystemHandler::ListTools
+            | SystemHandler::InspectTool
+            | SystemHandler::ListNamespaces
+            | SystemHandler::SearchTools
+            | SystemHandler::ListXmcpTools => {
+                tb.exec_tool(TclExecToolRequest {
+                    tool_path: params.name,
+                    params: arguments,
                 })
-            }).join() {
-                Ok(Ok(mcp_tools)) => {
-                    // Add each MCP server's tools to the list
-                    for (server_id, server_tools) in mcp_tools {
-                        for tool in server_tools {
-                            // Create MCP tool path under the MCP namespace
-                            let tool_path = crate::namespace::ToolPath {
-                                namespace: crate::namespace::Namespace::Mcp(server_id.clone()),
-                                name: tool.name.clone(),
-                                package: None,
-                                version: "latest".to_string(),
-                            };
-                            
-                            tools.push(McpToolInfo {
-                                name: tool_path.to_mcp_name(),
-                                description: Some(format!("{} [MCP:{}]", 
-                                    tool.description.as_ref().unwrap_or(&"No description".to_string()), 
-                                    server_id
-                                )),
-                                input_schema: tool.input_schema,
-                            });
-                        }
+                .await
+            }
+        }
+    }
+
+    pub async fn initialize_persistence(&self) -> Result<()> {
+        match self.tool_box.initialize_persistence().await {
+            Ok(message) => {
+                info!("{}", message);
+                Ok(())
+            }
+            Err(e) => {
+                tracing::warn!("Failed to initialize persistence: {}", e);
+                Err(e)
+            }
+        }
+    }
+
+    /// Handle a single request that expects a response (used by the CLI).
+    /// Session transports should use `handle_message` instead.
+    pub async fn handle_request(&self, request: serde_json::Value) -> Result<serde_json::Value> {
+        let request_str = serde_json::to_string(&request)?;
+        let response_str = self
+            .handler
+            .handle_request(&request_str)
+            .await
+            .ok_or_else(|| anyhow!("No response from handler"))?;
+
+        serde_json::from_str(&response_str).map_err(|e| anyhow!("Failed to parse response: {}", e))
+    }
+
+    /// Resolve a tool path in any supported form, including bare tool names
+    pub async fn resolve_tool_path(&self, path: &str) -> Result<ToolPath> {
+        self.tool_box.resolve_tool_path(path).await
+    }
+
+    /// Call a tool through `tools/call`, returning the JSON-RPC response.
+    /// Tools other than system tools run through `bin__exec_tool`, which
+    /// also reaches discovered tools.
+    pub async fn call_tool_path(&self, path: &ToolPath, args: Value) -> Result<Value> {
+        let (name, arguments) = if system_tools::find_path(path).is_some() {
+            (path.to_mcp_name(), args)
+        } else {
+            (
+                ToolPath::bin("exec_tool").to_mcp_name(),
+                json!({ "tool_path": path.to_mcp_name(), "params": args }),
+            )
+        };
+        self.handle_request(json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "tools/call",
+            "params": {
+                "name": name,
+                "arguments": arguments
+            }
+        }))
+        .await
+    }
+
+    /// The definition and script of a user or filesystem tool
+    pub async fn tool_definition(&self, path: &str) -> Result<ToolDefinition> {
+        self.tool_box.tool_definition(path).await
+    }
+
+    /// Collect every tool from `tools/list`, following `nextCursor` across pages
+    pub async fn list_all_tools(&self) -> Result<Vec<Value>> {
+        let mut tools = Vec::new();
+        let mut cursor: Option<String> = None;
+
+        loop {
+            let mut request = json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "method": "tools/list"
+            });
+            if let Some(cursor) = &cursor {
+                request["params"] = json!({ "cursor": cursor });
+            }
+
+            let response = self.handle_request(request).await?;
+            if let Some(error) = response.get("error") {
+                return Err(anyhow!("tools/list failed: {}", error));
+            }
+
+            let result = response.get("result").cloned().unwrap_or_default();
+            if let Some(page) = result.get("tools").and_then(|t| t.as_array()) {
+                tools.extend(page.iter().cloned());
+            }
+
+            match result.get("nextCursor").and_then(|c| c.as_str()) {
+                Some(next) => cursor = Some(next.to_string()),
+                None => return Ok(tools),
+            }
+        }
+    }
+
+    /// Handle one incoming JSON-RPC message, single or batch, enforcing the
+    /// MCP lifecycle. Returns `None` when nothing must be sent back, i.e. for
+    /// notifications and batches made up only of notifications.
+    pub async fn handle_message(&self, message: Value) -> Option<Value> {
+        match message {
+            Value::Array(batch) if batch.is_empty() => {
+                Some(error_response(Value::Null, -32600, "Invalid Request"))
+            }
+            Value::Array(batch) => {
+                let mut responses = Vec::new();
+                for item in batch {
+                    if let Some(response) = self.handle_single_message(item).await {
+                        responses.push(response);
                     }
                 }
-                Ok(Err(e)) => {
-                    debug!("Failed to get MCP server tools: {}", e);
-                }
-                Err(_) => {
-                    debug!("Thread panic while getting MCP server tools");
-                }
+                (!responses.is_empty()).then_some(Value::Array(responses))
+            }
+            message => self.handle_single_message(message).await,
+        }
+    }
+
+    async fn handle_single_message(&self, message: Value) -> Option<Value> {
+        let id = message.get("id").cloned();
+        let method = message
+            .get("method")
+            .and_then(Value::as_str)
+            .map(str::to_string);
+
+        if let Some(method) = &method {
+            if let Err((code, error)) = self.check_lifecycle(method) {
+                debug!("Rejecting '{}': {}", method, error);
+                // Notifications never get a reply, even when rejected
+                return id.map(|id| error_response(id, code, error));
+            }
+        }
+
+        let request_str = serde_json::to_string(&message).ok()?;
+        let response_str = self.handler.handle_request(&request_str).await?;
+        let response: Value = match serde_json::from_str(&response_str) {
+            Ok(response) => response,
+            Err(e) => {
+                return Some(error_response(
+                    id.unwrap_or(Value::Null),
+                    -32603,
+                    &format!("Failed to parse response: {}", e),
+                ))
+            }
+        };
+
+        if method.as_deref() == Some("initialize") && response.get("result").is_some() {
+            *self.session.lock().unwrap() = SessionState::Initializing;
+        }
+
+        Some(response)
+    }
+
+    fn is_initialized(&self) -> bool {
+        *self.session.lock().unwrap() != SessionState::Uninitialized
+    }
+
+    /// Check whether a method may be called in the current session state
+    fn check_lifecycle(&self, method: &str) -> std::result::Result<(), (i64, &'static str)> {
+        let mut state = self.session.lock().unwrap();
+        match (method, *state) {
+            ("ping", _) => Ok(()),
+            ("initialize", SessionState::Uninitialized) => Ok(()),
+            ("initialize", _) => Err((-32600, "Server already initialized")),
+            ("notifications/initialized", SessionState::Initializing) => {
+                *state = SessionState::Ready;
+                Ok(())
+            }
+            (_, SessionState::Uninitialized) => {
+                Err((SERVER_NOT_INITIALIZED, "Server not initialized"))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    pub async fn run_stdio(self) -> Result<()> {
+        info!("Starting TCL MCP server on stdio");
+
+        // All outbound traffic (responses and server-initiated notifications)
+        // goes through a single writer so lines are never interleaved
+        let (outbound_tx, mut outbound_rx) = mpsc::unbounded_channel::<Value>();
+        self.peer.attach(outbound_tx.clone());
+
+        let writer = tokio::spawn(async move {
+            let mut stdout = tokio::io::stdout();
+            while let Some(message) = outbound_rx.recv().await {
+                let message_str = serde_json::to_string(&message)?;
+                stdout.write_all(message_str.as_bytes()).await?;
+                stdout.write_all(b"\n").await?;
+                stdout.flush().await?;
             }
-            
-            Ok(json!(McpListToolsResult { tools }))
+            Ok::<(), anyhow::Error>(())
         });
 
-        let is_privileged_call = privileged;
-        handler.add_sync_method("tools/call", move |params: Params| {
-            debug!("MCP tools/call called with params: {:?}", params);
-            let tb = tb2.clone();
-            
-            let params: McpCallToolParams = params.parse()?;
-            info!("Calling tool: {} (privileged: {})", params.name, is_privileged_call);
-            
-            let result = std::thread::spawn(move || {
-                let rt = tokio::runtime::Runtime::new().unwrap();
-                rt.block_on(async move {
-                // Check if it's a system tool by MCP name
-                match params.name.as_str() {
-                    "bin__tcl_execute" => {
-                        let request: TclExecuteRequest = serde_json::from_value(params.arguments)?;
-                        tb.tcl_execute(request).await
-                    }
-                    "sbin__tcl_tool_add" => {
-                        if !is_privileged_call {
-                            return Err(anyhow::anyhow!("Tool management requires --privileged mode"));
-                        }
-                        let request: TclToolAddRequest = serde_json::from_value(params.arguments)?;
-                        tb.tcl_tool_add(request).await
-                    }
-                    "sbin__tcl_tool_remove" => {
-                        if !is_privileged_call {
-                            return Err(anyhow::anyhow!("Tool management requires --privileged mode"));
-                        }
-                        let request: TclToolRemoveRequest = serde_json::from_value(params.arguments)?;
-                        tb.tcl_tool_remove(request).await
-                    }
-                    "sbin__tcl_tool_reload" => {
-                        if !is_privileged_call {
-                            return Err(anyhow::anyhow!("Tool management requires --privileged mode"));
-                        }
-                        tb.reload_tools().await
-                    }
-                    "bin__exec_tool" => {
-                        let request: TclExecToolRequest = serde_json::from_value(params.arguments)?;
-                        tb.exec_tool(request).await
-                    }
-                    "bin__discover_tools" => {
-                        tb.discover_tools().await
-                    }
-                    "bin__execute_mcp" => {
-                        let request: crate::tcl_tools::McpExecuteRequest = serde_json::from_value(params.arguments)?;
-                        tb.mcp_execute(request).await
-                    }
-                    "sbin__mcp_add" => {
-                        if !is_privileged_call {
-                            return Err(anyhow::anyhow!("MCP server management requires --privileged mode"));
-                        }
-                        let request: crate::tcl_tools::McpServerAddRequest = serde_json::from_value(params.arguments)?;
-                        tb.mcp_add_server(request).await
-                    }
-                    "sbin__mcp_remove" => {
-                        if !is_privileged_call {
-                            return Err(anyhow::anyhow!("MCP server management requires --privileged mode"));
-                        }
-                        let request: crate::tcl_tools::McpServerRemoveRequest = serde_json::from_value(params.arguments)?;
-                        tb.mcp_remove_server(request).await
-                    }
-                    "bin__mcp_list" => {
-                        tb.mcp_list_servers().await
-                    }
-                    "sbin__mcp_connect" => {
-                        if !is_privileged_call {
-                            return Err(anyhow::anyhow!("MCP debugging requires --privileged mode"));
-                        }
-                        let request: crate::tcl_tools::McpDebugRequest = serde_json::from_value(params.arguments)?;
-                        tb.debug_connect_mcp(request).await
-                    }
-                    "sbin__mcp_disconnect" => {
-                        if !is_privileged_call {
-                            return Err(anyhow::anyhow!("MCP debugging requires --privileged mode"));
-                        }
-                        let request: crate::tcl_tools::McpDebugRequest = serde_json::from_value(params.arguments)?;
-                        tb.debug_disconnect_mcp(request).await
-                    }
-                    "sbin__mcp_info" => {
-                        if !is_privileged_call {
-                            return Err(anyhow::anyhow!("MCP debugging requires --privileged mode"));
-                        }
-                        let request: crate::tcl_tools::McpDebugRequest = serde_json::from_value(params.arguments)?;
-                        tb.debug_mcp_info(request).await
+        // Read stdin on its own task so responses to server-initiated
+        // requests (elicitation, sampling) are delivered while a tool call
+        // is still waiting on them
+        let (inbound_tx, mut inbound_rx) = mpsc::unbounded_channel::<Value>();
+        let reader_peer = self.peer.clone();
+        let reader_outbound = outbound_tx.clone();
+        let reader = tokio::spawn(async move {
+            let stdin = tokio::io::stdin();
+            let mut reader = BufReader::new(stdin);
+            let mut line = String::new();
+
+            loop {
+                line.clear();
+                let n = reader.read_line(&mut line).await?;
+
+                if n == 0 {
+                    break; // EOF
+                }
+
+                let line = line.trim();
+                if line.is_empty() {
+                    continue;
+                }
+
+                // Parse JSON-RPC message
+                let message: Value = match serde_json::from_str(line) {
+                    Ok(message) => message,
+                    Err(e) => {
+                        debug!("Failed to parse request: {}", e);
+                        let _ = reader_outbound.send(error_response(
+                            Value::Null,
+                            -32700,
+                            "Parse error",
+                        ));
+                        continue;
                     }
-                    "sbin__mcp_ping" => {
-                        if !is_privileged_call {
-                            return Err(anyhow::anyhow!("MCP debugging requires --privileged mode"));
-                        }
-                        let request: crate::tcl_tools::McpDebugRequest = serde_json::from_value(params.arguments)?;
-                        tb.debug_ping_mcp(request).await
+                };
+
+                if let Some(message) = route_responses(&reader_peer, message) {
+                    if inbound_tx.send(message).is_err() {
+                        break;
                     }
-                    "docs__molt_book" => {
-                        // Handle documentation request
-                        let topic = params.arguments.get("topic")
-                            .and_then(|v| v.as_str())
-                            .unwrap_or("overview");
-                        
-                        match topic {
-                            "overview" => Ok(format!(r#"# Molt TCL Interpreter Overview
+                }
+            }
+
+            // Nobody is left to answer outstanding server requests
+            reader_peer.cancel_pending();
+            Ok::<(), anyhow::Error>(())
+        });
+
+        // Requests run as separate tasks so a slow tool call does not hold up
+        // the rest of the session; the semaphore bounds how many are in flight
+        let server = Arc::new(self);
+        let limit = server.max_concurrent_requests;
+        let in_flight = Arc::new(Semaphore::new(limit));
+
+        while let Some(message) = inbound_rx.recv().await {
+            if outbound_tx.is_closed() {
+                reader.abort(); // Writer stopped
+                break;
+            }
+
+            // `initialize` is answered before anything else is looked at
+            if !server.is_initialized() {
+                if let Some(response) = server.handle_message(message).await {
+                    let _ = outbound_tx.send(response);
+                }
+                continue;
+            }
+
+            let permit = in_flight.clone().acquire_owned().await?;
+            let task_server = server.clone();
+            let task_outbound = outbound_tx.clone();
+            tokio::spawn(async move {
+                if let Some(response) = task_server.handle_message(message).await {
+                    let _ = task_outbound.send(response);
+                }
+                drop(permit);
+            });
+        }
+
+        // Let in-flight requests finish before shutting down the writer
+        let _ = in_flight.acquire_many(limit as u32).await;
+
+        server.peer.detach();
+        drop(outbound_tx);
+        if let Ok(result) = reader.await {
+            result?;
+        }
+        writer.await??;
+
+        Ok(())
+    }
+}
+
+/// Build a JSON-RPC error response
+fn error_response(id: Value, code: i64, message: &str) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "error": {
+            "code": code,
+            "message": message
+        },
+        "id": id
+    })
+}
+
+/// Content of the `docs__molt_book` topics
+fn molt_book(topic: &str) -> Result<String> {
+    match topic {
+        "overview" => Ok(r#"# Molt TCL Interpreter Overview
 
 ## What is Molt?
 Molt is a TCL (Tool Command Language) interpreter implemented in Rust. It provides a memory-safe, 
@@ -673,8 +894,8 @@ embeddable scripting language with familiar TCL syntax.
 - GitHub Repository: https://github.com/wduquette/molt
 - Source Documentation: https://github.com/wduquette/molt/tree/master/molt-book/src
 
-Use 'basic_syntax', 'commands', 'examples', or 'links' for more specific information."#)),
-                            "basic_syntax" => Ok(format!(r#"# TCL Basic Syntax
+Use 'basic_syntax', 'commands', 'examples', or 'links' for more specific information."#.to_string()),
+        "basic_syntax" => Ok(r#"# TCL Basic Syntax
 
 ## Variables
 ```tcl
@@ -693,33 +914,33 @@ set length [llength $fruits]  ;# 3
 ## Control Structures
 ```tcl
 # If statement
-if {{$age >= 18}} {{
+if {$age >= 18} {
     puts "Adult"
-}} else {{
+} else {
     puts "Minor"
-}}
+}
 
 # For loop
-for {{set i 0}} {{$i < 5}} {{incr i}} {{
+for {set i 0} {$i < 5} {incr i} {
     puts "Count: $i"
-}}
+}
 
 # Foreach loop
-foreach fruit $fruits {{
+foreach fruit $fruits {
     puts "Fruit: $fruit"
-}}
+}
 ```
 
 ## Procedures
 ```tcl
-proc greet {{name}} {{
+proc greet {name} {
     return "Hello, $name!"
-}}
+}
 
 set message [greet "World"]
 puts $message
-```"#)),
-                            "commands" => Ok(format!(r#"# Common TCL Commands in Molt
+```"#.to_string()),
+        "commands" => Ok(r#"# Common TCL Commands in Molt
 
 ## String Operations
 - `string length $str` - Get string length
@@ -743,35 +964,35 @@ puts $message
 - `&& || !` - Logical operators
 
 ## Control Flow
-- `if {{condition}} {{...}} else {{...}}` - Conditional
-- `for {{init}} {{condition}} {{update}} {{...}}` - For loop
-- `foreach var $list {{...}}` - Iterate over list
-- `while {{condition}} {{...}}` - While loop
+- `if {condition} {...} else {...}` - Conditional
+- `for {init} {condition} {update} {...}` - For loop
+- `foreach var $list {...}` - Iterate over list
+- `while {condition} {...}` - While loop
 - `break` / `continue` - Loop control
 
 ## I/O and Variables
 - `puts $string` - Print to stdout
 - `set varName $value` - Set variable
 - `unset varName` - Delete variable
-- `global varName` - Access global variable"#)),
-                            "examples" => Ok(format!(r#"# TCL Examples
+- `global varName` - Access global variable"#.to_string()),
+        "examples" => Ok(r#"# TCL Examples
 
 ## Example 1: Calculator
 ```tcl
-proc calculate {{op a b}} {{
-    switch $op {{
-        "+" {{ return [expr {{$a + $b}}] }}
-        "-" {{ return [expr {{$a - $b}}] }}
-        "*" {{ return [expr {{$a * $b}}] }}
-        "/" {{ 
-            if {{$b == 0}} {{
+proc calculate {op a b} {
+    switch $op {
+        "+" { return [expr {$a + $b}] }
+        "-" { return [expr {$a - $b}] }
+        "*" { return [expr {$a * $b}] }
+        "/" { 
+            if {$b == 0} {
                 error "Division by zero"
-            }}
-            return [expr {{$a / $b}}] 
-        }}
-        default {{ error "Unknown operation: $op" }}
-    }}
-}}
+            }
+            return [expr {$a / $b}] 
+        }
+        default { error "Unknown operation: $op" }
+    }
+}
 
 puts [calculate + 5 3]    ;# 8
 puts [calculate * 4 7]    ;# 28
@@ -782,42 +1003,42 @@ puts [calculate * 4 7]    ;# 28
 set numbers [list 1 2 3 4 5]
 set sum 0
 
-foreach num $numbers {{
-    set sum [expr {{$sum + $num}}]
-}}
+foreach num $numbers {
+    set sum [expr {$sum + $num}]
+}
 
 puts "Sum: $sum"  ;# Sum: 15
 
 # Find maximum
 set max [lindex $numbers 0]
-foreach num $numbers {{
-    if {{$num > $max}} {{
+foreach num $numbers {
+    if {$num > $max} {
         set max $num
-    }}
-}}
+    }
+}
 puts "Max: $max"  ;# Max: 5
 ```
 
 ## Example 3: String Processing
 ```tcl
-proc word_count {{text}} {{
+proc word_count {text} {
     set words [split $text]
     return [llength $words]
-}}
+}
 
-proc reverse_string {{str}} {{
+proc reverse_string {str} {
     set result ""
     set len [string length $str]
-    for {{set i [expr {{$len - 1}}]}} {{$i >= 0}} {{incr i -1}} {{
+    for {set i [expr {$len - 1}]} {$i >= 0} {incr i -1} {
         append result [string index $str $i]
-    }}
+    }
     return $result
-}}
+}
 
 puts [word_count "Hello world from TCL"]  ;# 4
 puts [reverse_string "hello"]              ;# olleh
-```"#)),
-                            "links" => Ok(format!(r#"# Molt TCL Documentation Links
+```"#.to_string()),
+        "links" => Ok(r#"# Molt TCL Documentation Links
 
 ## Official Documentation
 - **Molt Book**: https://wduquette.github.io/molt/
@@ -845,157 +1066,30 @@ puts [reverse_string "hello"]              ;# olleh
 - **Test Suite**: https://github.com/wduquette/molt/tree/master/tests
 
 Note: Molt implements a subset of full TCL but covers the core language features.
-For Molt-specific capabilities and limitations, refer to the Molt Book."#)),
-                            _ => Err(anyhow::anyhow!("Unknown documentation topic: {}. Available topics: overview, basic_syntax, commands, examples, links", topic))
-                        }
-                    }
-                    "bin__list_tools" => {
-                        let request: crate::tcl_tools::TclExecToolRequest = serde_json::from_value(json!({
-                            "tool_path": "bin__list_tools",
-                            "params": params.arguments
-                        }))?;
-                        tb.exec_tool(request).await
-                    }
-                    "bin__inspect_tool" => {
-                        let request: crate::tcl_tools::TclExecToolRequest = serde_json::from_value(json!({
-                            "tool_path": "bin__inspect_tool", 
-                            "params": params.arguments
-                        }))?;
-                        tb.exec_tool(request).await
-                    }
-                    "bin__list_namespaces" => {
-                        let request: crate::tcl_tools::TclExecToolRequest = serde_json::from_value(json!({
-                            "tool_path": "bin__list_namespaces",
-                            "params": params.arguments
-                        }))?;
-                        tb.exec_tool(request).await
-                    }
-                    "bin__search_tools" => {
-                        let request: crate::tcl_tools::TclExecToolRequest = serde_json::from_value(json!({
-                            "tool_path": "bin__search_tools",
-                            "params": params.arguments
-                        }))?;
-                        tb.exec_tool(request).await
-                    }
-                    "bin__list_xmcp_tools" => {
-                        let request: crate::tcl_tools::TclExecToolRequest = serde_json::from_value(json!({
-                            "tool_path": "bin__list_xmcp_tools",
-                            "params": params.arguments
-                        }))?;
-                        tb.exec_tool(request).await
-                    }
-                    mcp_name => {
-                        // Try to execute as a custom tool
-                        tb.execute_custom_tool(mcp_name, params.arguments).await
-                    }
-                }
-                })
-            }).join();
-            
-            match result {
-                Ok(Ok(text)) => Ok(json!(McpCallToolResult {
-                    content: vec![McpContent::Text { text }],
-                })),
-                Ok(Err(e)) => Err(jsonrpc_core::Error {
-                    code: jsonrpc_core::ErrorCode::InternalError,
-                    message: e.to_string(),
-                    data: None,
-                }),
-                Err(_) => Err(jsonrpc_core::Error {
-                    code: jsonrpc_core::ErrorCode::InternalError,
-                    message: "Thread panic".to_string(),
-                    data: None,
-                }),
-            }
-        });
-
-        Self { tool_box, handler }
+For Molt-specific capabilities and limitations, refer to the Molt Book."#.to_string()),
+        _ => Err(anyhow::anyhow!("Unknown documentation topic: {}. Available topics: overview, basic_syntax, commands, examples, links", topic))
     }
+}
 
-    pub async fn initialize_persistence(&self) -> Result<()> {
-        match self.tool_box.initialize_persistence().await {
-            Ok(message) => {
-                info!("{}", message);
-                Ok(())
-            }
-            Err(e) => {
-                tracing::warn!("Failed to initialize persistence: {}", e);
-                Err(e.into())
-            }
+/// Deliver client responses (single or inside a batch) to the requests
+/// waiting on them, returning whatever is left to be handled as requests
+fn route_responses(peer: &McpPeer, message: Value) -> Option<Value> {
+    let route = |message: &Value| {
+        if !McpPeer::is_response(message) {
+            return false;
         }
-    }
-
-    pub async fn handle_request(&self, request: serde_json::Value) -> Result<serde_json::Value> {
-        let request_str = serde_json::to_string(&request)?;
-        let response_str = self
-            .handler
-            .handle_request(&request_str)
-            .await
-            .ok_or_else(|| anyhow!("No response from handler"))?;
-
-        serde_json::from_str(&response_str).map_err(|e| anyhow!("Failed to parse response: {}", e))
-    }
-
-    pub async fn run_stdio(self) -> Result<()> {
-        info!("Starting TCL MCP server on stdio");
-
-        let stdin = tokio::io::stdin();
-        let mut stdout = tokio::io::stdout();
-        let mut reader = BufReader::new(stdin);
-        let mut line = String::new();
-
-        loop {
-            line.clear();
-            let n = reader.read_line(&mut line).await?;
-
-            if n == 0 {
-                break; // EOF
-            }
-
-            let line = line.trim();
-            if line.is_empty() {
-                continue;
-            }
-
-            // Parse JSON-RPC request
-            let request: serde_json::Value = match serde_json::from_str(line) {
-                Ok(req) => req,
-                Err(e) => {
-                    debug!("Failed to parse request: {}", e);
-                    continue;
-                }
-            };
-
-            // Get the request ID before moving the request
-            let request_id = request.get("id").cloned();
-
-            // Handle request
-            match self.handle_request(request).await {
-                Ok(response) => {
-                    let response_str = serde_json::to_string(&response)?;
-                    stdout.write_all(response_str.as_bytes()).await?;
-                    stdout.write_all(b"\n").await?;
-                    stdout.flush().await?;
-                }
-                Err(e) => {
-                    debug!("Error handling request: {}", e);
-                    // Send error response
-                    let error_response = json!({
-                        "jsonrpc": "2.0",
-                        "error": {
-                            "code": -32603,
-                            "message": e.to_string()
-                        },
-                        "id": request_id
-                    });
-                    let response_str = serde_json::to_string(&error_response)?;
-                    stdout.write_all(response_str.as_bytes()).await?;
-                    stdout.write_all(b"\n").await?;
-                    stdout.flush().await?;
-                }
-            }
+        if !peer.handle_response(message) {
+            debug!("Ignoring response to unknown request: {}", message["id"]);
         }
+        true
+    };
 
-        Ok(())
+    match message {
+        Value::Array(batch) if !batch.is_empty() => {
+            let remaining: Vec<Value> = batch.into_iter().filter(|m| !route(m)).collect();
+            (!remaining.is_empty()).then_some(Value::Array(remaining))
+        }
+        message if route(&message) => None,
+        message => Some(message),
     }
 }
@@ -0,0 +1,453 @@
+/// Tool signing and the trust policy for loading signed tools
+///
+/// Stored tools carry an ed25519 signature over their SHA-256 checksum in
+/// their metadata. Discovered `.tcl` files are signed with a detached
+/// `<file>.sig` next to them, over the checksum of the file's bytes.
+///
+/// Public keys are trusted by name in `trust.json` in the config directory,
+/// which also holds the policy:
+///
+/// ```json
+/// {
+///   "policy": "enforce",
+///   "keys": { "alice": { "public_key": "<base64>", "added_at": "..." } }
+/// }
+/// ```
+///
+/// With `off` every tool loads, `warn` logs tools that are not signed by a
+/// trusted key, and `enforce` refuses them. Signing keys generated with
+/// `trust keygen` live in the `keys` directory next to `trust.json`.
+use anyhow::{anyhow, Result};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use crate::persistence;
+use crate::platform_dirs;
+
+/// Extension of detached signature files
+const SIGNATURE_EXTENSION: &str = "sig";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TrustPolicy {
+    #[default]
+    Off,
+    Warn,
+    Enforce,
+}
+
+impl FromStr for TrustPolicy {
+    type Err = anyhow::Error;
+
+    fn from_str(text: &str) -> Result<Self> {
+        match text {
+            "off" => Ok(Self::Off),
+            "warn" => Ok(Self::Warn),
+            "enforce" => Ok(Self::Enforce),
+            _ => Err(anyhow!(
+                "Unknown trust policy '{}' (expected off, warn or enforce)",
+                text
+            )),
+        }
+    }
+}
+
+impl fmt::Display for TrustPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Off => "off",
+            Self::Warn => "warn",
+            Self::Enforce => "enforce",
+        })
+    }
+}
+
+/// An ed25519 signature and the key that made it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolSignature {
+    /// Base64 ed25519 public key
+    pub public_key: String,
+    /// Base64 ed25519 signature
+    pub signature: String,
+    pub signed_at: DateTime<Utc>,
+}
+
+/// Outcome of checking a tool's signature against the trusted keys
+#[derive(Debug, Clone, PartialEq)]
+pub enum SignatureStatus {
+    Unsigned,
+    Invalid,
+    Untrusted { public_key: String },
+    Trusted { signer: String },
+}
+
+impl fmt::Display for SignatureStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unsigned => write!(f, "unsigned"),
+            Self::Invalid => write!(f, "signature does not match its content"),
+            Self::Untrusted { public_key } => write!(f, "signed by untrusted key {}", public_key),
+            Self::Trusted { signer } => write!(f, "signed by trusted key '{}'", signer),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrustedKey {
+    pub public_key: String,
+    pub added_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct TrustConfig {
+    #[serde(default)]
+    policy: TrustPolicy,
+    #[serde(default)]
+    keys: BTreeMap<String, TrustedKey>,
+}
+
+/// Trusted public keys and the policy applied to tools as they load
+#[derive(Debug, Clone)]
+pub struct TrustStore {
+    path: PathBuf,
+    config: TrustConfig,
+}
+
+impl TrustStore {
+    /// The trust store in the config directory
+    pub fn load() -> Result<Self> {
+        Self::load_from(&platform_dirs::trust_path()?)
+    }
+
+    /// The trust store at `path`; a missing file is an empty store with
+    /// the policy off
+    pub fn load_from(path: &Path) -> Result<Self> {
+        let config = if path.exists() {
+            serde_json::from_str(&std::fs::read_to_string(path)?)
+                .map_err(|e| anyhow!("Invalid trust store {}: {}", path.display(), e))?
+        } else {
+            TrustConfig::default()
+        };
+        Ok(Self {
+            path: path.to_path_buf(),
+            config,
+        })
+    }
+
+    pub fn save(&self) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&self.path, serde_json::to_string_pretty(&self.config)?)?;
+        Ok(())
+    }
+
+    pub fn policy(&self) -> TrustPolicy {
+        self.config.policy
+    }
+
+    pub fn set_policy(&mut self, policy: TrustPolicy) {
+        self.config.policy = policy;
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = (&str, &TrustedKey)> {
+        self.config
+            .keys
+            .iter()
+            .map(|(name, key)| (name.as_str(), key))
+    }
+
+    /// Trust a base64 public key under a name, replacing any key of that name
+    pub fn trust(&mut self, name: &str, public_key: &str) -> Result<()> {
+        decode_public_key(public_key)?;
+        self.config.keys.insert(
+            name.to_string(),
+            TrustedKey {
+                public_key: public_key.to_string(),
+                added_at: Utc::now(),
+            },
+        );
+        Ok(())
+    }
+
+    /// Stop trusting a key. Returns whether it was trusted.
+    pub fn untrust(&mut self, name: &str) -> bool {
+        self.config.keys.remove(name).is_some()
+    }
+
+    /// Check a signature over `message`
+    pub fn verify(&self, message: &[u8], signature: Option<&ToolSignature>) -> SignatureStatus {
+        let Some(signature) = signature else {
+            return SignatureStatus::Unsigned;
+        };
+        if !signature_matches(message, signature) {
+            return SignatureStatus::Invalid;
+        }
+        match self
+            .config
+            .keys
+            .iter()
+            .find(|(_, key)| key.public_key == signature.public_key)
+        {
+            Some((name, _)) => SignatureStatus::Trusted {
+                signer: name.clone(),
+            },
+            None => SignatureStatus::Untrusted {
+                public_key: signature.public_key.clone(),
+            },
+        }
+    }
+
+    /// Apply the policy to a tool about to load. Errors when the tool must
+    /// be refused.
+    pub fn admit(&self, tool: &str, status: &SignatureStatus) -> Result<()> {
+        if matches!(status, SignatureStatus::Trusted { .. }) {
+            return Ok(());
+        }
+        match self.config.policy {
+            TrustPolicy::Off => Ok(()),
+            TrustPolicy::Warn => {
+                tracing::warn!("Loading tool {} that is {}", tool, status);
+                Ok(())
+            }
+            TrustPolicy::Enforce => Err(anyhow!("Refusing tool {}: {}", tool, status)),
+        }
+    }
+}
+
+/// Sign a message with a signing key
+pub fn sign(key: &SigningKey, message: &[u8]) -> ToolSignature {
+    ToolSignature {
+        public_key: BASE64.encode(key.verifying_key().as_bytes()),
+        signature: BASE64.encode(key.sign(message).to_bytes()),
+        signed_at: Utc::now(),
+    }
+}
+
+fn signature_matches(message: &[u8], signature: &ToolSignature) -> bool {
+    let Ok(public_key) = decode_public_key(&signature.public_key) else {
+        return false;
+    };
+    let Some(bytes) = BASE64
+        .decode(&signature.signature)
+        .ok()
+        .and_then(|bytes| <[u8; 64]>::try_from(bytes).ok())
+    else {
+        return false;
+    };
+    public_key
+        .verify(message, &Signature::from_bytes(&bytes))
+        .is_ok()
+}
+
+fn decode_public_key(public_key: &str) -> Result<VerifyingKey> {
+    let bytes: [u8; 32] = BASE64
+        .decode(public_key)
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| {
+            anyhow!(
+                "Invalid public key '{}': expected 32 base64 bytes",
+                public_key
+            )
+        })?;
+    VerifyingKey::from_bytes(&bytes)
+        .map_err(|e| anyhow!("Invalid public key '{}': {}", public_key, e))
+}
+
+/// Path of the signing key with the given name
+pub fn key_path(name: &str) -> Result<PathBuf> {
+    Ok(platform_dirs::keys_dir()?.join(format!("{}.key", name)))
+}
+
+/// Create a signing key in the keys directory. Returns the key file and
+/// the base64 public key.
+pub fn generate_key(name: &str) -> Result<(PathBuf, String)> {
+    let path = key_path(name)?;
+    if path.exists() {
+        return Err(anyhow!(
+            "Signing key '{}' already exists at {}",
+            name,
+            path.display()
+        ));
+    }
+
+    let key = SigningKey::generate(&mut rand_core::OsRng);
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(&path, BASE64.encode(key.to_bytes()))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600))?;
+    }
+    Ok((path, BASE64.encode(key.verifying_key().as_bytes())))
+}
+
+/// Load a signing key by name from the keys directory, or from a file path
+pub fn load_key(name_or_path: &str) -> Result<SigningKey> {
+    let path = if Path::new(name_or_path).exists() {
+        PathBuf::from(name_or_path)
+    } else {
+        key_path(name_or_path)?
+    };
+    let content = std::fs::read_to_string(&path)
+        .map_err(|e| anyhow!("Cannot read signing key {}: {}", path.display(), e))?;
+    let bytes: [u8; 32] = BASE64
+        .decode(content.trim())
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| anyhow!("Invalid signing key in {}", path.display()))?;
+    Ok(SigningKey::from_bytes(&bytes))
+}
+
+/// Detached signature file of a tool script
+pub fn signature_path(file_path: &Path) -> PathBuf {
+    let mut name = file_path.as_os_str().to_owned();
+    name.push(".");
+    name.push(SIGNATURE_EXTENSION);
+    PathBuf::from(name)
+}
+
+/// What a file signature covers: the checksum of the file's bytes
+pub fn file_message(file_path: &Path) -> Result<String> {
+    Ok(persistence::sha256_checksum(&std::fs::read(file_path)?))
+}
+
+/// Sign a tool script, writing its detached signature
+pub fn sign_file(file_path: &Path, key: &SigningKey) -> Result<ToolSignature> {
+    let signature = sign(key, file_message(file_path)?.as_bytes());
+    std::fs::write(
+        signature_path(file_path),
+        serde_json::to_string_pretty(&signature)?,
+    )?;
+    Ok(signature)
+}
+
+/// Check a tool script against its detached signature, if it has one
+pub fn verify_file(trust: &TrustStore, file_path: &Path) -> Result<SignatureStatus> {
+    verify_content(trust, file_path, &std::fs::read(file_path)?)
+}
+
+/// Check content read from a tool script against the script's detached
+/// signature, so that what is verified is exactly what runs
+pub fn verify_content(
+    trust: &TrustStore,
+    file_path: &Path,
+    content: &[u8],
+) -> Result<SignatureStatus> {
+    let signature_path = signature_path(file_path);
+    let signature: Option<ToolSignature> = if signature_path.exists() {
+        Some(
+            serde_json::from_str(&std::fs::read_to_string(&signature_path)?).map_err(|e| {
+                anyhow!("Invalid signature file {}: {}", signature_path.display(), e)
+            })?,
+        )
+    } else {
+        None
+    };
+    let message = persistence::sha256_checksum(content);
+    Ok(trust.verify(message.as_bytes(), signature.as_ref()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_key(seed: u8) -> SigningKey {
+        SigningKey::from_bytes(&[seed; 32])
+    }
+
+    #[test]
+    fn test_verify_and_admit() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut trust = TrustStore::load_from(&dir.path().join("trust.json")).unwrap();
+        let alice = test_key(1);
+        let signature = sign(&alice, b"sha256:abc");
+
+        assert_eq!(trust.verify(b"sha256:abc", None), SignatureStatus::Unsigned);
+        assert!(matches!(
+            trust.verify(b"sha256:abc", Some(&signature)),
+            SignatureStatus::Untrusted { .. }
+        ));
+
+        trust.trust("alice", &signature.public_key).unwrap();
+        assert_eq!(
+            trust.verify(b"sha256:abc", Some(&signature)),
+            SignatureStatus::Trusted {
+                signer: "alice".to_string()
+            }
+        );
+        assert_eq!(
+            trust.verify(b"sha256:abd", Some(&signature)),
+            SignatureStatus::Invalid
+        );
+
+        // Only enforce refuses tools
+        assert!(trust.admit("t", &SignatureStatus::Unsigned).is_ok());
+        trust.set_policy(TrustPolicy::Warn);
+        assert!(trust.admit("t", &SignatureStatus::Invalid).is_ok());
+        trust.set_policy(TrustPolicy::Enforce);
+        assert!(trust.admit("t", &SignatureStatus::Unsigned).is_err());
+        assert!(trust
+            .admit("t", &trust.verify(b"sha256:abc", Some(&signature)))
+            .is_ok());
+    }
+
+    #[test]
+    fn test_trust_store_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("trust.json");
+        let mut trust = TrustStore::load_from(&path).unwrap();
+        assert_eq!(trust.policy(), TrustPolicy::Off);
+
+        let public_key = sign(&test_key(2), b"").public_key;
+        trust.trust("bob", &public_key).unwrap();
+        trust.set_policy("enforce".parse().unwrap());
+        trust.save().unwrap();
+
+        let mut loaded = TrustStore::load_from(&path).unwrap();
+        assert_eq!(loaded.policy(), TrustPolicy::Enforce);
+        assert_eq!(loaded.keys().count(), 1);
+        assert!(loaded.untrust("bob"));
+        assert!(loaded.trust("eve", "not a key").is_err());
+    }
+
+    #[test]
+    fn test_file_signatures() {
+        let dir = tempfile::tempdir().unwrap();
+        let script = dir.path().join("hello.tcl");
+        std::fs::write(&script, "return hello").unwrap();
+        let mut trust = TrustStore::load_from(&dir.path().join("trust.json")).unwrap();
+
+        assert_eq!(
+            verify_file(&trust, &script).unwrap(),
+            SignatureStatus::Unsigned
+        );
+        let signature = sign_file(&script, &test_key(3)).unwrap();
+        assert!(signature_path(&script).ends_with("hello.tcl.sig"));
+        trust.trust("carol", &signature.public_key).unwrap();
+        assert!(matches!(
+            verify_file(&trust, &script).unwrap(),
+            SignatureStatus::Trusted { .. }
+        ));
+
+        assert_eq!(
+            verify_content(&trust, &script, b"return goodbye").unwrap(),
+            SignatureStatus::Invalid
+        );
+        std::fs::write(&script, "return goodbye").unwrap();
+        assert_eq!(
+            verify_file(&trust, &script).unwrap(),
+            SignatureStatus::Invalid
+        );
+    }
+}
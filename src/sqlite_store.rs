@@ -0,0 +1,1133 @@
+/// SQLite storage backend
+///
+/// Keeps tools, their revision history, version pins, quarantined tools and
+/// MCP server configurations in one database, `store.db` in the data
+/// directory. Every change is a single transaction, and tools are indexed
+/// with FTS5 for search over their names, descriptions, parameters and
+/// scripts.
+///
+/// The first time the database is opened, the JSON tool store and MCP
+/// server index are imported into it. The JSON files are left in place, so
+/// switching back to `--storage json` finds them as they were.
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use rusqlite::{
+    params, Connection, OpenFlags, OptionalExtension, Row, Transaction, TransactionBehavior,
+};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+use std::time::Duration;
+use uuid::Uuid;
+
+use crate::mcp_persistence::{McpPersistence, McpServerEntry};
+use crate::migrations::TOOL_FILE_VERSION;
+use crate::namespace::{Namespace, ToolPath};
+use crate::persistence::{
+    calculate_checksum, FilePersistence, Integrity, PersistedTool, ToolMetadata, CHECKSUM_PREFIX,
+};
+use crate::platform_dirs;
+use crate::signing::{self, ToolSignature};
+use crate::tcl_tools::ToolDefinition;
+use crate::tool_history::{RevisionAction, ToolRevision};
+use crate::tool_store::ToolStore;
+use crate::versioning::{self, VersionSpec, LATEST};
+
+/// Meta key recording when the JSON stores were imported
+const JSON_IMPORTED: &str = "json_imported_at";
+
+/// How long a write waits for another process's transaction
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Schema migrations, applied in order; the database's `user_version` is the
+/// number applied. Databases from before versioning are at 0, and the first
+/// step only creates what is missing.
+const MIGRATIONS: &[(&str, &str)] = &[("Create the tool, history and server tables", SCHEMA)];
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS tools (
+    key TEXT PRIMARY KEY,
+    path TEXT NOT NULL,
+    tool TEXT NOT NULL,
+    version TEXT NOT NULL,
+    namespace TEXT NOT NULL,
+    is_mcp INTEGER NOT NULL,
+    id TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL,
+    checksum TEXT NOT NULL,
+    file_version INTEGER NOT NULL,
+    signature TEXT,
+    definition TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS tools_by_tool ON tools (tool);
+CREATE INDEX IF NOT EXISTS tools_by_namespace ON tools (namespace);
+
+CREATE TABLE IF NOT EXISTS tool_history (
+    key TEXT NOT NULL,
+    revision INTEGER NOT NULL,
+    action TEXT NOT NULL,
+    saved_at TEXT NOT NULL,
+    checksum TEXT NOT NULL,
+    restored_from INTEGER,
+    definition TEXT NOT NULL,
+    PRIMARY KEY (key, revision)
+);
+
+CREATE TABLE IF NOT EXISTS version_pins (
+    tool TEXT PRIMARY KEY,
+    version TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS quarantine (
+    key TEXT PRIMARY KEY,
+    path TEXT NOT NULL,
+    reason TEXT NOT NULL,
+    definition TEXT NOT NULL,
+    quarantined_at TEXT NOT NULL
+);
+
+CREATE VIRTUAL TABLE IF NOT EXISTS tools_fts USING fts5 (
+    key UNINDEXED, name, description, parameters, script
+);
+
+CREATE TABLE IF NOT EXISTS mcp_servers (
+    id TEXT PRIMARY KEY,
+    config TEXT NOT NULL,
+    added_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL,
+    auto_start INTEGER NOT NULL,
+    metadata TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS meta (
+    key TEXT PRIMARY KEY,
+    value TEXT NOT NULL
+);
+";
+
+/// Columns read into a `ToolRow`, in order
+const TOOL_COLUMNS: &str = "tools.key, tools.path, tools.id, tools.created_at, \
+     tools.updated_at, tools.checksum, tools.file_version, tools.signature, tools.definition";
+
+/// What the one-shot import from the JSON stores brought over
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JsonImport {
+    pub tools: usize,
+    pub servers: usize,
+}
+
+/// SQLite-backed tool and MCP server store
+pub struct SqliteStore {
+    db_path: PathBuf,
+    conn: Mutex<Connection>,
+}
+
+impl SqliteStore {
+    /// Open the database in the data directory, importing the JSON stores
+    /// the first time
+    pub async fn new() -> Result<Self> {
+        platform_dirs::init_directories()?;
+        let store = Self::open(&platform_dirs::database_path()?)?;
+        if let Some(import) = store
+            .import_json(
+                &platform_dirs::tools_dir()?,
+                &platform_dirs::mcp_index_path()?,
+            )
+            .await?
+        {
+            tracing::info!(
+                "Imported {} tools and {} MCP servers from the JSON stores into {}",
+                import.tools,
+                import.servers,
+                store.db_path.display()
+            );
+        }
+        Ok(store)
+    }
+
+    /// Open or create a database file
+    pub fn open(db_path: &Path) -> Result<Self> {
+        if let Some(parent) = db_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let mut conn = Connection::open(db_path)?;
+        conn.busy_timeout(BUSY_TIMEOUT)?;
+        // Readers don't block the writer of another server process
+        conn.pragma_update_and_check(None, "journal_mode", "WAL", |_| Ok(()))?;
+        migrate(&mut conn, db_path)?;
+
+        Ok(Self {
+            db_path: db_path.to_path_buf(),
+            conn: Mutex::new(conn),
+        })
+    }
+
+    /// Schema version of a database and the steps that would bring it up to
+    /// date, without changing it
+    pub fn pending_migrations(db_path: &Path) -> Result<(u32, Vec<&'static str>)> {
+        let conn = Connection::open_with_flags(db_path, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+        let version = schema_version(&conn)?;
+        check_schema_version(version)?;
+        let steps = MIGRATIONS[version as usize..]
+            .iter()
+            .map(|(description, _)| *description)
+            .collect();
+        Ok((version, steps))
+    }
+
+    fn conn(&self) -> MutexGuard<'_, Connection> {
+        self.conn.lock().unwrap()
+    }
+
+    /// Import the JSON tool store and MCP server index. Runs once per
+    /// database; returns `None` if the import was already done.
+    pub async fn import_json(
+        &self,
+        tools_dir: &Path,
+        mcp_index_path: &Path,
+    ) -> Result<Option<JsonImport>> {
+        if self.meta(JSON_IMPORTED)?.is_some() {
+            return Ok(None);
+        }
+
+        // Read both stores first, then write them in one transaction
+        let mut tools = Vec::new();
+        let mut pins = HashMap::new();
+        if tools_dir.join("index.json").exists() {
+            let mut persistence = FilePersistence::with_directory(tools_dir.to_path_buf()).await?;
+            for persisted in persistence.list_tools(None).await? {
+                let history = persistence.history(&persisted.tool.path).await?;
+                tools.push((persisted, history));
+            }
+            pins = persistence.version_pins().clone();
+        }
+        let servers = if mcp_index_path.exists() {
+            McpPersistence::load_or_create_index(mcp_index_path)
+                .await?
+                .servers
+        } else {
+            HashMap::new()
+        };
+
+        let mut conn = self.conn();
+        let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+        // Another server process may have imported while the files were read
+        if meta(&tx, JSON_IMPORTED)?.is_some() {
+            return Ok(None);
+        }
+
+        for (persisted, history) in &tools {
+            let key = persisted.tool.path.to_string();
+            insert_tool(&tx, persisted)?;
+            if history.is_empty() {
+                // Tools saved before history was kept start at their
+                // current definition
+                insert_revision(
+                    &tx,
+                    &key,
+                    &ToolRevision {
+                        revision: persisted.metadata.file_version.max(1),
+                        action: RevisionAction::Save,
+                        saved_at: persisted.metadata.updated_at,
+                        checksum: persisted.metadata.checksum.clone(),
+                        restored_from: None,
+                        tool: persisted.tool.clone(),
+                    },
+                )?;
+            }
+            for revision in history {
+                insert_revision(&tx, &key, revision)?;
+            }
+        }
+        for (tool, version) in &pins {
+            tx.execute(
+                "INSERT OR REPLACE INTO version_pins (tool, version) VALUES (?1, ?2)",
+                params![tool, version],
+            )?;
+        }
+        for (id, entry) in &servers {
+            upsert_server(&tx, id, entry)?;
+        }
+        tx.execute(
+            "INSERT INTO meta (key, value) VALUES (?1, ?2)",
+            params![JSON_IMPORTED, Utc::now().to_rfc3339()],
+        )?;
+        tx.commit()?;
+
+        Ok(Some(JsonImport {
+            tools: tools.len(),
+            servers: servers.len(),
+        }))
+    }
+
+    fn meta(&self, key: &str) -> Result<Option<String>> {
+        meta(&self.conn(), key)
+    }
+
+    /// Load a stored tool, failing if it does not match its checksum
+    fn load(&self, path: &ToolPath) -> Result<Option<PersistedTool>> {
+        let row = self
+            .conn()
+            .query_row(
+                &format!("SELECT {} FROM tools WHERE key = ?1", TOOL_COLUMNS),
+                [path.to_string()],
+                ToolRow::from_row,
+            )
+            .optional()?;
+        match row.map(ToolRow::verify) {
+            None => Ok(None),
+            Some(Ok(persisted)) => Ok(Some(persisted)),
+            Some(Err(reason)) => Err(anyhow!(
+                "Tool '{}' failed its integrity check: {}",
+                path,
+                reason
+            )),
+        }
+    }
+
+    /// Verify tool rows, quarantining the ones that fail
+    fn verified(&self, rows: Vec<ToolRow>) -> Result<Vec<PersistedTool>> {
+        let mut tools = Vec::new();
+        for row in rows {
+            let failed = (row.key.clone(), row.path.clone(), row.definition.clone());
+            match row.verify() {
+                Ok(persisted) => tools.push(persisted),
+                Err(reason) => self.quarantine(&failed.0, &failed.1, &failed.2, reason)?,
+            }
+        }
+        Ok(tools)
+    }
+
+    /// Move a tool that failed verification out of the tools table
+    fn quarantine(&self, key: &str, path: &str, definition: &str, reason: String) -> Result<()> {
+        let mut conn = self.conn();
+        let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+        tx.execute(
+            "INSERT OR REPLACE INTO quarantine (key, path, reason, definition, quarantined_at)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![key, path, reason, definition, Utc::now()],
+        )?;
+        tx.execute("DELETE FROM tools WHERE key = ?1", [key])?;
+        tx.execute("DELETE FROM tools_fts WHERE key = ?1", [key])?;
+        tx.commit()?;
+
+        tracing::warn!(
+            "Quarantined tool {} ({}) in {}",
+            key,
+            reason,
+            self.db_path.display()
+        );
+        Ok(())
+    }
+
+    /// Write a tool and the revision recording it in one transaction
+    fn write_revision(
+        &self,
+        tool: &ToolDefinition,
+        action: RevisionAction,
+        restored_from: Option<u32>,
+    ) -> Result<ToolRevision> {
+        let key = tool.path.to_string();
+        let now = Utc::now();
+        let checksum = calculate_checksum(tool);
+
+        let mut conn = self.conn();
+        let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+
+        // Keep the identity of a tool that is being overwritten
+        let previous: Option<(String, DateTime<Utc>)> = tx
+            .query_row(
+                "SELECT id, created_at FROM tools WHERE key = ?1",
+                [&key],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()?;
+        let revision = next_revision(&tx, &key)?;
+        let (id, created_at) = previous.unwrap_or_else(|| (Uuid::new_v4().to_string(), now));
+
+        insert_tool(
+            &tx,
+            &PersistedTool {
+                metadata: ToolMetadata {
+                    id,
+                    created_at,
+                    updated_at: now,
+                    checksum: checksum.clone(),
+                    file_version: revision,
+                    format_version: TOOL_FILE_VERSION,
+                    // A signature only covers the content it was made for
+                    signature: None,
+                },
+                tool: tool.clone(),
+            },
+        )?;
+        tx.execute("DELETE FROM quarantine WHERE key = ?1", [&key])?;
+
+        let entry = ToolRevision {
+            revision,
+            action,
+            saved_at: now,
+            checksum,
+            restored_from,
+            tool: tool.clone(),
+        };
+        insert_revision(&tx, &key, &entry)?;
+        tx.commit()?;
+
+        tracing::info!(
+            "Saved tool {} to {} (revision {})",
+            key,
+            self.db_path.display(),
+            revision
+        );
+        Ok(entry)
+    }
+
+    /// Every recorded revision of a tool, oldest first
+    fn revisions(&self, path: &ToolPath) -> Result<Vec<ToolRevision>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(
+            "SELECT revision, action, saved_at, checksum, restored_from, definition
+             FROM tool_history WHERE key = ?1 ORDER BY revision",
+        )?;
+        let rows = stmt
+            .query_map([path.to_string()], |row| {
+                Ok((
+                    row.get::<_, u32>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, DateTime<Utc>>(2)?,
+                    row.get::<_, String>(3)?,
+                    row.get::<_, Option<u32>>(4)?,
+                    row.get::<_, String>(5)?,
+                ))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        rows.into_iter()
+            .map(
+                |(revision, action, saved_at, checksum, restored_from, definition)| {
+                    Ok(ToolRevision {
+                        revision,
+                        action: serde_json::from_value(serde_json::Value::String(action))?,
+                        saved_at,
+                        checksum,
+                        restored_from,
+                        tool: serde_json::from_str(&definition).map_err(|e| {
+                            anyhow!("Corrupt history entry {} of {}: {}", revision, path, e)
+                        })?,
+                    })
+                },
+            )
+            .collect()
+    }
+
+    /// All registered MCP servers
+    pub fn mcp_servers(&self) -> Result<HashMap<String, McpServerEntry>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(
+            "SELECT id, config, added_at, updated_at, auto_start, metadata FROM mcp_servers",
+        )?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, DateTime<Utc>>(2)?,
+                    row.get::<_, DateTime<Utc>>(3)?,
+                    row.get::<_, bool>(4)?,
+                    row.get::<_, String>(5)?,
+                ))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        rows.into_iter()
+            .map(|(id, config, added_at, updated_at, auto_start, metadata)| {
+                let entry = McpServerEntry {
+                    config: serde_json::from_str(&config)?,
+                    added_at,
+                    updated_at,
+                    auto_start,
+                    metadata: serde_json::from_str(&metadata)?,
+                };
+                Ok((id, entry))
+            })
+            .collect()
+    }
+
+    /// Add or replace an MCP server
+    pub fn save_mcp_server(&self, id: &str, entry: &McpServerEntry) -> Result<()> {
+        upsert_server(&self.conn(), id, entry)
+    }
+
+    /// Remove an MCP server; false if it was not registered
+    pub fn remove_mcp_server(&self, id: &str) -> Result<bool> {
+        Ok(self
+            .conn()
+            .execute("DELETE FROM mcp_servers WHERE id = ?1", [id])?
+            > 0)
+    }
+}
+
+#[async_trait]
+impl ToolStore for SqliteStore {
+    async fn save_tool(&mut self, tool: &ToolDefinition) -> Result<()> {
+        self.write_revision(tool, RevisionAction::Save, None)?;
+        Ok(())
+    }
+
+    async fn load_persisted(&self, path: &ToolPath) -> Result<Option<PersistedTool>> {
+        self.load(path)
+    }
+
+    async fn list_tools(&mut self, namespace_filter: Option<&str>) -> Result<Vec<PersistedTool>> {
+        let rows = {
+            let conn = self.conn();
+            let mut stmt = conn.prepare(&format!(
+                "SELECT {} FROM tools
+                 WHERE ?1 IS NULL OR namespace = ?1 OR (?1 = 'mcp' AND is_mcp)
+                 ORDER BY key",
+                TOOL_COLUMNS
+            ))?;
+            let rows = stmt
+                .query_map([namespace_filter], ToolRow::from_row)?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            rows
+        };
+        self.verified(rows)
+    }
+
+    async fn search_tools(&mut self, query: &str, limit: usize) -> Result<Vec<PersistedTool>> {
+        // Quote each word as a prefix query, so FTS5 syntax in the query is
+        // matched as text
+        let terms: Vec<String> = query
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|word| !word.is_empty())
+            .map(|word| format!("\"{}\"*", word))
+            .collect();
+        if terms.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let rows = {
+            let conn = self.conn();
+            // Name matches weigh most, then descriptions, parameters and scripts
+            let mut stmt = conn.prepare(&format!(
+                "SELECT {} FROM tools_fts JOIN tools ON tools.key = tools_fts.key
+                 WHERE tools_fts MATCH ?1
+                 ORDER BY bm25(tools_fts, 0.0, 10.0, 4.0, 2.0, 1.0), tools.key
+                 LIMIT ?2",
+                TOOL_COLUMNS
+            ))?;
+            let rows = stmt
+                .query_map(params![terms.join(" "), limit as i64], ToolRow::from_row)?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            rows
+        };
+        self.verified(rows)
+    }
+
+    fn stored_path(&self, path: &ToolPath) -> Result<Option<ToolPath>> {
+        let conn = self.conn();
+        let exists = conn
+            .query_row(
+                "SELECT 1 FROM tools WHERE key = ?1",
+                [path.to_string()],
+                |_| Ok(()),
+            )
+            .optional()?
+            .is_some();
+        if exists {
+            return Ok(Some(path.clone()));
+        }
+
+        let Ok(spec) = VersionSpec::parse(&path.version) else {
+            return Ok(None);
+        };
+        let tool = path.with_version(LATEST).to_mcp_name();
+        let mut stmt = conn.prepare("SELECT version FROM tools WHERE tool = ?1")?;
+        let available = stmt
+            .query_map([&tool], |row| row.get::<_, String>(0))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        let pin: Option<String> = conn
+            .query_row(
+                "SELECT version FROM version_pins WHERE tool = ?1",
+                [&tool],
+                |row| row.get(0),
+            )
+            .optional()?;
+
+        let available: Vec<&str> = available.iter().map(String::as_str).collect();
+        Ok(versioning::select(&spec, &available, pin.as_deref())
+            .map(|version| path.with_version(version)))
+    }
+
+    async fn delete_tool(&mut self, path: &ToolPath) -> Result<bool> {
+        let key = path.to_string();
+        let mut conn = self.conn();
+        let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+
+        let stored: Option<(String, String)> = tx
+            .query_row(
+                "SELECT checksum, definition FROM tools WHERE key = ?1",
+                [&key],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()?;
+        let Some((checksum, definition)) = stored else {
+            return Ok(false);
+        };
+
+        // Record the deleted definition so it can be restored
+        let revision = next_revision(&tx, &key)?;
+        insert_revision(
+            &tx,
+            &key,
+            &ToolRevision {
+                revision,
+                action: RevisionAction::Delete,
+                saved_at: Utc::now(),
+                checksum,
+                restored_from: None,
+                tool: serde_json::from_str(&definition)?,
+            },
+        )?;
+        tx.execute("DELETE FROM tools WHERE key = ?1", [&key])?;
+        tx.execute("DELETE FROM tools_fts WHERE key = ?1", [&key])?;
+
+        // A pin on the deleted version no longer applies
+        tx.execute(
+            "DELETE FROM version_pins WHERE tool = ?1 AND version = ?2",
+            params![path.with_version(LATEST).to_mcp_name(), path.version],
+        )?;
+        tx.commit()?;
+
+        tracing::info!("Deleted tool {} from {}", key, self.db_path.display());
+        Ok(true)
+    }
+
+    async fn history(&self, path: &ToolPath) -> Result<Vec<ToolRevision>> {
+        self.revisions(path)
+    }
+
+    async fn rollback(&mut self, path: &ToolPath, revision: u32) -> Result<ToolRevision> {
+        let revisions = self.revisions(path)?;
+        let target = revisions
+            .iter()
+            .find(|r| r.revision == revision)
+            .ok_or_else(|| {
+                anyhow!(
+                    "Tool '{}' has no revision {} (latest is {})",
+                    path,
+                    revision,
+                    revisions.last().map_or(0, |last| last.revision)
+                )
+            })?;
+
+        self.write_revision(&target.tool, RevisionAction::Rollback, Some(revision))
+    }
+
+    fn version_pins(&self) -> Result<HashMap<String, String>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare("SELECT tool, version FROM version_pins")?;
+        let pins = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<rusqlite::Result<HashMap<_, _>>>()?;
+        Ok(pins)
+    }
+
+    async fn set_version_pin(&mut self, path: &ToolPath, version: Option<String>) -> Result<()> {
+        let tool = path.with_version(LATEST).to_mcp_name();
+        let conn = self.conn();
+        match version {
+            Some(version) => conn.execute(
+                "INSERT OR REPLACE INTO version_pins (tool, version) VALUES (?1, ?2)",
+                params![tool, version],
+            )?,
+            None => conn.execute("DELETE FROM version_pins WHERE tool = ?1", [tool])?,
+        };
+        Ok(())
+    }
+
+    fn integrity(&self) -> Result<HashMap<ToolPath, Integrity>> {
+        let conn = self.conn();
+        let mut stored = conn.prepare(&format!("SELECT {} FROM tools", TOOL_COLUMNS))?;
+        let stored = stored
+            .query_map([], ToolRow::from_row)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        let mut quarantined =
+            conn.prepare("SELECT path, reason, quarantined_at FROM quarantine")?;
+        let quarantined = quarantined
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, DateTime<Utc>>(2)?,
+                ))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        let mut integrity = HashMap::new();
+        for row in stored {
+            let path = serde_json::from_str(&row.path)?;
+            integrity.insert(path, Integrity::of_verification(&row.verify()));
+        }
+        for (path, reason, quarantined_at) in quarantined {
+            integrity.insert(
+                serde_json::from_str(&path)?,
+                Integrity::Quarantined {
+                    reason,
+                    file_path: self.db_path.clone(),
+                    quarantined_at,
+                },
+            );
+        }
+        Ok(integrity)
+    }
+
+    async fn sign_tool(
+        &mut self,
+        path: &ToolPath,
+        key: &ed25519_dalek::SigningKey,
+    ) -> Result<ToolSignature> {
+        let persisted = self
+            .load(path)?
+            .ok_or_else(|| anyhow!("Tool '{}' is not stored", path))?;
+
+        // Tools saved before SHA-256 checksums get one now
+        let checksum = calculate_checksum(&persisted.tool);
+        let signature = signing::sign(key, checksum.as_bytes());
+        self.conn().execute(
+            "UPDATE tools SET checksum = ?1, signature = ?2 WHERE key = ?3",
+            params![
+                checksum,
+                serde_json::to_string(&signature)?,
+                path.to_string()
+            ],
+        )?;
+        Ok(signature)
+    }
+}
+
+/// A row of the tools table, before its definition is checked
+struct ToolRow {
+    key: String,
+    path: String,
+    id: String,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+    checksum: String,
+    file_version: u32,
+    signature: Option<String>,
+    definition: String,
+}
+
+impl ToolRow {
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        Ok(Self {
+            key: row.get(0)?,
+            path: row.get(1)?,
+            id: row.get(2)?,
+            created_at: row.get(3)?,
+            updated_at: row.get(4)?,
+            checksum: row.get(5)?,
+            file_version: row.get(6)?,
+            signature: row.get(7)?,
+            definition: row.get(8)?,
+        })
+    }
+
+    /// Parse the definition and check it against the recorded checksum
+    fn verify(self) -> std::result::Result<PersistedTool, String> {
+        let tool: ToolDefinition = serde_json::from_str(&self.definition)
+            .map_err(|e| format!("unreadable tool definition: {}", e))?;
+        let signature: Option<ToolSignature> = self
+            .signature
+            .as_deref()
+            .map(serde_json::from_str)
+            .transpose()
+            .map_err(|e| format!("unreadable signature: {}", e))?;
+
+        if self.checksum.starts_with(CHECKSUM_PREFIX) {
+            let actual = calculate_checksum(&tool);
+            if actual != self.checksum {
+                return Err(format!(
+                    "checksum mismatch: database records {}, content hashes to {}",
+                    self.checksum, actual
+                ));
+            }
+        }
+
+        Ok(PersistedTool {
+            metadata: ToolMetadata {
+                id: self.id,
+                created_at: self.created_at,
+                updated_at: self.updated_at,
+                checksum: self.checksum,
+                file_version: self.file_version,
+                format_version: TOOL_FILE_VERSION,
+                signature,
+            },
+            tool,
+        })
+    }
+}
+
+fn schema_version(conn: &Connection) -> Result<u32> {
+    Ok(conn.pragma_query_value(None, "user_version", |row| row.get(0))?)
+}
+
+fn check_schema_version(version: u32) -> Result<()> {
+    if version as usize > MIGRATIONS.len() {
+        return Err(anyhow!(
+            "schema version {} is newer than this server supports ({}); upgrade tcl-mcp-server to read it",
+            version,
+            MIGRATIONS.len()
+        ));
+    }
+    Ok(())
+}
+
+/// Apply the pending schema migrations, copying a database that already
+/// holds data to `store.db.v<N>-backup` first
+fn migrate(conn: &mut Connection, db_path: &Path) -> Result<()> {
+    let version = schema_version(conn)?;
+    check_schema_version(version).map_err(|e| anyhow!("{}: {}", db_path.display(), e))?;
+    if version as usize == MIGRATIONS.len() {
+        return Ok(());
+    }
+
+    let tables: u32 = conn.query_row("SELECT COUNT(*) FROM sqlite_master", [], |row| row.get(0))?;
+    if tables > 0 {
+        let backup = db_path.with_extension(format!("db.v{}-backup", version));
+        if !backup.exists() {
+            conn.execute("VACUUM INTO ?1", [backup.to_string_lossy()])?;
+            tracing::info!(
+                "Backed up {} to {} before migrating its schema",
+                db_path.display(),
+                backup.display()
+            );
+        }
+    }
+
+    let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+    // Another server process may have migrated since the version was read
+    for (step, (description, sql)) in MIGRATIONS
+        .iter()
+        .enumerate()
+        .skip(schema_version(&tx)? as usize)
+    {
+        tx.execute_batch(sql)?;
+        tx.pragma_update(None, "user_version", step as u32 + 1)?;
+        tracing::info!(
+            "Migrated {} to schema version {}: {}",
+            db_path.display(),
+            step + 1,
+            description
+        );
+    }
+    tx.commit()?;
+    Ok(())
+}
+
+fn meta(conn: &Connection, key: &str) -> Result<Option<String>> {
+    Ok(conn
+        .query_row("SELECT value FROM meta WHERE key = ?1", [key], |row| {
+            row.get(0)
+        })
+        .optional()?)
+}
+
+/// The column values a namespace filter matches: the user, server or
+/// system namespace name, and whether the tool is an MCP server tool
+fn namespace_columns(path: &ToolPath) -> (&str, bool) {
+    match &path.namespace {
+        Namespace::User(user) => (user, false),
+        Namespace::Bin => ("bin", false),
+        Namespace::Sbin => ("sbin", false),
+        Namespace::Docs => ("docs", false),
+        Namespace::Mcp(server) => (server, true),
+    }
+}
+
+/// Insert or replace a tool and its search index entry
+fn insert_tool(tx: &Transaction, persisted: &PersistedTool) -> Result<()> {
+    let tool = &persisted.tool;
+    let metadata = &persisted.metadata;
+    let key = tool.path.to_string();
+    let (namespace, is_mcp) = namespace_columns(&tool.path);
+    let signature = metadata
+        .signature
+        .as_ref()
+        .map(serde_json::to_string)
+        .transpose()?;
+
+    tx.execute(
+        "INSERT OR REPLACE INTO tools (key, path, tool, version, namespace, is_mcp, id,
+             created_at, updated_at, checksum, file_version, signature, definition)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
+        params![
+            key,
+            serde_json::to_string(&tool.path)?,
+            tool.path.with_version(LATEST).to_mcp_name(),
+            tool.path.version,
+            namespace,
+            is_mcp,
+            metadata.id,
+            metadata.created_at,
+            metadata.updated_at,
+            metadata.checksum,
+            metadata.file_version,
+            signature,
+            serde_json::to_string(tool)?,
+        ],
+    )?;
+
+    let parameters: Vec<String> = tool
+        .parameters
+        .iter()
+        .map(|param| format!("{} {}", param.name, param.description))
+        .collect();
+    tx.execute("DELETE FROM tools_fts WHERE key = ?1", [&key])?;
+    tx.execute(
+        "INSERT INTO tools_fts (key, name, description, parameters, script)
+         VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![
+            key,
+            tool.path.name,
+            tool.description,
+            parameters.join("\n"),
+            tool.script
+        ],
+    )?;
+    Ok(())
+}
+
+fn insert_revision(tx: &Transaction, key: &str, revision: &ToolRevision) -> Result<()> {
+    let action = serde_json::to_value(revision.action)?;
+    tx.execute(
+        "INSERT OR REPLACE INTO tool_history
+             (key, revision, action, saved_at, checksum, restored_from, definition)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        params![
+            key,
+            revision.revision,
+            action.as_str(),
+            revision.saved_at,
+            revision.checksum,
+            revision.restored_from,
+            serde_json::to_string(&revision.tool)?,
+        ],
+    )?;
+    Ok(())
+}
+
+fn next_revision(tx: &Transaction, key: &str) -> Result<u32> {
+    let last: Option<u32> = tx.query_row(
+        "SELECT MAX(revision) FROM tool_history WHERE key = ?1",
+        [key],
+        |row| row.get(0),
+    )?;
+    Ok(last.map_or(1, |last| last + 1))
+}
+
+fn upsert_server(conn: &Connection, id: &str, entry: &McpServerEntry) -> Result<()> {
+    conn.execute(
+        "INSERT OR REPLACE INTO mcp_servers
+             (id, config, added_at, updated_at, auto_start, metadata)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![
+            id,
+            serde_json::to_string(&entry.config)?,
+            entry.added_at,
+            entry.updated_at,
+            entry.auto_start,
+            serde_json::to_string(&entry.metadata)?,
+        ],
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tcl_tools::ParameterDefinition;
+    use tempfile::TempDir;
+
+    fn create_test_store() -> Result<(SqliteStore, TempDir)> {
+        let temp_dir = TempDir::new()?;
+        let store = SqliteStore::open(&temp_dir.path().join("store.db"))?;
+        Ok((store, temp_dir))
+    }
+
+    fn create_test_tool() -> ToolDefinition {
+        ToolDefinition {
+            path: ToolPath::user("alice", "utils", "reverse_string", "1.0"),
+            description: "Reverse a string".to_string(),
+            script: "string reverse $text".to_string(),
+            parameters: vec![ParameterDefinition {
+                name: "text".to_string(),
+                description: "Text to reverse".to_string(),
+                required: true,
+                type_name: "string".to_string(),
+                completion: None,
+                default: None,
+            }],
+            examples: Vec::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_save_list_and_delete() -> Result<()> {
+        let (mut store, _temp) = create_test_store()?;
+        let tool = create_test_tool();
+        store.save_tool(&tool).await?;
+        store.save_tool(&tool).await?;
+
+        let loaded = store.load_persisted(&tool.path).await?.unwrap();
+        assert_eq!(loaded.tool.script, tool.script);
+        assert_eq!(loaded.metadata.file_version, 2);
+        assert_eq!(store.list_tools(Some("alice")).await?.len(), 1);
+        assert!(store.list_tools(Some("bob")).await?.is_empty());
+
+        let latest = tool.path.with_version(LATEST);
+        assert_eq!(store.stored_path(&latest)?, Some(tool.path.clone()));
+
+        assert!(store.delete_tool(&tool.path).await?);
+        assert!(!store.delete_tool(&tool.path).await?);
+        assert!(store.load_persisted(&tool.path).await?.is_none());
+
+        // The deleted tool can be restored from its history
+        let actions: Vec<RevisionAction> = store
+            .history(&tool.path)
+            .await?
+            .iter()
+            .map(|r| r.action)
+            .collect();
+        assert_eq!(
+            actions,
+            vec![
+                RevisionAction::Save,
+                RevisionAction::Save,
+                RevisionAction::Delete
+            ]
+        );
+        let restored = store.rollback(&tool.path, 1).await?;
+        assert_eq!(restored.revision, 4);
+        assert_eq!(store.list_tools(None).await?.len(), 1);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_search() -> Result<()> {
+        let (mut store, _temp) = create_test_store()?;
+        store.save_tool(&create_test_tool()).await?;
+        store
+            .save_tool(&ToolDefinition {
+                path: ToolPath::user("bob", "math", "add", "1.0"),
+                description: "Add two numbers".to_string(),
+                script: "expr {$a + $b}".to_string(),
+                parameters: vec![],
+                examples: vec![],
+            })
+            .await?;
+
+        let found = store.search_tools("revers", 10).await?;
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].tool.path.name, "reverse_string");
+        assert_eq!(store.search_tools("numbers", 10).await?.len(), 1);
+        assert!(store.search_tools("\"unbalanced", 10).await?.is_empty());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_tampered_tools_are_quarantined() -> Result<()> {
+        let (mut store, _temp) = create_test_store()?;
+        let tool = create_test_tool();
+        store.save_tool(&tool).await?;
+
+        store.conn().execute(
+            "UPDATE tools SET definition = replace(definition, 'reverse $text', 'toupper $text')",
+            [],
+        )?;
+        assert!(matches!(
+            store.integrity()?.get(&tool.path),
+            Some(Integrity::Failed { .. })
+        ));
+
+        assert!(store.load_persisted(&tool.path).await.is_err());
+        assert!(store.list_tools(None).await?.is_empty());
+        assert!(matches!(
+            store.integrity()?.get(&tool.path),
+            Some(Integrity::Quarantined { .. })
+        ));
+
+        store.save_tool(&tool).await?;
+        assert!(matches!(
+            store.integrity()?.get(&tool.path),
+            Some(Integrity::Verified { .. })
+        ));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_import_json() -> Result<()> {
+        let temp = TempDir::new()?;
+        let tools_dir = temp.path().join("tools");
+        let tool = create_test_tool();
+        let mut persistence = FilePersistence::with_directory(tools_dir.clone()).await?;
+        persistence.save_tool(&tool).await?;
+        persistence
+            .set_version_pin(&tool.path, Some("1.0".to_string()))
+            .await?;
+
+        let mut store = SqliteStore::open(&temp.path().join("store.db"))?;
+        let mcp_index = temp.path().join("mcp-index.json");
+        let import = store.import_json(&tools_dir, &mcp_index).await?;
+        assert_eq!(
+            import,
+            Some(JsonImport {
+                tools: 1,
+                servers: 0
+            })
+        );
+        // Only the first open imports
+        assert_eq!(store.import_json(&tools_dir, &mcp_index).await?, None);
+
+        let tools = store.list_tools(None).await?;
+        assert_eq!(tools.len(), 1);
+        assert_eq!(tools[0].tool.path, tool.path);
+        assert_eq!(store.history(&tool.path).await?.len(), 1);
+        assert_eq!(store.version_pins()?.len(), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_schema_migrations() -> Result<()> {
+        let temp = TempDir::new()?;
+        let db_path = temp.path().join("store.db");
+
+        // A database from before schema versioning is backed up and stamped
+        let conn = Connection::open(&db_path)?;
+        conn.execute_batch(SCHEMA)?;
+        drop(conn);
+        assert_eq!(SqliteStore::pending_migrations(&db_path)?.0, 0);
+        SqliteStore::open(&db_path)?;
+        assert!(temp.path().join("store.db.v0-backup").exists());
+        let (version, steps) = SqliteStore::pending_migrations(&db_path)?;
+        assert_eq!(version as usize, MIGRATIONS.len());
+        assert!(steps.is_empty());
+
+        // A database from a newer server is refused
+        Connection::open(&db_path)?.pragma_update(None, "user_version", 99)?;
+        assert!(SqliteStore::open(&db_path).is_err());
+
+        Ok(())
+    }
+}
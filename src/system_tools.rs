@@ -0,0 +1,803 @@
+/// Built-in system tools
+///
+/// This table is the only place system tools are declared. `tools/list`,
+/// `tools/call` dispatch, the tool registry (`bin__inspect_tool`,
+/// `bin__list_tools`), argument completion and the CLI `info` command all
+/// read from it.
+use serde_json::{json, Value};
+
+use crate::namespace::{Namespace, ToolPath};
+use crate::tcl_tools::ParameterDefinition;
+
+/// What runs when a system tool is called
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SystemHandler {
+    TclExecute,
+    ExecTool,
+    DiscoverTools,
+    ExecuteMcp,
+    ListTools,
+    InspectTool,
+    ListNamespaces,
+    SearchTools,
+    ListXmcpTools,
+    McpList,
+    MoltBook,
+    ToolAdd,
+    ToolRemove,
+    ToolPin,
+    ToolHistory,
+    ToolDiff,
+    ToolRollback,
+    ToolExport,
+    ToolImport,
+    ToolReload,
+    McpAdd,
+    McpRemove,
+    McpConnect,
+    McpDisconnect,
+    McpInfo,
+    McpPing,
+}
+
+/// A built-in tool declaration
+pub struct SystemTool {
+    pub namespace: Namespace,
+    pub name: &'static str,
+    pub description: &'static str,
+    /// Only available with `--privileged`
+    pub privileged: bool,
+    pub handler: SystemHandler,
+    /// JSON schema properties and required list, without the `$schema` header
+    schema: fn() -> Value,
+}
+
+impl SystemTool {
+    pub fn path(&self) -> ToolPath {
+        ToolPath {
+            namespace: self.namespace.clone(),
+            package: None,
+            name: self.name.to_string(),
+            version: "latest".to_string(),
+        }
+    }
+
+    pub fn mcp_name(&self) -> String {
+        self.path().to_mcp_name()
+    }
+
+    /// The `inputSchema` advertised in `tools/list`
+    pub fn input_schema(&self) -> Value {
+        let mut schema = json!({
+            "$schema": "https://json-schema.org/draft/2020-12/schema",
+            "type": "object"
+        });
+        if let (Some(target), Value::Object(fields)) = (schema.as_object_mut(), (self.schema)()) {
+            target.extend(fields);
+        }
+        schema
+    }
+
+    /// Parameter list derived from the schema
+    pub fn parameters(&self) -> Vec<ParameterDefinition> {
+        let schema = (self.schema)();
+        let required: Vec<&str> = schema["required"]
+            .as_array()
+            .map(|names| names.iter().filter_map(Value::as_str).collect())
+            .unwrap_or_default();
+
+        schema["properties"]
+            .as_object()
+            .map(|properties| {
+                properties
+                    .iter()
+                    .map(|(name, property)| ParameterDefinition {
+                        name: name.clone(),
+                        description: property["description"]
+                            .as_str()
+                            .unwrap_or_default()
+                            .to_string(),
+                        required: required.contains(&name.as_str()),
+                        type_name: property["type"].as_str().unwrap_or("string").to_string(),
+                        completion: None,
+                        default: property.get("default").cloned(),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+/// Every system tool, in `tools/list` order
+pub fn all() -> &'static [SystemTool] {
+    &SYSTEM_TOOLS
+}
+
+/// System tools visible in the given privilege mode
+pub fn available(privileged: bool) -> impl Iterator<Item = &'static SystemTool> {
+    SYSTEM_TOOLS
+        .iter()
+        .filter(move |tool| privileged || !tool.privileged)
+}
+
+/// Look up a system tool by MCP name (e.g. `bin__tcl_execute`)
+pub fn find(mcp_name: &str) -> Option<&'static SystemTool> {
+    SYSTEM_TOOLS.iter().find(|tool| tool.mcp_name() == mcp_name)
+}
+
+/// Look up a system tool by path
+pub fn find_path(path: &ToolPath) -> Option<&'static SystemTool> {
+    if !path.is_system() {
+        return None;
+    }
+    SYSTEM_TOOLS
+        .iter()
+        .find(|tool| tool.namespace == path.namespace && tool.name == path.name)
+}
+
+static SYSTEM_TOOLS: [SystemTool; 26] = [
+    SystemTool {
+        namespace: Namespace::Bin,
+        name: "tcl_execute",
+        description: "Execute a TCL script and return the result",
+        privileged: false,
+        handler: SystemHandler::TclExecute,
+        schema: || {
+            json!({
+                "properties": {
+                    "script": {
+                        "type": "string",
+                        "description": "TCL script to execute"
+                    }
+                },
+                "required": ["script"]
+            })
+        },
+    },
+    SystemTool {
+        namespace: Namespace::Docs,
+        name: "molt_book",
+        description: "Access Molt TCL interpreter documentation and examples",
+        privileged: false,
+        handler: SystemHandler::MoltBook,
+        schema: || {
+            json!({
+                "properties": {
+                    "topic": {
+                        "type": "string",
+                        "description": "Documentation topic: 'overview', 'commands', 'examples', 'links', or 'basic_syntax'",
+                        "enum": ["overview", "commands", "examples", "links", "basic_syntax"]
+                    }
+                },
+                "required": ["topic"]
+            })
+        },
+    },
+    SystemTool {
+        namespace: Namespace::Bin,
+        name: "exec_tool",
+        description: "Execute a tool by its path with parameters",
+        privileged: false,
+        handler: SystemHandler::ExecTool,
+        schema: || {
+            json!({
+                "properties": {
+                    "tool_path": {
+                        "type": "string",
+                        "description": "Tool to run: MCP name ('bin__list_dir'), slash path ('/alice/utils/reverse_string:1.0') or bare name ('reverse_string')"
+                    },
+                    "params": {
+                        "type": "object",
+                        "description": "Parameters to pass to the tool",
+                        "default": {}
+                    }
+                },
+                "required": ["tool_path"]
+            })
+        },
+    },
+    SystemTool {
+        namespace: Namespace::Bin,
+        name: "discover_tools",
+        description: "Discover and index tools from the filesystem",
+        privileged: false,
+        handler: SystemHandler::DiscoverTools,
+        schema: || json!({"properties": {}}),
+    },
+    SystemTool {
+        namespace: Namespace::Bin,
+        name: "execute_mcp",
+        description: "Execute a tool on a registered MCP server",
+        privileged: false,
+        handler: SystemHandler::ExecuteMcp,
+        schema: || {
+            json!({
+                "properties": {
+                    "server_id": {
+                        "type": "string",
+                        "description": "ID of the MCP server to execute tool on"
+                    },
+                    "tool_name": {
+                        "type": "string",
+                        "description": "Name of the tool to execute"
+                    },
+                    "params": {
+                        "type": "object",
+                        "description": "Parameters to pass to the tool",
+                        "default": {}
+                    },
+                    "response_format": {
+                        "type": "string",
+                        "description": "Response format: json, text, or auto",
+                        "enum": ["json", "text", "auto"],
+                        "default": "auto"
+                    },
+                    "timeout_ms": {
+                        "type": "integer",
+                        "description": "Timeout in milliseconds",
+                        "default": 30000
+                    }
+                },
+                "required": ["server_id", "tool_name"]
+            })
+        },
+    },
+    SystemTool {
+        namespace: Namespace::Bin,
+        name: "list_tools",
+        description: "List tools with flexible output formats and advanced filtering",
+        privileged: false,
+        handler: SystemHandler::ListTools,
+        schema: || {
+            json!({
+                "properties": {
+                    "namespace": {
+                        "type": "string",
+                        "description": "Filter by namespace (bin, sbin, docs, user, xmcp)",
+                        "examples": ["bin", "sbin", "docs", "user", "xmcp"]
+                    },
+                    "server": {
+                        "type": "string",
+                        "description": "Filter by server (for xmcp namespace)"
+                    },
+                    "search": {
+                        "type": "string",
+                        "description": "Text search filter"
+                    },
+                    "include_schemas": {
+                        "type": "boolean",
+                        "description": "Include detailed schemas",
+                        "default": false
+                    },
+                    "limit": {
+                        "type": "integer",
+                        "description": "Limit number of results",
+                        "minimum": 1
+                    },
+                    "offset": {
+                        "type": "integer",
+                        "description": "Skip this many results (after cursor, if given)"
+                    },
+                    "cursor": {
+                        "type": "string",
+                        "description": "Continue from the next_cursor of a previous detailed listing"
+                    },
+                    "latest_only": {
+                        "type": "boolean",
+                        "description": "Only list the version of each tool that 'latest' resolves to",
+                        "default": false
+                    },
+                    "format": {
+                        "type": "string",
+                        "description": "Output format: 'simple' (paths only) or 'detailed' (rich metadata)",
+                        "enum": ["simple", "detailed"],
+                        "default": "detailed"
+                    }
+                }
+            })
+        },
+    },
+    SystemTool {
+        namespace: Namespace::Bin,
+        name: "inspect_tool",
+        description: "Get detailed information about a specific tool including schema",
+        privileged: false,
+        handler: SystemHandler::InspectTool,
+        schema: || {
+            json!({
+                "properties": {
+                    "tool_path": {
+                        "type": "string",
+                        "description": "Tool to inspect: MCP name ('bin__tcl_execute'), slash path ('/bin/tcl_execute') or bare name"
+                    }
+                },
+                "required": ["tool_path"]
+            })
+        },
+    },
+    SystemTool {
+        namespace: Namespace::Bin,
+        name: "list_namespaces",
+        description: "List available namespaces and their tool counts",
+        privileged: false,
+        handler: SystemHandler::ListNamespaces,
+        schema: || json!({"properties": {}}),
+    },
+    SystemTool {
+        namespace: Namespace::Bin,
+        name: "search_tools",
+        description: "Search tools by name, description, parameters and tags, ranked by relevance and tolerant of typos",
+        privileged: false,
+        handler: SystemHandler::SearchTools,
+        schema: || {
+            json!({
+                "properties": {
+                    "query": {
+                        "type": "string",
+                        "description": "Search query string"
+                    },
+                    "limit": {
+                        "type": "integer",
+                        "description": "Limit number of results"
+                    }
+                },
+                "required": ["query"]
+            })
+        },
+    },
+    SystemTool {
+        namespace: Namespace::Bin,
+        name: "list_xmcp_tools",
+        description: "List tools in xmcp namespace with server grouping",
+        privileged: false,
+        handler: SystemHandler::ListXmcpTools,
+        schema: || {
+            json!({
+                "properties": {
+                    "server": {
+                        "type": "string",
+                        "description": "Filter by server ID"
+                    }
+                }
+            })
+        },
+    },
+    SystemTool {
+        namespace: Namespace::Bin,
+        name: "mcp_list",
+        description: "List registered MCP servers",
+        privileged: false,
+        handler: SystemHandler::McpList,
+        schema: || json!({"properties": {}}),
+    },
+    SystemTool {
+        namespace: Namespace::Sbin,
+        name: "tcl_tool_add",
+        description: "Add a new TCL tool to the available tools (PRIVILEGED)",
+        privileged: true,
+        handler: SystemHandler::ToolAdd,
+        schema: || {
+            json!({
+                "properties": {
+                    "user": {
+                        "type": "string",
+                        "description": "User namespace"
+                    },
+                    "package": {
+                        "type": "string",
+                        "description": "Package name"
+                    },
+                    "name": {
+                        "type": "string",
+                        "description": "Name of the new tool"
+                    },
+                    "version": {
+                        "type": "string",
+                        "description": "Semantic version of the tool, e.g. '1.2' or '1.2.3' (defaults to 'latest')",
+                        "default": "latest"
+                    },
+                    "description": {
+                        "type": "string",
+                        "description": "Description of what the tool does"
+                    },
+                    "script": {
+                        "type": "string",
+                        "description": "TCL script that implements the tool"
+                    },
+                    "parameters": {
+                        "type": "array",
+                        "description": "Parameters that the tool accepts. When omitted they are inferred from the script (a single proc's arguments, or the variables it reads without setting) and returned as a preview unless confirm is true",
+                        "items": {
+                            "type": "object",
+                            "properties": {
+                                "name": { "type": "string" },
+                                "description": { "type": "string" },
+                                "required": { "type": "boolean" },
+                                "type_name": { "type": "string" },
+                                "completion": {
+                                    "type": "string",
+                                    "description": "Optional TCL proc body returning candidate values; receives $value (partial input) and $context (dict of other arguments)"
+                                },
+                                "default": {
+                                    "description": "Value the tool receives when the argument is omitted"
+                                }
+                            },
+                            "required": ["name", "description", "required", "type_name"]
+                        }
+                    },
+                    "examples": {
+                        "type": "array",
+                        "description": "Sample calls, shown in bin__inspect_tool and run by `tcl-mcp-server test`",
+                        "items": {
+                            "type": "object",
+                            "properties": {
+                                "args": {
+                                    "type": "object",
+                                    "description": "Arguments of the call"
+                                },
+                                "expected": {
+                                    "type": "string",
+                                    "description": "Output the call must return"
+                                },
+                                "pattern": {
+                                    "type": "string",
+                                    "description": "Glob the output must match instead (* and ?)"
+                                },
+                                "test_only": {
+                                    "type": "boolean",
+                                    "description": "Only run as a test, not shown as usage"
+                                }
+                            }
+                        }
+                    },
+                    "overwrite": {
+                        "type": "boolean",
+                        "description": "Replace an existing tool with the same path; its earlier revisions stay in the tool's history (default: false)"
+                    },
+                    "confirm": {
+                        "type": "boolean",
+                        "description": "Save a tool whose parameters were inferred instead of returning a preview (default: false)"
+                    },
+                    "strict": {
+                        "type": "boolean",
+                        "description": "Reject the tool if linting its script finds errors; otherwise lint diagnostics are returned with the result (default: false)"
+                    }
+                },
+                "required": ["user", "package", "name", "description", "script"]
+            })
+        },
+    },
+    SystemTool {
+        namespace: Namespace::Sbin,
+        name: "tcl_tool_remove",
+        description: "Remove a TCL tool from the available tools (PRIVILEGED)",
+        privileged: true,
+        handler: SystemHandler::ToolRemove,
+        schema: || {
+            json!({
+                "properties": {
+                    "path": {
+                        "type": "string",
+                        "description": "Tool to remove: slash path ('/alice/utils/reverse_string:1.0') or MCP name ('user__alice__utils__reverse_string__v1_0')"
+                    }
+                },
+                "required": ["path"]
+            })
+        },
+    },
+    SystemTool {
+        namespace: Namespace::Sbin,
+        name: "tcl_tool_pin",
+        description: "Pin the version a tool's 'latest' resolves to, or remove the pin (PRIVILEGED)",
+        privileged: true,
+        handler: SystemHandler::ToolPin,
+        schema: || {
+            json!({
+                "properties": {
+                    "path": {
+                        "type": "string",
+                        "description": "Tool to pin, in any tool path form (e.g., '/alice/utils/reverse_string')"
+                    },
+                    "version": {
+                        "type": "string",
+                        "description": "Version or range to make the default (e.g., '1.2' or '^1.2'); omit to remove the pin"
+                    }
+                },
+                "required": ["path"]
+            })
+        },
+    },
+    SystemTool {
+        namespace: Namespace::Sbin,
+        name: "tcl_tool_history",
+        description: "List the saved revisions of a tool, including deletions (PRIVILEGED)",
+        privileged: true,
+        handler: SystemHandler::ToolHistory,
+        schema: || {
+            json!({
+                "properties": {
+                    "path": {
+                        "type": "string",
+                        "description": "Tool whose history to list, in any tool path form (e.g., '/alice/utils/reverse_string:1.0')"
+                    }
+                },
+                "required": ["path"]
+            })
+        },
+    },
+    SystemTool {
+        namespace: Namespace::Sbin,
+        name: "tcl_tool_diff",
+        description: "Show a unified diff between two revisions of a tool (PRIVILEGED)",
+        privileged: true,
+        handler: SystemHandler::ToolDiff,
+        schema: || {
+            json!({
+                "properties": {
+                    "path": {
+                        "type": "string",
+                        "description": "Tool to compare revisions of, in any tool path form"
+                    },
+                    "from": {
+                        "type": "integer",
+                        "description": "Older revision (default: the one before 'to')"
+                    },
+                    "to": {
+                        "type": "integer",
+                        "description": "Newer revision (default: the latest)"
+                    }
+                },
+                "required": ["path"]
+            })
+        },
+    },
+    SystemTool {
+        namespace: Namespace::Sbin,
+        name: "tcl_tool_rollback",
+        description: "Restore an earlier revision of a tool, recorded as a new revision (PRIVILEGED)",
+        privileged: true,
+        handler: SystemHandler::ToolRollback,
+        schema: || {
+            json!({
+                "properties": {
+                    "path": {
+                        "type": "string",
+                        "description": "Tool to roll back, in any tool path form; deleted tools need their exact path"
+                    },
+                    "revision": {
+                        "type": "integer",
+                        "description": "Revision to restore"
+                    }
+                },
+                "required": ["path", "revision"]
+            })
+        },
+    },
+    SystemTool {
+        namespace: Namespace::Sbin,
+        name: "tcl_tool_export",
+        description: "Export the stored tools of a user or user package as a .tclpkg bundle (PRIVILEGED)",
+        privileged: true,
+        handler: SystemHandler::ToolExport,
+        schema: || {
+            json!({
+                "properties": {
+                    "package": {
+                        "type": "string",
+                        "description": "User or user package to export (e.g., 'alice/utils' or 'alice')"
+                    },
+                    "output": {
+                        "type": "string",
+                        "description": "File to write the package to; the package is returned when omitted"
+                    }
+                },
+                "required": ["package"]
+            })
+        },
+    },
+    SystemTool {
+        namespace: Namespace::Sbin,
+        name: "tcl_tool_import",
+        description: "Import the tools of a .tclpkg bundle, checking their checksums first (PRIVILEGED)",
+        privileged: true,
+        handler: SystemHandler::ToolImport,
+        schema: || {
+            json!({
+                "properties": {
+                    "package": {
+                        "type": "object",
+                        "description": "Package to import, as returned by sbin__tcl_tool_export"
+                    },
+                    "file": {
+                        "type": "string",
+                        "description": "Package file to import, instead of 'package'"
+                    },
+                    "on_conflict": {
+                        "type": "string",
+                        "enum": ["skip", "overwrite", "new_version"],
+                        "description": "What to do with tools whose version is already stored: keep it, replace it, or save the packaged tool as the next patch version",
+                        "default": "skip"
+                    }
+                }
+            })
+        },
+    },
+    SystemTool {
+        namespace: Namespace::Sbin,
+        name: "tcl_tool_reload",
+        description: "Reload tools from persistent storage (PRIVILEGED)",
+        privileged: true,
+        handler: SystemHandler::ToolReload,
+        schema: || json!({"properties": {}}),
+    },
+    SystemTool {
+        namespace: Namespace::Sbin,
+        name: "mcp_add",
+        description: "Register a new MCP server (PRIVILEGED)",
+        privileged: true,
+        handler: SystemHandler::McpAdd,
+        schema: || {
+            json!({
+                "properties": {
+                    "id": {
+                        "type": "string",
+                        "description": "Unique identifier for the server"
+                    },
+                    "name": {
+                        "type": "string",
+                        "description": "Human-readable name for the server"
+                    },
+                    "description": {
+                        "type": "string",
+                        "description": "Optional description of the server"
+                    },
+                    "command": {
+                        "type": "string",
+                        "description": "Command to start the server"
+                    },
+                    "args": {
+                        "type": "array",
+                        "description": "Command line arguments",
+                        "items": { "type": "string" },
+                        "default": []
+                    },
+                    "env": {
+                        "type": "object",
+                        "description": "Environment variables",
+                        "additionalProperties": { "type": "string" },
+                        "default": {}
+                    },
+                    "auto_start": {
+                        "type": "boolean",
+                        "description": "Whether to auto-start the server",
+                        "default": true
+                    },
+                    "timeout_ms": {
+                        "type": "integer",
+                        "description": "Connection timeout in milliseconds",
+                        "default": 30000
+                    },
+                    "max_retries": {
+                        "type": "integer",
+                        "description": "Maximum retry attempts",
+                        "default": 3
+                    }
+                },
+                "required": ["id", "name", "command"]
+            })
+        },
+    },
+    SystemTool {
+        namespace: Namespace::Sbin,
+        name: "mcp_remove",
+        description: "Remove an MCP server (PRIVILEGED)",
+        privileged: true,
+        handler: SystemHandler::McpRemove,
+        schema: || {
+            json!({
+                "properties": {
+                    "server_id": {
+                        "type": "string",
+                        "description": "ID of the server to remove"
+                    },
+                    "force": {
+                        "type": "boolean",
+                        "description": "Whether to force removal (kill process)",
+                        "default": false
+                    }
+                },
+                "required": ["server_id"]
+            })
+        },
+    },
+    SystemTool {
+        namespace: Namespace::Sbin,
+        name: "mcp_connect",
+        description: "Manually connect to an MCP server (PRIVILEGED)",
+        privileged: true,
+        handler: SystemHandler::McpConnect,
+        schema: || server_id_schema("ID of the server to connect to"),
+    },
+    SystemTool {
+        namespace: Namespace::Sbin,
+        name: "mcp_disconnect",
+        description: "Manually disconnect from an MCP server (PRIVILEGED)",
+        privileged: true,
+        handler: SystemHandler::McpDisconnect,
+        schema: || server_id_schema("ID of the server to disconnect from"),
+    },
+    SystemTool {
+        namespace: Namespace::Sbin,
+        name: "mcp_info",
+        description: "Get detailed information about an MCP server (PRIVILEGED)",
+        privileged: true,
+        handler: SystemHandler::McpInfo,
+        schema: || server_id_schema("ID of the server to get info for"),
+    },
+    SystemTool {
+        namespace: Namespace::Sbin,
+        name: "mcp_ping",
+        description: "Test connectivity to an MCP server (PRIVILEGED)",
+        privileged: true,
+        handler: SystemHandler::McpPing,
+        schema: || server_id_schema("ID of the server to ping"),
+    },
+];
+
+/// Schema for the MCP debugging tools, which take a single server ID
+fn server_id_schema(description: &str) -> Value {
+    json!({
+        "properties": {
+            "server_id": {
+                "type": "string",
+                "description": description
+            }
+        },
+        "required": ["server_id"]
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_table_is_consistent() {
+        let names: HashSet<String> = all().iter().map(SystemTool::mcp_name).collect();
+        assert_eq!(names.len(), all().len(), "duplicate system tool names");
+
+        for tool in all() {
+            assert_eq!(tool.privileged, tool.namespace == Namespace::Sbin);
+            assert!(tool.path().is_system());
+            assert!(find_path(&tool.path()).is_some());
+
+            let schema = tool.input_schema();
+            assert_eq!(schema["type"], "object");
+            assert!(schema["properties"].is_object(), "{}", tool.mcp_name());
+            for required in schema["required"].as_array().into_iter().flatten() {
+                let required = required.as_str().unwrap();
+                assert!(tool
+                    .parameters()
+                    .iter()
+                    .any(|p| p.name == required && p.required));
+            }
+        }
+    }
+
+    #[test]
+    fn test_lookup_and_privilege_filter() {
+        let tool = find("sbin__mcp_disconnect").unwrap();
+        assert_eq!(tool.handler, SystemHandler::McpDisconnect);
+        assert!(find("sbin__mcp_list").is_none());
+        assert!(find("bin__mcp_list").is_some());
+
+        assert!(available(false).all(|tool| !tool.privileged));
+        assert_eq!(available(true).count(), all().len());
+
+        let params = find("bin__exec_tool").unwrap().parameters();
+        let tool_path = params.iter().find(|p| p.name == "tool_path").unwrap();
+        assert!(tool_path.required);
+        assert_eq!(tool_path.type_name, "string");
+    }
+}
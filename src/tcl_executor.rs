@@ -1,19 +1,34 @@
 use anyhow::{anyhow, Result};
 use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
 use std::collections::HashMap;
+use std::path::PathBuf;
 use std::thread;
 use std::time::Duration;
 use tokio::sync::{mpsc, oneshot};
 
 use crate::advanced_tools;
+use crate::completion::{self, CompletionSource};
+use crate::host_commands;
 use crate::mcp_client::{McpClient, McpServerConfig};
+use crate::mcp_peer::{LogLevel, McpPeer};
 use crate::mcp_persistence::McpPersistence;
 use crate::namespace::{Namespace, ToolPath};
-use crate::persistence::FilePersistence;
+use crate::persistence::calculate_checksum;
+use crate::signing::{self, SignatureStatus, TrustStore};
+use crate::system_tools::{self, SystemHandler};
+use crate::tcl_lint;
+use crate::tcl_list;
 use crate::tcl_runtime::{create_runtime, RuntimeConfig, TclRuntime};
-use crate::tcl_tools::{ParameterDefinition, ToolDefinition};
+use crate::tcl_tools::{ParameterDefinition, ToolDefinition, ToolExample};
 use crate::tool_discovery::{DiscoveredTool, ToolDiscovery};
-use crate::tool_registry::ToolRegistry;
+use crate::tool_history::{self, ToolRevision};
+use crate::tool_package::{self, ConflictPolicy, ImportOutcome, ImportedTool, ToolPackage};
+use crate::tool_registry::{ToolQuery, ToolRegistry};
+use crate::tool_store::{self, StorageBackend, ToolStore};
+use crate::versioning::LATEST;
+
+/// The tools of each connected MCP server, keyed by server id
+pub type McpServerTools = Vec<(String, Vec<crate::mcp_client::McpToolDefinition>)>;
 
 pub enum TclCommand {
     Execute {
@@ -25,10 +40,13 @@ pub enum TclCommand {
         description: String,
         script: String,
         parameters: Vec<ParameterDefinition>,
+        examples: Vec<ToolExample>,
+        overwrite: bool,
+        strict: bool,
         response: oneshot::Sender<Result<String>>,
     },
     RemoveTool {
-        path: ToolPath,
+        path: String,
         response: oneshot::Sender<Result<String>>,
     },
     ListTools {
@@ -42,6 +60,7 @@ pub enum TclCommand {
         response: oneshot::Sender<Result<String>>,
     },
     GetToolDefinitions {
+        latest_only: bool,
         response: oneshot::Sender<Vec<ToolDefinition>>,
     },
     InitializePersistence {
@@ -76,7 +95,7 @@ pub enum TclCommand {
         response: oneshot::Sender<Result<String>>,
     },
     GetMcpServerTools {
-        response: oneshot::Sender<Result<Vec<(String, Vec<crate::mcp_client::McpToolDefinition>)>>>,
+        response: oneshot::Sender<Result<McpServerTools>>,
     },
     DebugConnectMcp {
         server_id: String,
@@ -97,6 +116,50 @@ pub enum TclCommand {
     ReloadTools {
         response: oneshot::Sender<Result<String>>,
     },
+    Complete {
+        tool: String,
+        argument: String,
+        value: String,
+        context: serde_json::Map<String, serde_json::Value>,
+        response: oneshot::Sender<Result<serde_json::Value>>,
+    },
+    ResolveToolPath {
+        path: String,
+        response: oneshot::Sender<Result<ToolPath>>,
+    },
+    GetToolDefinition {
+        path: String,
+        response: oneshot::Sender<Result<ToolDefinition>>,
+    },
+    PinToolVersion {
+        path: String,
+        version: Option<String>,
+        response: oneshot::Sender<Result<String>>,
+    },
+    ToolHistory {
+        path: String,
+        response: oneshot::Sender<Result<serde_json::Value>>,
+    },
+    ToolDiff {
+        path: String,
+        from: Option<u32>,
+        to: Option<u32>,
+        response: oneshot::Sender<Result<String>>,
+    },
+    ToolRollback {
+        path: String,
+        revision: u32,
+        response: oneshot::Sender<Result<String>>,
+    },
+    ExportTools {
+        package: String,
+        response: oneshot::Sender<Result<ToolPackage>>,
+    },
+    ImportTools {
+        package: ToolPackage,
+        on_conflict: ConflictPolicy,
+        response: oneshot::Sender<Result<Vec<ImportedTool>>>,
+    },
 }
 
 pub struct TclExecutor {
@@ -104,10 +167,14 @@ pub struct TclExecutor {
     custom_tools: HashMap<ToolPath, ToolDefinition>,
     discovered_tools: HashMap<ToolPath, DiscoveredTool>,
     tool_discovery: ToolDiscovery,
-    persistence: Option<FilePersistence>,
+    storage: StorageBackend,
+    persistence: Option<Box<dyn ToolStore>>,
     mcp_persistence: Option<McpPersistence>,
     mcp_client: McpClient,
     tool_registry: ToolRegistry,
+    peer: McpPeer,
+    /// Trust store to apply instead of the one in the config directory
+    trust_path: Option<PathBuf>,
 }
 
 impl TclExecutor {
@@ -123,24 +190,31 @@ impl TclExecutor {
 
         tracing::info!("Initialized TCL runtime: {}", runtime.name());
 
-        let mcp_client = McpClient::new();
+        let peer = McpPeer::new();
+        let mcp_client = McpClient::with_peer(peer.clone());
         let tool_registry = ToolRegistry::new(mcp_client.clone());
 
-        Self {
+        let mut executor = Self {
             runtime,
             custom_tools: HashMap::new(),
             discovered_tools: HashMap::new(),
             tool_discovery: ToolDiscovery::new(),
+            storage: StorageBackend::default(),
             persistence: None,
             mcp_persistence: None,
             mcp_client,
             tool_registry,
-        }
+            peer,
+            trust_path: None,
+        };
+        host_commands::install(executor.runtime.as_mut(), &executor.peer);
+        executor
     }
 
     pub fn new_with_runtime(
         privileged: bool,
         runtime_config: RuntimeConfig,
+        peer: McpPeer,
     ) -> Result<Self, String> {
         let runtime = crate::tcl_runtime::create_runtime_with_config(runtime_config)
             .map_err(|e| format!("Failed to create TCL runtime: {}", e))?;
@@ -154,42 +228,60 @@ impl TclExecutor {
 
         tracing::info!("Initialized TCL runtime: {}", runtime.name());
 
-        let mcp_client = McpClient::new();
+        let mcp_client = McpClient::with_peer(peer.clone());
         let tool_registry = ToolRegistry::new(mcp_client.clone());
 
-        Ok(Self {
+        let mut executor = Self {
             runtime,
             custom_tools: HashMap::new(),
             discovered_tools: HashMap::new(),
             tool_discovery: ToolDiscovery::new(),
+            storage: StorageBackend::default(),
             persistence: None,
             mcp_persistence: None,
             mcp_client,
             tool_registry,
-        })
+            peer,
+            trust_path: None,
+        };
+        host_commands::install(executor.runtime.as_mut(), &executor.peer);
+        Ok(executor)
     }
 
     pub fn spawn(privileged: bool) -> mpsc::Sender<TclCommand> {
-        Self::spawn_with_runtime(privileged, RuntimeConfig::default())
+        Self::spawn_with_runtime(privileged, RuntimeConfig::default(), McpPeer::new())
             .expect("Failed to create executor with default runtime")
     }
 
     pub fn spawn_with_runtime(
         privileged: bool,
         runtime_config: RuntimeConfig,
+        peer: McpPeer,
+    ) -> Result<mpsc::Sender<TclCommand>, String> {
+        Self::spawn_with_storage(privileged, runtime_config, peer, StorageBackend::default())
+    }
+
+    /// Spawn an executor that persists tools in the given storage backend
+    pub fn spawn_with_storage(
+        privileged: bool,
+        runtime_config: RuntimeConfig,
+        peer: McpPeer,
+        storage: StorageBackend,
     ) -> Result<mpsc::Sender<TclCommand>, String> {
         let (tx, mut rx) = mpsc::channel::<TclCommand>(100);
         let tx_clone = tx.clone();
 
         // Spawn a dedicated thread for the TCL interpreter
         thread::spawn(move || {
-            let mut executor = match TclExecutor::new_with_runtime(privileged, runtime_config) {
+            let mut executor = match TclExecutor::new_with_runtime(privileged, runtime_config, peer)
+            {
                 Ok(exec) => exec,
                 Err(e) => {
                     eprintln!("Failed to create TCL executor: {}", e);
                     return;
                 }
             };
+            executor.storage = storage;
 
             // Create a single-threaded runtime for this thread
             let runtime = tokio::runtime::Builder::new_current_thread()
@@ -212,15 +304,26 @@ impl TclExecutor {
                             description,
                             script,
                             parameters,
+                            examples,
+                            overwrite,
+                            strict,
                             response,
                         } => {
-                            let result = executor
-                                .add_tool(path, description, script, parameters)
-                                .await;
+                            let tool = ToolDefinition {
+                                path,
+                                description,
+                                script,
+                                parameters,
+                                examples,
+                            };
+                            let result = executor.add_tool(tool, overwrite, strict).await;
                             let _ = response.send(result);
                         }
                         TclCommand::RemoveTool { path, response } => {
-                            let result = executor.remove_tool(&path).await;
+                            let result = match executor.stored_tool_path(&path).await {
+                                Ok(path) => executor.remove_tool(&path).await,
+                                Err(e) => Err(e),
+                            };
                             let _ = response.send(result);
                         }
                         TclCommand::ListTools {
@@ -236,11 +339,17 @@ impl TclExecutor {
                             params,
                             response,
                         } => {
-                            let result = executor.execute_custom_tool(&path, params);
+                            let result = match executor.tool_registry.resolve_version(path) {
+                                Ok(path) => executor.execute_custom_tool(&path, params),
+                                Err(e) => Err(e),
+                            };
                             let _ = response.send(result);
                         }
-                        TclCommand::GetToolDefinitions { response } => {
-                            let tools = executor.get_tool_definitions();
+                        TclCommand::GetToolDefinitions {
+                            latest_only,
+                            response,
+                        } => {
+                            let tools = executor.get_tool_definitions(latest_only);
                             let _ = response.send(tools);
                         }
                         TclCommand::InitializePersistence { response } => {
@@ -251,10 +360,33 @@ impl TclExecutor {
                             tool_path,
                             params,
                             response,
-                        } => {
-                            let result = executor.exec_tool(&tool_path, params).await;
-                            let _ = response.send(result);
-                        }
+                        } => match executor.resolve_tool_path(&tool_path).await {
+                            Ok(path) => match executor.mcp_server_for(&path) {
+                                // Remote calls run off the command loop, as for ExecuteMcp
+                                Some(server_id) => {
+                                    let mcp_client = executor.mcp_client.clone();
+                                    tokio::spawn(async move {
+                                        let result = TclExecutor::execute_mcp_tool(
+                                            &mcp_client,
+                                            &server_id,
+                                            &path.name,
+                                            params,
+                                            "json",
+                                            30000,
+                                        )
+                                        .await;
+                                        let _ = response.send(result);
+                                    });
+                                }
+                                None => {
+                                    let result = executor.exec_tool(&tool_path, path, params).await;
+                                    let _ = response.send(result);
+                                }
+                            },
+                            Err(e) => {
+                                let _ = response.send(Err(e));
+                            }
+                        },
                         TclCommand::DiscoverTools { response } => {
                             let result = executor.discover_tools().await;
                             let _ = response.send(result);
@@ -267,8 +399,12 @@ impl TclExecutor {
                             timeout_ms,
                             response,
                         } => {
-                            let result = executor
-                                .execute_mcp_tool(
+                            // Remote calls can take a long time; run them off the
+                            // command loop so other requests are not held up
+                            let mcp_client = executor.mcp_client.clone();
+                            tokio::spawn(async move {
+                                let result = TclExecutor::execute_mcp_tool(
+                                    &mcp_client,
                                     &server_id,
                                     &tool_name,
                                     params,
@@ -276,7 +412,8 @@ impl TclExecutor {
                                     timeout_ms,
                                 )
                                 .await;
-                            let _ = response.send(result);
+                                let _ = response.send(result);
+                            });
                         }
                         TclCommand::AddMcpServer { config, response } => {
                             let result = executor.add_mcp_server(config).await;
@@ -330,6 +467,67 @@ impl TclExecutor {
                             let result = executor.reload_tools().await;
                             let _ = response.send(result);
                         }
+                        TclCommand::Complete {
+                            tool,
+                            argument,
+                            value,
+                            context,
+                            response,
+                        } => {
+                            let result = executor
+                                .complete_argument(&tool, &argument, &value, &context)
+                                .await;
+                            let _ = response.send(result);
+                        }
+                        TclCommand::ResolveToolPath { path, response } => {
+                            let result = executor.resolve_tool_path(&path).await;
+                            let _ = response.send(result);
+                        }
+                        TclCommand::GetToolDefinition { path, response } => {
+                            let result = executor.tool_definition(&path).await;
+                            let _ = response.send(result);
+                        }
+                        TclCommand::PinToolVersion {
+                            path,
+                            version,
+                            response,
+                        } => {
+                            let result = executor.pin_tool_version(&path, version).await;
+                            let _ = response.send(result);
+                        }
+                        TclCommand::ToolHistory { path, response } => {
+                            let result = executor.tool_history(&path).await;
+                            let _ = response.send(result);
+                        }
+                        TclCommand::ToolDiff {
+                            path,
+                            from,
+                            to,
+                            response,
+                        } => {
+                            let result = executor.tool_diff(&path, from, to).await;
+                            let _ = response.send(result);
+                        }
+                        TclCommand::ToolRollback {
+                            path,
+                            revision,
+                            response,
+                        } => {
+                            let result = executor.rollback_tool(&path, revision).await;
+                            let _ = response.send(result);
+                        }
+                        TclCommand::ExportTools { package, response } => {
+                            let result = executor.export_tools(&package).await;
+                            let _ = response.send(result);
+                        }
+                        TclCommand::ImportTools {
+                            package,
+                            on_conflict,
+                            response,
+                        } => {
+                            let result = executor.import_tools(&package, on_conflict).await;
+                            let _ = response.send(result);
+                        }
                     }
                 }
             });
@@ -339,16 +537,25 @@ impl TclExecutor {
     }
 
     fn execute_script(&mut self, script: &str) -> Result<String> {
-        self.runtime.eval(script)
+        let result = self.runtime.eval(script);
+        if let Err(ref e) = result {
+            self.peer.log(
+                LogLevel::Error,
+                "tcl_executor",
+                format!("Script error: {}", e),
+            );
+        }
+        result
     }
 
     async fn add_tool(
         &mut self,
-        path: ToolPath,
-        description: String,
-        script: String,
-        parameters: Vec<ParameterDefinition>,
+        tool_def: ToolDefinition,
+        overwrite: bool,
+        strict: bool,
     ) -> Result<String> {
+        let path = tool_def.path.clone();
+
         // Only allow adding tools to user namespace
         if !matches!(path.namespace, Namespace::User(_)) {
             return Err(anyhow!(
@@ -357,17 +564,53 @@ impl TclExecutor {
             ));
         }
 
-        if self.custom_tools.contains_key(&path) {
-            return Err(anyhow!("Tool '{}' already exists", path));
+        if path.version != LATEST && path.semver().is_none() {
+            return Err(anyhow!(
+                "Invalid version '{}': expected a semantic version such as 1.0 or 1.2.3",
+                path.version
+            ));
+        }
+
+        // Tools added at runtime are unsigned, so they are subject to the
+        // trust policy like unsigned stored tools: refused under enforce
+        self.admit_unsigned(
+            &path,
+            "Add the tool with `tcl-mcp-server tool add` and sign it with `tcl-mcp-server sign` instead",
+        )?;
+
+        let diagnostics = tcl_lint::lint(
+            &tool_def.script,
+            &tool_def.parameters,
+            self.runtime.as_ref(),
+        );
+        if strict && tcl_lint::has_errors(&diagnostics) {
+            let diagnostics: Vec<String> = diagnostics.iter().map(ToString::to_string).collect();
+            return Err(anyhow!(
+                "Tool '{}' was not added because its script has errors:\n{}",
+                path,
+                diagnostics.join("\n")
+            ));
+        }
+
+        let replaced = self.custom_tools.contains_key(&path);
+        if replaced && !overwrite {
+            return Err(anyhow!(
+                "Tool '{}' already exists (set overwrite to replace it)",
+                path
+            ));
         }
 
         // Initialize persistence if not already initialized
         if self.persistence.is_none() {
-            match FilePersistence::new().await {
-                Ok(persistence) => {
+            match tool_store::open(self.storage).await {
+                Ok(mut persistence) => {
                     // Load existing tools from storage
-                    match persistence.list_tools(None).await {
-                        Ok(stored_tools) => {
+                    let trusted = match self.trust_store() {
+                        Ok(trust) => Self::trusted_tools(persistence.as_mut(), &trust).await,
+                        Err(e) => Err(e),
+                    };
+                    match trusted {
+                        Ok((stored_tools, _)) => {
                             for tool in stored_tools {
                                 if matches!(tool.path.namespace, Namespace::User(_)) {
                                     self.custom_tools.insert(tool.path.clone(), tool.clone());
@@ -385,6 +628,7 @@ impl TclExecutor {
                         }
                     }
                     self.persistence = Some(persistence);
+                    self.sync_store_state();
                 }
                 Err(e) => {
                     tracing::warn!("Failed to initialize persistence: {}", e);
@@ -392,13 +636,6 @@ impl TclExecutor {
             }
         }
 
-        let tool_def = ToolDefinition {
-            path: path.clone(),
-            description,
-            script,
-            parameters,
-        };
-
         // Save to persistence if available
         let persisted = if let Some(ref mut persistence) = self.persistence {
             match persistence.save_tool(&tool_def).await {
@@ -417,15 +654,27 @@ impl TclExecutor {
 
         // Also add to tool registry for immediate availability in list_tools_advanced
         self.tool_registry.add_tcl_tool(tool_def);
+        self.sync_store_state();
+
+        let action = if replaced { "replaced" } else { "added" };
+        self.peer.log(
+            LogLevel::Info,
+            "tool_registry",
+            format!("Tool '{}' {}", path, action),
+        );
 
-        if persisted {
-            Ok(format!("Tool '{}' added successfully and persisted", path))
+        let message = if persisted {
+            format!("Tool '{}' {} successfully and persisted", path, action)
         } else {
-            Ok(format!(
-                "Tool '{}' added to memory (persistence unavailable)",
-                path
-            ))
+            format!("Tool '{}' added to memory (persistence unavailable)", path)
+        };
+        if diagnostics.is_empty() {
+            return Ok(message);
         }
+        Ok(serde_json::to_string_pretty(&serde_json::json!({
+            "message": message,
+            "diagnostics": diagnostics,
+        }))?)
     }
 
     async fn remove_tool(&mut self, path: &ToolPath) -> Result<String> {
@@ -442,11 +691,21 @@ impl TclExecutor {
 
         // Remove from persistent storage
         let removed_from_storage = self.remove_tool_from_storage(path).await?;
+        self.sync_store_state();
+
+        if self.tool_registry.version_pin(path) == Some(path.version.as_str()) {
+            self.tool_registry.set_version_pin(path, None);
+        }
 
         if removed_from_memory || removed_from_registry || removed_from_storage {
+            self.peer.log(
+                LogLevel::Info,
+                "tool_registry",
+                format!("Tool '{}' removed", path),
+            );
             Ok(format!("Tool '{}' removed successfully", path))
         } else {
-            Err(anyhow!("Tool '{}' not found", path))
+            Err(self.tool_not_found(&path.to_mcp_name()).await)
         }
     }
 
@@ -454,26 +713,12 @@ impl TclExecutor {
         let mut tools = Vec::new();
 
         // Add system tools
-        let system_tools = vec![
-            ToolPath::bin("tcl_execute"),
-            ToolPath::sbin("tcl_tool_add"),
-            ToolPath::sbin("tcl_tool_remove"),
-            ToolPath::bin("tcl_tool_list"),
-            ToolPath::bin("list_tools"),
-            ToolPath::bin("inspect_tool"),
-            ToolPath::bin("list_namespaces"),
-            ToolPath::bin("search_tools"),
-            ToolPath::bin("list_xmcp_tools"),
-            ToolPath::bin("exec_tool"),
-            ToolPath::bin("discover_tools"),
-            ToolPath::docs("molt_book"),
-        ];
-
-        for tool in system_tools {
+        for tool in system_tools::all().iter().map(|tool| tool.path()) {
             if let Some(ref ns) = namespace {
                 let matches = match (&tool.namespace, ns.as_str()) {
                     (Namespace::Bin, "bin") => true,
                     (Namespace::Sbin, "sbin") => true,
+                    (Namespace::Docs, "docs") => true,
                     (Namespace::User(user_ns), filter_ns) if user_ns == filter_ns => true,
                     _ => false,
                 };
@@ -495,10 +740,8 @@ impl TclExecutor {
         // Add custom tools
         for path in self.custom_tools.keys() {
             if let Some(ref ns) = namespace {
-                let matches = match (&path.namespace, ns.as_str()) {
-                    (Namespace::User(user_ns), filter_ns) if user_ns == filter_ns => true,
-                    _ => false,
-                };
+                let matches =
+                    matches!(&path.namespace, Namespace::User(user_ns) if user_ns == ns);
                 if !matches {
                     continue;
                 }
@@ -557,7 +800,10 @@ impl TclExecutor {
         // Set parameters as TCL variables
         if let Some(params_obj) = params.as_object() {
             for param_def in &tool.parameters {
-                if let Some(value) = params_obj.get(&param_def.name) {
+                let value = params_obj
+                    .get(&param_def.name)
+                    .or(param_def.default.as_ref());
+                if let Some(value) = value {
                     let tcl_value = match value {
                         serde_json::Value::String(s) => s.to_string(),
                         _ => value.to_string(),
@@ -573,7 +819,7 @@ impl TclExecutor {
         self.execute_script(&tool.script)
     }
 
-    fn get_tool_definitions(&self) -> Vec<ToolDefinition> {
+    fn get_tool_definitions(&self, latest_only: bool) -> Vec<ToolDefinition> {
         let mut tools = Vec::new();
 
         // Add custom tools
@@ -586,10 +832,15 @@ impl TclExecutor {
                 description: discovered.description.clone(),
                 script: format!("# Tool loaded from: {}", discovered.file_path.display()),
                 parameters: discovered.parameters.clone(),
+                examples: discovered.examples.clone(),
             };
             tools.push(tool_def);
         }
 
+        if latest_only {
+            let defaults = self.tool_registry.default_versions();
+            tools.retain(|tool| defaults.contains(&tool.path));
+        }
         tools
     }
 
@@ -599,10 +850,12 @@ impl TclExecutor {
 
         // Initialize tool persistence if not already done
         if self.persistence.is_none() {
-            let persistence = FilePersistence::new().await?;
+            let mut persistence = tool_store::open(self.storage).await?;
 
             // Load existing tools from storage
-            let stored_tools = persistence.list_tools(None).await?;
+            let trust = self.trust_store()?;
+            let (stored_tools, refused) =
+                Self::trusted_tools(persistence.as_mut(), &trust).await?;
             let loaded_count = stored_tools.len();
 
             // Add stored tools to in-memory cache
@@ -616,15 +869,22 @@ impl TclExecutor {
             }
 
             self.persistence = Some(persistence);
+            self.sync_store_state();
             message.push_str(&format!(
                 "Tool persistence initialized. Loaded {} tools from storage. ",
                 loaded_count
             ));
+            if refused > 0 {
+                message.push_str(&format!(
+                    "Refused {} tools under the trust policy. ",
+                    refused
+                ));
+            }
         }
 
         // Initialize MCP persistence if not already done
         if self.mcp_persistence.is_none() {
-            let mcp_persistence = McpPersistence::new().await?;
+            let mcp_persistence = McpPersistence::open(self.storage).await?;
 
             // Get auto-start servers
             let auto_start_servers = mcp_persistence.get_auto_start_servers();
@@ -655,7 +915,8 @@ impl TclExecutor {
 
     /// Reload tools from persistent storage
     async fn reload_tools(&mut self) -> Result<String> {
-        if let Some(ref persistence) = self.persistence {
+        let trust = self.trust_store()?;
+        if let Some(ref mut persistence) = self.persistence {
             // Clear existing custom tools from both maps
             for path in self.custom_tools.keys() {
                 self.tool_registry.remove_tcl_tool(path);
@@ -663,7 +924,8 @@ impl TclExecutor {
             self.custom_tools.clear();
 
             // Reload from storage
-            let stored_tools = persistence.list_tools(None).await?;
+            let (stored_tools, refused) =
+                Self::trusted_tools(persistence.as_mut(), &trust).await?;
             let loaded_count = stored_tools.len();
 
             // Add stored tools back to in-memory cache
@@ -676,16 +938,90 @@ impl TclExecutor {
                 }
             }
 
+            self.sync_store_state();
+
             tracing::info!("Reloaded {} tools from persistent storage", loaded_count);
             Ok(format!(
-                "Reloaded {} tools from persistent storage",
-                loaded_count
+                "Reloaded {} tools from persistent storage{}",
+                loaded_count,
+                refused_note(refused)
             ))
         } else {
             Ok("Persistence not initialized, cannot reload tools".to_string())
         }
     }
 
+    /// Complete an argument value for `completion/complete`
+    async fn complete_argument(
+        &mut self,
+        tool: &str,
+        argument: &str,
+        value: &str,
+        context: &serde_json::Map<String, serde_json::Value>,
+    ) -> Result<serde_json::Value> {
+        let path = self.resolve_tool_path(tool).await?;
+
+        let (parameters, schema) = if let Some(tool_def) = self.custom_tools.get(&path) {
+            (tool_def.parameters.clone(), None)
+        } else if let Some(discovered) = self.discovered_tools.get(&path) {
+            (discovered.parameters.clone(), None)
+        } else if let Some(unified) = self.tool_registry.get_tool(&path).await {
+            (unified.parameters, unified.schema)
+        } else if path.is_system() {
+            (Vec::new(), None)
+        } else {
+            return Err(self.tool_not_found(tool).await);
+        };
+
+        let candidates = match completion::source_for(&path, &parameters, schema.as_ref(), argument)
+        {
+            Some(CompletionSource::ToolPaths) => {
+                let mut names: Vec<String> = self
+                    .tool_registry
+                    .query_tools(ToolQuery::default())
+                    .await?
+                    .into_iter()
+                    .map(|tool| tool.path.to_mcp_name())
+                    .collect();
+                names.extend(self.discovered_tools.keys().map(|path| path.to_mcp_name()));
+                names
+            }
+            Some(CompletionSource::McpServers) => self
+                .mcp_client
+                .list_servers()
+                .await
+                .into_iter()
+                .map(|(server_id, _)| server_id)
+                .collect(),
+            Some(CompletionSource::Values(values)) => values,
+            Some(CompletionSource::Provider(body)) => {
+                self.run_completion_provider(&body, value, context)?
+            }
+            None => Vec::new(),
+        };
+
+        Ok(completion::completion_result(candidates, value))
+    }
+
+    /// Run a parameter's TCL completion provider and return its candidates
+    fn run_completion_provider(
+        &mut self,
+        body: &str,
+        value: &str,
+        context: &serde_json::Map<String, serde_json::Value>,
+    ) -> Result<Vec<String>> {
+        let context_dict = tcl_list::json_to_tcl(&serde_json::Value::Object(context.clone()));
+        let script = format!(
+            "proc __mcp_complete {{value context}} {}\n__mcp_complete {}",
+            tcl_list::format_list([body]),
+            tcl_list::format_list([value, context_dict.as_str()])
+        );
+
+        let result = self.runtime.eval(&script);
+        let _ = self.runtime.eval("rename __mcp_complete {}");
+        tcl_list::parse_list(&result?)
+    }
+
     /// Remove tool from persistent storage
     async fn remove_tool_from_storage(&mut self, path: &ToolPath) -> Result<bool> {
         if let Some(ref mut persistence) = self.persistence {
@@ -694,10 +1030,27 @@ impl TclExecutor {
         Ok(false)
     }
 
-    /// Execute a tool from the filesystem or custom tools
-    async fn exec_tool(&mut self, tool_path: &str, params: serde_json::Value) -> Result<String> {
-        // Parse the tool path
-        let path = ToolPath::parse(tool_path)?;
+    /// The MCP server that runs `path`, when it is addressed directly or
+    /// through xmcp and no local tool shadows it
+    fn mcp_server_for(&self, path: &ToolPath) -> Option<String> {
+        if self.custom_tools.contains_key(path) || self.discovered_tools.contains_key(path) {
+            return None;
+        }
+        match &path.namespace {
+            Namespace::Mcp(server_id) => Some(server_id.clone()),
+            Namespace::User(user) if user == "xmcp" => path.package.clone(),
+            _ => None,
+        }
+    }
+
+    /// Execute a tool from the filesystem, custom tools or MCP, given the
+    /// caller's `tool_path` and the `path` it resolved to
+    async fn exec_tool(
+        &mut self,
+        tool_path: &str,
+        path: ToolPath,
+        params: serde_json::Value,
+    ) -> Result<String> {
 
         // Check custom tools first (added via tcl_tool_add)
         if self.custom_tools.contains_key(&path) {
@@ -706,13 +1059,26 @@ impl TclExecutor {
 
         // Check if it's a discovered tool
         if let Some(discovered_tool) = self.discovered_tools.get(&path) {
-            // Read and execute the tool file
-            let script_content = tokio::fs::read_to_string(&discovered_tool.file_path).await?;
+            // Read the tool file and check the trust policy against exactly
+            // what was read, since the file may have changed since discovery
+            let content = tokio::fs::read(&discovered_tool.file_path).await?;
+            let trust = self.trust_store()?;
+            let status = signing::verify_content(&trust, &discovered_tool.file_path, &content)?;
+            trust.admit(&discovered_tool.file_path.display().to_string(), &status)?;
+            let script_content = String::from_utf8(content).map_err(|_| {
+                anyhow!(
+                    "Tool file {} is not valid UTF-8",
+                    discovered_tool.file_path.display()
+                )
+            })?;
 
             // Set parameters as TCL variables
             if let Some(params_obj) = params.as_object() {
                 for param_def in &discovered_tool.parameters {
-                    if let Some(value) = params_obj.get(&param_def.name) {
+                    let value = params_obj
+                        .get(&param_def.name)
+                        .or(param_def.default.as_ref());
+                    if let Some(value) = value {
                         let tcl_value = match value {
                             serde_json::Value::String(s) => s.to_string(),
                             _ => value.to_string(),
@@ -733,77 +1099,66 @@ impl TclExecutor {
             return self.execute_custom_tool(&path, params);
         }
 
-        // Check if it's an MCP tool
-        if let Namespace::Mcp(server_id) = &path.namespace {
-            return self
-                .execute_mcp_tool(server_id, &path.name, params, "json", 30000)
-                .await;
+        // Check if it's an MCP tool, addressed directly or through xmcp
+        if let Some(server_id) = self.mcp_server_for(&path) {
+            return Self::execute_mcp_tool(
+                &self.mcp_client,
+                &server_id,
+                &path.name,
+                params,
+                "json",
+                30000,
+            )
+            .await;
         }
 
         // Check if it's a built-in system tool
-        match tool_path {
-            "bin__tcl_execute" => {
+        match system_tools::find_path(&path).map(|tool| tool.handler) {
+            Some(SystemHandler::TclExecute) => {
                 if let Some(script) = params.get("script").and_then(|s| s.as_str()) {
                     self.execute_script(script)
                 } else {
                     Err(anyhow!("Missing required parameter: script"))
                 }
             }
-            "bin__tcl_tool_list" => {
-                let namespace = params
-                    .get("namespace")
-                    .and_then(|s| s.as_str())
-                    .map(String::from);
-                let filter = params
-                    .get("filter")
-                    .and_then(|s| s.as_str())
-                    .map(String::from);
-                let tools = self.list_tools(namespace, filter);
-                Ok(tools.join("\n"))
-            }
-            "bin__list_tools" => {
-                let namespace = params
-                    .get("namespace")
-                    .and_then(|s| s.as_str())
-                    .map(String::from);
-                let server = params
-                    .get("server")
-                    .and_then(|s| s.as_str())
-                    .map(String::from);
-                let search = params
-                    .get("search")
-                    .and_then(|s| s.as_str())
-                    .map(String::from);
-                let include_schemas = params
-                    .get("include_schemas")
-                    .and_then(|b| b.as_bool())
-                    .unwrap_or(false);
-                let limit = params
-                    .get("limit")
-                    .and_then(|n| n.as_u64())
-                    .map(|n| n as usize);
-                let format = params
-                    .get("format")
-                    .and_then(|s| s.as_str())
-                    .map(String::from);
+            Some(SystemHandler::ListTools) => {
+                self.sync_mcp_search_index().await;
+                let string_param =
+                    |name: &str| params.get(name).and_then(|s| s.as_str()).map(String::from);
+                let usize_param = |name: &str| {
+                    params
+                        .get(name)
+                        .and_then(|n| n.as_u64())
+                        .map(|n| n as usize)
+                };
 
-                match advanced_tools::list_tools(
-                    &self.tool_registry,
-                    namespace,
-                    server,
-                    search,
-                    include_schemas,
-                    limit,
-                    format,
-                )
-                .await
+                let query = ToolQuery {
+                    namespace: string_param("namespace"),
+                    server: string_param("server"),
+                    search: string_param("search"),
+                    include_schemas: params
+                        .get("include_schemas")
+                        .and_then(|b| b.as_bool())
+                        .unwrap_or(false),
+                    limit: usize_param("limit"),
+                    offset: usize_param("offset"),
+                    cursor: string_param("cursor"),
+                    latest_only: params
+                        .get("latest_only")
+                        .and_then(|b| b.as_bool())
+                        .unwrap_or(false),
+                };
+
+                match advanced_tools::list_tools(&self.tool_registry, query, string_param("format"))
+                    .await
                 {
                     Ok(result) => Ok(result),
                     Err(e) => Err(anyhow!("Failed to list tools: {}", e)),
                 }
             }
-            "bin__inspect_tool" => {
+            Some(SystemHandler::InspectTool) => {
                 if let Some(tool_path) = params.get("tool_path").and_then(|s| s.as_str()) {
+                    self.sync_mcp_search_index().await;
                     match advanced_tools::inspect_tool(&self.tool_registry, tool_path).await {
                         Ok(result) => Ok(result),
                         Err(e) => Err(anyhow!("Failed to inspect tool: {}", e)),
@@ -812,14 +1167,15 @@ impl TclExecutor {
                     Err(anyhow!("Missing required parameter: tool_path"))
                 }
             }
-            "bin__list_namespaces" => {
+            Some(SystemHandler::ListNamespaces) => {
                 match advanced_tools::list_namespaces(&self.tool_registry).await {
                     Ok(result) => Ok(result),
                     Err(e) => Err(anyhow!("Failed to list namespaces: {}", e)),
                 }
             }
-            "bin__search_tools" => {
+            Some(SystemHandler::SearchTools) => {
                 if let Some(query) = params.get("query").and_then(|s| s.as_str()) {
+                    self.sync_mcp_search_index().await;
                     let limit = params
                         .get("limit")
                         .and_then(|n| n.as_u64())
@@ -832,7 +1188,7 @@ impl TclExecutor {
                     Err(anyhow!("Missing required parameter: query"))
                 }
             }
-            "bin__list_xmcp_tools" => {
+            Some(SystemHandler::ListXmcpTools) => {
                 let server_filter = params
                     .get("server")
                     .and_then(|s| s.as_str())
@@ -842,17 +1198,335 @@ impl TclExecutor {
                     Err(e) => Err(anyhow!("Failed to list external MCP tools: {}", e)),
                 }
             }
-            _ => Err(anyhow!("Tool '{}' not found", tool_path)),
+            Some(_) => Err(anyhow!(
+                "System tool '{}' must be called directly, not through exec_tool",
+                tool_path
+            )),
+            None => Err(self.tool_not_found(tool_path).await),
+        }
+    }
+
+    /// Resolve a tool path given by a caller, in any supported form
+    async fn resolve_tool_path(&mut self, input: &str) -> Result<ToolPath> {
+        if ToolPath::is_bare_name(input) {
+            self.sync_mcp_search_index().await;
+        }
+        self.tool_registry.resolve(input)
+    }
+
+    /// The definition of a TCL tool, with the script of a filesystem tool
+    /// read from its file
+    async fn tool_definition(&mut self, input: &str) -> Result<ToolDefinition> {
+        let path = self.resolve_tool_path(input).await?;
+        if let Some(tool) = self.custom_tools.get(&path) {
+            return Ok(tool.clone());
+        }
+        if let Some(discovered) = self.discovered_tools.get(&path) {
+            return Ok(ToolDefinition {
+                path: discovered.path.clone(),
+                description: discovered.description.clone(),
+                script: tokio::fs::read_to_string(&discovered.file_path).await?,
+                parameters: discovered.parameters.clone(),
+                examples: discovered.examples.clone(),
+            });
+        }
+        Err(anyhow!(
+            "Tool '{}' has no TCL script; only user and filesystem tools do",
+            path
+        ))
+    }
+
+    /// The stored tool a request names. A path naming a stored version
+    /// exactly, including an unversioned `latest` tool, names that tool;
+    /// anything else is resolved like any other tool path.
+    async fn stored_tool_path(&mut self, input: &str) -> Result<ToolPath> {
+        match ToolPath::parse(input) {
+            Ok(path) if self.custom_tools.contains_key(&path) => Ok(path),
+            _ => self.resolve_tool_path(input).await,
+        }
+    }
+
+    /// The tool whose history a request names, which may since have been
+    /// deleted, with its recorded revisions
+    async fn tool_revisions(&mut self, input: &str) -> Result<(ToolPath, Vec<ToolRevision>)> {
+        let path = match self.stored_tool_path(input).await {
+            Ok(path) => path,
+            // Deleted tools are only found by their exact path
+            Err(e) => match ToolPath::parse(input) {
+                Ok(path) if matches!(path.namespace, Namespace::User(_)) => path,
+                _ => return Err(e),
+            },
+        };
+        let persistence = self
+            .persistence
+            .as_ref()
+            .ok_or_else(|| anyhow!("Tool persistence is not initialized"))?;
+        let revisions = persistence.history(&path).await?;
+        if revisions.is_empty() {
+            if self.custom_tools.contains_key(&path) {
+                return Err(anyhow!("Tool '{}' has no recorded history", path));
+            }
+            return Err(self.tool_not_found(input).await);
+        }
+        Ok((path, revisions))
+    }
+
+    /// Saved revisions of a tool, oldest first
+    async fn tool_history(&mut self, input: &str) -> Result<serde_json::Value> {
+        let (path, revisions) = self.tool_revisions(input).await?;
+        let revisions: Vec<serde_json::Value> = revisions
+            .iter()
+            .map(|r| {
+                serde_json::json!({
+                    "revision": r.revision,
+                    "action": r.action,
+                    "saved_at": r.saved_at,
+                    "checksum": r.checksum,
+                    "restored_from": r.restored_from,
+                    "description": r.tool.description,
+                })
+            })
+            .collect();
+        Ok(serde_json::json!({
+            "tool": path.to_mcp_name(),
+            "revisions": revisions,
+        }))
+    }
+
+    /// Diff between two revisions of a tool, by default the last two
+    async fn tool_diff(
+        &mut self,
+        input: &str,
+        from: Option<u32>,
+        to: Option<u32>,
+    ) -> Result<String> {
+        let (path, revisions) = self.tool_revisions(input).await?;
+        let latest = revisions.last().map_or(0, |last| last.revision);
+        let to = to.unwrap_or(latest);
+        let from = match from {
+            Some(from) => from,
+            None if to > 1 => to - 1,
+            None => {
+                return Err(anyhow!(
+                    "Tool '{}' has only one revision; nothing to compare",
+                    path
+                ))
+            }
+        };
+        let find = |revision: u32| {
+            revisions
+                .iter()
+                .find(|r| r.revision == revision)
+                .ok_or_else(|| {
+                    anyhow!(
+                        "Tool '{}' has no revision {} (latest is {})",
+                        path,
+                        revision,
+                        latest
+                    )
+                })
+        };
+        Ok(tool_history::diff(find(from)?, find(to)?))
+    }
+
+    /// Restore an earlier revision of a tool as its newest revision
+    async fn rollback_tool(&mut self, input: &str, revision: u32) -> Result<String> {
+        let (path, _) = self.tool_revisions(input).await?;
+        // Revisions are restored without a signature, like runtime additions
+        self.admit_unsigned(
+            &path,
+            "A rolled-back revision is unsigned; restore it with the trust policy relaxed, then sign it with `tcl-mcp-server sign`",
+        )?;
+        let persistence = self
+            .persistence
+            .as_mut()
+            .ok_or_else(|| anyhow!("Tool persistence is not initialized"))?;
+        let restored = persistence.rollback(&path, revision).await?;
+
+        self.custom_tools
+            .insert(path.clone(), restored.tool.clone());
+        self.tool_registry.add_tcl_tool(restored.tool);
+        self.sync_store_state();
+        self.peer.log(
+            LogLevel::Info,
+            "tool_registry",
+            format!("Tool '{}' rolled back to revision {}", path, revision),
+        );
+
+        Ok(format!(
+            "Tool '{}' rolled back to revision {} (now revision {})",
+            path, revision, restored.revision
+        ))
+    }
+
+    /// Bundle the stored tools of a user or user package
+    async fn export_tools(&mut self, selector: &str) -> Result<ToolPackage> {
+        let persistence = self
+            .persistence
+            .as_mut()
+            .ok_or_else(|| anyhow!("Tool persistence is not initialized"))?;
+        tool_package::export(persistence.as_mut(), selector).await
+    }
+
+    /// Save the tools of a package, then reload the store so that the trust
+    /// policy applies to them like any other stored tool
+    async fn import_tools(
+        &mut self,
+        package: &ToolPackage,
+        on_conflict: ConflictPolicy,
+    ) -> Result<Vec<ImportedTool>> {
+        let persistence = self
+            .persistence
+            .as_mut()
+            .ok_or_else(|| anyhow!("Tool persistence is not initialized"))?;
+        let imported = tool_package::import(persistence.as_mut(), package, on_conflict).await?;
+        self.reload_tools().await?;
+
+        let saved = imported
+            .iter()
+            .filter(|tool| {
+                !matches!(
+                    tool.outcome,
+                    ImportOutcome::Skipped | ImportOutcome::Unchanged
+                )
+            })
+            .count();
+        self.peer.log(
+            LogLevel::Info,
+            "tool_registry",
+            format!(
+                "Imported {} of {} tools from package '{}'",
+                saved,
+                imported.len(),
+                package.user
+            ),
+        );
+        Ok(imported)
+    }
+
+    /// Pin the version `latest` resolves to for a tool, or remove the pin
+    async fn pin_tool_version(&mut self, input: &str, version: Option<String>) -> Result<String> {
+        let path = self.resolve_tool_path(input).await?;
+        if !matches!(path.namespace, Namespace::User(_)) {
+            return Err(anyhow!(
+                "Only user tools have versions to pin, not '{}'",
+                path
+            ));
+        }
+        if version.is_none() && self.tool_registry.version_pin(&path).is_none() {
+            return Err(anyhow!(
+                "Tool '{}' has no pinned version",
+                path.with_version(LATEST)
+            ));
+        }
+        let pinned = match version {
+            Some(version) => {
+                let target = self
+                    .tool_registry
+                    .resolve_version(path.with_version(version))?;
+                if !self.custom_tools.contains_key(&target) {
+                    return Err(self.tool_not_found(&target.to_mcp_name()).await);
+                }
+                Some(target.version)
+            }
+            None => None,
+        };
+
+        if let Some(ref mut persistence) = self.persistence {
+            persistence.set_version_pin(&path, pinned.clone()).await?;
+        }
+        self.tool_registry.set_version_pin(&path, pinned.clone());
+
+        let tool = path.with_version(LATEST);
+        Ok(match pinned {
+            Some(version) => format!("Default version of '{}' pinned to {}", tool, version),
+            None => format!("Default version pin of '{}' removed", tool),
+        })
+    }
+
+    /// The trust store the policy is read from
+    fn trust_store(&self) -> Result<TrustStore> {
+        match &self.trust_path {
+            Some(path) => TrustStore::load_from(path),
+            None => TrustStore::load(),
+        }
+    }
+
+    /// Apply the trust policy to a tool about to become active without a
+    /// signature, explaining how to proceed if it is refused
+    fn admit_unsigned(&self, path: &ToolPath, hint: &str) -> Result<()> {
+        self.trust_store()?
+            .admit(&path.to_string(), &SignatureStatus::Unsigned)
+            .map_err(|e| anyhow!("{}. {}", e, hint))
+    }
+
+    /// Stored tools the trust policy admits, and how many it refused
+    async fn trusted_tools(
+        persistence: &mut dyn ToolStore,
+        trust: &TrustStore,
+    ) -> Result<(Vec<ToolDefinition>, usize)> {
+        let mut tools = Vec::new();
+        let mut refused = 0;
+        for persisted in persistence.list_tools(None).await? {
+            let checksum = calculate_checksum(&persisted.tool);
+            let status = trust.verify(checksum.as_bytes(), persisted.metadata.signature.as_ref());
+            match trust.admit(&persisted.tool.path.to_string(), &status) {
+                Ok(()) => tools.push(persisted.tool),
+                Err(e) => {
+                    tracing::warn!("{}", e);
+                    refused += 1;
+                }
+            }
+        }
+        Ok((tools, refused))
+    }
+
+    /// Refresh the version pins and integrity status the registry reports
+    /// for stored tools
+    fn sync_store_state(&mut self) {
+        if let Some(ref persistence) = self.persistence {
+            match persistence.version_pins() {
+                Ok(pins) => self.tool_registry.set_version_pins(pins),
+                Err(e) => tracing::warn!("Failed to read version pins: {}", e),
+            }
+            match persistence.integrity() {
+                Ok(integrity) => self.tool_registry.set_integrity(integrity),
+                Err(e) => tracing::warn!("Failed to read tool integrity: {}", e),
+            }
+        }
+    }
+
+    /// Error for a tool that does not exist, suggesting similar tools
+    async fn tool_not_found(&mut self, input: &str) -> anyhow::Error {
+        self.sync_mcp_search_index().await;
+        self.tool_registry.not_found(input)
+    }
+
+    /// Index tools of connected MCP servers that changed since the last search
+    async fn sync_mcp_search_index(&mut self) {
+        if let Err(e) = self.tool_registry.sync_mcp_tools().await {
+            tracing::warn!("Failed to index MCP server tools: {}", e);
         }
     }
 
     /// Discover and index tools from the filesystem
     async fn discover_tools(&mut self) -> Result<String> {
         // Discover tools from the filesystem
-        let discovered = self.tool_discovery.discover_tools().await?;
+        let trust = self.trust_store()?;
+        let mut discovered = self.tool_discovery.discover_tools().await?;
+        let found = discovered.len();
+        discovered.retain(|tool| {
+            let admitted = signing::verify_file(&trust, &tool.file_path)
+                .and_then(|status| trust.admit(&tool.file_path.display().to_string(), &status));
+            if let Err(e) = &admitted {
+                tracing::warn!("{}", e);
+            }
+            admitted.is_ok()
+        });
         let count = discovered.len();
 
-        // Add discovered tools to our cache
+        // Add discovered tools to our cache and the registry's search index
+        self.tool_registry.add_discovered_tools(discovered.clone());
         for tool in discovered {
             self.discovered_tools.insert(tool.path.clone(), tool);
         }
@@ -861,12 +1535,22 @@ impl TclExecutor {
         // Note: We don't add them as TCL commands directly since that would require
         // complex callback handling. Instead, they can be executed via exec_tool.
 
-        Ok(format!("Discovered {} tools from filesystem", count))
+        self.peer.log(
+            LogLevel::Info,
+            "tool_discovery",
+            format!("Discovered {} tools from filesystem", count),
+        );
+
+        Ok(format!(
+            "Discovered {} tools from filesystem{}",
+            count,
+            refused_note(found - count)
+        ))
     }
 
     // MCP server management methods
     async fn execute_mcp_tool(
-        &mut self,
+        mcp_client: &McpClient,
         server_id: &str,
         tool_name: &str,
         params: serde_json::Value,
@@ -876,8 +1560,7 @@ impl TclExecutor {
         // Execute the tool via MCP client
         let result = tokio::time::timeout(
             std::time::Duration::from_millis(timeout_ms),
-            self.mcp_client
-                .execute_tool(server_id, tool_name, params.clone()),
+            mcp_client.execute_tool(server_id, tool_name, params.clone()),
         )
         .await
         .map_err(|_| anyhow!("Tool execution timeout after {}ms", timeout_ms))?
@@ -900,7 +1583,7 @@ impl TclExecutor {
                     Ok(result.to_string())
                 }
             }
-            "auto" | _ => {
+            _ => {
                 // Auto-detect best format
                 if let Some(content) = result.get("content").and_then(|c| c.as_array()) {
                     if content.len() == 1
@@ -1019,7 +1702,7 @@ impl TclExecutor {
 
     async fn get_mcp_server_tools(
         &mut self,
-    ) -> Result<Vec<(String, Vec<crate::mcp_client::McpToolDefinition>)>> {
+    ) -> Result<McpServerTools> {
         let mut all_tools = Vec::new();
 
         // Get list of all connected servers
@@ -1063,6 +1746,15 @@ impl TclExecutor {
     }
 }
 
+/// Suffix noting tools the trust policy refused
+fn refused_note(refused: usize) -> String {
+    if refused == 0 {
+        String::new()
+    } else {
+        format!(" ({} refused by the trust policy)", refused)
+    }
+}
+
 /// Setup filesystem watcher for tools directory
 async fn setup_filesystem_watcher(tx: mpsc::Sender<TclCommand>) -> Option<RecommendedWatcher> {
     use crate::platform_dirs;
@@ -1126,7 +1818,7 @@ async fn setup_filesystem_watcher(tx: mpsc::Sender<TclCommand>) -> Option<Recomm
                     event
                         .paths
                         .iter()
-                        .any(|path| path.extension().map_or(false, |ext| ext == "json"))
+                        .any(|path| path.extension().is_some_and(|ext| ext == "json"))
                 }
                 _ => false,
             };
@@ -1161,7 +1853,9 @@ mod tests {
 
     #[tokio::test]
     async fn test_tcl_script_execution() {
-        let executor = TclExecutor::spawn_with_runtime(false, RuntimeConfig::default()).unwrap();
+        let executor =
+            TclExecutor::spawn_with_runtime(false, RuntimeConfig::default(), McpPeer::new())
+                .unwrap();
         let (tx, rx) = oneshot::channel();
 
         executor
@@ -1182,7 +1876,9 @@ mod tests {
 
     #[tokio::test]
     async fn test_tcl_error_handling() {
-        let executor = TclExecutor::spawn_with_runtime(false, RuntimeConfig::default()).unwrap();
+        let executor =
+            TclExecutor::spawn_with_runtime(false, RuntimeConfig::default(), McpPeer::new())
+                .unwrap();
         let (tx, rx) = oneshot::channel();
 
         // Execute invalid TCL script
@@ -1200,7 +1896,9 @@ mod tests {
 
     #[tokio::test]
     async fn test_special_character_handling() {
-        let executor = TclExecutor::spawn_with_runtime(false, RuntimeConfig::default()).unwrap();
+        let executor =
+            TclExecutor::spawn_with_runtime(false, RuntimeConfig::default(), McpPeer::new())
+                .unwrap();
         let (tx, rx) = oneshot::channel();
 
         // Test string with special characters
@@ -1224,7 +1922,9 @@ mod tests {
 
     #[tokio::test]
     async fn test_tool_addition_and_execution() {
-        let executor = TclExecutor::spawn_with_runtime(true, RuntimeConfig::default()).unwrap();
+        let executor =
+            TclExecutor::spawn_with_runtime(true, RuntimeConfig::default(), McpPeer::new())
+                .unwrap();
 
         // Add a simple test tool
         let (add_tx, add_rx) = oneshot::channel();
@@ -1242,14 +1942,21 @@ mod tests {
                         description: "First number".to_string(),
                         required: true,
                         type_name: "number".to_string(),
+                        completion: None,
+                        default: None,
                     },
                     ParameterDefinition {
                         name: "b".to_string(),
                         description: "Second number".to_string(),
                         required: true,
                         type_name: "number".to_string(),
+                        completion: None,
+                        default: None,
                     },
                 ],
+                examples: Vec::new(),
+                overwrite: false,
+                strict: false,
                 response: add_tx,
             })
             .await
@@ -1284,9 +1991,125 @@ mod tests {
         assert!(exec_result.contains("Result: 5 + 3 = 8"));
     }
 
+    #[tokio::test]
+    async fn test_strict_add_rejects_lint_errors() {
+        let executor =
+            TclExecutor::spawn_with_runtime(true, RuntimeConfig::default(), McpPeer::new())
+                .unwrap();
+
+        let add = |name: &str, strict: bool| {
+            let (tx, rx) = oneshot::channel();
+            let command = TclCommand::AddTool {
+                path: ToolPath::user("test", "lint", name, "latest"),
+                description: "Greet someone".to_string(),
+                script: "return \"Hello, $nmae\"".to_string(),
+                parameters: vec![ParameterDefinition {
+                    name: "name".to_string(),
+                    description: "Who to greet".to_string(),
+                    required: true,
+                    type_name: "string".to_string(),
+                    completion: None,
+                    default: None,
+                }],
+                examples: Vec::new(),
+                overwrite: false,
+                strict,
+                response: tx,
+            };
+            (command, rx)
+        };
+
+        let (command, rx) = add("strict", true);
+        executor.send(command).await.unwrap();
+        let err = timeout(Duration::from_secs(5), rx)
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("line 1: error[undeclared-variable]: Variable `$nmae`"));
+
+        // Without strict mode the tool is added and the diagnostics returned
+        let (command, rx) = add("lenient", false);
+        executor.send(command).await.unwrap();
+        let result = timeout(Duration::from_secs(5), rx)
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+        let result: serde_json::Value = serde_json::from_str(&result).unwrap();
+        assert!(result["message"]
+            .as_str()
+            .unwrap()
+            .contains("added successfully"));
+        let codes: Vec<&str> = result["diagnostics"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter_map(|diagnostic| diagnostic["code"].as_str())
+            .collect();
+        assert_eq!(codes, vec!["unused-parameter", "undeclared-variable"]);
+    }
+
+    #[tokio::test]
+    async fn test_rollback_refused_under_enforce() {
+        let temp = tempfile::tempdir().unwrap();
+        let trust_path = temp.path().join("trust.json");
+        let mut executor =
+            TclExecutor::new_with_runtime(true, RuntimeConfig::default(), McpPeer::new()).unwrap();
+        executor.trust_path = Some(trust_path.clone());
+        executor.persistence = Some(Box::new(
+            crate::persistence::FilePersistence::with_directory(temp.path().join("tools"))
+                .await
+                .unwrap(),
+        ));
+
+        let path = ToolPath::user("test", "trust", "greet", "1.0");
+        let tool = ToolDefinition {
+            path: path.clone(),
+            description: "Greet".to_string(),
+            script: "return hello".to_string(),
+            parameters: Vec::new(),
+            examples: Vec::new(),
+        };
+        executor.add_tool(tool.clone(), false, false).await.unwrap();
+        let edited = ToolDefinition {
+            script: "return goodbye".to_string(),
+            ..tool
+        };
+        executor.add_tool(edited.clone(), true, false).await.unwrap();
+
+        let mut trust = TrustStore::load_from(&trust_path).unwrap();
+        trust.set_policy(signing::TrustPolicy::Enforce);
+        trust.save().unwrap();
+
+        let err = executor
+            .rollback_tool("/test/trust/greet:1.0", 1)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("Refusing tool"), "{}", err);
+        assert_eq!(executor.custom_tools[&path].script, "return goodbye");
+        let stored = executor
+            .persistence
+            .as_ref()
+            .unwrap()
+            .load_persisted(&path)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(stored.tool.script, "return goodbye");
+
+        // Runtime additions are refused the same way
+        let err = executor.add_tool(edited, true, false).await.unwrap_err();
+        assert!(err.to_string().contains("Refusing tool"), "{}", err);
+    }
+
     #[tokio::test]
     async fn test_parameter_validation() {
-        let executor = TclExecutor::spawn_with_runtime(true, RuntimeConfig::default()).unwrap();
+        let executor =
+            TclExecutor::spawn_with_runtime(true, RuntimeConfig::default(), McpPeer::new())
+                .unwrap();
 
         // Add a tool with required parameters
         let (add_tx, add_rx) = oneshot::channel();
@@ -1302,7 +2125,12 @@ mod tests {
                     description: "Required value".to_string(),
                     required: true,
                     type_name: "string".to_string(),
+                    completion: None,
+                    default: None,
                 }],
+                examples: Vec::new(),
+                overwrite: false,
+                strict: false,
                 response: add_tx,
             })
             .await
@@ -1336,4 +2164,162 @@ mod tests {
             "Should fail with missing required parameter"
         );
     }
+
+    #[tokio::test]
+    async fn test_mcp_log_command() {
+        let peer = McpPeer::new();
+        let (out_tx, mut out_rx) = mpsc::unbounded_channel();
+        peer.attach(out_tx);
+
+        let executor =
+            TclExecutor::spawn_with_runtime(false, RuntimeConfig::default(), peer.clone()).unwrap();
+        let (tx, rx) = oneshot::channel();
+
+        executor
+            .send(TclCommand::Execute {
+                script: "mcp::log debug hidden; mcp::log warning {disk almost full}".to_string(),
+                response: tx,
+            })
+            .await
+            .unwrap();
+
+        timeout(Duration::from_secs(5), rx)
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+
+        // Debug is below the default level, so only the warning is delivered
+        let message = out_rx.try_recv().unwrap();
+        assert_eq!(message["method"], "notifications/message");
+        assert_eq!(message["params"]["level"], "warning");
+        assert_eq!(message["params"]["logger"], "tcl");
+        assert_eq!(message["params"]["data"], "disk almost full");
+        assert!(out_rx.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_mcp_elicit_round_trip() {
+        let peer = McpPeer::new();
+        let (out_tx, mut out_rx) = mpsc::unbounded_channel();
+        peer.attach(out_tx);
+        peer.set_client_capabilities(serde_json::json!({"elicitation": {}}));
+
+        let executor =
+            TclExecutor::spawn_with_runtime(false, RuntimeConfig::default(), peer.clone()).unwrap();
+        let (tx, rx) = oneshot::channel();
+
+        executor
+            .send(TclCommand::Execute {
+                script: "set answer [mcp::elicit {Deploy now?} {confirm boolean}]; \
+                         dict get $answer action"
+                    .to_string(),
+                response: tx,
+            })
+            .await
+            .unwrap();
+
+        // Play the client: answer the elicitation request
+        let request = timeout(Duration::from_secs(5), out_rx.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(request["method"], "elicitation/create");
+        assert_eq!(request["params"]["message"], "Deploy now?");
+        assert_eq!(
+            request["params"]["requestedSchema"]["properties"]["confirm"]["type"],
+            "boolean"
+        );
+        assert!(peer.handle_response(&serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": request["id"],
+            "result": {"action": "accept", "content": {"confirm": true}}
+        })));
+
+        let result = timeout(Duration::from_secs(5), rx)
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+        assert_eq!(result, "accept");
+    }
+
+    #[tokio::test]
+    async fn test_completion_provider() {
+        let executor =
+            TclExecutor::spawn_with_runtime(true, RuntimeConfig::default(), McpPeer::new())
+                .unwrap();
+        let (add_tx, add_rx) = oneshot::channel();
+
+        executor
+            .send(TclCommand::AddTool {
+                path: ToolPath::user("test", "paint", "mix", "1.0"),
+                description: "Mix two colors".to_string(),
+                script: "return \"$first + $second\"".to_string(),
+                parameters: vec![
+                    ParameterDefinition {
+                        name: "first".to_string(),
+                        description: "First color".to_string(),
+                        required: true,
+                        type_name: "string".to_string(),
+                        completion: Some("return {red green blue}".to_string()),
+                        default: None,
+                    },
+                    ParameterDefinition {
+                        name: "second".to_string(),
+                        description: "Second color".to_string(),
+                        required: true,
+                        type_name: "string".to_string(),
+                        completion: Some(
+                            "return [list \"not [dict get $context first]\"]".to_string(),
+                        ),
+                        default: None,
+                    },
+                ],
+                examples: Vec::new(),
+                overwrite: false,
+                strict: false,
+                response: add_tx,
+            })
+            .await
+            .unwrap();
+        timeout(Duration::from_secs(5), add_rx)
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+
+        let complete = |argument: &str, value: &str, context: serde_json::Value| {
+            let (tx, rx) = oneshot::channel();
+            let command = TclCommand::Complete {
+                tool: "user__test__paint__mix__v1_0".to_string(),
+                argument: argument.to_string(),
+                value: value.to_string(),
+                context: context.as_object().cloned().unwrap_or_default(),
+                response: tx,
+            };
+            (command, rx)
+        };
+
+        let (command, rx) = complete("first", "gr", serde_json::json!({}));
+        executor.send(command).await.unwrap();
+        let result = timeout(Duration::from_secs(5), rx)
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+        assert_eq!(result["completion"]["values"], serde_json::json!(["green"]));
+
+        let (command, rx) = complete("second", "", serde_json::json!({"first": "red"}));
+        executor.send(command).await.unwrap();
+        let result = timeout(Duration::from_secs(5), rx)
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            result["completion"]["values"],
+            serde_json::json!(["not red"])
+        );
+    }
 }
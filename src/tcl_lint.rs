@@ -0,0 +1,1152 @@
+/// Static checks of tool scripts
+///
+/// Scripts are parsed with TCL's word and substitution rules without being
+/// evaluated; the bodies of control structures and procs are checked like
+/// the script itself. The checks are:
+///
+/// - `syntax`: unbalanced braces, brackets, quotes or parentheses (error)
+/// - `undeclared-variable`: a variable that is read but is neither a
+///   parameter nor set anywhere in its script or proc (error)
+/// - `unused-parameter`: a parameter the script never reads (warning)
+/// - `unknown-command`: a command the runtime does not have and the script
+///   does not define, including the `mcp::*` host commands on runtimes
+///   that cannot register them (error)
+/// - `restricted-command`: a command that is only available in privileged
+///   mode (warning)
+///
+/// Scripts can build command and variable names at run time, so only names
+/// written literally are checked.
+///
+/// The same parser finds the free variables and proc definition of a script
+/// for `param_inference`.
+use serde::Serialize;
+use std::collections::HashSet;
+use std::fmt;
+
+use crate::capabilities::TclRuntimeCapabilities;
+use crate::host_commands;
+use crate::tcl_list;
+use crate::tcl_runtime::TclRuntime;
+use crate::tcl_tools::ParameterDefinition;
+
+/// Variables every interpreter has
+const BUILTIN_VARIABLES: &[&str] = &[
+    "argc",
+    "argv",
+    "argv0",
+    "env",
+    "errorCode",
+    "errorInfo",
+    "tcl_patchLevel",
+    "tcl_platform",
+    "tcl_version",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// The check that found the problem, e.g. `unknown-command`
+    pub code: &'static str,
+    pub message: String,
+    /// Line of the script, from 1; `None` for problems with the parameters
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line: Option<usize>,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let severity = match self.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+        if let Some(line) = self.line {
+            write!(f, "line {}: ", line)?;
+        }
+        write!(f, "{}[{}]: {}", severity, self.code, self.message)
+    }
+}
+
+pub fn has_errors(diagnostics: &[Diagnostic]) -> bool {
+    diagnostics
+        .iter()
+        .any(|diagnostic| diagnostic.severity == Severity::Error)
+}
+
+/// Check a tool's script against its parameters and the runtime it will run
+/// on. Diagnostics are ordered by line, parameter problems first.
+pub fn lint(
+    script: &str,
+    parameters: &[ParameterDefinition],
+    runtime: &dyn TclRuntime,
+) -> Vec<Diagnostic> {
+    let mut linter = Linter::new(script);
+    let commands = match Parser::new(script, 0).script(false) {
+        Ok(commands) => commands,
+        // Nothing after a syntax error can be trusted
+        Err(e) => return vec![linter.syntax_error(e)],
+    };
+
+    // Tool parameters are global variables of the interpreter
+    let mut scope = Scope::default();
+    scope
+        .set
+        .extend(parameters.iter().map(|param| param.name.clone()));
+    for command in &commands {
+        linter.command(command, &mut scope);
+    }
+    linter.undeclared(&scope, None);
+
+    for param in parameters {
+        if !linter.read.contains(&param.name) {
+            linter.diagnostics.push(Diagnostic {
+                severity: Severity::Warning,
+                code: "unused-parameter",
+                message: format!("Parameter `{}` is never used", param.name),
+                line: None,
+            });
+        }
+    }
+    linter.check_calls(runtime);
+
+    linter.diagnostics.sort_by_key(|diagnostic| diagnostic.line);
+    linter.diagnostics
+}
+
+/// Variables a script reads without setting them, in the order they are
+/// first read. Variables of the procs it defines are not included.
+pub fn free_variables(script: &str) -> Result<Vec<String>, Diagnostic> {
+    let mut linter = Linter::new(script);
+    let commands = Parser::new(script, 0)
+        .script(false)
+        .map_err(|e| linter.syntax_error(e))?;
+    let mut scope = Scope::default();
+    for command in &commands {
+        linter.command(command, &mut scope);
+    }
+
+    let mut seen = HashSet::new();
+    Ok(scope
+        .reads
+        .into_iter()
+        .map(|(name, _)| name)
+        .filter(|name| {
+            !scope.set.contains(name)
+                && !name.starts_with("::")
+                && !BUILTIN_VARIABLES.contains(&name.as_str())
+        })
+        .filter(|name| seen.insert(name.clone()))
+        .collect())
+}
+
+/// A proc defined by a script that consists of nothing else
+#[derive(Debug, PartialEq)]
+pub struct ProcDefinition {
+    pub name: String,
+    /// Argument names with their defaults
+    pub args: Vec<(String, Option<String>)>,
+}
+
+pub fn proc_definition(script: &str) -> Option<ProcDefinition> {
+    let commands = Parser::new(script, 0).script(false).ok()?;
+    let [command] = &commands[..] else {
+        return None;
+    };
+    let [proc, name, args, _body] = &command[..] else {
+        return None;
+    };
+    if proc.literal.as_deref() != Some("proc") {
+        return None;
+    }
+    let args = tcl_list::parse_list(args.literal.as_deref()?)
+        .ok()?
+        .iter()
+        .map(|arg| {
+            let mut words = tcl_list::parse_list(arg).ok()?.into_iter();
+            Some((words.next()?, words.next()))
+        })
+        .collect::<Option<Vec<_>>>()?;
+    Some(ProcDefinition {
+        name: name.literal.clone()?,
+        args,
+    })
+}
+
+#[derive(Debug)]
+struct SyntaxError {
+    /// Offset in the whole script
+    offset: usize,
+    message: &'static str,
+}
+
+/// A word of a command
+#[derive(Debug, Default)]
+struct Word {
+    /// Offset of the word's content in the whole script
+    offset: usize,
+    /// Whether the word was in braces, so its content is unsubstituted
+    braced: bool,
+    /// Value of a word without substitutions
+    literal: Option<String>,
+    /// Variables read by `$` substitutions, with their offsets
+    variables: Vec<(String, usize)>,
+    /// Scripts of `[...]` substitutions
+    commands: Vec<Vec<Command>>,
+}
+
+type Command = Vec<Word>;
+
+struct Parser<'a> {
+    source: &'a str,
+    pos: usize,
+    /// Offset of `source` in the whole script
+    base: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(source: &'a str, base: usize) -> Self {
+        Self {
+            source,
+            pos: 0,
+            base,
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.source[self.pos..].chars().next()
+    }
+
+    fn skip_char(&mut self) {
+        if let Some(c) = self.peek() {
+            self.pos += c.len_utf8();
+        }
+    }
+
+    fn error(&self, pos: usize, message: &'static str) -> SyntaxError {
+        SyntaxError {
+            offset: self.base + pos,
+            message,
+        }
+    }
+
+    /// Parse commands up to the end of the source or, for a command
+    /// substitution, the closing bracket
+    fn script(&mut self, nested: bool) -> Result<Vec<Command>, SyntaxError> {
+        let start = self.pos;
+        let mut commands = Vec::new();
+        loop {
+            while let Some(c) = self.peek() {
+                if c.is_whitespace() || c == ';' {
+                    self.skip_char();
+                } else if self.source[self.pos..].starts_with("\\\n") {
+                    self.pos += 2;
+                } else {
+                    break;
+                }
+            }
+            match self.peek() {
+                None if nested => return Err(self.error(start - 1, "missing close-bracket")),
+                None => return Ok(commands),
+                Some(']') if nested => {
+                    self.pos += 1;
+                    return Ok(commands);
+                }
+                Some('#') => self.comment(),
+                Some(_) => commands.push(self.command(nested)?),
+            }
+        }
+    }
+
+    fn comment(&mut self) {
+        while let Some(c) = self.peek() {
+            self.skip_char();
+            match c {
+                // A backslash-newline continues the comment
+                '\\' => self.skip_char(),
+                '\n' => return,
+                _ => {}
+            }
+        }
+    }
+
+    fn command(&mut self, nested: bool) -> Result<Command, SyntaxError> {
+        let mut words = Vec::new();
+        loop {
+            while let Some(c) = self.peek() {
+                if c == ' ' || c == '\t' || c == '\r' {
+                    self.skip_char();
+                } else if self.source[self.pos..].starts_with("\\\n") {
+                    self.pos += 2;
+                } else {
+                    break;
+                }
+            }
+            match self.peek() {
+                None => return Ok(words),
+                Some('\n' | ';') => {
+                    self.pos += 1;
+                    return Ok(words);
+                }
+                Some(']') if nested => return Ok(words),
+                Some(_) => words.push(self.word(nested)?),
+            }
+        }
+    }
+
+    fn at_word_end(&self, nested: bool) -> bool {
+        match self.peek() {
+            None => true,
+            Some(c) => c.is_whitespace() || c == ';' || (nested && c == ']'),
+        }
+    }
+
+    fn word(&mut self, nested: bool) -> Result<Word, SyntaxError> {
+        match self.peek() {
+            Some('{') => {
+                let offset = self.base + self.pos + 1;
+                let content = self.braced()?;
+                // {*} expands the word that follows it
+                if content == "*" && !self.at_word_end(nested) {
+                    return self.word(nested);
+                }
+                if !self.at_word_end(nested) {
+                    return Err(self.error(self.pos, "extra characters after close-brace"));
+                }
+                Ok(Word {
+                    offset,
+                    braced: true,
+                    literal: Some(content),
+                    ..Default::default()
+                })
+            }
+            Some('"') => {
+                let open = self.pos;
+                self.pos += 1;
+                let word = self.substituted(true, nested)?;
+                if self.peek() != Some('"') {
+                    return Err(self.error(open, "missing \""));
+                }
+                self.pos += 1;
+                if !self.at_word_end(nested) {
+                    return Err(self.error(self.pos, "extra characters after close-quote"));
+                }
+                Ok(word)
+            }
+            _ => self.substituted(false, nested),
+        }
+    }
+
+    /// Parse a braced word, returning its content
+    fn braced(&mut self) -> Result<String, SyntaxError> {
+        let open = self.pos;
+        self.pos += 1;
+        let mut depth = 1;
+        while let Some(c) = self.peek() {
+            self.skip_char();
+            match c {
+                '\\' => self.skip_char(),
+                '{' => depth += 1,
+                '}' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return Ok(self.source[open + 1..self.pos - 1].to_string());
+                    }
+                }
+                _ => {}
+            }
+        }
+        Err(self.error(open, "missing close-brace"))
+    }
+
+    /// Parse a bare or quoted word, up to but not including its end
+    fn substituted(&mut self, quoted: bool, nested: bool) -> Result<Word, SyntaxError> {
+        let mut word = Word {
+            offset: self.base + self.pos,
+            ..Default::default()
+        };
+        let mut text = String::new();
+        let mut literal = true;
+        while let Some(c) = self.peek() {
+            let end = if quoted {
+                c == '"'
+            } else {
+                self.at_word_end(nested)
+            };
+            if end {
+                break;
+            }
+            match c {
+                '\\' => {
+                    self.skip_char();
+                    if let Some(escaped) = self.peek() {
+                        text.push(escaped);
+                        self.skip_char();
+                    }
+                }
+                '$' => {
+                    if self.variable(&mut word)? {
+                        literal = false;
+                    } else {
+                        text.push('$');
+                    }
+                }
+                '[' => {
+                    self.pos += 1;
+                    word.commands.push(self.script(true)?);
+                    literal = false;
+                }
+                _ => {
+                    text.push(c);
+                    self.skip_char();
+                }
+            }
+        }
+        word.literal = literal.then_some(text);
+        Ok(word)
+    }
+
+    /// Parse a `$` substitution into `word`; false if the `$` is literal
+    fn variable(&mut self, word: &mut Word) -> Result<bool, SyntaxError> {
+        let dollar = self.pos;
+        self.pos += 1;
+        if self.peek() == Some('{') {
+            let name = self.braced()?;
+            word.variables.push((name, self.base + dollar));
+            return Ok(true);
+        }
+
+        let start = self.pos;
+        loop {
+            match self.peek() {
+                Some(c) if c.is_ascii_alphanumeric() || c == '_' => self.pos += 1,
+                Some(':') if self.source[self.pos..].starts_with("::") => self.pos += 2,
+                _ => break,
+            }
+        }
+        if self.pos == start {
+            return Ok(false);
+        }
+        let name = self.source[start..self.pos].to_string();
+        word.variables.push((name, self.base + dollar));
+
+        // The index of an array element is substituted too
+        if self.peek() == Some('(') {
+            self.pos += 1;
+            loop {
+                match self.peek() {
+                    None => return Err(self.error(dollar, "missing )")),
+                    Some(')') => {
+                        self.pos += 1;
+                        break;
+                    }
+                    Some('$') => {
+                        self.variable(word)?;
+                    }
+                    Some('[') => {
+                        self.pos += 1;
+                        word.commands.push(self.script(true)?);
+                    }
+                    Some('\\') => {
+                        self.skip_char();
+                        self.skip_char();
+                    }
+                    Some(_) => self.skip_char(),
+                }
+            }
+        }
+        Ok(true)
+    }
+
+    /// Parse an `expr` expression for its substitutions; braces in an
+    /// expression quote strings, so their content is skipped
+    fn expression(&mut self) -> Result<Word, SyntaxError> {
+        let mut word = Word {
+            offset: self.base,
+            ..Default::default()
+        };
+        while let Some(c) = self.peek() {
+            match c {
+                '{' => {
+                    self.braced()?;
+                }
+                '$' => {
+                    self.variable(&mut word)?;
+                }
+                '[' => {
+                    self.pos += 1;
+                    word.commands.push(self.script(true)?);
+                }
+                '\\' => {
+                    self.skip_char();
+                    self.skip_char();
+                }
+                _ => self.skip_char(),
+            }
+        }
+        Ok(word)
+    }
+
+    /// Split a braced list into words, keeping their offsets
+    fn list(&mut self) -> Result<Vec<Word>, SyntaxError> {
+        let mut words = Vec::new();
+        loop {
+            while self.peek().is_some_and(char::is_whitespace) {
+                self.skip_char();
+            }
+            if self.peek().is_none() {
+                return Ok(words);
+            }
+            let offset = self.base + self.pos;
+            let word = match self.peek() {
+                Some('{') => {
+                    let content = self.braced()?;
+                    Word {
+                        offset: offset + 1,
+                        braced: true,
+                        literal: Some(content),
+                        ..Default::default()
+                    }
+                }
+                _ => {
+                    let start = self.pos;
+                    while self.peek().is_some_and(|c| !c.is_whitespace()) {
+                        self.skip_char();
+                    }
+                    Word {
+                        offset,
+                        literal: Some(self.source[start..self.pos].to_string()),
+                        ..Default::default()
+                    }
+                }
+            };
+            words.push(word);
+        }
+    }
+}
+
+/// Where variables live: the tool script itself or a proc body
+#[derive(Default)]
+struct Scope {
+    set: HashSet<String>,
+    reads: Vec<(String, usize)>,
+}
+
+struct Linter<'a> {
+    script: &'a str,
+    diagnostics: Vec<Diagnostic>,
+    /// Procs the script defines
+    procs: HashSet<String>,
+    /// Commands the script calls, with their offsets
+    calls: Vec<(String, usize)>,
+    /// Variables read anywhere, including procs' `global` declarations
+    read: HashSet<String>,
+}
+
+impl<'a> Linter<'a> {
+    fn new(script: &'a str) -> Self {
+        Self {
+            script,
+            diagnostics: Vec::new(),
+            procs: HashSet::new(),
+            calls: Vec::new(),
+            read: HashSet::new(),
+        }
+    }
+
+    fn line(&self, offset: usize) -> usize {
+        self.script[..offset.min(self.script.len())]
+            .matches('\n')
+            .count()
+            + 1
+    }
+
+    fn syntax_error(&self, error: SyntaxError) -> Diagnostic {
+        Diagnostic {
+            severity: Severity::Error,
+            code: "syntax",
+            message: format!("Syntax error: {}", error.message),
+            line: Some(self.line(error.offset)),
+        }
+    }
+
+    fn read(&mut self, scope: &mut Scope, name: &str, offset: usize) {
+        self.read.insert(name.trim_start_matches("::").to_string());
+        scope.reads.push((name.to_string(), offset));
+    }
+
+    fn word(&mut self, word: &Word, scope: &mut Scope) {
+        for (name, offset) in &word.variables {
+            self.read(scope, name, *offset);
+        }
+        for commands in &word.commands {
+            for command in commands {
+                self.command(command, scope);
+            }
+        }
+    }
+
+    /// Check a braced script argument, such as a loop body
+    fn body(&mut self, word: &Word, scope: &mut Scope) {
+        let Some(script) = word.literal.as_deref().filter(|_| word.braced) else {
+            return;
+        };
+        match Parser::new(script, word.offset).script(false) {
+            Ok(commands) => {
+                for command in &commands {
+                    self.command(command, scope);
+                }
+            }
+            Err(e) => {
+                let diagnostic = self.syntax_error(e);
+                self.diagnostics.push(diagnostic);
+            }
+        }
+    }
+
+    /// Check a braced expression argument, such as a loop condition
+    fn expr(&mut self, word: &Word, scope: &mut Scope) {
+        let Some(expression) = word.literal.as_deref().filter(|_| word.braced) else {
+            return;
+        };
+        match Parser::new(expression, word.offset).expression() {
+            Ok(word) => self.word(&word, scope),
+            Err(e) => {
+                let diagnostic = self.syntax_error(e);
+                self.diagnostics.push(diagnostic);
+            }
+        }
+    }
+
+    fn command(&mut self, words: &[Word], scope: &mut Scope) {
+        for word in words {
+            self.word(word, scope);
+        }
+        let Some(name) = words.first().and_then(|word| word.literal.as_deref()) else {
+            return;
+        };
+        let name = name.trim_start_matches("::");
+        self.calls.push((name.to_string(), words[0].offset));
+
+        let args = &words[1..];
+        let define = |scope: &mut Scope, words: &[Word]| {
+            scope.set.extend(words.iter().filter_map(variable_name));
+        };
+        match name {
+            "set" => match args {
+                [var] => {
+                    if let Some(name) = variable_name(var) {
+                        self.read(scope, &name, var.offset);
+                    }
+                }
+                [_, _] => define(scope, &args[..1]),
+                _ => {}
+            },
+            "incr" | "append" | "lappend" => define(scope, &args[..args.len().min(1)]),
+            "gets" => define(scope, args.get(1..2).unwrap_or_default()),
+            "global" => {
+                for name in args.iter().filter_map(variable_name) {
+                    self.read.insert(name.clone());
+                    scope.set.insert(name);
+                }
+            }
+            "variable" => define(scope, args),
+            "upvar" => {
+                // upvar ?level? otherVar myVar ?otherVar myVar ...?
+                let level = args.len() % 2;
+                scope.set.extend(
+                    args.iter()
+                        .skip(level + 1)
+                        .step_by(2)
+                        .filter_map(variable_name),
+                );
+            }
+            "lassign" if !args.is_empty() => define(scope, &args[1..]),
+            "scan" if args.len() > 2 => define(scope, &args[2..]),
+            "regexp" | "regsub" => {
+                let args = skip_options(args);
+                if name == "regexp" && args.len() > 2 {
+                    define(scope, &args[2..]);
+                } else if name == "regsub" && args.len() > 3 {
+                    define(scope, &args[3..4]);
+                }
+            }
+            "array" if args.len() > 1 && args[0].literal.as_deref() == Some("set") => {
+                define(scope, &args[1..2])
+            }
+            "dict" if args.len() > 1 => match args[0].literal.as_deref() {
+                Some("set" | "unset" | "lappend" | "append" | "incr" | "update" | "with") => {
+                    define(scope, &args[1..2])
+                }
+                Some("for" | "map") if args.len() == 4 => {
+                    self.list_variables(&args[1], scope);
+                    self.body(&args[3], scope);
+                }
+                _ => {}
+            },
+            "foreach" | "lmap" if args.len() >= 3 && args.len() % 2 == 1 => {
+                for var_list in args[..args.len() - 1].iter().step_by(2) {
+                    self.list_variables(var_list, scope);
+                }
+                self.body(&args[args.len() - 1], scope);
+            }
+            "catch" if !args.is_empty() => {
+                self.body(&args[0], scope);
+                define(scope, &args[1..]);
+            }
+            "if" => self.if_command(args, scope),
+            "while" if args.len() == 2 => {
+                self.expr(&args[0], scope);
+                self.body(&args[1], scope);
+            }
+            "for" if args.len() == 4 => {
+                self.body(&args[0], scope);
+                self.expr(&args[1], scope);
+                self.body(&args[2], scope);
+                self.body(&args[3], scope);
+            }
+            "expr" if args.len() == 1 => self.expr(&args[0], scope),
+            "time" if !args.is_empty() => self.body(&args[0], scope),
+            "try" => self.try_command(args, scope),
+            "switch" => self.switch_command(args, scope),
+            "proc" if args.len() == 3 => self.proc_command(args),
+            _ => {}
+        }
+    }
+
+    /// Set the variables named by a list, as in `foreach {key value} ...`
+    fn list_variables(&mut self, word: &Word, scope: &mut Scope) {
+        if let Some(names) = word
+            .literal
+            .as_deref()
+            .and_then(|list| tcl_list::parse_list(list).ok())
+        {
+            scope.set.extend(names);
+        }
+    }
+
+    /// if expr ?then? body ?elseif expr ?then? body ...? ?else? ?body?
+    fn if_command(&mut self, args: &[Word], scope: &mut Scope) {
+        let keyword = |word: Option<&Word>, keyword: &str| {
+            word.and_then(|word| word.literal.as_deref()) == Some(keyword)
+        };
+        let mut i = 0;
+        while i < args.len() {
+            self.expr(&args[i], scope);
+            i += 1;
+            if keyword(args.get(i), "then") {
+                i += 1;
+            }
+            if let Some(body) = args.get(i) {
+                self.body(body, scope);
+            }
+            i += 1;
+            if keyword(args.get(i), "elseif") {
+                i += 1;
+                continue;
+            }
+            if keyword(args.get(i), "else") {
+                i += 1;
+            }
+            if let Some(body) = args.get(i) {
+                self.body(body, scope);
+            }
+            break;
+        }
+    }
+
+    /// try body ?on code {var opts} body ...? ?trap pattern {var opts} body ...? ?finally body?
+    fn try_command(&mut self, args: &[Word], scope: &mut Scope) {
+        let Some(body) = args.first() else {
+            return;
+        };
+        self.body(body, scope);
+        let mut i = 1;
+        while i < args.len() {
+            match args[i].literal.as_deref() {
+                Some("on" | "trap") if i + 3 < args.len() => {
+                    self.list_variables(&args[i + 2], scope);
+                    self.body(&args[i + 3], scope);
+                    i += 4;
+                }
+                Some("finally") if i + 1 < args.len() => {
+                    self.body(&args[i + 1], scope);
+                    i += 2;
+                }
+                _ => break,
+            }
+        }
+    }
+
+    /// switch ?options? string {pattern body ...} or switch ?options? string pattern body ...
+    fn switch_command(&mut self, args: &[Word], scope: &mut Scope) {
+        let args = skip_options(args);
+        let owned;
+        let cases = match args {
+            [_, cases] if cases.braced => {
+                let content = cases.literal.as_deref().unwrap_or_default();
+                match Parser::new(content, cases.offset).list() {
+                    Ok(words) => {
+                        owned = words;
+                        &owned[..]
+                    }
+                    Err(e) => {
+                        let diagnostic = self.syntax_error(e);
+                        self.diagnostics.push(diagnostic);
+                        return;
+                    }
+                }
+            }
+            [_, cases @ ..] => cases,
+            [] => return,
+        };
+        for body in cases.iter().skip(1).step_by(2) {
+            // A body of "-" falls through to the next one
+            if body.literal.as_deref() != Some("-") {
+                self.body(body, scope);
+            }
+        }
+    }
+
+    /// proc name args body: the body is a scope of its own
+    fn proc_command(&mut self, args: &[Word]) {
+        let Some(name) = args[0].literal.as_deref() else {
+            return;
+        };
+        let name = name.trim_start_matches("::").to_string();
+        self.procs.insert(name.clone());
+
+        let mut scope = Scope::default();
+        if let Some(params) = args[1]
+            .literal
+            .as_deref()
+            .and_then(|list| tcl_list::parse_list(list).ok())
+        {
+            for param in params {
+                // An argument with a default is a list of its name and default
+                if let Some(name) = tcl_list::parse_list(&param)
+                    .ok()
+                    .and_then(|words| words.into_iter().next())
+                {
+                    scope.set.insert(name);
+                }
+            }
+        }
+        self.body(&args[2], &mut scope);
+        self.undeclared(&scope, Some(&name));
+    }
+
+    /// Report variables a scope reads but never sets
+    fn undeclared(&mut self, scope: &Scope, proc: Option<&str>) {
+        let mut reported = HashSet::new();
+        for (name, offset) in &scope.reads {
+            if scope.set.contains(name)
+                || name.starts_with("::")
+                || BUILTIN_VARIABLES.contains(&name.as_str())
+                || !reported.insert(name)
+            {
+                continue;
+            }
+            let message = match proc {
+                Some(proc) => format!(
+                    "Variable `${}` is not an argument of proc `{}` and is never set in it",
+                    name, proc
+                ),
+                None => format!("Variable `${}` is not a parameter and is never set", name),
+            };
+            self.diagnostics.push(Diagnostic {
+                severity: Severity::Error,
+                code: "undeclared-variable",
+                message,
+                line: Some(self.line(*offset)),
+            });
+        }
+    }
+
+    /// Report commands that are missing from the runtime or restricted
+    fn check_calls(&mut self, runtime: &dyn TclRuntime) {
+        let restricted = TclRuntimeCapabilities::restricted_commands();
+        // The runtime the tool could be run on instead
+        let other = if runtime.name() == "Molt" {
+            TclRuntimeCapabilities::for_tcl(true)
+        } else {
+            TclRuntimeCapabilities::for_molt(true)
+        };
+        let mut checked = HashSet::new();
+        for (name, offset) in std::mem::take(&mut self.calls) {
+            if self.procs.contains(&name) || !checked.insert(name.clone()) {
+                continue;
+            }
+            let line = Some(self.line(offset));
+            if restricted.contains(&name) {
+                self.diagnostics.push(Diagnostic {
+                    severity: Severity::Warning,
+                    code: "restricted-command",
+                    message: format!("Command `{}` is not allowed in restricted mode", name),
+                    line,
+                });
+            }
+            let unsupported_host_command = host_commands::COMMANDS.contains(&name.as_str())
+                && !runtime.supports_host_commands();
+            if unsupported_host_command || !runtime.has_command(&name) {
+                let mut message = format!(
+                    "Command `{}` is not available in the {} runtime",
+                    name,
+                    runtime.name()
+                );
+                if other.has_command(&name) {
+                    message.push_str(&format!("; the {} runtime has it", other.runtime_name));
+                }
+                self.diagnostics.push(Diagnostic {
+                    severity: Severity::Error,
+                    code: "unknown-command",
+                    message,
+                    line,
+                });
+            }
+        }
+    }
+}
+
+/// The name of the variable a word names, without an array index
+fn variable_name(word: &Word) -> Option<String> {
+    let name = word.literal.as_deref()?;
+    let name = name.split_once('(').map_or(name, |(name, _)| name);
+    (!name.is_empty()).then(|| name.to_string())
+}
+
+/// Skip leading `-options` of a command, and the `--` that ends them
+fn skip_options(args: &[Word]) -> &[Word] {
+    let mut i = 0;
+    while let Some(option) = args.get(i).and_then(|word| word.literal.as_deref()) {
+        if !option.starts_with('-') {
+            break;
+        }
+        i += 1;
+        if option == "--" {
+            break;
+        }
+    }
+    &args[i..]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+
+    /// A runtime that only knows a fixed set of commands
+    struct Stub;
+
+    impl TclRuntime for Stub {
+        fn new() -> Self {
+            Stub
+        }
+        fn eval(&mut self, _script: &str) -> Result<String> {
+            Ok(String::new())
+        }
+        fn set_var(&mut self, _name: &str, _value: &str) -> Result<()> {
+            Ok(())
+        }
+        fn get_var(&self, _name: &str) -> Result<String> {
+            Ok(String::new())
+        }
+        fn has_command(&self, command: &str) -> bool {
+            [
+                "set", "expr", "if", "foreach", "proc", "return", "string", "lappend", "puts",
+                "incr", "catch", "switch",
+            ]
+            .contains(&command)
+        }
+        fn name(&self) -> &'static str {
+            "Molt"
+        }
+        fn version(&self) -> &'static str {
+            "0.3.1"
+        }
+        fn features(&self) -> Vec<String> {
+            Vec::new()
+        }
+        fn is_safe(&self) -> bool {
+            true
+        }
+    }
+
+    /// A runtime with every command but without host commands, like the
+    /// official TCL interpreter
+    struct Official;
+
+    impl TclRuntime for Official {
+        fn new() -> Self {
+            Official
+        }
+        fn eval(&mut self, _script: &str) -> Result<String> {
+            Ok(String::new())
+        }
+        fn set_var(&mut self, _name: &str, _value: &str) -> Result<()> {
+            Ok(())
+        }
+        fn get_var(&self, _name: &str) -> Result<String> {
+            Ok(String::new())
+        }
+        fn has_command(&self, _command: &str) -> bool {
+            true
+        }
+        fn name(&self) -> &'static str {
+            "TCL (Official)"
+        }
+        fn version(&self) -> &'static str {
+            "8.6"
+        }
+        fn features(&self) -> Vec<String> {
+            Vec::new()
+        }
+        fn is_safe(&self) -> bool {
+            false
+        }
+    }
+
+    fn params(names: &[&str]) -> Vec<ParameterDefinition> {
+        names
+            .iter()
+            .map(|name| ParameterDefinition {
+                name: name.to_string(),
+                description: String::new(),
+                required: true,
+                type_name: "string".to_string(),
+                completion: None,
+                default: None,
+            })
+            .collect()
+    }
+
+    fn codes(diagnostics: &[Diagnostic]) -> Vec<(&'static str, Option<usize>)> {
+        diagnostics.iter().map(|d| (d.code, d.line)).collect()
+    }
+
+    #[test]
+    fn test_clean_script() {
+        let script = r#"
+proc shout {text {times 1}} {
+    set out ""
+    foreach i [string repeat x $times] { append out [string toupper $text] }
+    return $out
+}
+set result {}
+foreach {key value} $pairs {
+    if {$key eq "skip"} { continue } elseif {[catch {expr {$value * 2}} doubled]} {
+        lappend result $key
+    } else {
+        lappend result [shout $doubled]
+    }
+}
+switch -exact -- $mode {
+    a - b { incr count }
+    default { set count 0 }
+}
+return "$result ${total}"
+"#;
+        let diagnostics = lint(script, &params(&["pairs", "mode"]), &Stub);
+        // append and continue are not in the stub runtime
+        assert_eq!(
+            codes(&diagnostics),
+            vec![
+                ("unknown-command", Some(4)),
+                ("unknown-command", Some(9)),
+                ("undeclared-variable", Some(19)),
+            ]
+        );
+        assert!(diagnostics[2].message.contains("`$total`"));
+    }
+
+    #[test]
+    fn test_variable_and_command_checks() {
+        let script =
+            "set x [string length $text]\nexec ls $dir\nproc f {} { return $x }\nfile exists $x";
+        let diagnostics = lint(script, &params(&["text", "unused"]), &Stub);
+        assert_eq!(
+            codes(&diagnostics),
+            vec![
+                ("unused-parameter", None),
+                ("undeclared-variable", Some(2)),
+                ("restricted-command", Some(2)),
+                ("unknown-command", Some(2)),
+                ("undeclared-variable", Some(3)),
+                ("restricted-command", Some(4)),
+                ("unknown-command", Some(4)),
+            ]
+        );
+        assert!(diagnostics[6]
+            .message
+            .ends_with("the TCL (Official) runtime has it"));
+        assert!(has_errors(&diagnostics));
+        assert_eq!(
+            diagnostics[0].to_string(),
+            "warning[unused-parameter]: Parameter `unused` is never used"
+        );
+    }
+
+    #[test]
+    fn test_host_commands_need_runtime_support() {
+        let script = "mcp::log info starting\nset summary [mcp::sample $text]\nreturn $summary";
+        let diagnostics = lint(script, &params(&["text"]), &Official);
+        assert_eq!(
+            codes(&diagnostics),
+            vec![("unknown-command", Some(1)), ("unknown-command", Some(2))]
+        );
+        assert_eq!(
+            diagnostics[1].message,
+            "Command `mcp::sample` is not available in the TCL (Official) runtime; \
+             the Molt runtime has it"
+        );
+    }
+
+    #[test]
+    fn test_free_variables_and_proc_definitions() {
+        let script = "# greet\nset greeting \"Hello, $name\"\nforeach item $items { lappend greeting $item }\nreturn \"$greeting $::env(USER) $name\"";
+        assert_eq!(free_variables(script).unwrap(), vec!["name", "items"]);
+        assert_eq!(free_variables("set x {").unwrap_err().code, "syntax");
+
+        assert_eq!(
+            proc_definition("# Repeat text\nproc repeat {text {times 2}} {\n    string repeat $text $times\n}\n"),
+            Some(ProcDefinition {
+                name: "repeat".to_string(),
+                args: vec![
+                    ("text".to_string(), None),
+                    ("times".to_string(), Some("2".to_string())),
+                ],
+            })
+        );
+        assert_eq!(proc_definition("proc f {} { return 1 }\nf"), None);
+        assert_eq!(proc_definition("return [proc f {} {}]"), None);
+    }
+
+    #[test]
+    fn test_syntax_errors() {
+        for (script, line, message) in [
+            ("set x {a\nb", 1, "missing close-brace"),
+            (
+                "set x 1\nputs [string length $x",
+                2,
+                "missing close-bracket",
+            ),
+            ("set x \"abc", 1, "missing \""),
+            ("set x {a}b", 1, "extra characters after close-brace"),
+            ("if {1} {\n  set y [expr {1}\n}", 2, "missing close-bracket"),
+        ] {
+            let diagnostics = lint(script, &[], &Stub);
+            assert_eq!(
+                codes(&diagnostics),
+                vec![("syntax", Some(line))],
+                "{}",
+                script
+            );
+            assert!(diagnostics[0].message.ends_with(message), "{}", script);
+        }
+    }
+}
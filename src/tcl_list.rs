@@ -0,0 +1,238 @@
+/// TCL list parsing and formatting for values crossing the Rust/TCL boundary
+///
+/// Host commands receive their arguments as plain strings, independent of the
+/// runtime in use, so list and dict arguments are split here rather than
+/// through a particular interpreter's API.
+use anyhow::{anyhow, Result};
+use serde_json::Value;
+
+/// Split a TCL list into its elements
+pub fn parse_list(input: &str) -> Result<Vec<String>> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut elements = Vec::new();
+    let mut i = 0;
+
+    loop {
+        while i < chars.len() && chars[i].is_whitespace() {
+            i += 1;
+        }
+        if i >= chars.len() {
+            break;
+        }
+
+        let mut element = String::new();
+        match chars[i] {
+            '{' => {
+                let mut depth = 1;
+                i += 1;
+                while i < chars.len() {
+                    match chars[i] {
+                        '\\' if i + 1 < chars.len() => {
+                            element.push(chars[i]);
+                            i += 1;
+                        }
+                        '{' => depth += 1,
+                        '}' => {
+                            depth -= 1;
+                            if depth == 0 {
+                                break;
+                            }
+                        }
+                        _ => {}
+                    }
+                    element.push(chars[i]);
+                    i += 1;
+                }
+                if depth != 0 {
+                    return Err(anyhow!("unmatched open brace in list"));
+                }
+                i += 1;
+                if i < chars.len() && !chars[i].is_whitespace() {
+                    return Err(anyhow!(
+                        "list element in braces followed by \"{}\" instead of space",
+                        chars[i]
+                    ));
+                }
+            }
+            '"' => {
+                i += 1;
+                loop {
+                    match chars.get(i) {
+                        None => return Err(anyhow!("unmatched open quote in list")),
+                        Some('"') => break,
+                        Some('\\') => {
+                            i += 1;
+                            push_escape(&mut element, chars.get(i).copied());
+                        }
+                        Some(c) => element.push(*c),
+                    }
+                    i += 1;
+                }
+                i += 1;
+                if i < chars.len() && !chars[i].is_whitespace() {
+                    return Err(anyhow!(
+                        "list element in quotes followed by \"{}\" instead of space",
+                        chars[i]
+                    ));
+                }
+            }
+            _ => {
+                while i < chars.len() && !chars[i].is_whitespace() {
+                    if chars[i] == '\\' {
+                        i += 1;
+                        push_escape(&mut element, chars.get(i).copied());
+                    } else {
+                        element.push(chars[i]);
+                    }
+                    i += 1;
+                }
+            }
+        }
+
+        elements.push(element);
+    }
+
+    Ok(elements)
+}
+
+/// Split a TCL dict into key/value pairs
+pub fn parse_dict(input: &str) -> Result<Vec<(String, String)>> {
+    let elements = parse_list(input)?;
+    if elements.len() % 2 != 0 {
+        return Err(anyhow!("missing value to go with key"));
+    }
+
+    let mut pairs = Vec::with_capacity(elements.len() / 2);
+    let mut iter = elements.into_iter();
+    while let (Some(key), Some(value)) = (iter.next(), iter.next()) {
+        pairs.push((key, value));
+    }
+    Ok(pairs)
+}
+
+/// Join elements into a well-formed TCL list
+pub fn format_list<I, S>(elements: I) -> String
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    elements
+        .into_iter()
+        .map(|element| quote_element(element.as_ref()))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Convert JSON into its natural TCL form: objects become dicts, arrays
+/// become lists and scalars become plain strings
+pub fn json_to_tcl(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        Value::Bool(_) | Value::Number(_) => value.to_string(),
+        Value::Array(items) => format_list(items.iter().map(json_to_tcl)),
+        Value::Object(map) => format_list(
+            map.iter()
+                .flat_map(|(key, value)| [key.clone(), json_to_tcl(value)]),
+        ),
+    }
+}
+
+fn push_escape(out: &mut String, escaped: Option<char>) {
+    match escaped {
+        Some('n') => out.push('\n'),
+        Some('t') => out.push('\t'),
+        Some('r') => out.push('\r'),
+        Some(c) => out.push(c),
+        None => out.push('\\'),
+    }
+}
+
+fn quote_element(element: &str) -> String {
+    if element.is_empty() {
+        return "{}".to_string();
+    }
+
+    let needs_quoting = element.starts_with('#')
+        || element
+            .chars()
+            .any(|c| c.is_whitespace() || "{}[]$\"\\;".contains(c));
+    if !needs_quoting {
+        return element.to_string();
+    }
+
+    if !element.contains('\\') && braces_balanced(element) {
+        return format!("{{{}}}", element);
+    }
+
+    let mut escaped = String::with_capacity(element.len() * 2);
+    for c in element.chars() {
+        match c {
+            '\n' => escaped.push_str("\\n"),
+            '\t' => escaped.push_str("\\t"),
+            '\r' => escaped.push_str("\\r"),
+            c if c.is_whitespace() || "{}[]$\"\\;#".contains(c) => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn braces_balanced(element: &str) -> bool {
+    let mut depth = 0i32;
+    for c in element.chars() {
+        match c {
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth < 0 {
+                    return false;
+                }
+            }
+            _ => {}
+        }
+    }
+    depth == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_list() {
+        assert_eq!(parse_list("a b  c").unwrap(), vec!["a", "b", "c"]);
+        assert_eq!(
+            parse_list("name {hello world} \"x\\ty\" {}").unwrap(),
+            vec!["name", "hello world", "x\ty", ""]
+        );
+        assert_eq!(parse_list("{a {b c}}").unwrap(), vec!["a {b c}"]);
+        assert!(parse_list("{unbalanced").is_err());
+        assert!(parse_dict("a 1 b").is_err());
+    }
+
+    #[test]
+    fn test_format_round_trip() {
+        let elements = vec!["plain", "two words", "", "{open", "$var", "#comment"];
+        let formatted = format_list(&elements);
+        assert_eq!(parse_list(&formatted).unwrap(), elements);
+    }
+
+    #[test]
+    fn test_json_to_tcl() {
+        let value = json!({"action": "accept", "content": {"name": "Ada Lovelace", "ok": true}});
+        let dict = parse_dict(&json_to_tcl(&value)).unwrap();
+        assert_eq!(dict[0], ("action".to_string(), "accept".to_string()));
+        assert_eq!(
+            parse_dict(&dict[1].1).unwrap(),
+            vec![
+                ("name".to_string(), "Ada Lovelace".to_string()),
+                ("ok".to_string(), "true".to_string())
+            ]
+        );
+    }
+}
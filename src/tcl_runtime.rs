@@ -81,6 +81,12 @@ impl RuntimeConfig {
     }
 }
 
+/// Command implemented in Rust and exposed to TCL scripts.
+///
+/// Receives the command arguments (without the command name) and returns the
+/// command result, or an error that is raised as a TCL error.
+pub type HostCommand = Box<dyn FnMut(&[String]) -> Result<String>>;
+
 /// Trait defining the interface for TCL runtime implementations
 pub trait TclRuntime {
     /// Create a new instance of the TCL runtime
@@ -111,6 +117,21 @@ pub trait TclRuntime {
 
     /// Check if runtime is safe/sandboxed
     fn is_safe(&self) -> bool;
+
+    /// Whether `register_command` is supported, i.e. whether the `mcp::*`
+    /// commands are available to scripts
+    fn supports_host_commands(&self) -> bool {
+        false
+    }
+
+    /// Register a host command (e.g. `mcp::log`) callable from scripts
+    fn register_command(&mut self, name: &str, _command: HostCommand) -> Result<()> {
+        Err(anyhow!(
+            "{} runtime does not support host command '{}'",
+            self.name(),
+            name
+        ))
+    }
 }
 
 #[cfg(feature = "molt")]
@@ -171,7 +192,7 @@ pub fn create_runtime_with_config(config: RuntimeConfig) -> Result<Box<dyn TclRu
     #[cfg(all(feature = "molt", not(feature = "tcl")))]
     {
         tracing::info!("Auto-selecting Molt TCL runtime");
-        return Ok(Box::new(MoltRuntime::new()));
+        Ok(Box::new(MoltRuntime::new()))
     }
 
     #[cfg(all(not(feature = "molt"), not(feature = "tcl")))]
@@ -213,6 +234,7 @@ fn create_specific_runtime(runtime_type: RuntimeType) -> Result<Box<dyn TclRunti
 }
 
 /// Get list of available runtime types based on compiled features
+#[allow(clippy::vec_init_then_push)]
 pub fn get_available_runtimes() -> Vec<RuntimeType> {
     let mut runtimes = Vec::new();
 
@@ -1,6 +1,7 @@
-use super::TclRuntime;
+use super::{HostCommand, TclRuntime};
 use anyhow::{anyhow, Result};
-use molt::Interp;
+use molt::types::{ContextID, Exception, MoltResult, Value};
+use molt::{molt_err, molt_ok, Interp};
 
 /// Molt TCL interpreter implementation
 pub struct MoltRuntime {
@@ -63,6 +64,28 @@ impl TclRuntime for MoltRuntime {
     fn is_safe(&self) -> bool {
         true
     }
+
+    fn supports_host_commands(&self) -> bool {
+        true
+    }
+
+    fn register_command(&mut self, name: &str, command: HostCommand) -> Result<()> {
+        let context_id = self.interp.save_context(command);
+        self.interp
+            .add_context_command(name, call_host_command, context_id);
+        Ok(())
+    }
+}
+
+/// Molt command trampoline that dispatches to the stored `HostCommand`
+fn call_host_command(interp: &mut Interp, context_id: ContextID, argv: &[Value]) -> MoltResult {
+    let args: Vec<String> = argv.iter().skip(1).map(|v| v.to_string()).collect();
+    let command = interp.context::<HostCommand>(context_id);
+
+    match command(&args) {
+        Ok(result) => molt_ok!(result),
+        Err(error) => molt_err!(error.to_string()),
+    }
 }
 
 #[cfg(test)]
@@ -92,6 +115,25 @@ mod tests {
         assert!(!runtime.has_command("nonexistent_command"));
     }
 
+    #[test]
+    fn test_molt_runtime_host_command() {
+        let mut runtime = MoltRuntime::new();
+        runtime
+            .register_command(
+                "host::join",
+                Box::new(|args| {
+                    if args.is_empty() {
+                        return Err(anyhow!("wrong # args"));
+                    }
+                    Ok(args.join("+"))
+                }),
+            )
+            .unwrap();
+
+        assert_eq!(runtime.eval("host::join a b c").unwrap(), "a+b+c");
+        assert!(runtime.eval("host::join").is_err());
+    }
+
     #[test]
     fn test_molt_runtime_string_ops() {
         let mut runtime = MoltRuntime::new();
@@ -5,8 +5,12 @@ use tracing::info;
 
 use crate::tcl_executor::TclCommand;
 
+use crate::exposure;
 use crate::mcp_client::McpServerConfig;
 use crate::namespace::ToolPath;
+use crate::param_inference;
+use crate::tcl_list;
+use crate::tool_package::{self, ConflictPolicy, ToolPackage};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ToolDefinition {
@@ -14,6 +18,78 @@ pub struct ToolDefinition {
     pub description: String,
     pub script: String,
     pub parameters: Vec<ParameterDefinition>,
+    /// Sample calls with their expected output
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub examples: Vec<ToolExample>,
+}
+
+/// A call of a tool and the output it should give, from an `@example` or
+/// `@test` header or the `examples` of `sbin__tcl_tool_add`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolExample {
+    /// Arguments of the call
+    #[serde(default)]
+    pub args: serde_json::Map<String, serde_json::Value>,
+    /// Output the call must return, ignoring surrounding whitespace
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expected: Option<String>,
+    /// Glob the output must match instead (`*` and `?`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pattern: Option<String>,
+    /// Only run by `tcl-mcp-server test`, not shown as usage (`@test`)
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub test_only: bool,
+}
+
+impl ToolExample {
+    /// Parse the text after `@example` or `@test`: the arguments as a JSON
+    /// object or `key value` pairs, then `=> output` or `=~ pattern`
+    pub fn parse(text: &str, test_only: bool) -> Result<Self> {
+        let text = format!("{} ", text.trim());
+        let (index, exact) = [(" => ", true), (" =~ ", false)]
+            .iter()
+            .filter_map(|&(separator, exact)| text.find(separator).map(|index| (index, exact)))
+            .min()
+            .ok_or_else(|| anyhow!("expected '<args> => <output>' or '<args> =~ <pattern>'"))?;
+        let output = text[index + 4..].trim().to_string();
+
+        let args_text = text[..index].trim();
+        let args = match serde_json::from_str(args_text) {
+            Ok(serde_json::Value::Object(args)) => args,
+            _ => tcl_list::parse_dict(args_text)?
+                .into_iter()
+                .map(|(key, value)| (key, serde_json::Value::String(value)))
+                .collect(),
+        };
+
+        Ok(Self {
+            args,
+            expected: exact.then(|| output.clone()),
+            pattern: (!exact).then_some(output),
+            test_only,
+        })
+    }
+
+    /// The example in header form, as `parse` reads it
+    pub fn to_header(&self) -> String {
+        let args = serde_json::Value::Object(self.args.clone());
+        let line = match (&self.expected, &self.pattern) {
+            (_, Some(pattern)) => format!("{} =~ {}", args, pattern),
+            (expected, None) => format!("{} => {}", args, expected.as_deref().unwrap_or("")),
+        };
+        line.trim_end().to_string()
+    }
+
+    /// Whether a call's output is what the example expects. An example
+    /// without an expected output only needs the call to succeed.
+    pub fn matches(&self, output: &str) -> bool {
+        let output = output.trim();
+        match (&self.expected, &self.pattern) {
+            (_, Some(pattern)) => exposure::glob_matches(pattern, output),
+            (Some(expected), None) => expected.trim() == output,
+            (None, None) => true,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -22,6 +98,14 @@ pub struct ParameterDefinition {
     pub description: String,
     pub required: bool,
     pub type_name: String,
+    /// TCL completion provider: a proc body called with `value` (the partial
+    /// argument) and `context` (a dict of the other arguments) that returns
+    /// a list of candidate values
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub completion: Option<String>,
+    /// Value the tool receives when the argument is omitted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default: Option<serde_json::Value>,
 }
 
 #[derive(Clone)]
@@ -50,9 +134,21 @@ pub struct TclToolAddRequest {
     pub description: String,
     /// TCL script that implements the tool
     pub script: String,
-    /// Parameters that the tool accepts
+    /// Parameters that the tool accepts; inferred from the script when omitted
     #[serde(default)]
-    pub parameters: Vec<ParameterDefinition>,
+    pub parameters: Option<Vec<ParameterDefinition>>,
+    /// Save a tool with inferred parameters instead of returning a preview
+    #[serde(default)]
+    pub confirm: bool,
+    /// Sample calls with their expected output
+    #[serde(default)]
+    pub examples: Vec<ToolExample>,
+    /// Replace an existing tool with the same path, keeping its history
+    #[serde(default)]
+    pub overwrite: bool,
+    /// Reject the tool if linting its script finds errors
+    #[serde(default)]
+    pub strict: bool,
 }
 
 fn default_version() -> String {
@@ -65,6 +161,63 @@ pub struct TclToolRemoveRequest {
     pub path: String,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TclToolPinRequest {
+    /// Tool to pin, in any tool path form
+    pub path: String,
+    /// Version or range to make the default; omit to remove the pin
+    #[serde(default)]
+    pub version: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TclToolHistoryRequest {
+    /// Tool whose history to list, in any tool path form
+    pub path: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TclToolDiffRequest {
+    /// Tool to compare revisions of, in any tool path form
+    pub path: String,
+    /// Older revision (defaults to the one before `to`)
+    #[serde(default)]
+    pub from: Option<u32>,
+    /// Newer revision (defaults to the latest)
+    #[serde(default)]
+    pub to: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TclToolRollbackRequest {
+    /// Tool to roll back, in any tool path form
+    pub path: String,
+    /// Revision to restore
+    pub revision: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TclToolExportRequest {
+    /// User or user package to export (e.g., "alice/utils")
+    pub package: String,
+    /// File to write the package to; the package is returned when omitted
+    #[serde(default)]
+    pub output: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TclToolImportRequest {
+    /// Package to import, as exported
+    #[serde(default)]
+    pub package: Option<ToolPackage>,
+    /// Package file to import, instead of `package`
+    #[serde(default)]
+    pub file: Option<String>,
+    /// What to do with tools whose version is already stored
+    #[serde(default)]
+    pub on_conflict: ConflictPolicy,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TclToolListRequest {
     /// Filter tools by namespace (optional)
@@ -189,13 +342,27 @@ impl TclToolBox {
         );
         info!("Adding new TCL tool: {}", path);
 
+        let (script, parameters) = match request.parameters {
+            Some(parameters) => (request.script, parameters),
+            None => {
+                let inference = param_inference::infer(&request.script)?;
+                if inference.needs_review() && !request.confirm {
+                    return Ok(serde_json::to_string_pretty(&inference.preview(&path))?);
+                }
+                (inference.script, inference.parameters)
+            }
+        };
+
         let (tx, rx) = oneshot::channel();
         self.executor
             .send(TclCommand::AddTool {
                 path,
                 description: request.description,
-                script: request.script,
-                parameters: request.parameters,
+                script,
+                parameters,
+                examples: request.examples,
+                overwrite: request.overwrite,
+                strict: request.strict,
                 response: tx,
             })
             .await
@@ -206,12 +373,84 @@ impl TclToolBox {
     }
 
     pub async fn tcl_tool_remove(&self, request: TclToolRemoveRequest) -> Result<String> {
-        let path = ToolPath::parse(&request.path)?;
-        info!("Removing TCL tool: {}", path);
+        info!("Removing TCL tool: {}", request.path);
+
+        let (tx, rx) = oneshot::channel();
+        self.executor
+            .send(TclCommand::RemoveTool {
+                path: request.path,
+                response: tx,
+            })
+            .await
+            .map_err(|_| anyhow!("Failed to send command to executor"))?;
+
+        rx.await
+            .map_err(|_| anyhow!("Failed to receive response from executor"))?
+    }
+
+    pub async fn pin_tool_version(&self, request: TclToolPinRequest) -> Result<String> {
+        info!(
+            "Pinning default version of {}: {:?}",
+            request.path, request.version
+        );
+
+        let (tx, rx) = oneshot::channel();
+        self.executor
+            .send(TclCommand::PinToolVersion {
+                path: request.path,
+                version: request.version,
+                response: tx,
+            })
+            .await
+            .map_err(|_| anyhow!("Failed to send command to executor"))?;
+
+        rx.await
+            .map_err(|_| anyhow!("Failed to receive response from executor"))?
+    }
+
+    pub async fn tool_history(&self, request: TclToolHistoryRequest) -> Result<serde_json::Value> {
+        let (tx, rx) = oneshot::channel();
+        self.executor
+            .send(TclCommand::ToolHistory {
+                path: request.path,
+                response: tx,
+            })
+            .await
+            .map_err(|_| anyhow!("Failed to send command to executor"))?;
+
+        rx.await
+            .map_err(|_| anyhow!("Failed to receive response from executor"))?
+    }
+
+    pub async fn tool_diff(&self, request: TclToolDiffRequest) -> Result<String> {
+        let (tx, rx) = oneshot::channel();
+        self.executor
+            .send(TclCommand::ToolDiff {
+                path: request.path,
+                from: request.from,
+                to: request.to,
+                response: tx,
+            })
+            .await
+            .map_err(|_| anyhow!("Failed to send command to executor"))?;
+
+        rx.await
+            .map_err(|_| anyhow!("Failed to receive response from executor"))?
+    }
+
+    pub async fn tool_rollback(&self, request: TclToolRollbackRequest) -> Result<String> {
+        info!(
+            "Rolling back TCL tool {} to revision {}",
+            request.path, request.revision
+        );
 
         let (tx, rx) = oneshot::channel();
         self.executor
-            .send(TclCommand::RemoveTool { path, response: tx })
+            .send(TclCommand::ToolRollback {
+                path: request.path,
+                revision: request.revision,
+                response: tx,
+            })
             .await
             .map_err(|_| anyhow!("Failed to send command to executor"))?;
 
@@ -219,6 +458,65 @@ impl TclToolBox {
             .map_err(|_| anyhow!("Failed to receive response from executor"))?
     }
 
+    pub async fn tool_export(&self, request: TclToolExportRequest) -> Result<String> {
+        let (tx, rx) = oneshot::channel();
+        self.executor
+            .send(TclCommand::ExportTools {
+                package: request.package.clone(),
+                response: tx,
+            })
+            .await
+            .map_err(|_| anyhow!("Failed to send command to executor"))?;
+
+        let package = rx
+            .await
+            .map_err(|_| anyhow!("Failed to receive response from executor"))??;
+        match request.output {
+            Some(output) => {
+                tool_package::write(&package, std::path::Path::new(&output)).await?;
+                Ok(format!(
+                    "Exported {} tools from '{}' to {}",
+                    package.tools.len(),
+                    request.package,
+                    output
+                ))
+            }
+            None => Ok(serde_json::to_string_pretty(&package)?),
+        }
+    }
+
+    pub async fn tool_import(&self, request: TclToolImportRequest) -> Result<serde_json::Value> {
+        let package = match (request.package, request.file) {
+            (Some(package), None) => package,
+            (None, Some(file)) => tool_package::read(std::path::Path::new(&file)).await?,
+            _ => return Err(anyhow!("Provide either 'package' or 'file'")),
+        };
+        info!(
+            "Importing {} TCL tools of '{}' ({} on conflict)",
+            package.tools.len(),
+            package.user,
+            request.on_conflict
+        );
+
+        let (tx, rx) = oneshot::channel();
+        self.executor
+            .send(TclCommand::ImportTools {
+                package,
+                on_conflict: request.on_conflict,
+                response: tx,
+            })
+            .await
+            .map_err(|_| anyhow!("Failed to send command to executor"))?;
+
+        let imported = rx
+            .await
+            .map_err(|_| anyhow!("Failed to receive response from executor"))??;
+        Ok(serde_json::json!({
+            "on_conflict": request.on_conflict,
+            "tools": imported,
+        }))
+    }
+
     pub async fn execute_custom_tool(
         &self,
         mcp_name: &str,
@@ -240,16 +538,19 @@ impl TclToolBox {
             .map_err(|_| anyhow!("Failed to receive response from executor"))?
     }
 
-    pub async fn get_tool_definitions(&self) -> Result<Vec<ToolDefinition>> {
+    pub async fn get_tool_definitions(&self, latest_only: bool) -> Result<Vec<ToolDefinition>> {
         let (tx, rx) = oneshot::channel();
         self.executor
-            .send(TclCommand::GetToolDefinitions { response: tx })
+            .send(TclCommand::GetToolDefinitions {
+                latest_only,
+                response: tx,
+            })
             .await
             .map_err(|_| anyhow!("Failed to send command to executor"))?;
 
-        Ok(rx
+        rx
             .await
-            .map_err(|_| anyhow!("Failed to receive response from executor"))?)
+            .map_err(|_| anyhow!("Failed to receive response from executor"))
     }
 
     pub async fn initialize_persistence(&self) -> Result<String> {
@@ -309,6 +610,59 @@ impl TclToolBox {
             .map_err(|_| anyhow!("Failed to receive response from executor"))?
     }
 
+    pub async fn complete(
+        &self,
+        tool: String,
+        argument: String,
+        value: String,
+        context: serde_json::Map<String, serde_json::Value>,
+    ) -> Result<serde_json::Value> {
+        let (tx, rx) = oneshot::channel();
+        self.executor
+            .send(TclCommand::Complete {
+                tool,
+                argument,
+                value,
+                context,
+                response: tx,
+            })
+            .await
+            .map_err(|_| anyhow!("Failed to send command to executor"))?;
+
+        rx.await
+            .map_err(|_| anyhow!("Failed to receive response from executor"))?
+    }
+
+    /// Resolve a tool path in any supported form, including bare tool names
+    pub async fn resolve_tool_path(&self, path: &str) -> Result<ToolPath> {
+        let (tx, rx) = oneshot::channel();
+        self.executor
+            .send(TclCommand::ResolveToolPath {
+                path: path.to_string(),
+                response: tx,
+            })
+            .await
+            .map_err(|_| anyhow!("Failed to send command to executor"))?;
+
+        rx.await
+            .map_err(|_| anyhow!("Failed to receive response from executor"))?
+    }
+
+    /// The definition of a user or filesystem tool, including its script
+    pub async fn tool_definition(&self, path: &str) -> Result<ToolDefinition> {
+        let (tx, rx) = oneshot::channel();
+        self.executor
+            .send(TclCommand::GetToolDefinition {
+                path: path.to_string(),
+                response: tx,
+            })
+            .await
+            .map_err(|_| anyhow!("Failed to send command to executor"))?;
+
+        rx.await
+            .map_err(|_| anyhow!("Failed to receive response from executor"))?
+    }
+
     // MCP server management methods
     pub async fn mcp_execute(&self, request: McpExecuteRequest) -> Result<String> {
         info!(
@@ -395,7 +749,7 @@ impl TclToolBox {
 
     pub async fn get_mcp_server_tools(
         &self,
-    ) -> Result<Vec<(String, Vec<crate::mcp_client::McpToolDefinition>)>> {
+    ) -> Result<crate::tcl_executor::McpServerTools> {
         info!("Getting MCP server tools");
 
         let (tx, rx) = oneshot::channel();
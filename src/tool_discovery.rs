@@ -1,5 +1,5 @@
 use crate::namespace::{Namespace, ToolPath};
-use crate::tcl_tools::ParameterDefinition;
+use crate::tcl_tools::{ParameterDefinition, ToolExample};
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -21,6 +21,18 @@ pub struct DiscoveredTool {
     pub description: String,
     pub file_path: PathBuf,
     pub parameters: Vec<ParameterDefinition>,
+    /// Search keywords from the `@tags` header
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Sample calls from the `@example` and `@test` headers
+    #[serde(default)]
+    pub examples: Vec<ToolExample>,
+}
+
+impl Default for ToolDiscovery {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl ToolDiscovery {
@@ -91,6 +103,8 @@ impl ToolDiscovery {
                         description: metadata.description,
                         file_path: path,
                         parameters: metadata.parameters,
+                        tags: metadata.tags,
+                        examples: metadata.examples,
                     };
 
                     self.discovered_tools.insert(tool_path, discovered);
@@ -144,6 +158,8 @@ impl ToolDiscovery {
                                 description: metadata.description,
                                 file_path: tool_file,
                                 parameters: metadata.parameters,
+                                tags: metadata.tags,
+                                examples: metadata.examples,
                             };
 
                             self.discovered_tools.insert(tool_path, discovered);
@@ -157,52 +173,95 @@ impl ToolDiscovery {
     }
 
     /// Read tool metadata from file header comments
-    async fn read_tool_metadata(&self, file_path: &Path) -> Result<ToolMetadata> {
+    async fn read_tool_metadata(&self, file_path: &Path) -> Result<ToolHeader> {
         let content = fs::read_to_string(file_path).await?;
-        let mut metadata = ToolMetadata::default();
-
-        // Parse header comments for metadata
-        for line in content.lines() {
-            if !line.trim_start().starts_with('#') {
-                break; // Stop at first non-comment line
-            }
-
-            let comment = line.trim_start_matches('#').trim();
-
-            if let Some(desc) = comment.strip_prefix("@description ") {
-                metadata.description = desc.to_string();
-            } else if let Some(version) = comment.strip_prefix("@version ") {
-                metadata.version = Some(version.to_string());
-            } else if let Some(param_line) = comment.strip_prefix("@param ") {
-                // Parse parameter definition: @param name:type:required description
-                if let Some((def, desc)) = param_line.split_once(' ') {
-                    let parts: Vec<&str> = def.split(':').collect();
-                    if parts.len() >= 2 {
-                        let param = ParameterDefinition {
-                            name: parts[0].to_string(),
-                            type_name: parts[1].to_string(),
-                            required: parts.get(2).map(|&r| r == "required").unwrap_or(false),
-                            description: desc.to_string(),
-                        };
-                        metadata.parameters.push(param);
-                    }
-                }
-            }
-        }
-
+        let mut metadata = parse_header(&content);
         if metadata.description.is_empty() {
             metadata.description = format!("Tool from {}", file_path.display());
         }
-
         Ok(metadata)
     }
 }
 
+/// Metadata from the header comments of a `.tcl` tool file
 #[derive(Debug, Default)]
-struct ToolMetadata {
-    description: String,
-    version: Option<String>,
-    parameters: Vec<ParameterDefinition>,
+pub struct ToolHeader {
+    pub description: String,
+    pub version: Option<String>,
+    pub parameters: Vec<ParameterDefinition>,
+    pub tags: Vec<String>,
+    pub examples: Vec<ToolExample>,
+}
+
+/// Parse the comment lines at the top of a tool file
+pub fn parse_header(content: &str) -> ToolHeader {
+    let mut metadata = ToolHeader::default();
+
+    for line in content.lines() {
+        if !line.trim_start().starts_with('#') {
+            break; // Stop at first non-comment line
+        }
+
+        let comment = line.trim_start_matches('#').trim();
+
+        if let Some(desc) = comment.strip_prefix("@description ") {
+            metadata.description = desc.to_string();
+        } else if let Some(version) = comment.strip_prefix("@version ") {
+            metadata.version = Some(version.to_string());
+        } else if let Some(tags) = comment.strip_prefix("@tags ") {
+            // Search keywords: @tags files listing, directory
+            metadata.tags.extend(
+                tags.split(|c: char| c == ',' || c.is_whitespace())
+                    .filter(|tag| !tag.is_empty())
+                    .map(String::from),
+            );
+        } else if let Some(param_line) = comment.strip_prefix("@param ") {
+            // Parse parameter definition: @param name:type:required description
+            let (def, desc) = param_line.split_once(' ').unwrap_or((param_line, ""));
+            let parts: Vec<&str> = def.split(':').collect();
+            if parts.len() >= 2 {
+                let param = ParameterDefinition {
+                    name: parts[0].to_string(),
+                    type_name: parts[1].to_string(),
+                    required: parts.get(2).map(|&r| r == "required").unwrap_or(false),
+                    description: desc.trim().to_string(),
+                    completion: None,
+                    default: None,
+                };
+                metadata.parameters.push(param);
+            }
+        } else if let Some((example, test_only)) = comment
+            .strip_prefix("@example ")
+            .map(|example| (example, false))
+            .or_else(|| comment.strip_prefix("@test ").map(|test| (test, true)))
+        {
+            // Sample call: @example {"text": "abc"} => cba
+            match ToolExample::parse(example, test_only) {
+                Ok(example) => metadata.examples.push(example),
+                Err(e) => tracing::warn!("Ignoring malformed header '{}': {}", comment, e),
+            }
+        } else if let Some(complete_line) = comment.strip_prefix("@complete ") {
+            // Attach a completion provider: @complete name tcl_body
+            if let Some((name, body)) = complete_line.split_once(' ') {
+                if let Some(param) = metadata.parameters.iter_mut().find(|p| p.name == name) {
+                    param.completion = Some(body.trim().to_string());
+                }
+            }
+        } else if let Some(default_line) = comment.strip_prefix("@default ") {
+            // Value for an omitted argument, as JSON or a string: @default name value
+            if let Some((name, value)) = default_line.split_once(' ') {
+                if let Some(param) = metadata.parameters.iter_mut().find(|p| p.name == name) {
+                    let value = value.trim();
+                    param.default = Some(
+                        serde_json::from_str(value)
+                            .unwrap_or_else(|_| serde_json::Value::String(value.to_string())),
+                    );
+                }
+            }
+        }
+    }
+
+    metadata
 }
 
 #[cfg(test)]
@@ -244,4 +303,55 @@ puts [glob -directory $path *]
         assert_eq!(tools[0].parameters[0].type_name, "string");
         assert!(tools[0].parameters[0].required);
     }
+
+    #[tokio::test]
+    async fn test_completion_provider_header() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let tools_dir = temp_dir.path().join("tools");
+        let bin_dir = tools_dir.join("bin");
+        fs::create_dir_all(&bin_dir).await.unwrap();
+
+        let tool_content = r#"# @description Greet someone
+# @param greeting:string:required Greeting to use
+# @complete greeting return {hello hi howdy}
+# @tags welcome, salutation
+
+return "$greeting!"
+"#;
+        std::fs::write(bin_dir.join("greet.tcl"), tool_content).unwrap();
+
+        let mut discovery = ToolDiscovery::new().with_tools_dir(tools_dir);
+        let tools = discovery.discover_tools().await.unwrap();
+
+        assert_eq!(
+            tools[0].parameters[0].completion.as_deref(),
+            Some("return {hello hi howdy}")
+        );
+        assert_eq!(tools[0].tags, vec!["welcome", "salutation"]);
+    }
+
+    #[test]
+    fn test_example_headers() {
+        let header = parse_header(
+            r#"# @description Reverse text
+# @param text:string:required Text to reverse
+# @example {"text": "abc"} => cba
+# @test text {hello world} =~ dlrow*
+# @test text
+"#,
+        );
+
+        assert_eq!(header.examples.len(), 2);
+        assert_eq!(header.examples[0].args["text"], "abc");
+        assert_eq!(header.examples[0].expected.as_deref(), Some("cba"));
+        assert!(!header.examples[0].test_only);
+        assert_eq!(header.examples[1].args["text"], "hello world");
+        assert_eq!(header.examples[1].pattern.as_deref(), Some("dlrow*"));
+        assert!(header.examples[1].test_only);
+
+        assert!(header.examples[0].matches("cba\n"));
+        assert!(!header.examples[0].matches("abc"));
+        assert!(header.examples[1].matches("dlrow olleh"));
+        assert_eq!(header.examples[0].to_header(), r#"{"text":"abc"} => cba"#);
+    }
 }
@@ -0,0 +1,321 @@
+/// Append-only revision history of persisted tools
+///
+/// Every save, rollback and deletion of a tool appends one JSON line to the
+/// tool's history file. Lines are never rewritten, so a script replaced by a
+/// bad edit, or a tool removed by mistake, can always be compared and
+/// restored.
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use tokio::fs;
+use tokio::io::AsyncWriteExt;
+
+use crate::tcl_tools::ToolDefinition;
+
+/// Lines of unchanged context around each change in a diff
+const DIFF_CONTEXT: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RevisionAction {
+    Save,
+    Rollback,
+    Delete,
+}
+
+/// One entry of a tool's history. Delete entries hold the tool as it was
+/// when it was removed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolRevision {
+    pub revision: u32,
+    pub action: RevisionAction,
+    pub saved_at: DateTime<Utc>,
+    pub checksum: String,
+    /// Revision restored by a rollback
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub restored_from: Option<u32>,
+    pub tool: ToolDefinition,
+}
+
+/// The history file of one tool, one JSON revision per line
+pub struct HistoryLog {
+    path: PathBuf,
+}
+
+impl HistoryLog {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    /// All revisions, oldest first
+    pub async fn read(&self) -> Result<Vec<ToolRevision>> {
+        if !self.path.exists() {
+            return Ok(Vec::new());
+        }
+
+        let content = fs::read_to_string(&self.path).await?;
+        content
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(number, line)| {
+                serde_json::from_str(line).map_err(|e| {
+                    anyhow!(
+                        "Corrupt history entry at {}:{}: {}",
+                        self.path.display(),
+                        number + 1,
+                        e
+                    )
+                })
+            })
+            .collect()
+    }
+
+    /// Append a revision
+    pub async fn append(&self, revision: &ToolRevision) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+
+        let mut line = serde_json::to_string(revision)?;
+        line.push('\n');
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .await?;
+        file.write_all(line.as_bytes()).await?;
+        file.flush().await?;
+        Ok(())
+    }
+}
+
+/// Unified diff between two revisions of a tool, covering its description,
+/// parameters, examples and script
+pub fn diff(from: &ToolRevision, to: &ToolRevision) -> String {
+    let old = render(&from.tool);
+    let new = render(&to.tool);
+    let edits = line_edits(&old, &new);
+    if edits.iter().all(|edit| matches!(edit, Edit::Equal(_))) {
+        return format!(
+            "No differences between revision {} and revision {}",
+            from.revision, to.revision
+        );
+    }
+
+    let mut output = format!(
+        "--- {} (revision {})\n+++ {} (revision {})\n",
+        from.tool.path, from.revision, to.tool.path, to.revision
+    );
+    for hunk in hunks(&edits) {
+        output.push_str(&hunk);
+    }
+    output
+}
+
+/// The diffable text of a tool
+fn render(tool: &ToolDefinition) -> Vec<String> {
+    let mut lines = vec![format!("description: {}", tool.description)];
+    for param in &tool.parameters {
+        let mut attributes = param.type_name.clone();
+        if param.required {
+            attributes.push_str(", required");
+        }
+        if let Some(default) = &param.default {
+            attributes.push_str(&format!(", default {}", default));
+        }
+        lines.push(format!(
+            "parameter {} ({}): {}",
+            param.name, attributes, param.description
+        ));
+    }
+    for example in &tool.examples {
+        let kind = if example.test_only { "test" } else { "example" };
+        lines.push(format!("{} {}", kind, example.to_header()));
+    }
+    lines.push("script:".to_string());
+    lines.extend(tool.script.lines().map(String::from));
+    lines
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Edit<'a> {
+    Equal(&'a str),
+    Delete(&'a str),
+    Insert(&'a str),
+}
+
+/// Shortest line edit script from `old` to `new`, via their longest common
+/// subsequence
+fn line_edits<'a>(old: &'a [String], new: &'a [String]) -> Vec<Edit<'a>> {
+    // common[i][j]: length of the LCS of old[i..] and new[j..]
+    let mut common = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            common[i][j] = if old[i] == new[j] {
+                common[i + 1][j + 1] + 1
+            } else {
+                common[i + 1][j].max(common[i][j + 1])
+            };
+        }
+    }
+
+    let (mut i, mut j) = (0, 0);
+    let mut edits = Vec::new();
+    while i < old.len() && j < new.len() {
+        if old[i] == new[j] {
+            edits.push(Edit::Equal(&old[i]));
+            i += 1;
+            j += 1;
+        } else if common[i + 1][j] >= common[i][j + 1] {
+            edits.push(Edit::Delete(&old[i]));
+            i += 1;
+        } else {
+            edits.push(Edit::Insert(&new[j]));
+            j += 1;
+        }
+    }
+    edits.extend(old[i..].iter().map(|line| Edit::Delete(line)));
+    edits.extend(new[j..].iter().map(|line| Edit::Insert(line)));
+    edits
+}
+
+/// Group edits into unified diff hunks with surrounding context
+fn hunks(edits: &[Edit]) -> Vec<String> {
+    let changes: Vec<usize> = edits
+        .iter()
+        .enumerate()
+        .filter(|(_, edit)| !matches!(edit, Edit::Equal(_)))
+        .map(|(index, _)| index)
+        .collect();
+
+    // Ranges of edits to show, merging changes whose context overlaps
+    let mut ranges: Vec<(usize, usize)> = Vec::new();
+    for &change in &changes {
+        let start = change.saturating_sub(DIFF_CONTEXT);
+        let end = (change + DIFF_CONTEXT + 1).min(edits.len());
+        match ranges.last_mut() {
+            Some(last) if start <= last.1 => last.1 = end,
+            _ => ranges.push((start, end)),
+        }
+    }
+
+    ranges
+        .into_iter()
+        .map(|(start, end)| {
+            let old_before = edits[..start]
+                .iter()
+                .filter(|edit| !matches!(edit, Edit::Insert(_)))
+                .count();
+            let new_before = edits[..start]
+                .iter()
+                .filter(|edit| !matches!(edit, Edit::Delete(_)))
+                .count();
+            let slice = &edits[start..end];
+            let old_count = slice
+                .iter()
+                .filter(|edit| !matches!(edit, Edit::Insert(_)))
+                .count();
+            let new_count = slice
+                .iter()
+                .filter(|edit| !matches!(edit, Edit::Delete(_)))
+                .count();
+
+            let mut hunk = format!(
+                "@@ -{} +{} @@\n",
+                hunk_range(old_before, old_count),
+                hunk_range(new_before, new_count)
+            );
+            for edit in slice {
+                let (marker, line) = match edit {
+                    Edit::Equal(line) => (' ', line),
+                    Edit::Delete(line) => ('-', line),
+                    Edit::Insert(line) => ('+', line),
+                };
+                hunk.push(marker);
+                hunk.push_str(line);
+                hunk.push('\n');
+            }
+            hunk
+        })
+        .collect()
+}
+
+/// `start,count` of a hunk side; an empty side names the line before it
+fn hunk_range(before: usize, count: usize) -> String {
+    if count == 0 {
+        format!("{},0", before)
+    } else {
+        format!("{},{}", before + 1, count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::namespace::ToolPath;
+
+    fn revision(revision: u32, script: &str) -> ToolRevision {
+        ToolRevision {
+            revision,
+            action: RevisionAction::Save,
+            saved_at: Utc::now(),
+            checksum: String::new(),
+            restored_from: None,
+            tool: ToolDefinition {
+                path: ToolPath::user("alice", "utils", "greet", "1.0"),
+                description: "Greet someone".to_string(),
+                script: script.to_string(),
+                parameters: vec![],
+                examples: vec![],
+            },
+        }
+    }
+
+    #[test]
+    fn test_diff_hunks() {
+        let old =
+            "set a 1\nset b 2\nset c 3\nset d 4\nset e 5\nset f 6\nset g 7\nset h 8\nreturn $a";
+        let new =
+            "set a 1\nset b 2\nset c 3\nset d 4\nset e 5\nset f 6\nset g 7\nset h 8\nreturn $b";
+        let text = diff(&revision(1, old), &revision(2, new));
+
+        assert!(text.starts_with("--- user__alice__utils__greet__v1_0 (revision 1)\n"));
+        assert!(text
+            .contains("@@ -8,4 +8,4 @@\n set f 6\n set g 7\n set h 8\n-return $a\n+return $b\n"));
+        assert!(!text.contains("description:"));
+
+        let same = diff(&revision(1, old), &revision(3, old));
+        assert_eq!(same, "No differences between revision 1 and revision 3");
+    }
+
+    #[test]
+    fn test_line_edits() {
+        let old: Vec<String> = ["a", "b", "c"].iter().map(|s| s.to_string()).collect();
+        let new: Vec<String> = ["a", "c", "d"].iter().map(|s| s.to_string()).collect();
+        assert_eq!(
+            line_edits(&old, &new),
+            vec![
+                Edit::Equal("a"),
+                Edit::Delete("b"),
+                Edit::Equal("c"),
+                Edit::Insert("d")
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_history_log_appends() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let log = HistoryLog::new(dir.path().join("tools").join("greet.jsonl"));
+        assert!(log.read().await?.is_empty());
+
+        log.append(&revision(1, "return hi")).await?;
+        log.append(&revision(2, "return hello")).await?;
+        let revisions = log.read().await?;
+        assert_eq!(revisions.len(), 2);
+        assert_eq!(revisions[1].tool.script, "return hello");
+        Ok(())
+    }
+}
@@ -0,0 +1,450 @@
+/// Tool packages
+///
+/// A package (`.tclpkg`) is a JSON bundle of the stored tools of one user or
+/// user package, every version of each, with their parameters, metadata and
+/// checksums. Importing checks every tool against its checksum before
+/// anything is saved, then saves each one through the tool store, so imports
+/// are recorded in the tools' history like any other save. Signatures are
+/// not carried over; sign imported tools again with the receiving team's key.
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+use tokio::fs;
+
+use crate::namespace::{Namespace, ToolPath};
+use crate::persistence::{calculate_checksum, PersistedTool};
+use crate::tcl_tools::ToolDefinition;
+use crate::tool_store::ToolStore;
+use crate::versioning::{self, LATEST};
+
+/// Current package format, recorded as `format_version`
+pub const PACKAGE_FORMAT_VERSION: u32 = 1;
+
+/// Exported tools of a user or user package
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolPackage {
+    pub format_version: u32,
+    pub user: String,
+    /// `None` when every tool of the user was exported
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub package: Option<String>,
+    pub exported_at: DateTime<Utc>,
+    pub tools: Vec<PersistedTool>,
+}
+
+/// What to do with a packaged tool whose version is already stored
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictPolicy {
+    /// Keep the stored tool
+    #[default]
+    Skip,
+    /// Replace the stored tool, recording a new revision
+    Overwrite,
+    /// Save the packaged tool as the next patch version
+    #[serde(alias = "new-version")]
+    NewVersion,
+}
+
+impl FromStr for ConflictPolicy {
+    type Err = anyhow::Error;
+
+    fn from_str(text: &str) -> Result<Self> {
+        match text {
+            "skip" => Ok(Self::Skip),
+            "overwrite" => Ok(Self::Overwrite),
+            "new_version" | "new-version" => Ok(Self::NewVersion),
+            _ => Err(anyhow!(
+                "Unknown conflict policy '{}' (expected skip, overwrite or new-version)",
+                text
+            )),
+        }
+    }
+}
+
+impl fmt::Display for ConflictPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Skip => "skip",
+            Self::Overwrite => "overwrite",
+            Self::NewVersion => "new-version",
+        })
+    }
+}
+
+/// What happened to one packaged tool
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportOutcome {
+    Added,
+    Overwritten,
+    /// Already stored with the same content
+    Unchanged,
+    Skipped,
+    NewVersion,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportedTool {
+    /// Where the tool was stored, or the stored tool it was skipped for
+    pub path: String,
+    pub outcome: ImportOutcome,
+    /// The packaged version, when it was saved under another one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub package_version: Option<String>,
+}
+
+/// Split `alice/utils`, `/alice/utils` or `alice` into user and package
+pub fn parse_selector(selector: &str) -> Result<(String, Option<String>)> {
+    let parts: Vec<&str> = selector.trim_matches('/').split('/').collect();
+    match parts.as_slice() {
+        [user] if !user.is_empty() => Ok((user.to_string(), None)),
+        [user, package] if !user.is_empty() && !package.is_empty() => {
+            Ok((user.to_string(), Some(package.to_string())))
+        }
+        _ => Err(anyhow!(
+            "Invalid package '{}': expected a user or user/package such as alice/utils",
+            selector
+        )),
+    }
+}
+
+/// Read a package file
+pub async fn read(path: &Path) -> Result<ToolPackage> {
+    let content = fs::read_to_string(path)
+        .await
+        .map_err(|e| anyhow!("Failed to read package {}: {}", path.display(), e))?;
+    serde_json::from_str(&content)
+        .map_err(|e| anyhow!("{} is not a tool package: {}", path.display(), e))
+}
+
+/// Write a package file
+pub async fn write(package: &ToolPackage, path: &Path) -> Result<()> {
+    let json = serde_json::to_string_pretty(package)?;
+    fs::write(path, json)
+        .await
+        .map_err(|e| anyhow!("Failed to write package {}: {}", path.display(), e))
+}
+
+/// Bundle the stored tools of a user or user package
+pub async fn export(store: &mut dyn ToolStore, selector: &str) -> Result<ToolPackage> {
+    let (user, package) = parse_selector(selector)?;
+    let mut tools: Vec<PersistedTool> = store
+        .list_tools(Some(user.as_str()))
+        .await?
+        .into_iter()
+        .filter(|persisted| {
+            matches!(&persisted.tool.path.namespace, Namespace::User(owner) if *owner == user)
+                && (package.is_none() || persisted.tool.path.package == package)
+        })
+        .map(|mut persisted| {
+            // Tools saved before SHA-256 checksums were verified on load
+            // like any other, so they can be exported with one
+            persisted.metadata.checksum = calculate_checksum(&persisted.tool);
+            persisted.metadata.signature = None;
+            persisted
+        })
+        .collect();
+    if tools.is_empty() {
+        return Err(anyhow!("No stored tools in '{}'", selector));
+    }
+    tools.sort_by(|a, b| {
+        a.tool
+            .path
+            .with_version(LATEST)
+            .to_string()
+            .cmp(&b.tool.path.with_version(LATEST).to_string())
+            .then_with(|| versioning::compare(&a.tool.path.version, &b.tool.path.version))
+    });
+
+    Ok(ToolPackage {
+        format_version: PACKAGE_FORMAT_VERSION,
+        user,
+        package,
+        exported_at: Utc::now(),
+        tools,
+    })
+}
+
+/// Check a package before anything in it is saved
+fn validate(package: &ToolPackage) -> Result<()> {
+    if package.format_version > PACKAGE_FORMAT_VERSION {
+        return Err(anyhow!(
+            "Package format version {} is newer than this server supports ({}); upgrade tcl-mcp-server to import it",
+            package.format_version,
+            PACKAGE_FORMAT_VERSION
+        ));
+    }
+    for persisted in &package.tools {
+        let path = &persisted.tool.path;
+        let Namespace::User(user) = &path.namespace else {
+            return Err(anyhow!(
+                "Package contains '{}'; only user tools can be imported",
+                path
+            ));
+        };
+        // Conflicts are looked up among the package user's tools, so a tool
+        // of anyone else would replace theirs unchecked
+        let outside_package = package
+            .package
+            .as_ref()
+            .is_some_and(|name| path.package.as_ref() != Some(name));
+        if *user != package.user || outside_package {
+            let selector = match &package.package {
+                Some(name) => format!("{}/{}", package.user, name),
+                None => package.user.clone(),
+            };
+            return Err(anyhow!(
+                "Package '{}' contains '{}', which belongs elsewhere; nothing was imported",
+                selector,
+                path
+            ));
+        }
+        let checksum = calculate_checksum(&persisted.tool);
+        if checksum != persisted.metadata.checksum {
+            return Err(anyhow!(
+                "Tool '{}' does not match its checksum (package records {}, content hashes to {}); nothing was imported",
+                path,
+                persisted.metadata.checksum,
+                checksum
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// The stored version a packaged version collides with. `1.0` and `1.0.0`
+/// are the same version.
+fn stored_version<'a>(stored: &'a [String], version: &str) -> Option<&'a String> {
+    let parsed = versioning::parse_version(version);
+    stored
+        .iter()
+        .find(|v| *v == version || (parsed.is_some() && versioning::parse_version(v) == parsed))
+}
+
+/// The patch release after the highest stored version
+fn next_version(stored: &[String]) -> String {
+    match stored
+        .iter()
+        .filter_map(|v| versioning::parse_version(v))
+        .max()
+    {
+        Some(highest) => format!("{}.{}.{}", highest.major, highest.minor, highest.patch + 1),
+        None => "1.0.0".to_string(),
+    }
+}
+
+/// Save the tools of a package, resolving versions that are already stored
+/// with `policy`
+pub async fn import(
+    store: &mut dyn ToolStore,
+    package: &ToolPackage,
+    policy: ConflictPolicy,
+) -> Result<Vec<ImportedTool>> {
+    validate(package)?;
+
+    // Stored versions of each tool, by its unversioned path
+    let mut stored: HashMap<ToolPath, Vec<String>> = HashMap::new();
+    let mut checksums: HashMap<ToolPath, String> = HashMap::new();
+    for persisted in store.list_tools(Some(package.user.as_str())).await? {
+        let checksum = calculate_checksum(&persisted.tool);
+        let path = persisted.tool.path;
+        stored
+            .entry(path.with_version(LATEST))
+            .or_default()
+            .push(path.version.clone());
+        checksums.insert(path, checksum);
+    }
+
+    let mut imported = Vec::new();
+    for persisted in &package.tools {
+        let mut tool = persisted.tool.clone();
+        let versions = stored.entry(tool.path.with_version(LATEST)).or_default();
+        let (outcome, package_version) = match stored_version(versions, &tool.path.version) {
+            None => (ImportOutcome::Added, None),
+            Some(existing) => {
+                let existing = tool.path.with_version(existing.clone());
+                let as_stored = ToolDefinition {
+                    path: existing.clone(),
+                    ..tool.clone()
+                };
+                let unchanged = checksums.get(&existing) == Some(&calculate_checksum(&as_stored));
+                if unchanged || policy == ConflictPolicy::Skip {
+                    imported.push(ImportedTool {
+                        path: existing.to_string(),
+                        outcome: if unchanged {
+                            ImportOutcome::Unchanged
+                        } else {
+                            ImportOutcome::Skipped
+                        },
+                        package_version: None,
+                    });
+                    continue;
+                }
+                if policy == ConflictPolicy::NewVersion {
+                    let package_version = tool.path.version.clone();
+                    tool.path = tool.path.with_version(next_version(versions));
+                    (ImportOutcome::NewVersion, Some(package_version))
+                } else {
+                    tool = as_stored;
+                    (ImportOutcome::Overwritten, None)
+                }
+            }
+        };
+
+        store.save_tool(&tool).await?;
+        if !versions.contains(&tool.path.version) {
+            versions.push(tool.path.version.clone());
+        }
+        checksums.insert(tool.path.clone(), calculate_checksum(&tool));
+        imported.push(ImportedTool {
+            path: tool.path.to_string(),
+            outcome,
+            package_version,
+        });
+    }
+    Ok(imported)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::persistence::FilePersistence;
+    use tempfile::TempDir;
+
+    fn create_test_tool(name: &str, version: &str, script: &str) -> ToolDefinition {
+        ToolDefinition {
+            path: ToolPath::user("alice", "utils", name, version),
+            description: format!("Test tool {}", name),
+            script: script.to_string(),
+            parameters: Vec::new(),
+            examples: Vec::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_export_and_import() -> Result<()> {
+        let source_dir = TempDir::new()?;
+        let mut source = FilePersistence::with_directory(source_dir.path().to_path_buf()).await?;
+        source
+            .save_tool(&create_test_tool("reverse", "1.0", "string reverse $text"))
+            .await?;
+        source
+            .save_tool(&create_test_tool("upper", "1.0", "string toupper $text"))
+            .await?;
+        source
+            .save_tool(&ToolDefinition {
+                path: ToolPath::user("alice", "other", "lower", "1.0"),
+                ..create_test_tool("lower", "1.0", "string tolower $text")
+            })
+            .await?;
+
+        let package = export(&mut source, "alice/utils").await?;
+        assert_eq!(package.tools.len(), 2);
+        assert!(export(&mut source, "bob").await.is_err());
+
+        let target_dir = TempDir::new()?;
+        let mut target = FilePersistence::with_directory(target_dir.path().to_path_buf()).await?;
+        let imported = import(&mut target, &package, ConflictPolicy::Skip).await?;
+        assert!(imported
+            .iter()
+            .all(|tool| tool.outcome == ImportOutcome::Added));
+        assert_eq!(target.list_tools(None).await?.len(), 2);
+
+        // Importing again changes nothing
+        let imported = import(&mut target, &package, ConflictPolicy::Overwrite).await?;
+        assert!(imported
+            .iter()
+            .all(|tool| tool.outcome == ImportOutcome::Unchanged));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_conflict_policies() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let mut store = FilePersistence::with_directory(temp_dir.path().to_path_buf()).await?;
+        store
+            .save_tool(&create_test_tool(
+                "reverse",
+                "1.0.0",
+                "string reverse $text",
+            ))
+            .await?;
+
+        let changed = create_test_tool("reverse", "1.0", "string reverse [string trim $text]");
+        let package = ToolPackage {
+            format_version: PACKAGE_FORMAT_VERSION,
+            user: "alice".to_string(),
+            package: Some("utils".to_string()),
+            exported_at: Utc::now(),
+            tools: vec![PersistedTool {
+                metadata: crate::persistence::ToolMetadata {
+                    id: "shared".to_string(),
+                    created_at: Utc::now(),
+                    updated_at: Utc::now(),
+                    checksum: calculate_checksum(&changed),
+                    file_version: 1,
+                    format_version: crate::migrations::TOOL_FILE_VERSION,
+                    signature: None,
+                },
+                tool: changed.clone(),
+            }],
+        };
+
+        let skipped = import(&mut store, &package, ConflictPolicy::Skip).await?;
+        assert_eq!(skipped[0].outcome, ImportOutcome::Skipped);
+
+        let renamed = import(&mut store, &package, ConflictPolicy::NewVersion).await?;
+        assert_eq!(renamed[0].outcome, ImportOutcome::NewVersion);
+        assert_eq!(renamed[0].package_version.as_deref(), Some("1.0"));
+        let new_path = ToolPath::user("alice", "utils", "reverse", "1.0.1");
+        assert_eq!(renamed[0].path, new_path.to_string());
+        assert!(store.load_persisted(&new_path).await?.is_some());
+
+        // `1.0` in the package replaces the stored `1.0.0`
+        let overwritten = import(&mut store, &package, ConflictPolicy::Overwrite).await?;
+        assert_eq!(overwritten[0].outcome, ImportOutcome::Overwritten);
+        let stored_path = ToolPath::user("alice", "utils", "reverse", "1.0.0");
+        let stored = store.load_persisted(&stored_path).await?.unwrap();
+        assert_eq!(stored.tool.script, changed.script);
+        assert_eq!(store.history(&stored_path).await?.len(), 2);
+
+        // Tools outside the package's user and package are refused, and
+        // the other user's tool is left alone
+        let bob_tool = ToolDefinition {
+            path: ToolPath::user("bob", "utils", "reverse", "1.0.0"),
+            ..create_test_tool("reverse", "1.0.0", "string reverse $text")
+        };
+        store.save_tool(&bob_tool).await?;
+        for (user, package_name) in [("bob", "utils"), ("alice", "other")] {
+            let foreign = ToolDefinition {
+                path: ToolPath::user(user, package_name, "reverse", "1.0.0"),
+                ..changed.clone()
+            };
+            let mut smuggled = package.clone();
+            smuggled.tools[0].metadata.checksum = calculate_checksum(&foreign);
+            smuggled.tools[0].tool = foreign;
+            let err = import(&mut store, &smuggled, ConflictPolicy::Skip)
+                .await
+                .unwrap_err();
+            assert!(err.to_string().contains("belongs elsewhere"), "{}", err);
+        }
+        let bob_path = ToolPath::user("bob", "utils", "reverse", "1.0.0");
+        let stored = store.load_persisted(&bob_path).await?.unwrap();
+        assert_eq!(stored.tool.script, bob_tool.script);
+
+        // Tampered packages are refused as a whole
+        let mut tampered = package.clone();
+        tampered.tools[0].tool.script = "string reverse $other".to_string();
+        assert!(import(&mut store, &tampered, ConflictPolicy::Overwrite)
+            .await
+            .is_err());
+
+        Ok(())
+    }
+}
@@ -1,16 +1,27 @@
 use crate::mcp_client::McpClient;
 use crate::namespace::{Namespace, ToolPath};
-use crate::tcl_tools::{ParameterDefinition, ToolDefinition};
-use crate::tool_discovery::{DiscoveredTool, ToolDiscovery};
+use crate::pagination::{self, Page};
+use crate::persistence::Integrity;
+use crate::search_index::{SearchDocument, SearchIndex};
+use crate::system_tools::{self, SystemTool};
+use crate::tcl_tools::{ParameterDefinition, ToolDefinition, ToolExample};
+use crate::tool_discovery::DiscoveredTool;
+use crate::versioning::{self, VersionSpec, LATEST};
 /// Tiered Tool Registry with Virtual File System
 ///
 /// Provides a unified interface over multiple tool registries:
 /// - Native TCL tools (bin, sbin, docs, user)
 /// - External MCP servers (xmcp namespace)
 /// - Discovered filesystem tools
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+
+/// Most tools named in a "did you mean" hint
+const MAX_SUGGESTIONS: usize = 3;
+
+/// Suggestions must score at least this fraction of the best match
+const SUGGESTION_CUTOFF: f64 = 0.5;
 
 /// Unified tool information combining all registry types
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -22,6 +33,44 @@ pub struct UnifiedTool {
     pub schema: Option<serde_json::Value>,
 }
 
+impl UnifiedTool {
+    /// Orders tools by MCP name. Tools that share a name are told apart by
+    /// their full path, version included, and where they come from.
+    fn sort_key(&self) -> String {
+        let origin = match &self.source {
+            ToolSource::System => "system".to_string(),
+            ToolSource::UserTcl { .. } => "tcl".to_string(),
+            ToolSource::ExternalMcp {
+                server_id,
+                tool_name,
+            } => format!("mcp:{}:{}", server_id, tool_name),
+            ToolSource::Filesystem { file_path } => format!("file:{}", file_path.display()),
+        };
+        format!(
+            "{}\0{}\0{}",
+            self.path.to_mcp_name(),
+            serde_json::to_string(&self.path).unwrap_or_default(),
+            origin
+        )
+    }
+}
+
+/// The version `latest` resolves to for each tool with stored versions,
+/// keyed by the unversioned MCP name
+#[derive(Debug, Default)]
+pub struct DefaultVersions(HashMap<String, Option<String>>);
+
+impl DefaultVersions {
+    /// Whether `path` is the version `latest` resolves to for its tool
+    pub fn contains(&self, path: &ToolPath) -> bool {
+        match self.0.get(&path.with_version(LATEST).to_mcp_name()) {
+            Some(default) => default.as_deref() == Some(path.version.as_str()),
+            // Tools with no stored versions are only known as `latest`
+            None => path.version == LATEST,
+        }
+    }
+}
+
 /// Source of a tool in the registry
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum ToolSource {
@@ -56,15 +105,18 @@ impl VfsPath {
     pub fn matches_namespace(&self, namespace: &str) -> bool {
         self.components
             .first()
-            .map_or(false, |first| first == namespace)
+            .is_some_and(|first| first == namespace)
     }
 
     pub fn matches_filter(&self, filter: &str) -> bool {
         self.components.iter().any(|comp| comp.contains(filter))
     }
 
-    pub fn to_string(&self) -> String {
-        format!("/{}", self.components.join("/"))
+}
+
+impl std::fmt::Display for VfsPath {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "/{}", self.components.join("/"))
     }
 }
 
@@ -81,83 +133,83 @@ pub struct ToolQuery {
     pub include_schemas: bool,
     /// Limit number of results
     pub limit: Option<usize>,
+    /// Skip this many results (after `cursor`, if given)
+    pub offset: Option<usize>,
+    /// Continue after the page that returned this cursor
+    pub cursor: Option<String>,
+    /// Only include the version of each tool that `latest` resolves to
+    pub latest_only: bool,
 }
 
 /// Unified tool registry combining all sources
 pub struct ToolRegistry {
     /// Native TCL tools
     tcl_tools: HashMap<ToolPath, ToolDefinition>,
-    /// System tools (hardcoded)
-    system_tools: Vec<ToolPath>,
     /// Discovered filesystem tools
     discovered_tools: HashMap<ToolPath, DiscoveredTool>,
     /// MCP client for external servers
     mcp_client: McpClient,
-    /// Tool discovery engine
-    tool_discovery: ToolDiscovery,
+    /// Ranked search over every known tool, keyed by MCP name
+    search_index: SearchIndex,
+    /// MCP names of the external MCP tools currently in the search index
+    indexed_mcp_tools: HashSet<String>,
+    /// Pinned default versions, keyed by the unversioned MCP name
+    version_pins: HashMap<String, String>,
+    /// Checksum verification status of stored tools
+    integrity: HashMap<ToolPath, Integrity>,
 }
 
 impl ToolRegistry {
     pub fn new(mcp_client: McpClient) -> Self {
-        let system_tools = vec![
-            ToolPath::bin("tcl_execute"),
-            ToolPath::bin("exec_tool"),
-            ToolPath::bin("discover_tools"),
-            ToolPath::bin("list_tools"),
-            ToolPath::bin("inspect_tool"),
-            ToolPath::bin("list_namespaces"),
-            ToolPath::bin("search_tools"),
-            ToolPath::bin("list_xmcp_tools"),
-            ToolPath::sbin("tcl_tool_add"),
-            ToolPath::sbin("tcl_tool_remove"),
-            ToolPath::sbin("mcp_add"),
-            ToolPath::sbin("mcp_remove"),
-            ToolPath::sbin("mcp_list"),
-            ToolPath::sbin("mcp_connect"),
-            ToolPath::sbin("mcp_info"),
-            ToolPath::sbin("mcp_ping"),
-            ToolPath::docs("molt_book"),
-        ];
+        let mut search_index = SearchIndex::new();
+        for tool in system_tools::all() {
+            let path = tool.path();
+            search_index.upsert(
+                &path.to_mcp_name(),
+                Self::search_document(&path, tool.description, &tool.parameters(), &[]),
+            );
+        }
 
         Self {
             tcl_tools: HashMap::new(),
-            system_tools,
             discovered_tools: HashMap::new(),
             mcp_client,
-            tool_discovery: ToolDiscovery::new(),
+            search_index,
+            indexed_mcp_tools: HashSet::new(),
+            version_pins: HashMap::new(),
+            integrity: HashMap::new(),
         }
     }
 
     /// Query tools with advanced filtering
     pub async fn query_tools(&self, query: ToolQuery) -> Result<Vec<UnifiedTool>> {
+        Ok(self.query_page(query).await?.items)
+    }
+
+    /// Query tools and return the requested page, ordered by MCP name
+    pub async fn query_page(&self, query: ToolQuery) -> Result<Page<UnifiedTool>> {
         let mut tools = Vec::new();
 
         // Add system tools
         if query
             .namespace
             .as_deref()
-            .map_or(true, |ns| ns == "bin" || ns == "sbin" || ns == "docs")
+            .is_none_or(|ns| ns == "bin" || ns == "sbin" || ns == "docs")
         {
-            for tool_path in &self.system_tools {
-                if self.matches_query(tool_path, &query) {
-                    let unified = UnifiedTool {
-                        path: tool_path.clone(),
-                        description: self.get_system_tool_description(tool_path),
-                        parameters: self.get_system_tool_parameters(tool_path),
-                        source: ToolSource::System,
-                        schema: if query.include_schemas {
-                            Some(self.get_system_tool_schema(tool_path))
-                        } else {
-                            None
-                        },
-                    };
+            for tool in system_tools::all() {
+                let tool_path = tool.path();
+                if self.matches_query(&tool_path, &query) {
+                    let mut unified = Self::system_tool(tool);
+                    if !query.include_schemas {
+                        unified.schema = None;
+                    }
                     tools.push(unified);
                 }
             }
         }
 
         // Add user TCL tools
-        if query.namespace.as_deref().map_or(true, |ns| ns == "user") {
+        if query.namespace.as_deref().is_none_or(|ns| ns == "user") {
             for (path, tool_def) in &self.tcl_tools {
                 if self.matches_query(path, &query) {
                     let unified = UnifiedTool {
@@ -168,7 +220,7 @@ impl ToolRegistry {
                             script: tool_def.script.clone(),
                         },
                         schema: if query.include_schemas {
-                            Some(self.generate_schema_from_parameters(&tool_def.parameters))
+                            Some(Self::generate_schema_from_parameters(&tool_def.parameters))
                         } else {
                             None
                         },
@@ -182,7 +234,7 @@ impl ToolRegistry {
         if query
             .namespace
             .as_deref()
-            .map_or(true, |ns| ["bin", "sbin", "docs", "user"].contains(&ns))
+            .is_none_or(|ns| ["bin", "sbin", "docs", "user"].contains(&ns))
         {
             for (path, discovered) in &self.discovered_tools {
                 if self.matches_query(path, &query) {
@@ -194,7 +246,9 @@ impl ToolRegistry {
                             file_path: discovered.file_path.clone(),
                         },
                         schema: if query.include_schemas {
-                            Some(self.generate_schema_from_parameters(&discovered.parameters))
+                            Some(Self::generate_schema_from_parameters(
+                                &discovered.parameters,
+                            ))
                         } else {
                             None
                         },
@@ -205,27 +259,260 @@ impl ToolRegistry {
         }
 
         // Add external MCP tools (xmcp namespace)
-        if query.namespace.as_deref().map_or(true, |ns| ns == "xmcp") {
+        if query.namespace.as_deref().is_none_or(|ns| ns == "xmcp") {
             let mcp_tools = self.get_mcp_tools(&query).await?;
             tools.extend(mcp_tools);
         }
 
+        if query.latest_only {
+            let defaults = self.default_versions();
+            tools.retain(|tool| defaults.contains(&tool.path));
+        }
+
         // Apply search filter
         if let Some(search) = &query.search {
-            tools.retain(|tool| {
-                tool.path.to_mcp_name().contains(search) || tool.description.contains(search)
-            });
+            let matches: HashSet<String> = self
+                .search_index
+                .search(search)
+                .into_iter()
+                .map(|hit| hit.key)
+                .collect();
+            tools.retain(|tool| matches.contains(&tool.path.to_mcp_name()));
         }
 
-        // Apply limit
-        if let Some(limit) = query.limit {
-            tools.truncate(limit);
+        // Sort by MCP name, then apply cursor, offset and limit
+        let keyed: Vec<(String, UnifiedTool)> = tools
+            .into_iter()
+            .map(|tool| (tool.sort_key(), tool))
+            .collect();
+        let page = pagination::paginate(
+            keyed,
+            |(name, _)| name.as_str(),
+            query.cursor.as_deref(),
+            query.offset.unwrap_or(0),
+            query.limit.unwrap_or(usize::MAX),
+        )?;
+
+        Ok(Page {
+            items: page.items.into_iter().map(|(_, tool)| tool).collect(),
+            next_cursor: page.next_cursor,
+            total: page.total,
+        })
+    }
+
+    /// Search tools by relevance, best match first, with their scores
+    pub async fn search_tools(
+        &self,
+        text: &str,
+        limit: Option<usize>,
+    ) -> Result<Vec<(UnifiedTool, f64)>> {
+        let mut tools: HashMap<String, UnifiedTool> = self
+            .query_tools(ToolQuery::default())
+            .await?
+            .into_iter()
+            .map(|tool| (tool.path.to_mcp_name(), tool))
+            .collect();
+
+        Ok(self
+            .search_index
+            .search(text)
+            .into_iter()
+            .filter_map(|hit| tools.remove(&hit.key).map(|tool| (tool, hit.score)))
+            .take(limit.unwrap_or(usize::MAX))
+            .collect())
+    }
+
+    /// Resolve a tool path in any form `ToolPath::parse` accepts. A bare tool
+    /// name, optionally with a `:version` suffix, resolves when exactly one
+    /// known tool has that name. The version is resolved with
+    /// `resolve_version`.
+    pub fn resolve(&self, input: &str) -> Result<ToolPath> {
+        if !ToolPath::is_bare_name(input) {
+            return self.resolve_version(ToolPath::parse(input)?);
         }
 
-        // Sort by path
-        tools.sort_by(|a, b| a.path.to_string().cmp(&b.path.to_string()));
+        let input = input.trim();
+        let (name, version) = match input.split_once(':') {
+            Some((name, version)) => (name, version),
+            None => (input, LATEST),
+        };
+        let mut matches: Vec<ToolPath> = self
+            .search_index
+            .keys()
+            .filter_map(|key| ToolPath::parse(key).ok())
+            .filter(|path| path.name == name)
+            .map(|path| path.with_version(LATEST))
+            .collect();
+        matches.sort_by_cached_key(|path| path.to_mcp_name());
+        matches.dedup();
+
+        match matches.len() {
+            0 => Err(self.not_found(input)),
+            1 => self.resolve_version(matches.remove(0).with_version(version)),
+            _ => {
+                let names: Vec<String> = matches.iter().map(|path| path.to_mcp_name()).collect();
+                Err(anyhow!(
+                    "Tool name '{}' is ambiguous, use one of: {}",
+                    input,
+                    names.join(", ")
+                ))
+            }
+        }
+    }
 
-        Ok(tools)
+    /// Resolve the version of a path, which may be `latest`, an exact version
+    /// or a range, to one of the stored versions of the tool. Paths of tools
+    /// with no stored versions are returned unchanged.
+    pub fn resolve_version(&self, path: ToolPath) -> Result<ToolPath> {
+        let available = self.versions_of(&path);
+        if available.is_empty() {
+            return Ok(path);
+        }
+
+        let spec = VersionSpec::parse(&path.version)?;
+        let pinned = self.version_pin(&path);
+        match versioning::select(&spec, &available, pinned) {
+            Some(version) => Ok(path.with_version(version)),
+            None => {
+                let mut available = available;
+                available.sort_by(|a, b| versioning::compare(a, b));
+                Err(anyhow!(
+                    "No version of '{}' matches '{}' (available: {})",
+                    path.with_version(LATEST),
+                    path.version,
+                    available.join(", ")
+                ))
+            }
+        }
+    }
+
+    /// Check if this is the version `latest` resolves to for its tool
+    pub fn is_default_version(&self, path: &ToolPath) -> bool {
+        self.resolve_version(path.with_version(LATEST))
+            .is_ok_and(|default| default == *path)
+    }
+
+    /// The default version of every tool, computed in one pass over the
+    /// stored tools
+    pub fn default_versions(&self) -> DefaultVersions {
+        let mut versions: HashMap<String, Vec<&str>> = HashMap::new();
+        for path in self.tcl_tools.keys().chain(self.discovered_tools.keys()) {
+            versions
+                .entry(path.with_version(LATEST).to_mcp_name())
+                .or_default()
+                .push(path.version.as_str());
+        }
+
+        let defaults = versions
+            .into_iter()
+            .map(|(name, mut available)| {
+                available.sort_unstable();
+                available.dedup();
+                let pinned = self.version_pins.get(&name).map(String::as_str);
+                let default = versioning::select(&VersionSpec::Latest, &available, pinned);
+                (name, default.map(str::to_string))
+            })
+            .collect();
+        DefaultVersions(defaults)
+    }
+
+    /// Stored versions of the tool, whatever the version of `path`
+    fn versions_of(&self, path: &ToolPath) -> Vec<&str> {
+        let mut versions: Vec<&str> = self
+            .tcl_tools
+            .keys()
+            .chain(self.discovered_tools.keys())
+            .filter(|stored| stored.same_tool(path))
+            .map(|stored| stored.version.as_str())
+            .collect();
+        versions.sort_unstable();
+        versions.dedup();
+        versions
+    }
+
+    /// The pinned default version of the tool, if any
+    pub fn version_pin(&self, path: &ToolPath) -> Option<&str> {
+        self.version_pins
+            .get(&path.with_version(LATEST).to_mcp_name())
+            .map(String::as_str)
+    }
+
+    /// Pin or unpin the default version of a tool
+    pub fn set_version_pin(&mut self, path: &ToolPath, version: Option<String>) {
+        let key = path.with_version(LATEST).to_mcp_name();
+        match version {
+            Some(version) => self.version_pins.insert(key, version),
+            None => self.version_pins.remove(&key),
+        };
+    }
+
+    /// Replace all pinned default versions, as loaded from storage
+    pub fn set_version_pins(&mut self, pins: HashMap<String, String>) {
+        self.version_pins = pins;
+    }
+
+    /// Integrity status of a stored tool, including quarantined ones
+    pub fn integrity(&self, path: &ToolPath) -> Option<&Integrity> {
+        self.integrity.get(path)
+    }
+
+    /// Examples of a user or filesystem tool
+    pub fn examples(&self, path: &ToolPath) -> &[ToolExample] {
+        if let Some(tool) = self.tcl_tools.get(path) {
+            &tool.examples
+        } else if let Some(discovered) = self.discovered_tools.get(path) {
+            &discovered.examples
+        } else {
+            &[]
+        }
+    }
+
+    /// Replace the integrity status of all stored tools
+    pub fn set_integrity(&mut self, integrity: HashMap<ToolPath, Integrity>) {
+        self.integrity = integrity;
+    }
+
+    /// Error for a tool path that does not resolve, naming the closest
+    /// known tools
+    pub fn not_found(&self, input: &str) -> anyhow::Error {
+        let hits = self.search_index.search(input);
+        let best = hits.first().map_or(0.0, |hit| hit.score);
+        let suggestions: Vec<&str> = hits
+            .iter()
+            .take_while(|hit| hit.score >= best * SUGGESTION_CUTOFF)
+            .take(MAX_SUGGESTIONS)
+            .map(|hit| hit.key.as_str())
+            .collect();
+
+        if suggestions.is_empty() {
+            anyhow!("Tool '{}' not found", input)
+        } else {
+            anyhow!(
+                "Tool '{}' not found. Did you mean: {}?",
+                input,
+                suggestions.join(", ")
+            )
+        }
+    }
+
+    /// Bring the search index up to date with the tools of connected MCP
+    /// servers, indexing new or changed tools and dropping ones that are gone
+    pub async fn sync_mcp_tools(&mut self) -> Result<()> {
+        let mut live = HashSet::new();
+        for tool in self.get_mcp_tools(&ToolQuery::default()).await? {
+            let key = tool.path.to_mcp_name();
+            self.search_index.upsert(
+                &key,
+                Self::search_document(&tool.path, &tool.description, &tool.parameters, &[]),
+            );
+            live.insert(key);
+        }
+
+        for stale in self.indexed_mcp_tools.difference(&live) {
+            self.search_index.remove(stale);
+        }
+        self.indexed_mcp_tools = live;
+        Ok(())
     }
 
     /// Get MCP tools with xmcp namespace mapping
@@ -325,8 +612,7 @@ impl ToolRegistry {
     }
 
     /// Generate JSON schema from parameter definitions
-    fn generate_schema_from_parameters(
-        &self,
+    pub fn generate_schema_from_parameters(
         parameters: &[ParameterDefinition],
     ) -> serde_json::Value {
         let mut properties = serde_json::Map::new();
@@ -343,13 +629,14 @@ impl ToolRegistry {
                 _ => "string",
             };
 
-            properties.insert(
-                param.name.clone(),
-                serde_json::json!({
-                    "type": json_type,
-                    "description": param.description
-                }),
-            );
+            let mut property = serde_json::json!({
+                "type": json_type,
+                "description": param.description
+            });
+            if let Some(default) = &param.default {
+                property["default"] = default.clone();
+            }
+            properties.insert(param.name.clone(), property);
 
             if param.required {
                 required.push(param.name.clone());
@@ -400,6 +687,8 @@ impl ToolRegistry {
                     description,
                     required: required_fields.contains(name),
                     type_name,
+                    completion: None,
+                    default: prop.get("default").cloned(),
                 });
             }
         }
@@ -407,171 +696,69 @@ impl ToolRegistry {
         parameters
     }
 
-    /// Get description for system tools
-    fn get_system_tool_description(&self, path: &ToolPath) -> String {
-        match path.to_mcp_name().as_str() {
-            "bin__tcl_execute" => "Execute a TCL script and return the result".to_string(),
-            "bin__exec_tool" => "Execute a tool by its path with parameters".to_string(),
-            "bin__discover_tools" => "Discover and index tools from the filesystem".to_string(),
-            "bin__list_tools" => {
-                "List tools with flexible output formats and advanced filtering".to_string()
-            }
-            "bin__inspect_tool" => {
-                "Get detailed information about a specific tool including schema".to_string()
-            }
-            "bin__list_namespaces" => "List available namespaces and their tool counts".to_string(),
-            "bin__search_tools" => "Search tools by description or name".to_string(),
-            "bin__list_xmcp_tools" => {
-                "List tools in xmcp namespace with server grouping".to_string()
-            }
-            "sbin__tcl_tool_add" => {
-                "Add a new TCL tool to the available tools (PRIVILEGED)".to_string()
-            }
-            "sbin__tcl_tool_remove" => {
-                "Remove a TCL tool from the available tools (PRIVILEGED)".to_string()
-            }
-            "sbin__mcp_add" => "Add an MCP server configuration (PRIVILEGED)".to_string(),
-            "sbin__mcp_remove" => "Remove an MCP server configuration (PRIVILEGED)".to_string(),
-            "sbin__mcp_list" => "List all registered MCP servers (PRIVILEGED)".to_string(),
-            "sbin__mcp_connect" => "Manually connect to an MCP server (PRIVILEGED)".to_string(),
-            "sbin__mcp_info" => {
-                "Get detailed information about an MCP server (PRIVILEGED)".to_string()
-            }
-            "sbin__mcp_ping" => "Test connectivity to an MCP server (PRIVILEGED)".to_string(),
-            "docs__molt_book" => {
-                "Access Molt TCL interpreter documentation and examples".to_string()
-            }
-            _ => "System tool".to_string(),
-        }
-    }
-
-    /// Get parameters for system tools
-    fn get_system_tool_parameters(&self, path: &ToolPath) -> Vec<ParameterDefinition> {
-        match path.to_mcp_name().as_str() {
-            "bin__tcl_execute" => vec![ParameterDefinition {
-                name: "script".to_string(),
-                description: "TCL script to execute".to_string(),
-                required: true,
-                type_name: "string".to_string(),
-            }],
-            "bin__tcl_tool_list" => vec![
-                ParameterDefinition {
-                    name: "namespace".to_string(),
-                    description: "Filter tools by namespace (optional)".to_string(),
-                    required: false,
-                    type_name: "string".to_string(),
-                },
-                ParameterDefinition {
-                    name: "filter".to_string(),
-                    description: "Filter tools by name pattern (optional)".to_string(),
-                    required: false,
-                    type_name: "string".to_string(),
-                },
-            ],
-            "bin__exec_tool" => vec![
-                ParameterDefinition {
-                    name: "tool_path".to_string(),
-                    description: "Full path to the tool (e.g., 'bin__list_dir')".to_string(),
-                    required: true,
-                    type_name: "string".to_string(),
-                },
-                ParameterDefinition {
-                    name: "params".to_string(),
-                    description: "Parameters to pass to the tool".to_string(),
-                    required: false,
-                    type_name: "object".to_string(),
-                },
-            ],
-            "bin__list_tools" => vec![
-                ParameterDefinition {
-                    name: "namespace".to_string(),
-                    description: "Filter by namespace (bin, sbin, docs, user, xmcp)".to_string(),
-                    required: false,
-                    type_name: "string".to_string(),
-                },
-                ParameterDefinition {
-                    name: "server".to_string(),
-                    description: "Filter by server (for xmcp namespace)".to_string(),
-                    required: false,
-                    type_name: "string".to_string(),
-                },
-                ParameterDefinition {
-                    name: "search".to_string(),
-                    description: "Text search filter".to_string(),
-                    required: false,
-                    type_name: "string".to_string(),
-                },
-                ParameterDefinition {
-                    name: "include_schemas".to_string(),
-                    description: "Include detailed schemas".to_string(),
-                    required: false,
-                    type_name: "boolean".to_string(),
-                },
-                ParameterDefinition {
-                    name: "limit".to_string(),
-                    description: "Limit number of results".to_string(),
-                    required: false,
-                    type_name: "integer".to_string(),
-                },
-                ParameterDefinition {
-                    name: "format".to_string(),
-                    description:
-                        "Output format: 'simple' (paths only) or 'detailed' (rich metadata)"
-                            .to_string(),
-                    required: false,
-                    type_name: "string".to_string(),
-                },
-            ],
-            "bin__inspect_tool" => vec![ParameterDefinition {
-                name: "tool_path".to_string(),
-                description: "Tool path to inspect (e.g., 'bin__tcl_execute')".to_string(),
-                required: true,
-                type_name: "string".to_string(),
-            }],
-            "bin__list_namespaces" => vec![],
-            "bin__search_tools" => vec![
-                ParameterDefinition {
-                    name: "query".to_string(),
-                    description: "Search query string".to_string(),
-                    required: true,
-                    type_name: "string".to_string(),
-                },
-                ParameterDefinition {
-                    name: "limit".to_string(),
-                    description: "Limit number of results".to_string(),
-                    required: false,
-                    type_name: "integer".to_string(),
-                },
-            ],
-            "bin__list_xmcp_tools" => vec![ParameterDefinition {
-                name: "server".to_string(),
-                description: "Filter by server ID".to_string(),
-                required: false,
-                type_name: "string".to_string(),
-            }],
-            _ => Vec::new(),
+    /// Registry entry for a built-in tool
+    fn system_tool(tool: &SystemTool) -> UnifiedTool {
+        UnifiedTool {
+            path: tool.path(),
+            description: tool.description.to_string(),
+            parameters: tool.parameters(),
+            source: ToolSource::System,
+            schema: Some(tool.input_schema()),
         }
     }
 
-    /// Get JSON schema for system tools
-    fn get_system_tool_schema(&self, path: &ToolPath) -> serde_json::Value {
-        let parameters = self.get_system_tool_parameters(path);
-        self.generate_schema_from_parameters(&parameters)
+    /// Searchable text of a tool
+    fn search_document(
+        path: &ToolPath,
+        description: &str,
+        parameters: &[ParameterDefinition],
+        tags: &[String],
+    ) -> SearchDocument {
+        SearchDocument {
+            name: path.to_mcp_name(),
+            description: description.to_string(),
+            parameters: parameters.iter().map(|param| param.name.clone()).collect(),
+            tags: tags.to_vec(),
+        }
+    }
+
+    fn index_discovered_tool(&mut self, tool: &DiscoveredTool) {
+        self.search_index.upsert(
+            &tool.path.to_mcp_name(),
+            Self::search_document(&tool.path, &tool.description, &tool.parameters, &tool.tags),
+        );
     }
 
     /// Add a TCL tool to the registry
     pub fn add_tcl_tool(&mut self, tool: ToolDefinition) {
+        self.search_index.upsert(
+            &tool.path.to_mcp_name(),
+            Self::search_document(&tool.path, &tool.description, &tool.parameters, &[]),
+        );
         self.tcl_tools.insert(tool.path.clone(), tool);
     }
 
     /// Remove a TCL tool from the registry
     pub fn remove_tcl_tool(&mut self, path: &ToolPath) -> bool {
-        self.tcl_tools.remove(path).is_some()
+        if self.tcl_tools.remove(path).is_none() {
+            return false;
+        }
+
+        // A discovered tool at the same path is visible again
+        self.search_index.remove(&path.to_mcp_name());
+        if let Some(discovered) = self.discovered_tools.get(path).cloned() {
+            self.index_discovered_tool(&discovered);
+        }
+        true
     }
 
     /// Add discovered tools to the registry
     pub fn add_discovered_tools(&mut self, tools: Vec<DiscoveredTool>) {
         for tool in tools {
+            // TCL tools take precedence over discovered tools at the same path
+            if !self.tcl_tools.contains_key(&tool.path) {
+                self.index_discovered_tool(&tool);
+            }
             self.discovered_tools.insert(tool.path.clone(), tool);
         }
     }
@@ -579,14 +766,8 @@ impl ToolRegistry {
     /// Get a specific tool by path
     pub async fn get_tool(&self, path: &ToolPath) -> Option<UnifiedTool> {
         // Check system tools
-        if self.system_tools.contains(path) {
-            return Some(UnifiedTool {
-                path: path.clone(),
-                description: self.get_system_tool_description(path),
-                parameters: self.get_system_tool_parameters(path),
-                source: ToolSource::System,
-                schema: Some(self.get_system_tool_schema(path)),
-            });
+        if let Some(tool) = system_tools::find_path(path) {
+            return Some(Self::system_tool(tool));
         }
 
         // Check TCL tools
@@ -598,7 +779,7 @@ impl ToolRegistry {
                 source: ToolSource::UserTcl {
                     script: tool_def.script.clone(),
                 },
-                schema: Some(self.generate_schema_from_parameters(&tool_def.parameters)),
+                schema: Some(Self::generate_schema_from_parameters(&tool_def.parameters)),
             });
         }
 
@@ -611,7 +792,9 @@ impl ToolRegistry {
                 source: ToolSource::Filesystem {
                     file_path: discovered.file_path.clone(),
                 },
-                schema: Some(self.generate_schema_from_parameters(&discovered.parameters)),
+                schema: Some(Self::generate_schema_from_parameters(
+                    &discovered.parameters,
+                )),
             });
         }
 
@@ -662,6 +845,46 @@ mod tests {
         assert!(path.matches_filter("context7"));
     }
 
+    fn registry_with_versions(versions: &[&str]) -> ToolRegistry {
+        let mut registry = ToolRegistry::new(McpClient::new());
+        for version in versions {
+            registry.add_tcl_tool(ToolDefinition {
+                path: ToolPath::user("alice", "utils", "reverse", *version),
+                description: "Reverse text".to_string(),
+                script: "string reverse $text".to_string(),
+                parameters: vec![],
+                examples: vec![],
+            });
+        }
+        registry
+    }
+
+    #[test]
+    fn test_resolve_versions() {
+        let mut registry = registry_with_versions(&["1.0", "1.2.5", "2.0.0"]);
+        let version =
+            |registry: &ToolRegistry, input: &str| registry.resolve(input).unwrap().version;
+
+        assert_eq!(version(&registry, "user__alice__utils__reverse"), "2.0.0");
+        assert_eq!(version(&registry, "/alice/utils/reverse:^1"), "1.2.5");
+        assert_eq!(version(&registry, "reverse:1.0.0"), "1.0");
+        assert_eq!(version(&registry, "bin__tcl_execute"), "latest");
+        let err = registry.resolve("/alice/utils/reverse:^3").unwrap_err();
+        assert!(err.to_string().contains("available: 1.0, 1.2.5, 2.0.0"));
+
+        let tool = ToolPath::user("alice", "utils", "reverse", "latest");
+        registry.set_version_pin(&tool, Some("1.0".to_string()));
+        assert_eq!(version(&registry, "reverse"), "1.0");
+        assert!(registry.is_default_version(&tool.with_version("1.0")));
+        assert!(!registry.is_default_version(&tool.with_version("2.0.0")));
+        assert!(registry.is_default_version(&ToolPath::bin("tcl_execute")));
+
+        let defaults = registry.default_versions();
+        assert!(defaults.contains(&tool.with_version("1.0")));
+        assert!(!defaults.contains(&tool.with_version("2.0.0")));
+        assert!(defaults.contains(&ToolPath::bin("tcl_execute")));
+    }
+
     #[test]
     fn test_tool_query_defaults() {
         let query = ToolQuery::default();
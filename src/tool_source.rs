@@ -0,0 +1,236 @@
+/// Stored tools as editable `.tcl` source
+///
+/// The CLI shows and edits stored tools in the same format as filesystem
+/// tools: header comments for the description, version and parameters
+/// (see `tool_discovery::parse_header`), a blank line, then the script.
+/// Headers are single lines, so line breaks in descriptions, completion
+/// providers and example outputs are flattened when a tool is rendered.
+use anyhow::{anyhow, Result};
+use serde_json::Value;
+
+use crate::namespace::{Namespace, ToolPath};
+use crate::tcl_tools::ToolDefinition;
+use crate::tool_discovery::{parse_header, ToolHeader};
+use crate::versioning::LATEST;
+
+/// Render a tool as header comments followed by its script
+pub fn render(tool: &ToolDefinition) -> String {
+    let mut source = format!("# @description {}\n", single_line(&tool.description, " "));
+    if tool.path.version != LATEST {
+        source.push_str(&format!("# @version {}\n", tool.path.version));
+    }
+    for param in &tool.parameters {
+        let required = if param.required {
+            "required"
+        } else {
+            "optional"
+        };
+        let line = format!(
+            "# @param {}:{}:{} {}",
+            param.name,
+            param.type_name,
+            required,
+            single_line(&param.description, " ")
+        );
+        source.push_str(line.trim_end());
+        source.push('\n');
+    }
+    for param in &tool.parameters {
+        if let Some(completion) = &param.completion {
+            source.push_str(&format!(
+                "# @complete {} {}\n",
+                param.name,
+                single_line(completion, "; ")
+            ));
+        }
+    }
+    for param in &tool.parameters {
+        // Strings are written bare unless they would read back as JSON
+        let default = match &param.default {
+            Some(Value::String(value))
+                if !value.is_empty() && serde_json::from_str::<Value>(value).is_err() =>
+            {
+                value.clone()
+            }
+            Some(value) => value.to_string(),
+            None => continue,
+        };
+        source.push_str(&format!(
+            "# @default {} {}\n",
+            param.name,
+            single_line(&default, " ")
+        ));
+    }
+    for example in &tool.examples {
+        let tag = if example.test_only { "test" } else { "example" };
+        source.push_str(&format!(
+            "# @{} {}\n",
+            tag,
+            single_line(&example.to_header(), " ")
+        ));
+    }
+    source.push('\n');
+    source.push_str(tool.script.trim_end());
+    source.push('\n');
+    source
+}
+
+/// Split tool source into its header and script. The script starts at the
+/// first line that is not a comment, so a script may open with comments of
+/// its own as long as a blank line separates them from the header.
+pub fn parse(source: &str) -> (ToolHeader, String) {
+    let header = parse_header(source);
+    let script_start = source
+        .split_inclusive('\n')
+        .take_while(|line| line.trim_start().starts_with('#'))
+        .map(str::len)
+        .sum::<usize>();
+    let script = source[script_start..]
+        .trim_start_matches(['\n', '\r'])
+        .trim_end()
+        .to_string();
+    (header, script)
+}
+
+/// Build a tool from parsed source, checking it can be stored at `path`
+pub fn definition(path: ToolPath, header: ToolHeader, script: String) -> Result<ToolDefinition> {
+    if header.description.is_empty() {
+        return Err(anyhow!("Tool source has no '# @description' header"));
+    }
+    if script.is_empty() {
+        return Err(anyhow!("Tool source has no script"));
+    }
+    let tool = ToolDefinition {
+        path,
+        description: header.description,
+        script,
+        parameters: header.parameters,
+        examples: header.examples,
+    };
+    validate_path(&tool.path)?;
+    Ok(tool)
+}
+
+/// Tools can only be stored in a user namespace, at a semantic version
+pub fn validate_path(path: &ToolPath) -> Result<()> {
+    if !matches!(path.namespace, Namespace::User(_)) {
+        return Err(anyhow!(
+            "Can only store tools in a user namespace, not {}",
+            path
+        ));
+    }
+    if path.version != LATEST && path.semver().is_none() {
+        return Err(anyhow!(
+            "Invalid version '{}': expected a semantic version such as 1.0 or 1.2.3",
+            path.version
+        ));
+    }
+    Ok(())
+}
+
+fn single_line(text: &str, separator: &str) -> String {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join(separator)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tcl_tools::{ParameterDefinition, ToolExample};
+    use serde_json::json;
+
+    fn sample_tool() -> ToolDefinition {
+        ToolDefinition {
+            path: ToolPath::user("alice", "utils", "greet", "1.2.0"),
+            description: "Greet someone".to_string(),
+            script: "# say hello\nreturn \"Hello, $name\"".to_string(),
+            parameters: vec![
+                ParameterDefinition {
+                    name: "name".to_string(),
+                    description: "Who to greet".to_string(),
+                    required: true,
+                    type_name: "string".to_string(),
+                    completion: Some("list alice\nbob".to_string()),
+                    default: None,
+                },
+                ParameterDefinition {
+                    name: "loud".to_string(),
+                    description: String::new(),
+                    required: false,
+                    type_name: "boolean".to_string(),
+                    completion: None,
+                    default: Some(json!(false)),
+                },
+            ],
+            examples: vec![ToolExample {
+                args: json!({ "name": "Ada" }).as_object().cloned().unwrap(),
+                expected: Some("Hello, Ada".to_string()),
+                pattern: None,
+                test_only: false,
+            }],
+        }
+    }
+
+    #[test]
+    fn test_render_and_parse_round_trip() -> Result<()> {
+        let tool = sample_tool();
+        let source = render(&tool);
+        assert!(source.starts_with("# @description Greet someone\n# @version 1.2.0\n"));
+        assert!(source.contains("# @param loud:boolean:optional\n"));
+        assert!(source.contains("# @complete name list alice; bob\n"));
+        assert!(source.contains("# @default loud false\n"));
+        assert!(source.contains("# @example {\"name\":\"Ada\"} => Hello, Ada\n"));
+
+        let (header, script) = parse(&source);
+        assert_eq!(header.version.as_deref(), Some("1.2.0"));
+        assert_eq!(script, tool.script);
+
+        let parsed = definition(tool.path.clone(), header, script)?;
+        assert_eq!(parsed.parameters.len(), 2);
+        assert_eq!(parsed.examples, tool.examples);
+        assert!(parsed.parameters[0].required);
+        assert!(!parsed.parameters[1].required);
+        assert_eq!(parsed.parameters[1].default, Some(json!(false)));
+        assert_eq!(
+            parsed.parameters[0].completion.as_deref(),
+            Some("list alice; bob")
+        );
+
+        // Rendering is stable once line breaks are flattened
+        assert_eq!(render(&parsed), source);
+        Ok(())
+    }
+
+    #[test]
+    fn test_definition_checks_source_and_path() {
+        let (header, script) = parse("return 1\n");
+        assert!(definition(
+            ToolPath::user("alice", "utils", "one", LATEST),
+            header,
+            script
+        )
+        .is_err());
+
+        let (header, script) = parse("# @description One\n\nreturn 1\n");
+        assert!(definition(ToolPath::bin("one"), header, script).is_err());
+
+        let (header, script) = parse("# @description One\n\nreturn 1\n");
+        assert!(definition(
+            ToolPath::user("alice", "utils", "one", "one"),
+            header,
+            script
+        )
+        .is_err());
+
+        let (header, script) = parse("# @description One\n\nreturn 1\n");
+        assert!(definition(
+            ToolPath::user("alice", "utils", "one", "1.0"),
+            header,
+            script
+        )
+        .is_ok());
+    }
+}
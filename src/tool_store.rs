@@ -0,0 +1,207 @@
+/// Storage backends for persisted tools
+///
+/// `FilePersistence` keeps one JSON file per tool with `index.json` beside
+/// them. `SqliteStore` keeps tools, their history and MCP server
+/// configurations in one SQLite database. Both implement `ToolStore`, and
+/// the backend is chosen at startup with `--storage` or `TCL_MCP_STORAGE`.
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::namespace::ToolPath;
+use crate::persistence::{FilePersistence, Integrity, PersistedTool};
+use crate::signing::ToolSignature;
+use crate::sqlite_store::SqliteStore;
+use crate::tcl_tools::ToolDefinition;
+use crate::tool_history::ToolRevision;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum StorageBackend {
+    #[default]
+    Json,
+    Sqlite,
+}
+
+impl FromStr for StorageBackend {
+    type Err = anyhow::Error;
+
+    fn from_str(text: &str) -> Result<Self> {
+        match text.to_lowercase().as_str() {
+            "json" => Ok(Self::Json),
+            "sqlite" => Ok(Self::Sqlite),
+            _ => Err(anyhow!(
+                "Unknown storage backend '{}' (expected json or sqlite)",
+                text
+            )),
+        }
+    }
+}
+
+impl fmt::Display for StorageBackend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Json => "json",
+            Self::Sqlite => "sqlite",
+        })
+    }
+}
+
+impl StorageBackend {
+    /// Backend from the CLI argument, else the environment variable, else
+    /// the JSON store
+    pub fn from_args_and_env(cli_storage: Option<&str>, env_storage: Option<&str>) -> Result<Self> {
+        match cli_storage.or(env_storage) {
+            Some(storage) => storage.parse(),
+            None => Ok(Self::default()),
+        }
+    }
+}
+
+/// Persistent storage of user tools, their revisions and version pins
+#[async_trait]
+pub trait ToolStore: Send + Sync {
+    /// Save a tool, recording the new revision in its history
+    async fn save_tool(&mut self, tool: &ToolDefinition) -> Result<()>;
+
+    /// Load a tool with its metadata, verifying its checksum
+    async fn load_persisted(&self, path: &ToolPath) -> Result<Option<PersistedTool>>;
+
+    /// All stored tools, optionally in one namespace. Tools that fail
+    /// verification are quarantined and left out.
+    async fn list_tools(&mut self, namespace_filter: Option<&str>) -> Result<Vec<PersistedTool>>;
+
+    /// Stored tools matching every word of a query, best matches first
+    async fn search_tools(&mut self, query: &str, limit: usize) -> Result<Vec<PersistedTool>>;
+
+    /// The stored tool a path names: the exact path if it is stored, or
+    /// else the stored version its version resolves to
+    fn stored_path(&self, path: &ToolPath) -> Result<Option<ToolPath>>;
+
+    /// Delete a tool, recording the deletion in its history
+    async fn delete_tool(&mut self, path: &ToolPath) -> Result<bool>;
+
+    /// Every recorded revision of a tool, oldest first
+    async fn history(&self, path: &ToolPath) -> Result<Vec<ToolRevision>>;
+
+    /// Restore the definition saved in an earlier revision as a new revision
+    async fn rollback(&mut self, path: &ToolPath, revision: u32) -> Result<ToolRevision>;
+
+    /// Pinned default versions, keyed by the unversioned MCP name
+    fn version_pins(&self) -> Result<HashMap<String, String>>;
+
+    /// Pin or unpin the default version of a tool
+    async fn set_version_pin(&mut self, path: &ToolPath, version: Option<String>) -> Result<()>;
+
+    /// Integrity of every stored and quarantined tool
+    fn integrity(&self) -> Result<HashMap<ToolPath, Integrity>>;
+
+    /// Sign a stored tool's checksum
+    async fn sign_tool(
+        &mut self,
+        path: &ToolPath,
+        key: &ed25519_dalek::SigningKey,
+    ) -> Result<ToolSignature>;
+}
+
+/// Open the tool store of a backend in the data directory
+pub async fn open(backend: StorageBackend) -> Result<Box<dyn ToolStore>> {
+    let store: Box<dyn ToolStore> = match backend {
+        StorageBackend::Json => Box::new(FilePersistence::new().await?),
+        StorageBackend::Sqlite => Box::new(SqliteStore::new().await?),
+    };
+    Ok(store)
+}
+
+#[async_trait]
+impl ToolStore for FilePersistence {
+    async fn save_tool(&mut self, tool: &ToolDefinition) -> Result<()> {
+        FilePersistence::save_tool(self, tool).await
+    }
+
+    async fn load_persisted(&self, path: &ToolPath) -> Result<Option<PersistedTool>> {
+        FilePersistence::load_persisted(self, path).await
+    }
+
+    async fn list_tools(&mut self, namespace_filter: Option<&str>) -> Result<Vec<PersistedTool>> {
+        FilePersistence::list_tools(self, namespace_filter).await
+    }
+
+    /// Without an index this scans every tool's name and description
+    async fn search_tools(&mut self, query: &str, limit: usize) -> Result<Vec<PersistedTool>> {
+        let words: Vec<String> = query.split_whitespace().map(str::to_lowercase).collect();
+        let mut tools: Vec<PersistedTool> = FilePersistence::list_tools(self, None)
+            .await?
+            .into_iter()
+            .filter(|persisted| {
+                let text = format!(
+                    "{} {}",
+                    persisted.tool.path.name, persisted.tool.description
+                )
+                .to_lowercase();
+                words.iter().all(|word| text.contains(word.as_str()))
+            })
+            .collect();
+        tools.sort_by_key(|persisted| persisted.tool.path.to_mcp_name());
+        tools.truncate(limit);
+        Ok(tools)
+    }
+
+    fn stored_path(&self, path: &ToolPath) -> Result<Option<ToolPath>> {
+        Ok(FilePersistence::stored_path(self, path))
+    }
+
+    async fn delete_tool(&mut self, path: &ToolPath) -> Result<bool> {
+        FilePersistence::delete_tool(self, path).await
+    }
+
+    async fn history(&self, path: &ToolPath) -> Result<Vec<ToolRevision>> {
+        FilePersistence::history(self, path).await
+    }
+
+    async fn rollback(&mut self, path: &ToolPath, revision: u32) -> Result<ToolRevision> {
+        FilePersistence::rollback(self, path, revision).await
+    }
+
+    fn version_pins(&self) -> Result<HashMap<String, String>> {
+        Ok(FilePersistence::version_pins(self).clone())
+    }
+
+    async fn set_version_pin(&mut self, path: &ToolPath, version: Option<String>) -> Result<()> {
+        FilePersistence::set_version_pin(self, path, version).await
+    }
+
+    fn integrity(&self) -> Result<HashMap<ToolPath, Integrity>> {
+        Ok(FilePersistence::integrity(self))
+    }
+
+    async fn sign_tool(
+        &mut self,
+        path: &ToolPath,
+        key: &ed25519_dalek::SigningKey,
+    ) -> Result<ToolSignature> {
+        FilePersistence::sign_tool(self, path, key).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backend_selection() -> Result<()> {
+        assert_eq!(StorageBackend::from_args_and_env(None, None)?, StorageBackend::Json);
+        assert_eq!(
+            StorageBackend::from_args_and_env(None, Some("sqlite"))?,
+            StorageBackend::Sqlite
+        );
+        // The CLI argument overrides the environment
+        assert_eq!(
+            StorageBackend::from_args_and_env(Some("json"), Some("sqlite"))?,
+            StorageBackend::Json
+        );
+        assert!(StorageBackend::from_args_and_env(Some("postgres"), None).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,229 @@
+/// Running tool examples as tests
+///
+/// Every example of a user or filesystem tool is a test case: the tool is
+/// called with the example's arguments through an in-process server, so it
+/// runs on the selected runtime exactly as `tools/call` would run it, and
+/// its output is checked against the expected output or pattern. Results
+/// are reported as JUnit XML for CI systems.
+use anyhow::Result;
+use serde_json::Value;
+use std::time::{Duration, Instant};
+
+use crate::exposure;
+use crate::server::TclMcpServer;
+use crate::tcl_tools::ToolExample;
+
+/// The result of one example
+#[derive(Debug, Clone)]
+pub struct TestCase {
+    /// MCP name of the tool
+    pub tool: String,
+    pub name: String,
+    pub time: Duration,
+    /// Why the case failed; `None` if it passed
+    pub failure: Option<String>,
+}
+
+/// Run the examples of every tool whose MCP name matches a glob
+pub async fn run(server: &TclMcpServer, pattern: Option<&str>) -> Result<Vec<TestCase>> {
+    let mut cases = Vec::new();
+    for tool in server.list_all_tools().await? {
+        let Some(name) = tool["name"].as_str() else {
+            continue;
+        };
+        if pattern.is_some_and(|pattern| !exposure::glob_matches(pattern, name)) {
+            continue;
+        }
+        // Only user and filesystem tools have a definition with examples
+        let Ok(definition) = server.tool_definition(name).await else {
+            continue;
+        };
+
+        for (index, example) in definition.examples.iter().enumerate() {
+            let started = Instant::now();
+            let response = server
+                .call_tool_path(&definition.path, Value::Object(example.args.clone()))
+                .await;
+            cases.push(TestCase {
+                tool: name.to_string(),
+                name: case_name(example, index),
+                time: started.elapsed(),
+                failure: check(example, response),
+            });
+        }
+    }
+    Ok(cases)
+}
+
+fn case_name(example: &ToolExample, index: usize) -> String {
+    let kind = if example.test_only { "test" } else { "example" };
+    format!(
+        "{} {}: {}",
+        kind,
+        index + 1,
+        Value::Object(example.args.clone())
+    )
+}
+
+/// The failure of a call, if it did not give the expected output
+fn check(example: &ToolExample, response: Result<Value>) -> Option<String> {
+    let response = match response {
+        Ok(response) => response,
+        Err(e) => return Some(format!("call failed: {}", e)),
+    };
+    if let Some(error) = response.get("error") {
+        return Some(format!(
+            "call failed: {}",
+            error["message"].as_str().unwrap_or("unknown error")
+        ));
+    }
+
+    let output = response["result"]["content"]
+        .as_array()
+        .map(|content| {
+            content
+                .iter()
+                .filter_map(|item| item["text"].as_str())
+                .collect::<Vec<_>>()
+                .join("\n")
+        })
+        .unwrap_or_default();
+    if example.matches(&output) {
+        return None;
+    }
+    Some(match (&example.pattern, &example.expected) {
+        (Some(pattern), _) => format!("output {:?} does not match {:?}", output, pattern),
+        (None, expected) => format!(
+            "expected {:?}, got {:?}",
+            expected.as_deref().unwrap_or_default(),
+            output
+        ),
+    })
+}
+
+/// Report test cases as JUnit XML, one test suite per tool
+pub fn junit_xml(cases: &[TestCase]) -> String {
+    let failures = cases.iter().filter(|case| case.failure.is_some()).count();
+    let total_time: Duration = cases.iter().map(|case| case.time).sum();
+    let mut xml = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<testsuites name=\"tcl-mcp-server\" tests=\"{}\" failures=\"{}\" time=\"{:.3}\">\n",
+        cases.len(),
+        failures,
+        total_time.as_secs_f64()
+    );
+
+    let mut start = 0;
+    while start < cases.len() {
+        let tool = &cases[start].tool;
+        let end = start
+            + cases[start..]
+                .iter()
+                .take_while(|case| &case.tool == tool)
+                .count();
+        let suite = &cases[start..end];
+        xml.push_str(&format!(
+            "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" time=\"{:.3}\">\n",
+            escape(tool),
+            suite.len(),
+            suite.iter().filter(|case| case.failure.is_some()).count(),
+            suite
+                .iter()
+                .map(|case| case.time)
+                .sum::<Duration>()
+                .as_secs_f64()
+        ));
+        for case in suite {
+            let attributes = format!(
+                "name=\"{}\" classname=\"{}\" time=\"{:.3}\"",
+                escape(&case.name),
+                escape(tool),
+                case.time.as_secs_f64()
+            );
+            match &case.failure {
+                None => xml.push_str(&format!("    <testcase {}/>\n", attributes)),
+                Some(failure) => xml.push_str(&format!(
+                    "    <testcase {}>\n      <failure message=\"{}\"/>\n    </testcase>\n",
+                    attributes,
+                    escape(failure)
+                )),
+            }
+        }
+        xml.push_str("  </testsuite>\n");
+        start = end;
+    }
+
+    xml.push_str("</testsuites>\n");
+    xml
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            '\n' => escaped.push_str("&#10;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn example(expected: &str) -> ToolExample {
+        ToolExample::parse(&format!("text abc => {}", expected), false).unwrap()
+    }
+
+    fn output(text: &str) -> Result<Value> {
+        Ok(json!({ "result": { "content": [{ "type": "text", "text": text }] } }))
+    }
+
+    #[test]
+    fn test_check_output() {
+        assert_eq!(check(&example("cba"), output("cba\n")), None);
+        assert_eq!(
+            check(&example("cba"), output("abc")),
+            Some("expected \"cba\", got \"abc\"".to_string())
+        );
+        assert_eq!(
+            check(
+                &example("cba"),
+                Ok(json!({ "error": { "code": -32603, "message": "invalid command name" } }))
+            ),
+            Some("call failed: invalid command name".to_string())
+        );
+    }
+
+    #[test]
+    fn test_junit_xml() {
+        let case = |tool: &str, failure: Option<&str>| TestCase {
+            tool: tool.to_string(),
+            name: "example 1: {\"text\":\"abc\"}".to_string(),
+            time: Duration::from_millis(2),
+            failure: failure.map(String::from),
+        };
+        let xml = junit_xml(&[
+            case("user__alice__utils__reverse", None),
+            case(
+                "user__alice__utils__reverse",
+                Some("expected \"cba\", got <abc>"),
+            ),
+            case("bin__hello", None),
+        ]);
+
+        assert!(xml.contains("<testsuites name=\"tcl-mcp-server\" tests=\"3\" failures=\"1\""));
+        assert!(xml.contains(
+            "<testsuite name=\"user__alice__utils__reverse\" tests=\"2\" failures=\"1\""
+        ));
+        assert!(xml.contains("<testsuite name=\"bin__hello\" tests=\"1\" failures=\"0\""));
+        assert!(xml.contains("name=\"example 1: {&quot;text&quot;:&quot;abc&quot;}\""));
+        assert!(xml.contains("<failure message=\"expected &quot;cba&quot;, got &lt;abc&gt;\"/>"));
+    }
+}
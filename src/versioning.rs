@@ -0,0 +1,187 @@
+/// Semantic versions for tools
+///
+/// Tools keep their version as written when added (`1.0`, `2.1.3`), but
+/// versions are compared as semver, reading missing minor and patch numbers
+/// as zero, so `1.0` and `1.0.0` are the same version. Callers address a
+/// version with a [`VersionSpec`]:
+///
+/// - `latest`: the tool's pinned default version, or else its highest stable
+///   version
+/// - an exact version such as `1.2` or `1.2.0-beta.1`
+/// - a range such as `^1.2`, `~1.2.3` or `>=1.0, <2.0`, resolving to the
+///   highest matching version
+///
+/// A tool stored without a version (`latest`) is only chosen when the tool
+/// has no versioned releases.
+use anyhow::{anyhow, Result};
+use semver::{Version, VersionReq};
+use std::cmp::Ordering;
+
+/// Version of tools added without one, and the spec for the default version
+pub const LATEST: &str = "latest";
+
+/// Parse a tool version, accepting `1` and `1.2` as `1.0.0` and `1.2.0`
+pub fn parse_version(text: &str) -> Option<Version> {
+    let text = text.trim();
+    let core_len = text.find(['-', '+']).unwrap_or(text.len());
+    let (core, suffix) = text.split_at(core_len);
+    let parts = core.split('.').count();
+    if core.is_empty() || parts > 3 {
+        return None;
+    }
+    Version::parse(&format!("{}{}{}", core, ".0".repeat(3 - parts), suffix)).ok()
+}
+
+/// A version as requested by a caller
+#[derive(Debug, Clone, PartialEq)]
+pub enum VersionSpec {
+    Latest,
+    Exact(String),
+    Range(VersionReq),
+}
+
+impl VersionSpec {
+    pub fn parse(text: &str) -> Result<Self> {
+        let text = text.trim();
+        if text.is_empty() || text == LATEST {
+            return Ok(Self::Latest);
+        }
+        if parse_version(text).is_some() {
+            return Ok(Self::Exact(text.to_string()));
+        }
+        if text.starts_with(['^', '~', '<', '>', '=', '*']) || text.contains([',', '*']) {
+            return VersionReq::parse(text)
+                .map(Self::Range)
+                .map_err(|e| anyhow!("Invalid version range '{}': {}", text, e));
+        }
+        // Versions stored before semver was enforced are matched as text
+        Ok(Self::Exact(text.to_string()))
+    }
+
+    pub fn matches(&self, version: &str) -> bool {
+        match self {
+            Self::Latest => true,
+            Self::Exact(exact) => {
+                exact == version
+                    || matches!(
+                        (parse_version(exact), parse_version(version)),
+                        (Some(a), Some(b)) if a == b
+                    )
+            }
+            Self::Range(req) => parse_version(version).is_some_and(|v| req.matches(&v)),
+        }
+    }
+}
+
+/// The version a spec resolves to among the available versions of one tool
+pub fn select<'a>(
+    spec: &VersionSpec,
+    available: &[&'a str],
+    pinned: Option<&str>,
+) -> Option<&'a str> {
+    match spec {
+        VersionSpec::Latest => {
+            let pinned = pinned.map(|pinned| VersionSpec::Exact(pinned.to_string()));
+            if let Some(version) = pinned.and_then(|pin| available.iter().find(|v| pin.matches(v)))
+            {
+                return Some(version);
+            }
+            // Prefer stable releases over pre-releases
+            let stable: Vec<&str> = available
+                .iter()
+                .copied()
+                .filter(|v| parse_version(v).is_some_and(|v| v.pre.is_empty()))
+                .collect();
+            let candidates = if stable.is_empty() {
+                available
+            } else {
+                &stable
+            };
+            candidates.iter().copied().max_by(|a, b| compare(a, b))
+        }
+        VersionSpec::Exact(exact) => available
+            .iter()
+            .copied()
+            .find(|v| v == exact)
+            .or_else(|| available.iter().copied().find(|v| spec.matches(v))),
+        VersionSpec::Range(_) => available
+            .iter()
+            .copied()
+            .filter(|v| spec.matches(v))
+            .max_by(|a, b| compare(a, b)),
+    }
+}
+
+/// Order versions by semver precedence. Versions that are not semver sort
+/// below all that are, and by text among themselves.
+pub fn compare(a: &str, b: &str) -> Ordering {
+    match (parse_version(a), parse_version(b)) {
+        (Some(x), Some(y)) => x.cmp(&y).then_with(|| a.cmp(b)),
+        (Some(_), None) => Ordering::Greater,
+        (None, Some(_)) => Ordering::Less,
+        (None, None) => a.cmp(b),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_version() {
+        assert_eq!(parse_version("1").unwrap(), Version::new(1, 0, 0));
+        assert_eq!(parse_version("1.2").unwrap(), Version::new(1, 2, 0));
+        assert_eq!(parse_version("1.2.3").unwrap(), Version::new(1, 2, 3));
+        assert_eq!(
+            parse_version("2.0-beta.1").unwrap().to_string(),
+            "2.0.0-beta.1"
+        );
+        assert!(parse_version("latest").is_none());
+        assert!(parse_version("1.2.3.4").is_none());
+        assert!(parse_version("^1.2").is_none());
+    }
+
+    #[test]
+    fn test_version_specs() {
+        assert_eq!(VersionSpec::parse("latest").unwrap(), VersionSpec::Latest);
+        assert_eq!(
+            VersionSpec::parse("1.0").unwrap(),
+            VersionSpec::Exact("1.0".into())
+        );
+        assert!(matches!(
+            VersionSpec::parse("^1.2").unwrap(),
+            VersionSpec::Range(_)
+        ));
+        assert!(matches!(
+            VersionSpec::parse(">=1.0, <2.0").unwrap(),
+            VersionSpec::Range(_)
+        ));
+        assert!(VersionSpec::parse("^x").is_err());
+
+        assert!(VersionSpec::parse("1.0").unwrap().matches("1.0.0"));
+        assert!(VersionSpec::parse("^1.2").unwrap().matches("1.9"));
+        assert!(!VersionSpec::parse("^1.2").unwrap().matches("2.0"));
+        assert!(!VersionSpec::parse("^1.2").unwrap().matches("latest"));
+    }
+
+    #[test]
+    fn test_select() {
+        let available = ["1.0", "1.2.5", "1.10.0", "2.0.0-rc.1", "latest"];
+        let select_spec =
+            |spec: &str, pinned| select(&VersionSpec::parse(spec).unwrap(), &available, pinned);
+
+        assert_eq!(select_spec("latest", None), Some("1.10.0"));
+        assert_eq!(select_spec("latest", Some("1.2.5")), Some("1.2.5"));
+        assert_eq!(select_spec("latest", Some("9.9")), Some("1.10.0"));
+        assert_eq!(select_spec("^1.2", None), Some("1.10.0"));
+        assert_eq!(select_spec("~1.2", None), Some("1.2.5"));
+        assert_eq!(select_spec("1.0.0", None), Some("1.0"));
+        assert_eq!(select_spec(">=2.0.0-rc.0", None), Some("2.0.0-rc.1"));
+        assert_eq!(select_spec("^3", None), None);
+
+        assert_eq!(
+            select(&VersionSpec::Latest, &["latest"], None),
+            Some("latest")
+        );
+    }
+}
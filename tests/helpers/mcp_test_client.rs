@@ -1,38 +1,62 @@
 use anyhow::{anyhow, Result};
 use serde_json::{json, Value};
 use std::io::{BufRead, BufReader, Write};
-use std::process::{Child, Command, Stdio};
-use tokio::time::{timeout, Duration};
+use std::process::{Child, ChildStdout, Command, Stdio};
+use tokio::time::Duration;
 
 /// A test client for MCP protocol communication with the TCL MCP server
 pub struct McpTestClient {
     process: Option<Child>,
+    stdout: Option<BufReader<ChildStdout>>,
     privileged: bool,
+    server_args: Vec<String>,
+    data_dir: Option<std::path::PathBuf>,
 }
 
 impl McpTestClient {
     pub fn new(privileged: bool) -> Self {
         Self {
             process: None,
+            stdout: None,
             privileged,
+            server_args: Vec::new(),
+            data_dir: None,
         }
     }
 
+    /// Pass extra command-line options to the server (e.g. `--page-size 5`)
+    pub fn with_args(mut self, args: &[&str]) -> Self {
+        self.server_args = args.iter().map(|arg| arg.to_string()).collect();
+        self
+    }
+
+    /// Keep the server's tool store under `dir` instead of the user's data
+    /// directory
+    pub fn with_data_dir(mut self, dir: &std::path::Path) -> Self {
+        self.data_dir = Some(dir.to_path_buf());
+        self
+    }
+
     /// Start the MCP server process
     pub async fn start(&mut self) -> Result<()> {
         let mut cmd = Command::new("cargo");
-        cmd.args(&["run", "--bin", "tcl-mcp-server", "--"]);
+        cmd.args(["run", "--bin", "tcl-mcp-server", "--"]);
 
         if self.privileged {
             cmd.arg("--privileged");
         }
+        cmd.args(&self.server_args);
+        if let Some(dir) = &self.data_dir {
+            cmd.env("XDG_DATA_HOME", dir);
+        }
 
         cmd.arg("server")
             .stdin(Stdio::piped())
             .stdout(Stdio::piped())
             .stderr(Stdio::piped());
 
-        let process = cmd.spawn()?;
+        let mut process = cmd.spawn()?;
+        self.stdout = process.stdout.take().map(BufReader::new);
 
         // Wait a bit for the server to start
         tokio::time::sleep(Duration::from_millis(1000)).await;
@@ -43,11 +67,6 @@ impl McpTestClient {
 
     /// Send an MCP request and get the response
     pub async fn send_request(&mut self, method: &str, params: Value) -> Result<Value> {
-        let process = self
-            .process
-            .as_mut()
-            .ok_or_else(|| anyhow!("Server not started"))?;
-
         let request = json!({
             "jsonrpc": "2.0",
             "id": 1,
@@ -56,19 +75,40 @@ impl McpTestClient {
         });
 
         let request_str = serde_json::to_string(&request)?;
+        let response = self.send_raw(&request_str).await?;
+
+        if let Some(error) = response.get("error") {
+            return Err(anyhow!("MCP error: {}", error));
+        }
+        Ok(response.get("result").unwrap_or(&Value::Null).clone())
+    }
+
+    /// Write a raw line to the server without waiting for a reply
+    pub fn write_line(&mut self, line: &str) -> Result<()> {
+        let process = self
+            .process
+            .as_mut()
+            .ok_or_else(|| anyhow!("Server not started"))?;
 
-        // Send request to stdin
         if let Some(stdin) = process.stdin.as_mut() {
-            writeln!(stdin, "{}", request_str)?;
+            writeln!(stdin, "{}", line)?;
             stdin.flush()?;
+            Ok(())
         } else {
-            return Err(anyhow!("No stdin available"));
+            Err(anyhow!("No stdin available"))
         }
+    }
 
-        // Read response from stdout
-        if let Some(stdout) = process.stdout.as_mut() {
-            let mut reader = BufReader::new(stdout);
+    /// Send a raw line and return the next full JSON-RPC response
+    pub async fn send_raw(&mut self, line: &str) -> Result<Value> {
+        self.write_line(line)?;
+        self.read_response().await
+    }
 
+    /// Read the next full JSON-RPC response, skipping server notifications
+    pub async fn read_response(&mut self) -> Result<Value> {
+        // Read response from stdout
+        if let Some(reader) = self.stdout.as_mut() {
             // Keep reading lines until we get a valid JSON response
             for _ in 0..100 {
                 // Try up to 100 lines
@@ -83,11 +123,17 @@ impl McpTestClient {
 
                         // Try to parse as JSON
                         if let Ok(response) = serde_json::from_str::<Value>(trimmed) {
-                            if response.is_object() && response.get("jsonrpc").is_some() {
-                                if let Some(error) = response.get("error") {
-                                    return Err(anyhow!("MCP error: {}", error));
-                                }
-                                return Ok(response.get("result").unwrap_or(&Value::Null).clone());
+                            // Batch responses are returned whole
+                            if response.is_array() {
+                                return Ok(response);
+                            }
+
+                            // Skip server notifications (e.g. notifications/message)
+                            if response.is_object()
+                                && response.get("jsonrpc").is_some()
+                                && response.get("id").is_some()
+                            {
+                                return Ok(response);
                             }
                         }
 
@@ -99,7 +145,7 @@ impl McpTestClient {
 
             Err(anyhow!("Too many lines without valid JSON response"))
         } else {
-            Err(anyhow!("No stdout available"))
+            Err(anyhow!("Server not started"))
         }
     }
 
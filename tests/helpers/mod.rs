@@ -1,7 +1,9 @@
+// Shared by several test crates, none of which uses every helper
+#![allow(dead_code)]
+
 use anyhow::Result;
 use serde_json::{json, Value};
 use std::process::Stdio;
-use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
 use tokio::process::{Child, Command};
 
 /// Mock MCP server for testing
@@ -115,19 +117,19 @@ pub fn validate_jsonrpc_message(message: &Value) -> Result<()> {
     }
 
     // Check for either result or error (for responses)
-    if obj.contains_key("id") && !obj.contains_key("method") {
-        if !obj.contains_key("result") && !obj.contains_key("error") {
-            return Err(anyhow::anyhow!(
-                "Response must contain either result or error"
-            ));
-        }
+    if obj.contains_key("id")
+        && !obj.contains_key("method")
+        && !obj.contains_key("result")
+        && !obj.contains_key("error")
+    {
+        return Err(anyhow::anyhow!(
+            "Response must contain either result or error"
+        ));
     }
 
     // Check method (for requests)
-    if obj.contains_key("method") {
-        if !obj["method"].is_string() {
-            return Err(anyhow::anyhow!("Method must be a string"));
-        }
+    if obj.contains_key("method") && !obj["method"].is_string() {
+        return Err(anyhow::anyhow!("Method must be a string"));
     }
 
     Ok(())
@@ -246,10 +248,10 @@ impl TestEnvironment {
         let tools_dir = temp_dir.path().join("tools");
 
         // Create standard directories
-        std::fs::create_dir_all(&tools_dir.join("bin"))?;
-        std::fs::create_dir_all(&tools_dir.join("sbin"))?;
-        std::fs::create_dir_all(&tools_dir.join("docs"))?;
-        std::fs::create_dir_all(&tools_dir.join("users"))?;
+        std::fs::create_dir_all(tools_dir.join("bin"))?;
+        std::fs::create_dir_all(tools_dir.join("sbin"))?;
+        std::fs::create_dir_all(tools_dir.join("docs"))?;
+        std::fs::create_dir_all(tools_dir.join("users"))?;
 
         Ok(Self {
             temp_dir,
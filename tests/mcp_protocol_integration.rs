@@ -1,5 +1,5 @@
 use anyhow::Result;
-use serde_json::json;
+use serde_json::{json, Value};
 
 mod helpers;
 use helpers::mcp_test_client::McpTestClient;
@@ -25,6 +25,156 @@ async fn test_mcp_initialize_handshake() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_lifecycle_enforcement() -> Result<()> {
+    let mut client = McpTestClient::new(false);
+    client.start().await?;
+
+    // Only ping is allowed before initialize
+    let response = client
+        .send_raw(r#"{"jsonrpc":"2.0","id":1,"method":"tools/list"}"#)
+        .await?;
+    assert_eq!(response["error"]["code"], -32002);
+
+    let response = client
+        .send_raw(r#"{"jsonrpc":"2.0","id":2,"method":"ping"}"#)
+        .await?;
+    assert_eq!(response["result"], json!({}));
+
+    client.initialize().await?;
+    assert!(client.initialize().await.is_err());
+
+    // Notifications get no reply, so the next response belongs to the ping
+    client.write_line(r#"{"jsonrpc":"2.0","method":"notifications/initialized"}"#)?;
+    let response = client
+        .send_raw(r#"{"jsonrpc":"2.0","id":3,"method":"ping"}"#)
+        .await?;
+    assert_eq!(response["id"], 3);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_parse_errors_and_batches() -> Result<()> {
+    let mut client = McpTestClient::new(false);
+    client.start().await?;
+    client.initialize().await?;
+
+    let response = client.send_raw("{not json").await?;
+    assert_eq!(response["error"]["code"], -32700);
+    assert!(response["id"].is_null());
+
+    let response = client
+        .send_raw(
+            r#"[{"jsonrpc":"2.0","id":1,"method":"ping"},
+                {"jsonrpc":"2.0","method":"notifications/initialized"},
+                {"jsonrpc":"2.0","id":2,"method":"tools/call","params":{"name":"bin__tcl_execute","arguments":{"script":"expr {6 * 7}"}}}]"#
+                .replace('\n', " ")
+                .as_str(),
+        )
+        .await?;
+    let responses = response.as_array().unwrap();
+    assert_eq!(responses.len(), 2);
+    assert_eq!(responses[0]["id"], 1);
+    assert_eq!(responses[1]["result"]["content"][0]["text"], "42");
+
+    let response = client.send_raw("[]").await?;
+    assert_eq!(response["error"]["code"], -32600);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_concurrent_requests() -> Result<()> {
+    let mut client = McpTestClient::new(false);
+    client.start().await?;
+    client.initialize().await?;
+
+    // A slow script must not hold up a ping sent after it
+    client.write_line(
+        &json!({
+            "jsonrpc": "2.0",
+            "id": 10,
+            "method": "tools/call",
+            "params": {
+                "name": "bin__tcl_execute",
+                "arguments": {"script": "set x 0; for {set i 0} {$i < 200000} {incr i} {incr x}; set x"}
+            }
+        })
+        .to_string(),
+    )?;
+    client.write_line(r#"{"jsonrpc":"2.0","id":11,"method":"ping"}"#)?;
+
+    let first = client.read_response().await?;
+    assert_eq!(first["id"], 11);
+    assert_eq!(first["result"], json!({}));
+
+    let second = client.read_response().await?;
+    assert_eq!(second["id"], 10);
+    assert_eq!(second["result"]["content"][0]["text"], "200000");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_slow_mcp_call_does_not_block_executor() -> Result<()> {
+    let data_dir = tempfile::tempdir()?;
+    let mut client = McpTestClient::new(true).with_data_dir(data_dir.path());
+    client.start().await?;
+    client.initialize().await?;
+
+    // Use a second server instance as the downstream MCP server
+    let added = client
+        .call_tool(
+            "sbin__mcp_add",
+            json!({
+                "id": "slow",
+                "name": "Slow downstream",
+                "command": env!("CARGO_BIN_EXE_tcl-mcp-server"),
+                "args": ["server"]
+            }),
+        )
+        .await?;
+    assert!(added.contains("connected successfully"), "{}", added);
+
+    // A slow remote call through bin__exec_tool must not hold up tools/list,
+    // which is answered by the executor
+    client.write_line(
+        &json!({
+            "jsonrpc": "2.0",
+            "id": 20,
+            "method": "tools/call",
+            "params": {
+                "name": "bin__exec_tool",
+                "arguments": {
+                    "tool_path": "mcp__slow__bin-x5f-x5ftcl_execute",
+                    "params": {"script": "set x 0; for {set i 0} {$i < 1000000} {incr i} {incr x}; set x"}
+                }
+            }
+        })
+        .to_string(),
+    )?;
+    tokio::time::sleep(std::time::Duration::from_millis(300)).await;
+    client.write_line(r#"{"jsonrpc":"2.0","id":21,"method":"tools/list","params":{}}"#)?;
+
+    let first = client.read_response().await?;
+    assert_eq!(first["id"], 21);
+    assert!(first["result"]["tools"].is_array());
+
+    let second = client.read_response().await?;
+    assert_eq!(second["id"], 20);
+    assert!(
+        second["result"]["content"][0]["text"]
+            .as_str()
+            .unwrap_or_default()
+            .contains("1000000"),
+        "{}",
+        second
+    );
+
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_tool_listing() -> Result<()> {
     let mut client = McpTestClient::new(false);
@@ -135,6 +285,40 @@ async fn test_special_character_handling() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_argument_completion() -> Result<()> {
+    let mut client = McpTestClient::new(false);
+    client.start().await?;
+    let init = client.initialize().await?;
+    assert!(init["capabilities"]["completions"].is_object());
+
+    let result = client
+        .send_request(
+            "completion/complete",
+            json!({
+                "ref": {"type": "ref/tool", "name": "docs__molt_book"},
+                "argument": {"name": "topic", "value": "ba"}
+            }),
+        )
+        .await?;
+    assert_eq!(result["completion"]["values"], json!(["basic_syntax"]));
+
+    let result = client
+        .send_request(
+            "completion/complete",
+            json!({
+                "ref": {"type": "ref/tool", "name": "bin__exec_tool"},
+                "argument": {"name": "tool_path", "value": "bin__list_"}
+            }),
+        )
+        .await?;
+    let values = result["completion"]["values"].as_array().unwrap();
+    assert!(values.contains(&json!("bin__list_tools")));
+    assert!(values.iter().all(|v| v.as_str().unwrap().contains("bin__list_")));
+
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_privileged_tool_management() -> Result<()> {
     let mut client = McpTestClient::new(true); // Privileged mode required
@@ -296,6 +480,51 @@ async fn test_privilege_mode_restrictions() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_system_tools_agree_across_listings() -> Result<()> {
+    let mut client = McpTestClient::new(true);
+    client.start().await?;
+    client.initialize().await?;
+
+    let listed = client.list_tools().await?;
+    let listed: Vec<String> = listed["tools"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter_map(|tool| tool["name"].as_str())
+        .filter(|name| name.starts_with("bin__") || name.starts_with("sbin__"))
+        .map(String::from)
+        .collect();
+
+    let registry = client
+        .call_tool(
+            "bin__list_tools",
+            json!({"namespace": "sbin", "format": "simple"}),
+        )
+        .await?;
+    let registry: serde_json::Value = serde_json::from_str(&registry)?;
+    assert!(registry.get("next_cursor").is_none());
+    let registry: Vec<String> = serde_json::from_value(registry["tools"].clone())?;
+    for name in &registry {
+        assert!(listed.contains(name), "{} missing from tools/list", name);
+    }
+    assert!(registry.contains(&"sbin__mcp_disconnect".to_string()));
+    assert!(registry.contains(&"sbin__tcl_tool_reload".to_string()));
+    assert!(listed.contains(&"bin__mcp_list".to_string()));
+
+    let info = client
+        .call_tool(
+            "bin__inspect_tool",
+            json!({"tool_path": "sbin__mcp_disconnect"}),
+        )
+        .await?;
+    let info: serde_json::Value = serde_json::from_str(&info)?;
+    assert_eq!(info["system_info"]["privileged"], true);
+    assert_eq!(info["schema"]["required"], json!(["server_id"]));
+
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_concurrent_tool_execution() -> Result<()> {
     // Create separate clients for each concurrent operation to avoid borrowing issues
@@ -330,3 +559,574 @@ async fn test_concurrent_tool_execution() -> Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_tools_list_pagination() -> Result<()> {
+    let mut client = McpTestClient::new(false).with_args(&["--page-size", "4"]);
+    client.start().await?;
+    client.initialize().await?;
+
+    let mut names: Vec<String> = Vec::new();
+    let mut cursor: Option<String> = None;
+    let mut pages = 0;
+    loop {
+        let params = match &cursor {
+            Some(cursor) => json!({"cursor": cursor}),
+            None => json!({}),
+        };
+        let page = client.send_request("tools/list", params).await?;
+        let tools = page["tools"].as_array().unwrap();
+        assert!(tools.len() <= 4);
+        names.extend(tools.iter().filter_map(|t| t["name"].as_str().map(String::from)));
+        pages += 1;
+
+        match page["nextCursor"].as_str() {
+            Some(next) => cursor = Some(next.to_string()),
+            None => break,
+        }
+    }
+
+    // Every tool appears exactly once, in name order
+    let mut sorted = names.clone();
+    sorted.sort();
+    sorted.dedup();
+    assert_eq!(names, sorted);
+    assert!(pages > 1);
+    assert!(names.contains(&"bin__tcl_execute".to_string()));
+    assert!(names.contains(&"docs__molt_book".to_string()));
+
+    let err = client
+        .send_request("tools/list", json!({"cursor": "not-a-cursor"}))
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("-32602"));
+
+    // bin__list_tools pages the same way with limit and cursor
+    let first = client
+        .call_tool("bin__list_tools", json!({"namespace": "bin", "limit": 3}))
+        .await?;
+    let first: serde_json::Value = serde_json::from_str(&first)?;
+    assert_eq!(first["count"], 3);
+    let next_cursor = first["next_cursor"].as_str().unwrap();
+
+    let second = client
+        .call_tool(
+            "bin__list_tools",
+            json!({"namespace": "bin", "limit": 3, "cursor": next_cursor}),
+        )
+        .await?;
+    let second: serde_json::Value = serde_json::from_str(&second)?;
+    assert!(
+        first["tools"][2]["mcp_name"].as_str().unwrap()
+            < second["tools"][0]["mcp_name"].as_str().unwrap()
+    );
+
+    let skipped = client
+        .call_tool(
+            "bin__list_tools",
+            json!({"namespace": "bin", "offset": 3, "limit": 1, "format": "simple"}),
+        )
+        .await?;
+    let skipped: serde_json::Value = serde_json::from_str(&skipped)?;
+    assert_eq!(skipped["tools"], json!([second["tools"][0]["mcp_name"]]));
+    assert!(skipped["next_cursor"].is_string());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_search_tools_ranked_and_incremental() -> Result<()> {
+    let mut client = McpTestClient::new(true);
+    client.start().await?;
+    client.initialize().await?;
+
+    // A typo still finds the tool, ranked first with a score
+    let results = client
+        .call_tool("bin__search_tools", json!({"query": "exectue script"}))
+        .await?;
+    let results: serde_json::Value = serde_json::from_str(&results)?;
+    assert_eq!(results["results"][0]["mcp_name"], "bin__tcl_execute");
+    assert!(results["results"][0]["score"].as_f64().unwrap() > 0.0);
+
+    // Newly added tools are searchable by parameter name, and drop out once removed
+    let tool_name = format!(
+        "shout_{}",
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_millis()
+    );
+    let mcp_name = format!("user__searcher__text__{}", tool_name);
+    client
+        .add_tool(
+            "searcher",
+            "text",
+            &tool_name,
+            "Upper-case a message",
+            "return [string toupper $message]",
+            vec![json!({
+                "name": "message",
+                "description": "Text to upper-case",
+                "required": true,
+                "type_name": "string"
+            })],
+        )
+        .await?;
+    let results = client
+        .call_tool("bin__search_tools", json!({"query": "mesage", "limit": 1}))
+        .await?;
+    let results: serde_json::Value = serde_json::from_str(&results)?;
+    assert_eq!(results["results"][0]["mcp_name"], mcp_name.as_str());
+
+    client
+        .call_tool(
+            "sbin__tcl_tool_remove",
+            json!({"path": mcp_name}),
+        )
+        .await?;
+    let results = client
+        .call_tool("bin__search_tools", json!({"query": "upper case message"}))
+        .await?;
+    assert!(!results.contains(&mcp_name));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_tool_paths_in_every_form() -> Result<()> {
+    let mut client = McpTestClient::new(true);
+    client.start().await?;
+    client.initialize().await?;
+
+    let tool_name = format!(
+        "echo_{}",
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_millis()
+    );
+    client
+        .call_tool(
+            "sbin__tcl_tool_add",
+            json!({
+                "user": "pathfinder",
+                "package": "text",
+                "name": tool_name,
+                "version": "1.0",
+                "description": "Echo a message",
+                "script": "return $message",
+                "parameters": [{
+                    "name": "message",
+                    "description": "Text to echo",
+                    "required": true,
+                    "type_name": "string"
+                }]
+            }),
+        )
+        .await?;
+
+    // MCP names, slash paths with and without the user prefix, and bare names
+    for tool_path in [
+        format!("user__pathfinder__text__{}__v1_0", tool_name),
+        format!("/user/pathfinder/text/{}:1.0", tool_name),
+        format!("/pathfinder/text/{}:1.0", tool_name),
+        tool_name.clone(),
+    ] {
+        let result = client
+            .exec_tool(&tool_path, json!({"message": "hi"}))
+            .await?;
+        assert_eq!(result, "hi", "{}", tool_path);
+    }
+    let result = client
+        .exec_tool("/bin/tcl_execute", json!({"script": "expr {6 * 7}"}))
+        .await?;
+    assert_eq!(result, "42");
+
+    let info = client
+        .call_tool(
+            "bin__inspect_tool",
+            json!({"tool_path": format!("/pathfinder/text/{}:1.0", tool_name)}),
+        )
+        .await?;
+    assert!(info.contains("Echo a message"));
+
+    // Unknown tools suggest close matches
+    let response = client
+        .send_request(
+            "tools/call",
+            json!({"name": "bin__exec_tool", "arguments": {"tool_path": "bin__tcl_exectue"}}),
+        )
+        .await;
+    let error = response.unwrap_err().to_string();
+    assert!(error.contains("Did you mean: bin__tcl_execute"), "{}", error);
+
+    // The slash path shown in the tcl_tool_remove schema works
+    client
+        .call_tool(
+            "sbin__tcl_tool_remove",
+            json!({"path": format!("/pathfinder/text/{}:1.0", tool_name)}),
+        )
+        .await?;
+    assert!(client.exec_tool(&tool_name, json!({"message": "hi"})).await.is_err());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_version_ranges_and_pins() -> Result<()> {
+    let mut client = McpTestClient::new(true);
+    client.start().await?;
+    client.initialize().await?;
+
+    let tool_name = format!(
+        "which_{}",
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_millis()
+    );
+    for version in ["1.0", "1.2.5", "2.0.0"] {
+        client
+            .call_tool(
+                "sbin__tcl_tool_add",
+                json!({
+                    "user": "versioner",
+                    "package": "demo",
+                    "name": tool_name,
+                    "version": version,
+                    "description": "Report the version",
+                    "script": format!("return {}", version)
+                }),
+            )
+            .await?;
+    }
+    let unversioned = format!("/versioner/demo/{}", tool_name);
+
+    // latest is the highest version, ranges pick the highest match
+    assert_eq!(client.exec_tool(&unversioned, json!({})).await?, "2.0.0");
+    assert_eq!(client.exec_tool(&format!("{}:^1", unversioned), json!({})).await?, "1.2.5");
+    assert_eq!(client.exec_tool(&format!("{}:1.0.0", tool_name), json!({})).await?, "1.0");
+    assert!(client.exec_tool(&format!("{}:^3", unversioned), json!({})).await.is_err());
+
+    // Invalid versions are rejected when adding
+    let invalid = client
+        .call_tool(
+            "sbin__tcl_tool_add",
+            json!({
+                "user": "versioner",
+                "package": "demo",
+                "name": tool_name,
+                "version": "next",
+                "description": "Bad version",
+                "script": "return"
+            }),
+        )
+        .await;
+    assert!(invalid.is_err());
+
+    // A pin changes what latest means, for calls and for latestOnly listings
+    client
+        .call_tool("sbin__tcl_tool_pin", json!({"path": unversioned, "version": "~1.0"}))
+        .await?;
+    assert_eq!(client.exec_tool(&unversioned, json!({})).await?, "1.0");
+    let listed = client
+        .send_request("tools/list", json!({"latestOnly": true}))
+        .await?;
+    let prefix = format!("user__versioner__demo__{}", tool_name);
+    let versions: Vec<String> = tool_names(&listed)
+        .into_iter()
+        .filter(|name| name.starts_with(&prefix))
+        .collect();
+    assert_eq!(versions, vec![format!("{}__v1_0", prefix)]);
+
+    client
+        .call_tool("sbin__tcl_tool_pin", json!({"path": unversioned}))
+        .await?;
+    assert_eq!(client.exec_tool(&unversioned, json!({})).await?, "2.0.0");
+
+    for version in ["1.0", "1.2.5", "2.0.0"] {
+        client
+            .call_tool(
+                "sbin__tcl_tool_remove",
+                json!({"path": format!("{}:{}", unversioned, version)}),
+            )
+            .await?;
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_tool_history_diff_and_rollback() -> Result<()> {
+    let mut client = McpTestClient::new(true);
+    client.start().await?;
+    client.initialize().await?;
+
+    let tool_name = format!(
+        "revised_{}",
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_millis()
+    );
+    let path = format!("/historian/demo/{}:1.0", tool_name);
+    let save = |script: &str, overwrite: bool| {
+        json!({
+            "user": "historian",
+            "package": "demo",
+            "name": tool_name,
+            "version": "1.0",
+            "description": "Return a word",
+            "script": script,
+            "overwrite": overwrite
+        })
+    };
+
+    client.call_tool("sbin__tcl_tool_add", save("return first", false)).await?;
+    // Replacing a tool needs overwrite
+    assert!(client
+        .call_tool("sbin__tcl_tool_add", save("return second", false))
+        .await
+        .is_err());
+    client.call_tool("sbin__tcl_tool_add", save("return second", true)).await?;
+    assert_eq!(client.exec_tool(&path, json!({})).await?, "second");
+
+    let history: Value = serde_json::from_str(
+        &client
+            .call_tool("sbin__tcl_tool_history", json!({"path": path}))
+            .await?,
+    )?;
+    let revisions = history["revisions"].as_array().unwrap();
+    assert_eq!(revisions.len(), 2);
+    assert_eq!(revisions[1]["action"], "save");
+
+    let diff = client
+        .call_tool("sbin__tcl_tool_diff", json!({"path": path}))
+        .await?;
+    assert!(diff.contains("-return first\n+return second"), "{}", diff);
+
+    let rolled_back = client
+        .call_tool("sbin__tcl_tool_rollback", json!({"path": path, "revision": 1}))
+        .await?;
+    assert!(rolled_back.contains("now revision 3"), "{}", rolled_back);
+    assert_eq!(client.exec_tool(&path, json!({})).await?, "first");
+
+    // Removal is recorded too, and a deleted tool can be restored
+    client
+        .call_tool("sbin__tcl_tool_remove", json!({"path": path}))
+        .await?;
+    client
+        .call_tool("sbin__tcl_tool_rollback", json!({"path": path, "revision": 2}))
+        .await?;
+    assert_eq!(client.exec_tool(&path, json!({})).await?, "second");
+
+    let history: Value = serde_json::from_str(
+        &client
+            .call_tool("sbin__tcl_tool_history", json!({"path": path}))
+            .await?,
+    )?;
+    let actions: Vec<&str> = history["revisions"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|r| r["action"].as_str().unwrap())
+        .collect();
+    assert_eq!(actions, vec!["save", "save", "rollback", "delete", "rollback"]);
+
+    client
+        .call_tool("sbin__tcl_tool_remove", json!({"path": path}))
+        .await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_tampered_tools_are_quarantined() -> Result<()> {
+    let data_dir = tempfile::tempdir()?;
+    let path = "/auditor/demo/greeting:1.0";
+
+    let mut client = McpTestClient::new(true).with_data_dir(data_dir.path());
+    client.start().await?;
+    client.initialize().await?;
+    client
+        .call_tool(
+            "sbin__tcl_tool_add",
+            json!({
+                "user": "auditor",
+                "package": "demo",
+                "name": "greeting",
+                "version": "1.0",
+                "description": "Say hello",
+                "script": "return hello"
+            }),
+        )
+        .await?;
+    let inspected: Value = serde_json::from_str(
+        &client
+            .call_tool("bin__inspect_tool", json!({"tool_path": path}))
+            .await?,
+    )?;
+    assert_eq!(inspected["integrity"]["status"], "verified");
+    drop(client);
+
+    // Change the script without updating its checksum
+    let tool_file = data_dir
+        .path()
+        .join("tcl-mcp-server/tools/users/auditor/demo/greeting_1.0.json");
+    let content = std::fs::read_to_string(&tool_file)?;
+    std::fs::write(&tool_file, content.replace("return hello", "return tampered"))?;
+
+    let mut client = McpTestClient::new(true).with_data_dir(data_dir.path());
+    client.start().await?;
+    client.initialize().await?;
+    assert!(client.exec_tool(path, json!({})).await.is_err());
+
+    let inspected: Value = serde_json::from_str(
+        &client
+            .call_tool("bin__inspect_tool", json!({"tool_path": path}))
+            .await?,
+    )?;
+    assert_eq!(inspected["integrity"]["status"], "quarantined");
+    assert!(inspected["integrity"]["reason"]
+        .as_str()
+        .unwrap()
+        .contains("checksum mismatch"));
+    assert!(!tool_file.exists());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_inferred_parameters_preview_then_save() -> Result<()> {
+    let data_dir = tempfile::tempdir()?;
+    let mut client = McpTestClient::new(true).with_data_dir(data_dir.path());
+    client.start().await?;
+    client.initialize().await?;
+
+    let add = |confirm: bool| {
+        json!({
+            "user": "inferrer",
+            "package": "demo",
+            "name": "greet",
+            "description": "Greet someone",
+            "script": "proc greet {name {greeting Hello}} {\n    return \"$greeting, $name!\"\n}",
+            "confirm": confirm
+        })
+    };
+
+    // Without confirm the inferred parameters come back as a preview
+    let preview: Value =
+        serde_json::from_str(&client.call_tool("sbin__tcl_tool_add", add(false)).await?)?;
+    assert_eq!(preview["saved"], false);
+    assert_eq!(preview["input_schema"]["required"], json!(["name"]));
+    assert_eq!(
+        preview["input_schema"]["properties"]["greeting"]["default"],
+        "Hello"
+    );
+    assert!(client
+        .exec_tool("/inferrer/demo/greet", json!({"name": "Ada"}))
+        .await
+        .is_err());
+
+    client.call_tool("sbin__tcl_tool_add", add(true)).await?;
+    assert_eq!(
+        client
+            .exec_tool("/inferrer/demo/greet", json!({"name": "Ada"}))
+            .await?,
+        "Hello, Ada!"
+    );
+    assert_eq!(
+        client
+            .exec_tool(
+                "/inferrer/demo/greet",
+                json!({"name": "Ada", "greeting": "Hi"})
+            )
+            .await?,
+        "Hi, Ada!"
+    );
+
+    Ok(())
+}
+
+fn tool_names(list: &Value) -> Vec<String> {
+    list["tools"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter_map(|tool| tool["name"].as_str().map(String::from))
+        .collect()
+}
+
+#[tokio::test]
+async fn test_meta_only_profile_keeps_hidden_tools_callable() -> Result<()> {
+    let mut client = McpTestClient::new(false).with_args(&["--profile", "meta-only"]);
+    client.start().await?;
+    client.initialize().await?;
+
+    let mut names = tool_names(&client.list_tools().await?);
+    names.sort();
+    assert_eq!(
+        names,
+        vec!["bin__exec_tool", "bin__inspect_tool", "bin__search_tools"]
+    );
+
+    // Hidden system tools still run through bin__exec_tool
+    let result = client
+        .exec_tool("bin__tcl_execute", json!({"script": "expr {2 + 3}"}))
+        .await?;
+    assert_eq!(result, "5");
+    let docs = client
+        .exec_tool("docs__molt_book", json!({"topic": "links"}))
+        .await?;
+    assert!(!docs.is_empty());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_session_profile_from_initialize() -> Result<()> {
+    let mut client = McpTestClient::new(false);
+    client.start().await?;
+    let init = client
+        .send_request(
+            "initialize",
+            json!({
+                "protocolVersion": "2024-11-05",
+                "capabilities": {
+                    "experimental": {
+                        "toolProfile": {"include": ["namespace:docs", "bin__list_*"]}
+                    }
+                }
+            }),
+        )
+        .await?;
+    assert!(init["capabilities"]["experimental"]["toolProfile"].is_object());
+
+    let mut names = tool_names(&client.list_tools().await?);
+    names.sort();
+    assert_eq!(
+        names,
+        vec![
+            "bin__list_namespaces",
+            "bin__list_tools",
+            "bin__list_xmcp_tools",
+            "docs__molt_book"
+        ]
+    );
+
+    let mut client = McpTestClient::new(false);
+    client.start().await?;
+    let err = client
+        .send_request(
+            "initialize",
+            json!({
+                "protocolVersion": "2024-11-05",
+                "capabilities": {"experimental": {"toolProfile": {"name": "no-such-profile"}}}
+            }),
+        )
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("Unknown exposure profile"));
+
+    Ok(())
+}
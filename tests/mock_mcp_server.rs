@@ -1,6 +1,5 @@
 use serde_json::{json, Value};
 use std::io::{BufRead, BufReader, Write};
-use std::process::{Command, Stdio};
 
 /// A simple mock MCP server for testing
 /// Can be compiled as a separate binary for integration tests
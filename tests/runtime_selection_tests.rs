@@ -100,12 +100,11 @@ mod runtime_selection_tests {
             );
 
             let runtime = result.unwrap();
-            assert_eq!(
+            assert!(
                 runtime
                     .name()
                     .to_lowercase()
-                    .contains(runtime_type.as_str()),
-                true
+                    .contains(runtime_type.as_str())
             );
         }
     }
@@ -28,6 +28,8 @@ mod bin_exec_tool_tests {
                     description: "Arguments to pass".to_string(),
                     required: false,
                     type_name: "string".to_string(),
+                    completion: None,
+                    default: None,
                 }
             ],
             response: tx,
@@ -70,12 +72,16 @@ mod bin_exec_tool_tests {
                     description: "User name".to_string(),
                     required: true,
                     type_name: "string".to_string(),
+                    completion: None,
+                    default: None,
                 },
                 ParameterDefinition {
                     name: "age".to_string(),
                     description: "User age".to_string(),
                     required: true,
                     type_name: "number".to_string(),
+                    completion: None,
+                    default: None,
                 }
             ],
             response: tx,
@@ -140,24 +146,32 @@ mod bin_exec_tool_tests {
                     description: "String parameter".to_string(),
                     required: true,
                     type_name: "string".to_string(),
+                    completion: None,
+                    default: None,
                 },
                 ParameterDefinition {
                     name: "num_param".to_string(),
                     description: "Number parameter".to_string(),
                     required: true,
                     type_name: "number".to_string(),
+                    completion: None,
+                    default: None,
                 },
                 ParameterDefinition {
                     name: "bool_param".to_string(),
                     description: "Boolean parameter".to_string(),
                     required: true,
                     type_name: "boolean".to_string(),
+                    completion: None,
+                    default: None,
                 },
                 ParameterDefinition {
                     name: "array_param".to_string(),
                     description: "Array parameter".to_string(),
                     required: true,
                     type_name: "array".to_string(),
+                    completion: None,
+                    default: None,
                 }
             ],
             response: tx,
@@ -204,6 +218,8 @@ mod bin_exec_tool_tests {
                     description: "Input to echo".to_string(),
                     required: true,
                     type_name: "string".to_string(),
+                    completion: None,
+                    default: None,
                 }
             ],
             response: tx,
@@ -258,6 +274,8 @@ mod bin_exec_tool_tests {
                     description: "Whether to throw error".to_string(),
                     required: true,
                     type_name: "string".to_string(),
+                    completion: None,
+                    default: None,
                 }
             ],
             response: tx,
@@ -334,6 +352,8 @@ mod bin_exec_tool_tests {
                     description: "Comma-separated list of numbers".to_string(),
                     required: true,
                     type_name: "string".to_string(),
+                    completion: None,
+                    default: None,
                 }
             ],
             response: tx,
@@ -375,6 +395,8 @@ mod bin_exec_tool_tests {
                     description: "Task identifier".to_string(),
                     required: true,
                     type_name: "string".to_string(),
+                    completion: None,
+                    default: None,
                 }
             ],
             response: tx,